@@ -0,0 +1,744 @@
+use crate::stock::{self, StockSymbolError};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use reactive_rs::Stream;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, iter::Peekable, str::FromStr};
+use ta::{indicators, Next};
+use thiserror::Error;
+use yahoo_finance::Bar;
+
+/// An alert on a stock, which fires whenever its condition becomes true.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Alert {
+    pub condition: AlertCondition,
+    #[serde(default = "Alert::default_enabled")]
+    pub enabled: bool,
+    /// Timestamp until which the alert does not fire, if snoozed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<i64>,
+    pub symbol: String,
+}
+
+impl Alert {
+    const SNOOZE_HOURS: i64 = 24;
+
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Returns whether the alert can fire, i.e. it is enabled and not snoozed.
+    pub fn is_armed(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && !self.is_snoozed(now)
+    }
+
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.snoozed_until, Some(snoozed_until) if Utc.timestamp(snoozed_until, 0) > now)
+    }
+
+    /// Returns the status of the alert, given whether its condition is currently true.
+    pub fn status(&self, triggered: bool, now: DateTime<Utc>) -> AlertStatus {
+        if !self.enabled {
+            AlertStatus::Disabled
+        } else if self.is_snoozed(now) {
+            AlertStatus::Snoozed
+        } else if triggered {
+            AlertStatus::Triggered
+        } else {
+            AlertStatus::Armed
+        }
+    }
+
+    /// Snoozes the alert for a day, or wakes it up if already snoozed.
+    pub fn toggle_snoozed(&mut self, now: DateTime<Utc>) {
+        self.snoozed_until = if self.is_snoozed(now) {
+            None
+        } else {
+            Some((now + Duration::hours(Self::SNOOZE_HOURS)).timestamp())
+        };
+    }
+}
+
+/// A change to the alerts store. The alerts are referred to by their index in the store.
+#[derive(Clone, Debug)]
+pub enum AlertEdit {
+    Add(Alert),
+    Delete(usize),
+    Replace(usize, Alert),
+    Snooze(usize),
+    ToggleEnabled(usize),
+}
+
+impl AlertEdit {
+    pub fn apply(&self, alerts: &mut Vec<Alert>, now: DateTime<Utc>) {
+        match self {
+            Self::Add(alert) => alerts.push(alert.clone()),
+            Self::Delete(n) => {
+                if *n < alerts.len() {
+                    alerts.remove(*n);
+                }
+            }
+            Self::Replace(n, alert) => {
+                if let Some(replaced_alert) = alerts.get_mut(*n) {
+                    *replaced_alert = alert.clone();
+                }
+            }
+            Self::Snooze(n) => {
+                if let Some(alert) = alerts.get_mut(*n) {
+                    alert.toggle_snoozed(now);
+                }
+            }
+            Self::ToggleEnabled(n) => {
+                if let Some(alert) = alerts.get_mut(*n) {
+                    alert.enabled = !alert.enabled;
+                }
+            }
+        }
+    }
+}
+
+/// Parses an alert from its symbol and condition, e.g. `AAPL: RSI(14) > 70`.
+impl FromStr for Alert {
+    type Err = ParseAlertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (symbol, condition) = match s.find(':') {
+            Some(i) => (s[..i].trim(), &s[i + 1..]),
+            None if s.trim().is_empty() => return Err(ParseAlertError::Empty),
+            None => return Err(ParseAlertError::MissingSymbol),
+        };
+        if symbol.is_empty() {
+            return Err(ParseAlertError::MissingSymbol);
+        }
+        stock::validate_stock_symbol(symbol)?;
+
+        Ok(Self {
+            condition: condition.parse()?,
+            enabled: true,
+            snoozed_until: None,
+            symbol: symbol.to_ascii_uppercase(),
+        })
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.symbol, self.condition)
+    }
+}
+
+/// Condition of an alert, e.g. `RSI(14) > 70` or `close crosses above SMA(200)`. It is stored as
+/// the source, so that the alerts store stays editable by hand.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AlertCondition {
+    And(Box<AlertCondition>, Box<AlertCondition>),
+    Compare(AlertTerm, Comparison, AlertTerm),
+    Or(Box<AlertCondition>, Box<AlertCondition>),
+}
+
+impl AlertCondition {
+    /// Evaluates the condition at the last of the bars, which must be in chronological order. It
+    /// is false if there are not enough bars for any of the indicators.
+    pub fn evaluate(&self, bars: &[Bar]) -> bool {
+        match self {
+            Self::And(lhs, rhs) => lhs.evaluate(bars) && rhs.evaluate(bars),
+            Self::Compare(lhs, comparison, rhs) => {
+                // Crossing needs the values at the previous bar too, even against a number
+                let needed_bars = lhs.period().max(rhs.period()) + comparison.lookback();
+                if bars.len() < needed_bars.max(comparison.lookback() + 1) {
+                    return false;
+                }
+
+                let (lhs, rhs) = (lhs.values(bars), rhs.values(bars));
+                let n = bars.len() - 1;
+                match comparison {
+                    Comparison::Crosses => {
+                        (lhs[n - 1] <= rhs[n - 1] && lhs[n] > rhs[n])
+                            || (lhs[n - 1] >= rhs[n - 1] && lhs[n] < rhs[n])
+                    }
+                    Comparison::CrossesAbove => lhs[n - 1] <= rhs[n - 1] && lhs[n] > rhs[n],
+                    Comparison::CrossesBelow => lhs[n - 1] >= rhs[n - 1] && lhs[n] < rhs[n],
+                    Comparison::Greater => lhs[n] > rhs[n],
+                    Comparison::GreaterOrEqual => lhs[n] >= rhs[n],
+                    Comparison::Less => lhs[n] < rhs[n],
+                    Comparison::LessOrEqual => lhs[n] <= rhs[n],
+                }
+            }
+            Self::Or(lhs, rhs) => lhs.evaluate(bars) || rhs.evaluate(bars),
+        }
+    }
+}
+
+impl FromStr for AlertCondition {
+    type Err = ParseAlertConditionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        if tokens.is_empty() {
+            return Err(ParseAlertConditionError::Empty);
+        }
+
+        let mut tokens = tokens.into_iter().peekable();
+        let condition = parse_or(&mut tokens)?;
+        match tokens.next() {
+            Some(token) => Err(ParseAlertConditionError::UnexpectedToken(token.to_string())),
+            None => Ok(condition),
+        }
+    }
+}
+
+impl TryFrom<String> for AlertCondition {
+    type Error = ParseAlertConditionError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<AlertCondition> for String {
+    fn from(condition: AlertCondition) -> Self {
+        condition.to_string()
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::And(lhs, rhs) => {
+                // "and" binds tighter than "or"
+                for (n, condition) in [lhs, rhs].iter().enumerate() {
+                    if n > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match condition.as_ref() {
+                        Self::Or(..) => write!(f, "({})", condition)?,
+                        _ => write!(f, "{}", condition)?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Compare(lhs, comparison, rhs) => write!(f, "{} {} {}", lhs, comparison, rhs),
+            Self::Or(lhs, rhs) => write!(f, "{} or {}", lhs, rhs),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ParseAlertError {
+    #[error(transparent)]
+    Condition(#[from] ParseAlertConditionError),
+    #[error("cannot parse alert from empty string")]
+    Empty,
+    #[error("missing symbol, e.g. AAPL: RSI(14) > 70")]
+    MissingSymbol,
+    #[error(transparent)]
+    Symbol(#[from] StockSymbolError),
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ParseAlertConditionError {
+    #[error("cannot parse alert condition from empty string")]
+    Empty,
+    #[error("invalid period {0}")]
+    InvalidPeriod(String),
+    #[error("unexpected end of condition")]
+    UnexpectedEnd,
+    #[error("unexpected {0}")]
+    UnexpectedToken(String),
+    #[error("unknown term {0}")]
+    UnknownTerm(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AlertStatus {
+    /// The condition is false, so the alert fires once it becomes true.
+    Armed,
+    Disabled,
+    Snoozed,
+    /// The condition is true, so the alert has fired and does not fire again until it is rearmed.
+    Triggered,
+}
+
+impl fmt::Display for AlertStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Armed => write!(f, "armed"),
+            Self::Disabled => write!(f, "disabled"),
+            Self::Snoozed => write!(f, "snoozed"),
+            Self::Triggered => write!(f, "triggered"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Crosses,
+    CrossesAbove,
+    CrossesBelow,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    /// Returns the number of bars before the last one needed for the comparison.
+    fn lookback(self) -> usize {
+        match self {
+            Self::Crosses | Self::CrossesAbove | Self::CrossesBelow => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crosses => write!(f, "crosses"),
+            Self::CrossesAbove => write!(f, "crosses above"),
+            Self::CrossesBelow => write!(f, "crosses below"),
+            Self::Greater => write!(f, ">"),
+            Self::GreaterOrEqual => write!(f, ">="),
+            Self::Less => write!(f, "<"),
+            Self::LessOrEqual => write!(f, "<="),
+        }
+    }
+}
+
+/// A value which can be compared in an alert condition, taken at each bar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertTerm {
+    Close,
+    ExponentialMovingAverage(u16),
+    High,
+    Low,
+    Number(f64),
+    Open,
+    RelativeStrengthIndex(u16),
+    SimpleMovingAverage(u16),
+    Volume,
+}
+
+impl AlertTerm {
+    /// Returns the number of bars needed to compute the first value.
+    fn period(self) -> usize {
+        match self {
+            Self::ExponentialMovingAverage(n)
+            | Self::RelativeStrengthIndex(n)
+            | Self::SimpleMovingAverage(n) => n as usize,
+            _ => 0,
+        }
+    }
+
+    /// Returns the value at each of the bars.
+    fn values(self, bars: &[Bar]) -> Vec<f64> {
+        let closes = bars.iter().map(|bar| bar.close);
+
+        match self {
+            Self::Close => closes.collect(),
+            Self::ExponentialMovingAverage(n) => {
+                let mut ema = indicators::ExponentialMovingAverage::new(n as usize).unwrap();
+                closes.map(|close| ema.next(close)).collect()
+            }
+            Self::High => bars.iter().map(|bar| bar.high).collect(),
+            Self::Low => bars.iter().map(|bar| bar.low).collect(),
+            Self::Number(value) => vec![value; bars.len()],
+            Self::Open => bars.iter().map(|bar| bar.open).collect(),
+            Self::RelativeStrengthIndex(n) => {
+                let mut rsi = indicators::RelativeStrengthIndex::new(n as usize).unwrap();
+                closes.map(|close| rsi.next(close)).collect()
+            }
+            Self::SimpleMovingAverage(n) => {
+                let mut sma = indicators::SimpleMovingAverage::new(n as usize).unwrap();
+                closes.map(|close| sma.next(close)).collect()
+            }
+            Self::Volume => bars
+                .iter()
+                .map(|bar| bar.volume.unwrap_or_default() as f64)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for AlertTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Close => write!(f, "close"),
+            Self::ExponentialMovingAverage(n) => write!(f, "EMA({})", n),
+            Self::High => write!(f, "high"),
+            Self::Low => write!(f, "low"),
+            Self::Number(value) => write!(f, "{}", value),
+            Self::Open => write!(f, "open"),
+            Self::RelativeStrengthIndex(n) => write!(f, "RSI({})", n),
+            Self::SimpleMovingAverage(n) => write!(f, "SMA({})", n),
+            Self::Volume => write!(f, "volume"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    CloseParen,
+    Number(f64),
+    OpenParen,
+    Operator(String),
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CloseParen => write!(f, ")"),
+            Self::Number(value) => write!(f, "{}", value),
+            Self::OpenParen => write!(f, "("),
+            Self::Operator(operator) => write!(f, "{}", operator),
+            Self::Word(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseAlertConditionError> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::OpenParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::CloseParen);
+            }
+            '<' | '>' => {
+                chars.next();
+                let mut operator = c.to_string();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    operator.push('=');
+                }
+                tokens.push(Token::Operator(operator));
+            }
+            c if c.is_ascii_digit() || c == '.' || c == '-' => {
+                let mut number = String::new();
+                number.push(c);
+                chars.next();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| ParseAlertConditionError::UnexpectedToken(number))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => return Err(ParseAlertConditionError::UnexpectedToken(c.to_string())),
+        }
+    }
+
+    Ok(tokens)
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+/// Checks whether the next token is the keyword, consuming it if so.
+fn next_if_keyword(tokens: &mut Tokens, keyword: &str) -> bool {
+    match tokens.peek() {
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+            tokens.next();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn expect(tokens: &mut Tokens, expected: Token) -> Result<(), ParseAlertConditionError> {
+    match tokens.next() {
+        Some(token) if token == expected => Ok(()),
+        Some(token) => Err(ParseAlertConditionError::UnexpectedToken(token.to_string())),
+        None => Err(ParseAlertConditionError::UnexpectedEnd),
+    }
+}
+
+fn parse_or(tokens: &mut Tokens) -> Result<AlertCondition, ParseAlertConditionError> {
+    let mut condition = parse_and(tokens)?;
+    while next_if_keyword(tokens, "or") {
+        condition = AlertCondition::Or(Box::new(condition), Box::new(parse_and(tokens)?));
+    }
+
+    Ok(condition)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<AlertCondition, ParseAlertConditionError> {
+    let mut condition = parse_compare(tokens)?;
+    while next_if_keyword(tokens, "and") {
+        condition = AlertCondition::And(Box::new(condition), Box::new(parse_compare(tokens)?));
+    }
+
+    Ok(condition)
+}
+
+fn parse_compare(tokens: &mut Tokens) -> Result<AlertCondition, ParseAlertConditionError> {
+    if tokens.peek() == Some(&Token::OpenParen) {
+        tokens.next();
+        let condition = parse_or(tokens)?;
+        expect(tokens, Token::CloseParen)?;
+        return Ok(condition);
+    }
+
+    let lhs = parse_term(tokens)?;
+    let comparison = match tokens.next() {
+        Some(Token::Operator(operator)) => match operator.as_str() {
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            _ => return Err(ParseAlertConditionError::UnexpectedToken(operator)),
+        },
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("crosses") => {
+            if next_if_keyword(tokens, "above") {
+                Comparison::CrossesAbove
+            } else if next_if_keyword(tokens, "below") {
+                Comparison::CrossesBelow
+            } else {
+                Comparison::Crosses
+            }
+        }
+        Some(token) => return Err(ParseAlertConditionError::UnexpectedToken(token.to_string())),
+        None => return Err(ParseAlertConditionError::UnexpectedEnd),
+    };
+    let rhs = parse_term(tokens)?;
+
+    Ok(AlertCondition::Compare(lhs, comparison, rhs))
+}
+
+fn parse_term(tokens: &mut Tokens) -> Result<AlertTerm, ParseAlertConditionError> {
+    let word = match tokens.next() {
+        Some(Token::Number(value)) => return Ok(AlertTerm::Number(value)),
+        Some(Token::Word(word)) => word,
+        Some(token) => return Err(ParseAlertConditionError::UnexpectedToken(token.to_string())),
+        None => return Err(ParseAlertConditionError::UnexpectedEnd),
+    };
+
+    let indicator: Option<fn(u16) -> AlertTerm> = match word.to_ascii_lowercase().as_str() {
+        "close" => return Ok(AlertTerm::Close),
+        "high" => return Ok(AlertTerm::High),
+        "low" => return Ok(AlertTerm::Low),
+        "open" => return Ok(AlertTerm::Open),
+        "volume" => return Ok(AlertTerm::Volume),
+        "ema" => Some(AlertTerm::ExponentialMovingAverage),
+        "rsi" => Some(AlertTerm::RelativeStrengthIndex),
+        "sma" => Some(AlertTerm::SimpleMovingAverage),
+        _ => None,
+    };
+    let indicator = indicator.ok_or(ParseAlertConditionError::UnknownTerm(word))?;
+
+    expect(tokens, Token::OpenParen)?;
+    let n = match tokens.next() {
+        Some(Token::Number(value))
+            if value >= 1.0 && value <= f64::from(u16::MAX) && value.fract() == 0.0 =>
+        {
+            value as u16
+        }
+        Some(token) => return Err(ParseAlertConditionError::InvalidPeriod(token.to_string())),
+        None => return Err(ParseAlertConditionError::UnexpectedEnd),
+    };
+    expect(tokens, Token::CloseParen)?;
+
+    Ok(indicator(n))
+}
+
+/// Maps the states of the alerts to the alerts which have just fired, i.e. whose condition has
+/// become true since the previous state. Alerts which are already true at the start fire too.
+pub fn to_fired_alert_lists<'a, S, C>(
+    alert_states: S,
+) -> impl Stream<'a, Item = Vec<Alert>, Context = C>
+where
+    S: Stream<'a, Item = Vec<(Alert, bool)>, Context = C>,
+    C: 'a,
+{
+    alert_states
+        .fold(
+            (vec![], vec![]),
+            |(acc_alert_states, _): &(Vec<(Alert, bool)>, Vec<Alert>), alert_states| {
+                let fired_alerts = alert_states
+                    .iter()
+                    .filter(|(alert, triggered)| {
+                        *triggered
+                            && !acc_alert_states.iter().any(|(acc_alert, acc_triggered)| {
+                                acc_alert == alert && *acc_triggered
+                            })
+                    })
+                    .map(|(alert, _)| alert.clone())
+                    .collect();
+                (alert_states.clone(), fired_alerts)
+            },
+        )
+        .map(|(_, fired_alerts)| fired_alerts.clone())
+        .filter(|fired_alerts| !fired_alerts.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars(closes: &[f64]) -> Vec<Bar> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Bar {
+                timestamp: i as i64 * 86_400_000,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: Some(1_000),
+            })
+            .collect()
+    }
+
+    fn condition(s: &str) -> AlertCondition {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_comparison() {
+        assert_eq!(
+            condition("RSI(14) > 70"),
+            AlertCondition::Compare(
+                AlertTerm::RelativeStrengthIndex(14),
+                Comparison::Greater,
+                AlertTerm::Number(70.0)
+            )
+        );
+        assert_eq!(
+            condition("close crosses above sma(200)"),
+            AlertCondition::Compare(
+                AlertTerm::Close,
+                Comparison::CrossesAbove,
+                AlertTerm::SimpleMovingAverage(200)
+            )
+        );
+    }
+
+    #[test]
+    fn parses_and_before_or() {
+        assert_eq!(
+            condition("close > 1 or close < 2 and volume >= 3"),
+            AlertCondition::Or(
+                Box::new(condition("close > 1")),
+                Box::new(AlertCondition::And(
+                    Box::new(condition("close < 2")),
+                    Box::new(condition("volume >= 3"))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn displays_parsable_condition() {
+        for s in &[
+            "RSI(14) > 70",
+            "close crosses below EMA(20)",
+            "(close > 1 or close < 2) and volume >= 3",
+        ] {
+            assert_eq!(condition(s).to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_condition() {
+        assert_eq!(
+            "".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::Empty)
+        );
+        assert_eq!(
+            "close >".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "price > 1".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::UnknownTerm("price".to_owned()))
+        );
+        assert_eq!(
+            "SMA(0) > 1".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::InvalidPeriod("0".to_owned()))
+        );
+        assert_eq!(
+            "(close > 1".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "close > 1 1".parse::<AlertCondition>(),
+            Err(ParseAlertConditionError::UnexpectedToken("1".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rejects_alert_without_symbol() {
+        assert_eq!(
+            ": close > 1".parse::<Alert>(),
+            Err(ParseAlertError::MissingSymbol)
+        );
+        assert_eq!(
+            "close > 1".parse::<Alert>(),
+            Err(ParseAlertError::MissingSymbol)
+        );
+    }
+
+    #[test]
+    fn evaluates_comparison_at_last_bar() {
+        assert!(condition("close > 1").evaluate(&bars(&[0.0, 2.0])));
+        assert!(!condition("close > 1").evaluate(&bars(&[2.0, 0.0])));
+    }
+
+    #[test]
+    fn evaluates_crossing() {
+        assert!(condition("close crosses above 1").evaluate(&bars(&[0.0, 2.0])));
+        assert!(!condition("close crosses above 1").evaluate(&bars(&[2.0, 3.0])));
+        assert!(condition("close crosses below 1").evaluate(&bars(&[2.0, 0.0])));
+        assert!(condition("close crosses 1").evaluate(&bars(&[2.0, 0.0])));
+    }
+
+    #[test]
+    fn evaluates_short_series_as_false() {
+        for s in &[
+            "close > 1",
+            "close crosses 1",
+            "close crosses above 1",
+            "close crosses below 1",
+            "1 crosses above close",
+            "SMA(3) > 1",
+            "close crosses above SMA(3)",
+        ] {
+            assert!(!condition(s).evaluate(&[]), "{} on no bars", s);
+        }
+        for s in &[
+            "close crosses 0",
+            "close crosses above 0",
+            "close crosses below 2",
+            "SMA(2) > 0",
+            "close crosses above SMA(1)",
+        ] {
+            assert!(!condition(s).evaluate(&bars(&[1.0])), "{} on one bar", s);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+/// A mark on the chart of a stock at a point in time, e.g. an ex-dividend date, a split or a news
+/// headline. Annotations are ordered by time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Annotation {
+    /// Line shown in the tooltip of the bar at the time of the annotation.
+    pub detail: String,
+    pub kind: AnnotationKind,
+    /// Short text of the marker drawn on the chart.
+    pub label: String,
+    pub time: DateTime<Utc>,
+}
+
+impl Annotation {
+    pub fn dividend(time: DateTime<Utc>, amount: f64) -> Self {
+        Self {
+            detail: format!("Dividend {:>9.4}", amount),
+            kind: AnnotationKind::Dividend,
+            label: "D".to_owned(),
+            time,
+        }
+    }
+
+    pub fn news(time: DateTime<Utc>, headline: &str) -> Self {
+        Self {
+            detail: headline.to_owned(),
+            kind: AnnotationKind::News,
+            label: "N".to_owned(),
+            time,
+        }
+    }
+
+    /// An annotation emitted by a hook of the user script.
+    pub fn script(time: DateTime<Utc>, label: &str, detail: &str) -> Self {
+        Self {
+            detail: detail.to_owned(),
+            kind: AnnotationKind::Script,
+            label: label.to_owned(),
+            time,
+        }
+    }
+
+    pub fn split(time: DateTime<Utc>, numerator: f64, denominator: f64) -> Self {
+        let ratio = format!("{}:{}", numerator, denominator);
+        Self {
+            detail: format!("Split {:>12}", ratio),
+            kind: AnnotationKind::Split,
+            label: ratio,
+            time,
+        }
+    }
+}
+
+impl Ord for Annotation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.kind, &self.label, &self.detail).cmp(&(
+            other.time,
+            other.kind,
+            &other.label,
+            &other.detail,
+        ))
+    }
+}
+
+impl PartialOrd for Annotation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum AnnotationKind {
+    Dividend,
+    News,
+    Script,
+    Split,
+}
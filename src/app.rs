@@ -1,80 +1,492 @@
 use crate::{
-    event::ChartEvent,
-    reactive::StreamExt,
-    stock::Stock,
-    widgets::{SelectMenuState, TextFieldState},
+    alert::{Alert, AlertStatus, ParseAlertError},
+    config::{PointAndFigureConfig, SymbolAliases},
+    depth::OrderBook,
+    event::{ChartEvent, HistoryEvent, PaneEvent},
+    market::MarketSession,
+    paper::{Order, ParseOrderError, Portfolio},
+    plugin::PluginPanel,
+    reactive::{EventTopic, StreamExt},
+    series::CustomSeries,
+    stock::{self, ConnectionStatus, DownloadProgress, MarketQuote, Stock, StockSymbolError},
+    widgets::{MultiSelectMenuState, PopupState, SelectMenuState, SortOrder, TextFieldState},
 };
-use chrono::{DateTime, Datelike, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
 use derivative::Derivative;
 use derive_more::{Display, From, Into};
 use derive_new::new;
+use im::{HashMap, OrdSet};
+use itertools::Itertools;
 use math::round;
 use once_cell::sync::Lazy;
-use reactive_rs::{Broadcast, Stream};
-use regex::Regex;
+use reactive_rs::Stream;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
 use shrinkwraprs::Shrinkwrap;
 use std::{
-    cell::RefCell, fmt, marker::PhantomData, num::ParseIntError, ops::Range, rc::Rc, str::FromStr,
+    cell::RefCell, cmp, collections::VecDeque, convert::TryFrom, fmt, marker::PhantomData,
+    num::ParseIntError, ops::Range, rc::Rc, str::FromStr,
 };
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use thiserror::Error;
 use tui::layout::Rect;
-use typenum::{Unsigned, U2, U20, U50};
-use yahoo_finance::Interval;
+use typenum::{Unsigned, U12, U14, U2, U20, U26, U50, U9};
+use yahoo_finance::{Bar, Timestamped};
 
 #[derive(Clone, Debug)]
 pub struct App<'r> {
-    pub stock: Stock,
-    pub ui_state: UiState<'r>,
+    /// Alerts in the alerts store, with their current status.
+    pub alerts: Vec<(Alert, AlertStatus)>,
+    /// Bars of the benchmark which the beta of the current stock is computed against.
+    pub benchmark_bar_set: Option<(String, OrdSet<Bar>)>,
+    /// Bars of the stocks compared with the current one, if any.
+    pub comparison_bar_sets: Rc<Vec<(String, OrdSet<Bar>)>>,
+    pub connection_status: ConnectionStatus,
+    /// Series imported from CSV files, which are overlaid on the chart.
+    pub custom_series: Rc<Vec<CustomSeries>>,
+    /// Daily bars of the past 2 years of the stocks in the performance screen.
+    pub daily_bars: HashMap<String, Vec<Bar>>,
+    /// Order book of the current stock, if it is a crypto symbol shown in the depth screen.
+    pub order_book: Rc<Option<OrderBook>>,
+    /// Progress of the historical prices download in progress, if any.
+    pub download_progress: Option<DownloadProgress>,
+    /// Symbols of the indices shown in the markets screen.
+    pub market_indices: Rc<Vec<String>>,
+    /// Panels rendered by the plugins for the current stock, while the plugins screen is shown.
+    pub plugin_panels: Rc<Vec<PluginPanel>>,
+    /// The paper trading portfolio.
+    pub portfolio: Rc<Portfolio>,
+    /// Most recent closing prices of the stocks seen so far.
+    pub recent_closes: HashMap<String, Vec<f64>>,
+    /// Quotes which passed the filters of the stock screener.
+    pub screener_quotes: Rc<Vec<MarketQuote>>,
+    pub stock: Rc<Stock>,
+    /// Symbols trending in the configured region, most active first.
+    pub trending_stock_symbols: Rc<Vec<String>>,
+    pub ui_state: Rc<UiState<'r>>,
+    /// Symbols of the watchlist shown in the watchlist pane.
+    pub watchlist: Rc<Vec<String>>,
+    pub watchlist_name: String,
 }
 
-type DateRange = Range<DateTime<Utc>>;
+impl<'r> App<'r> {
+    /// Returns the symbols in the watchlist, with the favorites first.
+    pub fn watchlist_symbols(&self) -> Vec<&String> {
+        to_watchlist_symbols(&self.ui_state.favorite_stock_symbols, &self.watchlist)
+    }
+
+    /// Returns the command line which launches the app with the view as it is shown, e.g. to share
+    /// it. It starts fresh, so that nothing is filled in from the workspace of the last session.
+    pub fn command_line(&self) -> String {
+        let ui_state = &self.ui_state;
+
+        let mut args = vec![
+            "stocker".to_owned(),
+            "--fresh".to_owned(),
+            "-s".to_owned(),
+            self.stock.symbol.clone(),
+            "-t".to_owned(),
+            ui_state.time_frame.to_string(),
+            "-b".to_owned(),
+            ui_state.bar_size.to_string(),
+            "--chart".to_owned(),
+            ui_state.chart_type.literal().to_owned(),
+            "--layout".to_owned(),
+            ui_state.layout_preset.literal().to_owned(),
+        ];
+        for indicator in &ui_state.indicators {
+            args.push("-i".to_owned());
+            args.push(indicator.to_string());
+        }
+        if let Some(date_range) = &ui_state.date_range {
+            // The end date of the range is included on the command line
+            args.push("--range".to_owned());
+            args.push(format!(
+                "{}..{}",
+                date_range.start.format("%Y-%m-%d"),
+                (date_range.end - Duration::seconds(1)).format("%Y-%m-%d")
+            ));
+        }
+        let switches = [
+            ("--compressed-time-axis", ui_state.compressed_time_axis),
+            ("--news-markers", ui_state.news_markers),
+            ("--summary", ui_state.summary_view),
+            ("--volume-profile", ui_state.volume_profile),
+        ];
+        args.extend(
+            switches
+                .iter()
+                .filter(|(_, on)| *on)
+                .map(|(switch, _)| (*switch).to_owned()),
+        );
+
+        args.iter().map(|arg| quote_shell_arg(arg)).join(" ")
+    }
+}
+
+/// Quotes the argument for POSIX shells, unless it is safe as it is.
+fn quote_shell_arg(arg: &str) -> String {
+    const SAFE_CHARS: &str = "+,-./:=@_";
+
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || SAFE_CHARS.contains(c))
+    {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Returns the symbols in the watchlist, with the favorites first.
+pub fn to_watchlist_symbols<'a>(
+    favorite_stock_symbols: &'a [String],
+    watchlist: &'a [String],
+) -> Vec<&'a String> {
+    favorite_stock_symbols
+        .iter()
+        .chain(
+            watchlist
+                .iter()
+                .filter(|symbol| !favorite_stock_symbols.contains(symbol)),
+        )
+        .collect()
+}
+
+pub type DateRange = Range<DateTime<Utc>>;
+
+/// Date range given as `START..END`, e.g. `2020-01-01..2020-12-31`, where the end date is
+/// included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DateRangeSpec(pub DateRange);
+
+impl FromStr for DateRangeSpec {
+    type Err = ParseDateRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseDateRangeError::Empty);
+        }
+
+        let (start_date, end_date) = s
+            .splitn(2, "..")
+            .map(|date| {
+                let date = date.trim();
+                NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|err| {
+                    ParseDateRangeError::ParseDate {
+                        source: err,
+                        value: date.to_owned(),
+                    }
+                })
+            })
+            .collect_tuple()
+            .ok_or(ParseDateRangeError::MissingSeparator)?;
+        let start_date = Utc.from_utc_date(&start_date?).and_hms(0, 0, 0);
+        let end_date = Utc.from_utc_date(&end_date?).and_hms(0, 0, 0) + Duration::days(1);
+        if end_date <= start_date {
+            return Err(ParseDateRangeError::Reversed);
+        }
+
+        Ok(Self(start_date..end_date))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseDateRangeError {
+    #[error("cannot parse date range from empty string")]
+    Empty,
+    #[error("missing .. between the start and end dates")]
+    MissingSeparator,
+    #[error("invalid date: {}", .value)]
+    ParseDate {
+        source: chrono::ParseError,
+        value: String,
+    },
+    #[error("end date is before the start date")]
+    Reversed,
+}
 
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct UiState<'r> {
+    pub alert_field_error: Option<ParseAlertError>,
+    pub alert_field_state: Rc<RefCell<TextFieldState>>,
+    /// Index of the selected alert in the alerts screen, if any.
+    pub alert_selection: Option<usize>,
+    pub bar_size: BarSize,
+    pub chart_type: ChartType,
+    pub company_profile_popup_state: Rc<RefCell<PopupState>>,
+    pub compare_menu_state: Rc<RefCell<MultiSelectMenuState<String>>>,
+    pub compressed_time_axis: bool,
+    /// Position of the mouse, which puts the crosshair on the bar under it when over the chart.
+    pub crosshair_point: Option<(u16, u16)>,
     pub date_range: Option<DateRange>,
     pub debug_draw: bool,
+    pub event_inspector: bool,
+    pub event_log: Rc<RefCell<EventLog>>,
+    pub favorite_stock_symbols: Rc<Vec<String>>,
+    pub financial_period: FinancialPeriod,
+    pub financial_statement: FinancialStatementKind,
+    /// Pane or control which the keyboard focus is on, on the chart screen.
+    pub focus: Focus,
     pub frame_rate_counter: Rc<RefCell<FrameRateCounter>>,
-    pub indicator: Option<Indicator>,
-    pub indicator_menu_state: Rc<RefCell<SelectMenuState<Indicator>>>,
+    pub full_screen: bool,
+    /// Position of the mouse, which highlights the interactive element under it.
+    pub hover_point: Option<(u16, u16)>,
+    pub indicator_menu_state: Rc<RefCell<MultiSelectMenuState<Indicator>>>,
+    pub indicators: Vec<Indicator>,
+    pub layout_field_state: Rc<RefCell<TextFieldState>>,
+    pub layout_preset: LayoutPreset,
+    /// API key of the data provider with all but its last characters masked, if one is set.
+    pub masked_api_key: Option<String>,
+    pub news_markers: bool,
+    /// Index of the selected news item, if any.
+    pub news_selection: Option<usize>,
+    pub note_field_state: Rc<RefCell<TextFieldState>>,
+    pub order_field_error: Option<ParseOrderError>,
+    pub order_field_state: Rc<RefCell<TextFieldState>>,
+    pub pane_sizes: PaneSizes,
+    pub point_and_figure: PointAndFigureConfig,
+    pub recent_stock_symbol_menu_state: Rc<RefCell<SelectMenuState<String>>>,
+    pub screen: Screen,
+    /// Column and order the screener results are sorted by, if any.
+    pub screener_sort: Option<(usize, SortOrder)>,
+    pub series_field_state: Rc<RefCell<TextFieldState>>,
+    pub stock_symbol_field_error: Option<StockSymbolError>,
     pub stock_symbol_field_state: Rc<RefCell<TextFieldState>>,
+    pub summary_view: bool,
+    /// Aliases of the symbols, which are shown next to them.
+    pub symbol_aliases: Rc<SymbolAliases>,
+    /// Interval between redraws of the screen, in milliseconds.
+    pub tick_rate: u64,
     pub time_frame: TimeFrame,
     pub time_frame_menu_state: Rc<RefCell<SelectMenuState<TimeFrame>>>,
+    pub toast: Option<Toast>,
+    /// Index of the selected row in the trending screen, if any.
+    pub trending_selection: Option<usize>,
+    pub volume_profile: bool,
+    pub watchlist_import_field_state: Rc<RefCell<TextFieldState>>,
+    pub watchlist_menu_state: Rc<RefCell<SelectMenuState<String>>>,
+    /// Index of the selected row in the watchlist, if any.
+    pub watchlist_selection: Option<usize>,
     #[derivative(Debug = "ignore")]
-    pub ui_target_areas: Broadcast<'r, (), (UiTarget, Option<Rect>)>,
+    pub ui_target_areas: EventTopic<'r, (UiTarget, Option<Rect>)>,
 }
 
 impl<'r> Default for UiState<'r> {
     fn default() -> Self {
         Self {
-            date_range: TimeFrame::default().now_date_range(),
+            alert_field_error: None,
+            alert_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            alert_selection: None,
+            bar_size: BarSize::default(),
+            chart_type: ChartType::default(),
+            company_profile_popup_state: Rc::new(RefCell::new(PopupState::default())),
+            compare_menu_state: Rc::new(RefCell::new(MultiSelectMenuState::default())),
+            compressed_time_axis: false,
+            crosshair_point: None,
+            date_range: TimeFrame::default().now_date_range(&MarketSession::default()),
             debug_draw: false,
+            event_inspector: false,
+            event_log: Rc::new(RefCell::new(EventLog::default())),
+            favorite_stock_symbols: Rc::new(vec![]),
+            financial_period: FinancialPeriod::default(),
+            financial_statement: FinancialStatementKind::default(),
+            focus: Focus::default(),
             frame_rate_counter: Rc::new(RefCell::new(FrameRateCounter::new(
                 Duration::milliseconds(1_000),
             ))),
-            indicator: None,
-            indicator_menu_state: Rc::new(RefCell::new({
-                let mut menu_state = SelectMenuState::new(Indicator::iter());
-                menu_state.allow_empty_selection = true;
-                menu_state.select(None).unwrap();
-                menu_state
-            })),
+            full_screen: false,
+            hover_point: None,
+            indicator_menu_state: Rc::new(RefCell::new(MultiSelectMenuState::new(
+                Indicator::iter(),
+            ))),
+            indicators: vec![],
+            layout_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            layout_preset: LayoutPreset::default(),
+            masked_api_key: None,
+            news_markers: false,
+            news_selection: None,
+            note_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            order_field_error: None,
+            order_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            pane_sizes: PaneSizes::default(),
+            point_and_figure: PointAndFigureConfig::default(),
+            recent_stock_symbol_menu_state: Rc::new(RefCell::new(SelectMenuState::default())),
+            screen: Screen::default(),
+            screener_sort: None,
+            series_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            stock_symbol_field_error: None,
             stock_symbol_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            summary_view: false,
+            symbol_aliases: Rc::new(SymbolAliases::default()),
+            tick_rate: crate::DEFAULT_TICK_RATE,
             time_frame: TimeFrame::default(),
             time_frame_menu_state: Rc::new(RefCell::new({
                 let mut menu_state = SelectMenuState::new(TimeFrame::iter());
                 menu_state.select(Some(TimeFrame::default())).unwrap();
                 menu_state
             })),
-            ui_target_areas: Broadcast::new(),
+            toast: None,
+            trending_selection: None,
+            volume_profile: false,
+            watchlist_import_field_state: Rc::new(RefCell::new(TextFieldState::default())),
+            watchlist_menu_state: Rc::new(RefCell::new(SelectMenuState::default())),
+            watchlist_selection: None,
+            ui_target_areas: EventTopic::new(),
         }
     }
 }
 
-pub fn to_date_ranges<'a, S, U, R, C>(
+impl<'r> UiState<'r> {
+    /// Returns the point of the cursor in the active text field, if any, given the areas which the
+    /// text fields were last drawn in.
+    pub fn cursor_point(&self, text_field_areas: &HashMap<UiTarget, Rect>) -> Option<(u16, u16)> {
+        [
+            (UiTarget::StockSymbolField, &self.stock_symbol_field_state),
+            (UiTarget::AlertField, &self.alert_field_state),
+            (UiTarget::OrderField, &self.order_field_state),
+            (UiTarget::NoteField, &self.note_field_state),
+            (UiTarget::SeriesField, &self.series_field_state),
+            (UiTarget::LayoutField, &self.layout_field_state),
+            (
+                UiTarget::WatchlistImportField,
+                &self.watchlist_import_field_state,
+            ),
+        ]
+        .iter()
+        .find_map(|(ui_target, text_field_state)| {
+            let area = text_field_areas.get(ui_target)?;
+            text_field_state.borrow().cursor_point(*area)
+        })
+    }
+}
+
+/// Change to one of the fields of the UI state. The state is updated one field at a time, so that
+/// the other fields are neither cloned nor rewrapped on every change.
+#[derive(Clone, Debug)]
+pub enum UiStateUpdate {
+    AlertFieldState(TextFieldState),
+    AlertSelection(Option<usize>),
+    CompanyProfilePopupState(PopupState),
+    CompareMenuState(MultiSelectMenuState<String>),
+    CrosshairPoint(Option<(u16, u16)>),
+    DateRange(Option<DateRange>),
+    HoverPoint(Option<(u16, u16)>),
+    IndicatorMenuState(MultiSelectMenuState<Indicator>),
+    Indicators(Vec<Indicator>),
+    LayoutFieldState(TextFieldState),
+    NewsSelection(Option<usize>),
+    NoteFieldState(TextFieldState),
+    OrderFieldState(TextFieldState),
+    RecentStockSymbolMenuState(SelectMenuState<String>),
+    Screen(Screen),
+    SeriesFieldState(TextFieldState),
+    StockSymbolFieldState(TextFieldState),
+    TimeFrame(TimeFrame),
+    TimeFrameMenuState(SelectMenuState<TimeFrame>),
+    Toast(Option<Toast>),
+    TrendingSelection(Option<usize>),
+    WatchlistImportFieldState(TextFieldState),
+    WatchlistMenuState(SelectMenuState<String>),
+    WatchlistSelection(Option<usize>),
+}
+
+impl UiStateUpdate {
+    /// Sets the field of the UI state to the new value.
+    pub fn apply(&self, ui_state: &mut UiState) {
+        match self {
+            Self::AlertFieldState(alert_field_state) => {
+                ui_state.alert_field_error =
+                    parse_field_value::<Alert>(&alert_field_state.value).err();
+                ui_state.alert_field_state = Rc::new(RefCell::new(alert_field_state.clone()))
+            }
+            Self::AlertSelection(alert_selection) => ui_state.alert_selection = *alert_selection,
+            Self::CompanyProfilePopupState(company_profile_popup_state) => {
+                ui_state.company_profile_popup_state =
+                    Rc::new(RefCell::new(*company_profile_popup_state))
+            }
+            Self::CompareMenuState(compare_menu_state) => {
+                ui_state.compare_menu_state = Rc::new(RefCell::new(compare_menu_state.clone()))
+            }
+            Self::CrosshairPoint(crosshair_point) => ui_state.crosshair_point = *crosshair_point,
+            Self::DateRange(date_range) => ui_state.date_range = date_range.clone(),
+            Self::HoverPoint(hover_point) => ui_state.hover_point = *hover_point,
+            Self::IndicatorMenuState(indicator_menu_state) => {
+                ui_state.indicator_menu_state = Rc::new(RefCell::new(indicator_menu_state.clone()))
+            }
+            Self::Indicators(indicators) => ui_state.indicators = indicators.clone(),
+            Self::LayoutFieldState(layout_field_state) => {
+                ui_state.layout_field_state = Rc::new(RefCell::new(layout_field_state.clone()))
+            }
+            Self::NewsSelection(news_selection) => ui_state.news_selection = *news_selection,
+            Self::NoteFieldState(note_field_state) => {
+                ui_state.note_field_state = Rc::new(RefCell::new(note_field_state.clone()))
+            }
+            Self::OrderFieldState(order_field_state) => {
+                ui_state.order_field_error =
+                    parse_field_value::<Order>(&order_field_state.value).err();
+                ui_state.order_field_state = Rc::new(RefCell::new(order_field_state.clone()))
+            }
+            Self::RecentStockSymbolMenuState(recent_stock_symbol_menu_state) => {
+                ui_state.recent_stock_symbol_menu_state =
+                    Rc::new(RefCell::new(recent_stock_symbol_menu_state.clone()))
+            }
+            Self::Screen(screen) => ui_state.screen = *screen,
+            Self::SeriesFieldState(series_field_state) => {
+                ui_state.series_field_state = Rc::new(RefCell::new(series_field_state.clone()))
+            }
+            Self::StockSymbolFieldState(stock_symbol_field_state) => {
+                ui_state.stock_symbol_field_error = stock::validate_stock_symbol(
+                    &ui_state
+                        .symbol_aliases
+                        .resolve(stock_symbol_field_state.value.trim()),
+                )
+                .err();
+                ui_state.stock_symbol_field_state =
+                    Rc::new(RefCell::new(stock_symbol_field_state.clone()))
+            }
+            Self::TimeFrame(time_frame) => ui_state.time_frame = *time_frame,
+            Self::TimeFrameMenuState(time_frame_menu_state) => {
+                ui_state.time_frame_menu_state =
+                    Rc::new(RefCell::new(time_frame_menu_state.clone()))
+            }
+            Self::Toast(toast) => ui_state.toast = toast.clone(),
+            Self::TrendingSelection(trending_selection) => {
+                ui_state.trending_selection = *trending_selection
+            }
+            Self::WatchlistImportFieldState(watchlist_import_field_state) => {
+                ui_state.watchlist_import_field_state =
+                    Rc::new(RefCell::new(watchlist_import_field_state.clone()))
+            }
+            Self::WatchlistMenuState(watchlist_menu_state) => {
+                ui_state.watchlist_menu_state = Rc::new(RefCell::new(watchlist_menu_state.clone()))
+            }
+            Self::WatchlistSelection(watchlist_selection) => {
+                ui_state.watchlist_selection = *watchlist_selection
+            }
+        }
+    }
+}
+
+/// Parses the value of a text field, which is not an error while the field is empty.
+fn parse_field_value<T>(value: &str) -> Result<Option<T>, T::Err>
+where
+    T: FromStr,
+{
+    let value = value.trim();
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        value.parse().map(Some)
+    }
+}
+
+pub fn to_date_ranges<'a, S, V, U, R, C>(
     chart_events: S,
+    restored_date_ranges: V,
     stock_symbols: U,
     init_stock_symbol: String,
     time_frames: R,
@@ -82,26 +494,34 @@ pub fn to_date_ranges<'a, S, U, R, C>(
 ) -> impl Stream<'a, Item = Option<DateRange>, Context = C>
 where
     S: Stream<'a, Item = ChartEvent, Context = C>,
+    V: Stream<'a, Item = Option<DateRange>, Context = C>,
     U: Stream<'a, Item = String>,
     R: Stream<'a, Item = TimeFrame>,
-    C: 'a + Clone,
+    C: 'a + Clone + Default,
 {
     chart_events
+        .map(|ev| DateRangeInput::Chart(*ev))
+        .merge(restored_date_ranges.map(|date_range| DateRangeInput::Restore(date_range.clone())))
+        // The date range starts out reset for the stock and the time frame, until it is changed
+        .start_with(DateRangeInput::Chart(ChartEvent::Reset))
         .combine_latest(
             stock_symbols.distinct_until_changed(),
-            |(ev, stock_symbol)| (*ev, stock_symbol.clone()),
+            |(input, stock_symbol)| (input.clone(), stock_symbol.clone()),
         )
         .combine_latest(
             time_frames.distinct_until_changed(),
-            |((ev, stock_symbol), time_frame)| (*ev, stock_symbol.clone(), *time_frame),
+            |((input, stock_symbol), time_frame)| {
+                (input.clone(), stock_symbol.clone(), *time_frame)
+            },
         )
         .fold(
             (
-                init_time_frame.now_date_range(),
+                init_time_frame.now_date_range(&MarketSession::default()),
                 init_stock_symbol,
                 init_time_frame,
             ),
-            |(acc_date_range, acc_stock_symbol, acc_time_frame), (ev, stock_symbol, time_frame)| {
+            |(acc_date_range, acc_stock_symbol, acc_time_frame),
+             (input, stock_symbol, time_frame)| {
                 let noop = || {
                     (
                         acc_date_range.clone(),
@@ -109,9 +529,10 @@ where
                         *acc_time_frame,
                     )
                 };
+                let market_session = stock::market_session(stock_symbol);
                 let reset = || {
                     (
-                        time_frame.now_date_range(),
+                        time_frame.now_date_range(&market_session),
                         stock_symbol.clone(),
                         *time_frame,
                     )
@@ -123,37 +544,35 @@ where
                     return reset();
                 }
 
+                let ev = match input {
+                    DateRangeInput::Chart(ev) => ev,
+                    DateRangeInput::Restore(date_range) => {
+                        return (date_range.clone(), stock_symbol.clone(), *time_frame);
+                    }
+                };
+
                 match ev {
                     ChartEvent::PanBackward if time_frame != &TimeFrame::YearToDate => {
-                        let date_range = time_frame.duration().map(|duration| {
-                            acc_date_range
-                                .as_ref()
-                                .map(|acc_date_range| {
-                                    let end_date = acc_date_range.start;
-                                    (end_date - duration)..end_date
-                                })
-                                .unwrap()
+                        let date_range = acc_date_range.as_ref().and_then(|acc_date_range| {
+                            time_frame.date_range_ending(acc_date_range.start, &market_session)
                         });
                         (date_range, stock_symbol.clone(), *time_frame)
                     }
                     ChartEvent::PanForward if time_frame != &TimeFrame::YearToDate => {
-                        let date_range = time_frame.duration().map(|duration| {
-                            acc_date_range
-                                .as_ref()
-                                .map(|acc_date_range| {
-                                    let start_date = acc_date_range.end;
-                                    start_date..(start_date + duration)
-                                })
-                                .map(|date_range| {
-                                    let max_date_range = time_frame.now_date_range().unwrap();
-                                    if date_range.end > max_date_range.end {
-                                        max_date_range
-                                    } else {
-                                        date_range
-                                    }
-                                })
-                                .unwrap()
-                        });
+                        let date_range = acc_date_range
+                            .as_ref()
+                            .and_then(|acc_date_range| {
+                                time_frame.date_range_starting(acc_date_range.end, &market_session)
+                            })
+                            .map(|date_range| {
+                                let max_date_range =
+                                    time_frame.now_date_range(&market_session).unwrap();
+                                if date_range.end > max_date_range.end {
+                                    max_date_range
+                                } else {
+                                    date_range
+                                }
+                            });
                         (date_range, stock_symbol.clone(), *time_frame)
                     }
                     ChartEvent::Reset => reset(),
@@ -165,31 +584,506 @@ where
         .distinct_until_changed()
 }
 
+/// Input of the date range, which is either a chart event or a date range to go back to.
+#[derive(Clone, Debug)]
+enum DateRangeInput {
+    Chart(ChartEvent),
+    Restore(Option<DateRange>),
+}
+
+/// Symbol which has been viewed, with the time frame and the date range it was last viewed at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolHistoryEntry {
+    pub date_range: Option<DateRange>,
+    pub stock_symbol: String,
+    pub time_frame: TimeFrame,
+}
+
+const MAX_SYMBOL_HISTORY_LEN: usize = 100;
+
+/// Sequence of the viewed symbols, which can be navigated back and forward like in a browser.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolHistory {
+    entries: Vec<SymbolHistoryEntry>,
+    index: usize,
+}
+
+impl SymbolHistory {
+    /// Records the view of the symbol. Viewing another symbol than the current one drops the
+    /// entries after the current one, and the oldest entry once there are too many.
+    pub fn view(
+        &mut self,
+        stock_symbol: &str,
+        time_frame: TimeFrame,
+        date_range: Option<DateRange>,
+    ) {
+        match self.entries.get_mut(self.index) {
+            Some(entry) if entry.stock_symbol == stock_symbol => {
+                entry.date_range = date_range;
+                entry.time_frame = time_frame;
+            }
+            _ => {
+                self.entries.truncate(self.index + 1);
+                self.entries.push(SymbolHistoryEntry {
+                    date_range,
+                    stock_symbol: stock_symbol.to_owned(),
+                    time_frame,
+                });
+                if self.entries.len() > MAX_SYMBOL_HISTORY_LEN {
+                    self.entries.remove(0);
+                }
+                self.index = self.entries.len() - 1;
+            }
+        }
+    }
+
+    /// Moves to the entry before the current one, if any, and returns it.
+    pub fn back(&mut self) -> Option<&SymbolHistoryEntry> {
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+
+        self.entries.get(self.index)
+    }
+
+    /// Moves to the entry after the current one, if any, and returns it.
+    pub fn forward(&mut self) -> Option<&SymbolHistoryEntry> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+
+        self.entries.get(self.index)
+    }
+}
+
+/// Input of the symbol history, which is either a history event or the symbol which is viewed.
+#[derive(Clone, Debug)]
+enum SymbolHistoryInput {
+    Navigate(HistoryEvent),
+    View(String, TimeFrame, Option<DateRange>),
+}
+
+/// Maps the history events to the entries of the symbol history to go back or forward to, as the
+/// stock symbols, time frames and date ranges are viewed.
+pub fn to_symbol_history_navigations<'a, S, U, R, D, C>(
+    history_events: S,
+    stock_symbols: U,
+    time_frames: R,
+    date_ranges: D,
+) -> impl Stream<'a, Item = SymbolHistoryEntry, Context = C>
+where
+    S: Stream<'a, Item = HistoryEvent, Context = C>,
+    U: Stream<'a, Item = String, Context = C>,
+    R: Stream<'a, Item = TimeFrame>,
+    D: Stream<'a, Item = Option<DateRange>>,
+    C: 'a + Clone,
+{
+    history_events
+        .map(|ev| SymbolHistoryInput::Navigate(*ev))
+        .merge(
+            stock_symbols
+                .combine_latest(time_frames, |(stock_symbol, time_frame)| {
+                    (stock_symbol.clone(), *time_frame)
+                })
+                .combine_latest(date_ranges, |((stock_symbol, time_frame), date_range)| {
+                    SymbolHistoryInput::View(stock_symbol.clone(), *time_frame, date_range.clone())
+                }),
+        )
+        .fold(
+            (SymbolHistory::default(), None),
+            |(acc_symbol_history, _), input| {
+                let mut symbol_history = acc_symbol_history.clone();
+                let navigation = match input {
+                    SymbolHistoryInput::Navigate(HistoryEvent::Back) => {
+                        symbol_history.back().cloned()
+                    }
+                    SymbolHistoryInput::Navigate(HistoryEvent::Forward) => {
+                        symbol_history.forward().cloned()
+                    }
+                    SymbolHistoryInput::View(stock_symbol, time_frame, date_range) => {
+                        symbol_history.view(stock_symbol, *time_frame, date_range.clone());
+                        None
+                    }
+                };
+
+                (symbol_history, navigation)
+            },
+        )
+        .filter_map(|(_, navigation)| navigation.clone())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Pane {
+    AnalystRatings,
+    Chart,
+    EsgScores,
+    Fundamentals,
+    Heatmap,
+    KeyRatios,
+    News,
+    Watchlist,
+}
+
+impl Pane {
+    /// Returns whether the pane can be focused, which routes the navigation keys to it.
+    pub fn is_focusable(self) -> bool {
+        matches!(self, Self::Chart | Self::News | Self::Watchlist)
+    }
+
+    /// Returns the target which the navigation keys are grouped into while the pane is focused,
+    /// or `None` for the chart, which takes the keys which are not grouped into any target.
+    pub fn ui_target(self) -> Option<UiTarget> {
+        match self {
+            Self::News => Some(UiTarget::NewsList),
+            Self::Watchlist => Some(UiTarget::WatchlistTable),
+            _ => None,
+        }
+    }
+}
+
+/// Controls in the header and footer which can be focused, in the order which the focus cycles
+/// through them after the panes.
+const FOCUSABLE_CONTROLS: [UiTarget; 4] = [
+    UiTarget::StockSymbolButton,
+    UiTarget::StockNameButton,
+    UiTarget::IndicatorBox,
+    UiTarget::TimeFrameBox,
+];
+
+/// Element which the keyboard focus is on, cycled through with Tab and Shift+Tab.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Focus {
+    /// The navigation keys are routed to the pane.
+    Pane(Pane),
+    /// Enter opens the overlay associated with the control, like clicking it.
+    Control(UiTarget),
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self::Pane(Pane::Chart)
+    }
+}
+
+impl Focus {
+    /// Returns the pane which is focused, if any.
+    pub fn pane(self) -> Option<Pane> {
+        match self {
+            Self::Pane(pane) => Some(pane),
+            Self::Control(_) => None,
+        }
+    }
+
+    /// Returns the control which is focused, if any.
+    pub fn control(self) -> Option<UiTarget> {
+        match self {
+            Self::Pane(_) => None,
+            Self::Control(ui_target) => Some(ui_target),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, EnumIter, Eq, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutPreset {
+    #[derivative(Default)]
+    ChartOnly,
+    ChartWatchlist,
+    ChartNewsFundamentals,
+    ChartHeatmap,
+}
+
+impl LayoutPreset {
+    /// Returns the literal which the layout preset is parsed from.
+    pub fn literal(self) -> &'static str {
+        match self {
+            Self::ChartOnly => "chart-only",
+            Self::ChartWatchlist => "chart-watchlist",
+            Self::ChartNewsFundamentals => "chart-news-fundamentals",
+            Self::ChartHeatmap => "chart-heatmap",
+        }
+    }
+
+    pub fn panes(self) -> &'static [Pane] {
+        match self {
+            Self::ChartOnly => &[Pane::Chart],
+            Self::ChartWatchlist => &[Pane::Chart, Pane::Watchlist],
+            Self::ChartNewsFundamentals => &[
+                Pane::Chart,
+                Pane::News,
+                Pane::Fundamentals,
+                Pane::KeyRatios,
+                Pane::EsgScores,
+                Pane::AnalystRatings,
+            ],
+            Self::ChartHeatmap => &[Pane::Chart, Pane::Heatmap],
+        }
+    }
+
+    pub fn has_pane(self, pane: Pane) -> bool {
+        self.panes().contains(&pane)
+    }
+
+    /// Returns the panes which can be focused, in the order which the focus cycles through them.
+    pub fn focusable_panes(self) -> Vec<Pane> {
+        self.panes()
+            .iter()
+            .copied()
+            .filter(|pane| pane.is_focusable())
+            .collect()
+    }
+
+    /// Returns the focusable panes followed by the focusable controls, in the order which the
+    /// focus cycles through them.
+    pub fn focus_order(self) -> Vec<Focus> {
+        self.focusable_panes()
+            .into_iter()
+            .map(Focus::Pane)
+            .chain(FOCUSABLE_CONTROLS.iter().copied().map(Focus::Control))
+            .collect()
+    }
+
+    /// Returns the focus after (or before, if reversed) the focus, wrapping around. The focus goes
+    /// back to the chart from a pane which is not in the layout.
+    pub fn cycle_focus(self, focus: Focus, reverse: bool) -> Focus {
+        let focus_order = self.focus_order();
+        let l = focus_order.len();
+
+        match focus_order.iter().position(|&f| f == focus) {
+            Some(n) if reverse => focus_order[(n + l - 1) % l],
+            Some(n) => focus_order[(n + 1) % l],
+            None => Focus::default(),
+        }
+    }
+
+    /// Returns the preset after this one, wrapping around to the first.
+    pub fn next(self) -> Self {
+        let layout_presets: Vec<_> = Self::iter().collect();
+        let n = layout_presets
+            .iter()
+            .position(|&layout_preset| layout_preset == self)
+            .unwrap();
+
+        layout_presets[(n + 1) % layout_presets.len()]
+    }
+}
+
+impl FromStr for LayoutPreset {
+    type Err = ParseLayoutPresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chart-only" => Ok(Self::ChartOnly),
+            "chart-watchlist" => Ok(Self::ChartWatchlist),
+            "chart-news-fundamentals" => Ok(Self::ChartNewsFundamentals),
+            "chart-heatmap" => Ok(Self::ChartHeatmap),
+            "" => Err(ParseLayoutPresetError::Empty),
+            _ => Err(ParseLayoutPresetError::Invalid),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseLayoutPresetError {
+    #[error("cannot parse layout preset from empty string")]
+    Empty,
+    #[error("invalid layout preset literal")]
+    Invalid,
+}
+
+impl fmt::Display for LayoutPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ChartOnly => write!(f, "chart-only"),
+            Self::ChartWatchlist => write!(f, "chart-watchlist"),
+            Self::ChartNewsFundamentals => write!(f, "chart-news-fundamentals"),
+            Self::ChartHeatmap => write!(f, "chart-heatmap"),
+        }
+    }
+}
+
+/// Screen shown in the body, in place of the panes of the layout preset.
+#[derive(Clone, Copy, Debug, Derivative, EnumIter, Eq, PartialEq)]
+#[derivative(Default)]
+pub enum Screen {
+    Alerts,
+    #[derivative(Default)]
+    Chart,
+    Depth,
+    Financials,
+    Journal,
+    Markets,
+    Performance,
+    Plugins,
+    Portfolio,
+    Screener,
+    Sectors,
+    Trending,
+}
+
+impl Screen {
+    /// Returns the screen after this one, wrapping around to the first.
+    pub fn next(self) -> Self {
+        Self::iter()
+            .cycle()
+            .skip_while(|&screen| screen != self)
+            .nth(1)
+            .unwrap()
+    }
+}
+
+/// A transient message shown on top of everything else.
+#[derive(Clone, Debug)]
+pub struct Toast {
+    /// Whether the toast flashes when shown, along with the header.
+    pub flash: bool,
+    pub message: String,
+    pub shown_at: DateTime<Utc>,
+}
+
+impl Toast {
+    const DURATION_SECS: i64 = 5;
+    const FLASH_DURATION_SECS: i64 = 3;
+    const FLASH_PERIOD_MILLIS: i64 = 500;
+
+    pub fn new(message: String) -> Self {
+        Self {
+            flash: false,
+            message,
+            shown_at: Utc::now(),
+        }
+    }
+
+    pub fn with_flash(self, flash: bool) -> Self {
+        Self { flash, ..self }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() - self.shown_at > Duration::seconds(Self::DURATION_SECS)
+    }
+
+    /// Returns whether the toast is currently highlighted. A flashing toast alternates between
+    /// highlighted and not for the first few seconds.
+    pub fn is_flashing(&self) -> bool {
+        let elapsed = Utc::now() - self.shown_at;
+
+        self.flash
+            && elapsed < Duration::seconds(Self::FLASH_DURATION_SECS)
+            && (elapsed.num_milliseconds() / Self::FLASH_PERIOD_MILLIS) % 2 == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PaneSizes {
+    /// Height of the bottom panes, as a percentage of the body height.
+    pub bottom: u16,
+    /// Width of the side panes, as a percentage of the body width.
+    pub side: u16,
+}
+
+impl PaneSizes {
+    const MAX_SIZE: u16 = 80;
+    const MIN_SIZE: u16 = 10;
+    const STEP: u16 = 5;
+
+    /// Clamps the sizes to the bounds allowed when resizing, e.g. after loading them from storage.
+    pub fn clamp(self) -> Self {
+        let clamp = |size: u16| cmp::min(cmp::max(size, Self::MIN_SIZE), Self::MAX_SIZE);
+
+        Self {
+            bottom: clamp(self.bottom),
+            side: clamp(self.side),
+        }
+    }
+
+    pub fn resize(self, ev: PaneEvent) -> Self {
+        let grow = |size: u16| cmp::min(size + Self::STEP, Self::MAX_SIZE);
+        let shrink = |size: u16| cmp::max(size.saturating_sub(Self::STEP), Self::MIN_SIZE);
+
+        match ev {
+            PaneEvent::GrowBottom => Self {
+                bottom: grow(self.bottom),
+                ..self
+            },
+            PaneEvent::GrowSide => Self {
+                side: grow(self.side),
+                ..self
+            },
+            PaneEvent::ShrinkBottom => Self {
+                bottom: shrink(self.bottom),
+                ..self
+            },
+            PaneEvent::ShrinkSide => Self {
+                side: shrink(self.side),
+                ..self
+            },
+        }
+    }
+}
+
+impl Default for PaneSizes {
+    fn default() -> Self {
+        Self {
+            bottom: 30,
+            side: 25,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum UiTarget {
+    AlertField,
+    AlertTable,
+    Chart,
+    CompanyProfilePopup,
+    CompareMenu,
+    FinancialsTable,
     IndicatorBox,
     IndicatorMenu,
+    LayoutField,
+    NewsList,
+    NoteField,
+    OrderField,
+    RecentStockSymbolMenu,
+    ScreenerTable,
+    SectorButton,
+    SeriesField,
     StockNameButton,
     StockSymbolButton,
     StockSymbolField,
     TimeFrameBox,
     TimeFrameMenu,
+    TrendingTable,
+    WatchlistImportField,
+    WatchlistMenu,
+    WatchlistTable,
 }
 
+const FRAME_TIME_HISTORY_LEN: usize = 200;
+
 #[derive(Debug)]
 pub struct FrameRateCounter {
     frame_time: u16,
+    frame_times: VecDeque<Duration>,
     frames: u16,
+    last_frame: DateTime<Utc>,
     last_interval: DateTime<Utc>,
     update_interval: Duration,
 }
 
 impl FrameRateCounter {
     pub fn new(update_interval: Duration) -> Self {
+        let now = Utc::now();
+
         Self {
             frame_time: 0,
+            frame_times: VecDeque::with_capacity(FRAME_TIME_HISTORY_LEN),
             frames: 0,
-            last_interval: Utc::now(),
+            last_frame: now,
+            last_interval: now,
             update_interval,
         }
     }
@@ -200,6 +1094,12 @@ impl FrameRateCounter {
 
         let now = Utc::now();
 
+        if self.frame_times.len() == FRAME_TIME_HISTORY_LEN {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(now - self.last_frame);
+        self.last_frame = now;
+
         if now >= self.last_interval + self.update_interval {
             let frame_time =
                 (now - self.last_interval).num_milliseconds() as f64 / self.frames as f64;
@@ -222,14 +1122,82 @@ impl FrameRateCounter {
             frame_time => Some(Duration::milliseconds(frame_time as i64)),
         }
     }
+
+    /// Returns the times of the recent frames, oldest first.
+    pub fn frame_times(&self) -> &VecDeque<Duration> {
+        &self.frame_times
+    }
+
+    pub fn frame_time_stats(&self) -> Option<FrameTimeStats> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+
+        let mut frame_times: Vec<_> = self.frame_times.iter().copied().collect();
+        frame_times.sort();
+
+        let total = frame_times
+            .iter()
+            .fold(Duration::zero(), |acc, frame_time| acc + *frame_time);
+        let p99_index = ((frame_times.len() as f64 * 0.99).ceil() as usize).saturating_sub(1);
+
+        Some(FrameTimeStats {
+            avg: total / frame_times.len() as i32,
+            min: frame_times[0],
+            p99: frame_times[p99_index],
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimeStats {
+    pub avg: Duration,
+    pub min: Duration,
+    pub p99: Duration,
 }
 
-#[derive(Clone, Copy, Debug, EnumIter, Eq, PartialEq)]
+const EVENT_LOG_LEN: usize = 100;
+
+/// The most recent events flowing through the app, for diagnosing event handling in the field.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    entries: VecDeque<EventLogEntry>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, kind: &'static str, event: String) {
+        if self.entries.len() == EVENT_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(EventLogEntry {
+            event,
+            kind,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Returns the entries, oldest first.
+    pub fn entries(&self) -> &VecDeque<EventLogEntry> {
+        &self.entries
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EventLogEntry {
+    pub event: String,
+    pub kind: &'static str,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Indicator {
     BollingerBands(Period<U20>, StdDevMultiplier<U2>),
     ExponentialMovingAverage(Period<U50>),
-    // MovingAverageConvergenceDivergence,
-    // RelativeStrengthIndex,
+    /// Annualized standard deviation of the log returns, as a percentage.
+    HistoricalVolatility(Period<U20>),
+    MovingAverageConvergenceDivergence(Period<U12>, Period<U26>, Period<U9>),
+    RelativeStrengthIndex(Period<U14>),
     SimpleMovingAverage(Period<U50>),
 }
 
@@ -285,25 +1253,61 @@ where
     }
 }
 
+impl Indicator {
+    /// Returns the number of periods of prices needed to compute the first value.
+    pub fn period(&self) -> u16 {
+        match self {
+            Self::BollingerBands(n, _) => **n,
+            Self::ExponentialMovingAverage(n) => **n,
+            // Each return is computed from the close before
+            Self::HistoricalVolatility(n) => **n + 1,
+            Self::MovingAverageConvergenceDivergence(_, slow, signal) => **slow + **signal,
+            Self::RelativeStrengthIndex(n) => **n,
+            Self::SimpleMovingAverage(n) => **n,
+        }
+    }
+
+    /// Returns the fixed bounds of the values of the indicator, if any.
+    pub fn bounds(&self) -> Option<[f64; 2]> {
+        match self {
+            Self::RelativeStrengthIndex(_) => Some([0.0, 100.0]),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the indicator is drawn in its own pane, as it is not on the scale of the
+    /// prices.
+    pub fn is_oscillator(&self) -> bool {
+        matches!(
+            self,
+            Self::HistoricalVolatility(_)
+                | Self::MovingAverageConvergenceDivergence(..)
+                | Self::RelativeStrengthIndex(_)
+        )
+    }
+}
+
 impl FromStr for Indicator {
     type Err = ParseIndicatorError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const BB_PATTERN: &str = r"BB\s*\(\s*(?P<n>\d+)\s*,\s*(?P<k>\d+)\s*\)";
-        const EMA_PATTERN: &str = r"EMA\s*\(\s*(?P<n>\d+)\s*\)";
-        const SMA_PATTERN: &str = r"SMA\s*\(\s*(?P<n>\d+)\s*\)";
+        const BB_PATTERN: &str = r"(?i)BB\s*\(\s*(?P<n>\d+)\s*,\s*(?P<k>\d+)\s*\)";
+        const EMA_PATTERN: &str = r"(?i)EMA\s*\(\s*(?P<n>\d+)\s*\)";
+        const HV_PATTERN: &str = r"(?i)HV\s*\(\s*(?P<n>\d+)\s*\)";
+        const MACD_PATTERN: &str =
+            r"(?i)MACD\s*\(\s*(?P<fast>\d+)\s*,\s*(?P<slow>\d+)\s*,\s*(?P<signal>\d+)\s*\)";
+        const RSI_PATTERN: &str = r"(?i)RSI\s*\(\s*(?P<n>\d+)\s*\)";
+        const SMA_PATTERN: &str = r"(?i)SMA\s*\(\s*(?P<n>\d+)\s*\)";
 
         static BB_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(BB_PATTERN).unwrap());
         static EMA_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(EMA_PATTERN).unwrap());
+        static HV_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(HV_PATTERN).unwrap());
+        static MACD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(MACD_PATTERN).unwrap());
+        static RSI_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(RSI_PATTERN).unwrap());
         static SMA_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(SMA_PATTERN).unwrap());
 
         if let Some(caps) = BB_REGEX.captures(s) {
-            let n = &caps["n"];
-            let n = n.parse().map_err(|err| ParseIndicatorError::ParseInt {
-                name: "n".to_owned(),
-                source: err,
-                value: n.to_owned(),
-            })?;
+            let n = parse_period(&caps, "n", 1)?;
             let k = &caps["k"];
             let k = k.parse().map_err(|err| ParseIndicatorError::ParseInt {
                 name: "k".to_owned(),
@@ -312,20 +1316,27 @@ impl FromStr for Indicator {
             })?;
             Ok(Indicator::BollingerBands(n, k))
         } else if let Some(caps) = EMA_REGEX.captures(s) {
-            let n = &caps["n"];
-            let n = n.parse().map_err(|err| ParseIndicatorError::ParseInt {
-                name: "n".to_owned(),
-                source: err,
-                value: n.to_owned(),
-            })?;
+            let n = parse_period(&caps, "n", 1)?;
             Ok(Indicator::ExponentialMovingAverage(n))
+        } else if let Some(caps) = HV_REGEX.captures(s) {
+            // The sample standard deviation needs at least 2 returns
+            let n = parse_period(&caps, "n", 2)?;
+            Ok(Indicator::HistoricalVolatility(n))
+        } else if let Some(caps) = MACD_REGEX.captures(s) {
+            let fast = parse_period(&caps, "fast", 1)?;
+            let slow = parse_period(&caps, "slow", 1)?;
+            let signal = parse_period(&caps, "signal", 1)?;
+            if *fast >= *slow {
+                return Err(ParseIndicatorError::FastNotShorterThanSlow);
+            }
+            Ok(Indicator::MovingAverageConvergenceDivergence(
+                fast, slow, signal,
+            ))
+        } else if let Some(caps) = RSI_REGEX.captures(s) {
+            let n = parse_period(&caps, "n", 1)?;
+            Ok(Indicator::RelativeStrengthIndex(n))
         } else if let Some(caps) = SMA_REGEX.captures(s) {
-            let n = &caps["n"];
-            let n = n.parse().map_err(|err| ParseIndicatorError::ParseInt {
-                name: "n".to_owned(),
-                source: err,
-                value: n.to_owned(),
-            })?;
+            let n = parse_period(&caps, "n", 1)?;
             Ok(Indicator::SimpleMovingAverage(n))
         } else if s.is_empty() {
             Err(ParseIndicatorError::Empty)
@@ -335,10 +1346,38 @@ impl FromStr for Indicator {
     }
 }
 
+/// Parses the parameter of the indicator which is a number of periods, and so cannot be less than
+/// the minimum of the indicator.
+fn parse_period<D>(
+    caps: &Captures<'_>,
+    name: &str,
+    min: u16,
+) -> Result<Period<D>, ParseIndicatorError>
+where
+    D: Unsigned,
+{
+    let value = &caps[name];
+    let period: Period<D> = value.parse().map_err(|err| ParseIndicatorError::ParseInt {
+        name: name.to_owned(),
+        source: err,
+        value: value.to_owned(),
+    })?;
+    if *period < min {
+        return Err(ParseIndicatorError::PeriodTooShort {
+            min,
+            name: name.to_owned(),
+        });
+    }
+
+    Ok(period)
+}
+
 #[derive(Debug, Error)]
 pub enum ParseIndicatorError {
     #[error("cannot parse indicator from empty string")]
     Empty,
+    #[error("fast period of MACD must be shorter than the slow period")]
+    FastNotShorterThanSlow,
     #[error("invalid indicator literal")]
     Invalid,
     #[error("invalid indicator parameter {}: {}", .name, .value)]
@@ -347,6 +1386,22 @@ pub enum ParseIndicatorError {
         source: ParseIntError,
         value: String,
     },
+    #[error("indicator parameter {} must be at least {} periods", .name, .min)]
+    PeriodTooShort { min: u16, name: String },
+}
+
+impl TryFrom<String> for Indicator {
+    type Error = ParseIndicatorError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Indicator> for String {
+    fn from(indicator: Indicator) -> Self {
+        indicator.to_string()
+    }
 }
 
 impl fmt::Display for Indicator {
@@ -354,15 +1409,271 @@ impl fmt::Display for Indicator {
         match self {
             Self::BollingerBands(n, k) => write!(f, "BB({}, {})", n, k),
             Self::ExponentialMovingAverage(n) => write!(f, "EMA({})", n),
-            // Self::MovingAverageConvergenceDivergence => write!(f, "MACD"),
-            // Self::RelativeStrengthIndex => write!(f, "RSI"),
+            Self::HistoricalVolatility(n) => write!(f, "HV({})", n),
+            Self::MovingAverageConvergenceDivergence(fast, slow, signal) => {
+                write!(f, "MACD({}, {}, {})", fast, slow, signal)
+            }
+            Self::RelativeStrengthIndex(n) => write!(f, "RSI({})", n),
             Self::SimpleMovingAverage(n) => write!(f, "SMA({})", n),
         }
     }
 }
 
+/// Type of the chart of historical prices.
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, EnumIter, Eq, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartType {
+    #[derivative(Default)]
+    Line,
+    /// Columns of rising (X) and falling (O) prices, regardless of time.
+    PointAndFigure,
+    /// Prices relative to the close of the previous session.
+    Baseline,
+    /// Open, high, low and close of each bar.
+    Candlestick,
+}
+
+impl ChartType {
+    /// Returns the literal which the chart type is parsed from.
+    pub fn literal(self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::PointAndFigure => "pnf",
+            Self::Baseline => "baseline",
+            Self::Candlestick => "candles",
+        }
+    }
+
+    /// Returns the next chart type, wrapping around after the last.
+    pub fn next(self) -> Self {
+        Self::iter()
+            .cycle()
+            .skip_while(|&chart_type| chart_type != self)
+            .nth(1)
+            .unwrap()
+    }
+}
+
+impl FromStr for ChartType {
+    type Err = ParseChartTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Self::Line),
+            "pnf" | "point-and-figure" => Ok(Self::PointAndFigure),
+            "baseline" => Ok(Self::Baseline),
+            "candles" | "candlestick" => Ok(Self::Candlestick),
+            "" => Err(ParseChartTypeError::Empty),
+            _ => Err(ParseChartTypeError::Invalid),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseChartTypeError {
+    #[error("cannot parse chart type from empty string")]
+    Empty,
+    #[error("invalid chart type literal")]
+    Invalid,
+}
+
+impl fmt::Display for ChartType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Line => write!(f, "Line"),
+            Self::PointAndFigure => write!(f, "Point & Figure"),
+            Self::Baseline => write!(f, "Baseline"),
+            Self::Candlestick => write!(f, "Candlestick"),
+        }
+    }
+}
+
+/// Financial statement shown in the financials screen.
 #[derive(Clone, Copy, Debug, Derivative, EnumIter, Eq, PartialEq)]
 #[derivative(Default)]
+pub enum FinancialStatementKind {
+    #[derivative(Default)]
+    Income,
+    BalanceSheet,
+    CashFlow,
+}
+
+impl FinancialStatementKind {
+    /// Returns the statement after this one, wrapping around to the first.
+    pub fn next(self) -> Self {
+        Self::iter()
+            .cycle()
+            .skip_while(|&kind| kind != self)
+            .nth(1)
+            .unwrap()
+    }
+}
+
+impl fmt::Display for FinancialStatementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Income => write!(f, "Income Statement"),
+            Self::BalanceSheet => write!(f, "Balance Sheet"),
+            Self::CashFlow => write!(f, "Cash Flow"),
+        }
+    }
+}
+
+/// Length of the fiscal periods of the financial statements.
+#[derive(Clone, Copy, Debug, Derivative, Eq, PartialEq)]
+#[derivative(Default)]
+pub enum FinancialPeriod {
+    #[derivative(Default)]
+    Annual,
+    Quarterly,
+}
+
+impl FinancialPeriod {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Annual => Self::Quarterly,
+            Self::Quarterly => Self::Annual,
+        }
+    }
+
+    /// Returns the number of periods in a year, i.e. how far apart the periods compared year over
+    /// year are.
+    pub fn periods_per_year(self) -> usize {
+        match self {
+            Self::Annual => 1,
+            Self::Quarterly => 4,
+        }
+    }
+}
+
+impl fmt::Display for FinancialPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Annual => write!(f, "Annual"),
+            Self::Quarterly => write!(f, "Quarterly"),
+        }
+    }
+}
+
+/// Size of the bars on the chart. Bars larger than a day are aggregated from the daily bars.
+#[derive(Clone, Copy, Debug, Derivative, EnumIter, Eq, PartialEq)]
+#[derivative(Default)]
+pub enum BarSize {
+    #[derivative(Default)]
+    OneDay,
+    OneWeek,
+    OneMonth,
+}
+
+impl BarSize {
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::OneDay => Duration::days(1),
+            Self::OneWeek => Duration::weeks(1),
+            Self::OneMonth => Duration::days(31),
+        }
+    }
+
+    /// Returns the number of bars of this size in a year of trading.
+    pub fn periods_per_year(self) -> u16 {
+        match self {
+            Self::OneDay => 252,
+            Self::OneWeek => 52,
+            Self::OneMonth => 12,
+        }
+    }
+
+    /// Returns the next bar size, wrapping around after the largest.
+    pub fn next(self) -> Self {
+        Self::iter()
+            .cycle()
+            .skip_while(|&bar_size| bar_size != self)
+            .nth(1)
+            .unwrap()
+    }
+
+    /// Resamples the daily bars into bars of this size. Each bar takes the open of its first
+    /// daily bar, the close of its last, and is timestamped at its first.
+    pub fn aggregate<'a, I>(self, bars: I) -> Vec<Bar>
+    where
+        I: IntoIterator<Item = &'a Bar>,
+    {
+        if self == Self::OneDay {
+            return bars.into_iter().cloned().collect();
+        }
+
+        bars.into_iter()
+            .group_by(|bar| {
+                let date = bar.datetime().naive_utc().date();
+                match self {
+                    Self::OneDay => unreachable!(),
+                    Self::OneWeek => (date.iso_week().year(), date.iso_week().week()),
+                    Self::OneMonth => (date.year(), date.month()),
+                }
+            })
+            .into_iter()
+            .map(|(_, bars)| {
+                bars.fold(None, |acc_bar: Option<Bar>, bar| {
+                    Some(match acc_bar {
+                        Some(acc_bar) => Bar {
+                            high: acc_bar.high.max(bar.high),
+                            low: acc_bar.low.min(bar.low),
+                            close: bar.close,
+                            volume: match (acc_bar.volume, bar.volume) {
+                                (Some(acc_volume), Some(volume)) => Some(acc_volume + volume),
+                                (acc_volume, volume) => acc_volume.or(volume),
+                            },
+                            ..acc_bar
+                        },
+                        None => bar.clone(),
+                    })
+                })
+                .unwrap()
+            })
+            .collect()
+    }
+}
+
+impl FromStr for BarSize {
+    type Err = ParseBarSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1D" | "1d" => Ok(Self::OneDay),
+            "1W" | "1wk" => Ok(Self::OneWeek),
+            "1M" | "1mo" => Ok(Self::OneMonth),
+            "" => Err(ParseBarSizeError::Empty),
+            _ => Err(ParseBarSizeError::Invalid),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseBarSizeError {
+    #[error("cannot parse bar size from empty string")]
+    Empty,
+    #[error("invalid bar size literal")]
+    Invalid,
+}
+
+impl fmt::Display for BarSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OneDay => write!(f, "1D"),
+            Self::OneWeek => write!(f, "1W"),
+            Self::OneMonth => write!(f, "1M"),
+        }
+    }
+}
+
+/// Number of trading days in the 5D time frame.
+const FIVE_DAYS: u32 = 5;
+const TRADING_DAYS_PER_MONTH: u32 = 21;
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, EnumIter, Eq, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(try_from = "String", into = "String")]
 pub enum TimeFrame {
     FiveDays,
     #[derivative(Default)]
@@ -378,44 +1689,73 @@ pub enum TimeFrame {
 }
 
 impl TimeFrame {
-    pub fn duration(self) -> Option<Duration> {
+    /// Returns the number of trading days in the time frame, with 21 trading days in a month.
+    pub fn trading_days(self) -> Option<u32> {
         match self {
-            Self::FiveDays => Some(Duration::days(5)),
-            Self::OneMonth => Some(Duration::days(30)),
-            Self::ThreeMonths => Some(Duration::days(30 * 3)),
-            Self::SixMonths => Some(Duration::days(30 * 6)),
-            Self::OneYear => Some(Duration::days(30 * 12)),
-            Self::TwoYears => Some(Duration::days(30 * 12 * 2)),
-            Self::FiveYears => Some(Duration::days(30 * 12 * 5)),
-            Self::TenYears => Some(Duration::days(30 * 12 * 10)),
+            Self::FiveDays => Some(FIVE_DAYS),
+            Self::OneMonth => Some(TRADING_DAYS_PER_MONTH),
+            Self::ThreeMonths => Some(TRADING_DAYS_PER_MONTH * 3),
+            Self::SixMonths => Some(TRADING_DAYS_PER_MONTH * 6),
+            Self::OneYear => Some(TRADING_DAYS_PER_MONTH * 12),
+            Self::TwoYears => Some(TRADING_DAYS_PER_MONTH * 12 * 2),
+            Self::FiveYears => Some(TRADING_DAYS_PER_MONTH * 12 * 5),
+            Self::TenYears => Some(TRADING_DAYS_PER_MONTH * 12 * 10),
             _ => None,
         }
     }
 
-    pub fn interval(self) -> Interval {
+    /// Returns the range of the historical prices of the time frame, as passed to the provider.
+    pub fn range(self) -> &'static str {
         match self {
-            Self::FiveDays => Interval::_5d,
-            Self::OneMonth => Interval::_1mo,
-            Self::ThreeMonths => Interval::_3mo,
-            Self::SixMonths => Interval::_6mo,
-            Self::YearToDate => Interval::_ytd,
-            Self::OneYear => Interval::_1y,
-            Self::TwoYears => Interval::_2y,
-            Self::FiveYears => Interval::_5y,
-            Self::TenYears => Interval::_10y,
-            Self::Max => Interval::_max,
+            Self::FiveDays => "5d",
+            Self::OneMonth => "1mo",
+            Self::ThreeMonths => "3mo",
+            Self::SixMonths => "6mo",
+            Self::YearToDate => "ytd",
+            Self::OneYear => "1y",
+            Self::TwoYears => "2y",
+            Self::FiveYears => "5y",
+            Self::TenYears => "10y",
+            Self::Max => "max",
         }
     }
 
-    pub fn now_date_range(self) -> Option<DateRange> {
+    pub fn now_date_range(self, market_session: &MarketSession) -> Option<DateRange> {
         let end_date = Utc::now().date().and_hms(0, 0, 0) + Duration::days(1);
 
         if self == Self::YearToDate {
             return Some(end_date.with_ordinal(1).unwrap()..end_date);
         }
 
-        self.duration()
-            .map(|duration| (end_date - duration)..end_date)
+        self.date_range_ending(end_date, market_session)
+    }
+
+    /// Returns the date range of the time frame ending at the date. Only the trading days are
+    /// counted, so that the time frame does not come up short around weekends and holidays.
+    pub fn date_range_ending(
+        self,
+        end_date: DateTime<Utc>,
+        market_session: &MarketSession,
+    ) -> Option<DateRange> {
+        self.trading_days().map(|trading_days| {
+            let start_date =
+                market_session.sub_trading_days(end_date.naive_utc().date(), trading_days);
+            Utc.from_utc_date(&start_date).and_hms(0, 0, 0)..end_date
+        })
+    }
+
+    /// Returns the date range of the time frame starting at the date. Only the trading days are
+    /// counted.
+    pub fn date_range_starting(
+        self,
+        start_date: DateTime<Utc>,
+        market_session: &MarketSession,
+    ) -> Option<DateRange> {
+        self.trading_days().map(|trading_days| {
+            let end_date =
+                market_session.add_trading_days(start_date.naive_utc().date(), trading_days);
+            start_date..Utc.from_utc_date(&end_date).and_hms(0, 0, 0)
+        })
     }
 }
 
@@ -448,6 +1788,20 @@ pub enum ParseTimeFrameError {
     Invalid,
 }
 
+impl TryFrom<String> for TimeFrame {
+    type Error = ParseTimeFrameError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeFrame> for String {
+    fn from(time_frame: TimeFrame) -> Self {
+        time_frame.to_string()
+    }
+}
+
 impl fmt::Display for TimeFrame {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -464,3 +1818,59 @@ impl fmt::Display for TimeFrame {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indicator_parses_periods() {
+        assert_eq!(
+            "rsi(7)".parse::<Indicator>().unwrap(),
+            Indicator::RelativeStrengthIndex(Period::new(7))
+        );
+        assert_eq!(
+            "MACD(5, 35, 5)".parse::<Indicator>().unwrap(),
+            Indicator::MovingAverageConvergenceDivergence(
+                Period::new(5),
+                Period::new(35),
+                Period::new(5)
+            )
+        );
+    }
+
+    #[test]
+    fn indicator_rejects_too_short_periods() {
+        for s in &[
+            "rsi(0)",
+            "sma(0)",
+            "ema(0)",
+            "hv(0)",
+            "hv(1)",
+            "bb(0, 2)",
+            "macd(0, 26, 9)",
+            "macd(12, 26, 0)",
+        ] {
+            assert!(
+                matches!(
+                    s.parse::<Indicator>(),
+                    Err(ParseIndicatorError::PeriodTooShort { .. })
+                ),
+                "{} is not rejected",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn indicator_rejects_macd_with_fast_period_not_shorter_than_slow() {
+        assert!(matches!(
+            "macd(26, 12, 9)".parse::<Indicator>(),
+            Err(ParseIndicatorError::FastNotShorterThanSlow)
+        ));
+        assert!(matches!(
+            "macd(12, 12, 9)".parse::<Indicator>(),
+            Err(ParseIndicatorError::FastNotShorterThanSlow)
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    app::{SymbolHistoryEntry, Toast, UiTarget},
+    event::{AlertEvent, ChartEvent, DataEvent, InputEvent, OverlayEvent, OverlayState},
+    layout::ChartLayout,
+    reactive::EventTopic,
+    remote::RemoteCommand,
+};
+use reactive_rs::Stream;
+use std::fmt::Debug;
+use tui::layout::Rect;
+
+/// Event bus of the app, with a topic for each type of event. The events are not replayed, so each
+/// of them is handled once by the subscribers set up before it is sent. The state derived from the
+/// events is kept in the store instead.
+#[derive(Clone, Default)]
+pub struct EventBus<'a> {
+    pub alert: EventTopic<'a, AlertEvent>,
+    pub chart: EventTopic<'a, ChartEvent>,
+    pub data: EventTopic<'a, DataEvent>,
+    /// Values to fill the text field of the UI target with, e.g. the alert being edited.
+    pub field_value: EventTopic<'a, (UiTarget, String)>,
+    pub input: EventTopic<'a, InputEvent>,
+    /// Saved chart layouts which are recalled.
+    pub layout: EventTopic<'a, ChartLayout>,
+    pub overlay: EventTopic<'a, (UiTarget, OverlayEvent)>,
+    pub overlay_state: EventTopic<'a, (UiTarget, OverlayState)>,
+    pub remote: EventTopic<'a, RemoteCommand>,
+    /// Stock symbols chosen in the table of the UI target, e.g. the watchlist.
+    pub stock_symbol: EventTopic<'a, (UiTarget, String)>,
+    /// Entries of the symbol history which are navigated to.
+    pub symbol_history: EventTopic<'a, SymbolHistoryEntry>,
+    pub toast: EventTopic<'a, Toast>,
+    /// Areas of the UI targets as they are drawn, or `None` for those which are not shown.
+    pub ui_target_area: EventTopic<'a, (UiTarget, Option<Rect>)>,
+    /// Symbols of the watchlist of the name, replacing its previous symbols.
+    pub watchlist: EventTopic<'a, (String, Vec<String>)>,
+}
+
+impl<'a> EventBus<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes to the events of all the topics, which are passed along with the name of their
+    /// topic. The events sent on every frame, i.e. the ticks and the areas of the UI targets, are
+    /// left out.
+    pub fn subscribe_all<F>(&self, observer: F)
+    where
+        F: 'a + Clone + FnMut(&'static str, &dyn Debug),
+    {
+        subscribe_topic(self.alert.clone(), "alert", observer.clone());
+        subscribe_topic(self.chart.clone(), "chart", observer.clone());
+        subscribe_topic(self.data.clone(), "data", observer.clone());
+        subscribe_topic(self.field_value.clone(), "field_value", observer.clone());
+        subscribe_topic(
+            self.input
+                .clone()
+                .filter(|ev| !matches!(ev, InputEvent::Render | InputEvent::Tick)),
+            "input",
+            observer.clone(),
+        );
+        subscribe_topic(self.layout.clone(), "layout", observer.clone());
+        subscribe_topic(self.overlay.clone(), "overlay", observer.clone());
+        subscribe_topic(
+            self.overlay_state.clone(),
+            "overlay_state",
+            observer.clone(),
+        );
+        subscribe_topic(self.remote.clone(), "remote", observer.clone());
+        subscribe_topic(self.stock_symbol.clone(), "stock_symbol", observer.clone());
+        subscribe_topic(
+            self.symbol_history.clone(),
+            "symbol_history",
+            observer.clone(),
+        );
+        subscribe_topic(self.toast.clone(), "toast", observer.clone());
+        subscribe_topic(self.watchlist.clone(), "watchlist", observer);
+    }
+}
+
+fn subscribe_topic<'a, S, F>(topic: S, name: &'static str, mut observer: F)
+where
+    S: Stream<'a>,
+    S::Item: Debug + Sized,
+    F: 'a + FnMut(&'static str, &dyn Debug),
+{
+    topic.subscribe(move |ev| observer(name, ev));
+}
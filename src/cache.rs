@@ -0,0 +1,246 @@
+use crate::storage;
+use anyhow::{bail, Context};
+use im::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    hash::Hash,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use yahoo_finance::Bar;
+
+const CACHE_DIR_NAME: &str = "cache";
+
+/// A cache whose entries expire once they are older than its time to live. The expired entries are
+/// evicted whenever another one is inserted, so that the cache does not keep growing.
+#[derive(Clone, Debug)]
+pub struct TtlCache<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    entries: HashMap<K, (V, Instant)>,
+    ttl: Duration,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// Returns the cached value, unless it has expired.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries
+            .get(key)
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            .map(|(value, _)| value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+        self.entries.insert(key, (value, Instant::now()));
+    }
+}
+
+/// Historical prices cached on disk, so that they are not downloaded again in later sessions.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CachedBars {
+    pub bars: Vec<CachedBar>,
+    /// Inclusive timestamp ranges covered by the bars.
+    pub covered_date_ranges: Vec<(i64, i64)>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct CachedBar {
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: Option<u64>,
+}
+
+impl From<&Bar> for CachedBar {
+    fn from(bar: &Bar) -> Self {
+        Self {
+            timestamp: bar.timestamp,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        }
+    }
+}
+
+impl From<CachedBar> for Bar {
+    fn from(cached_bar: CachedBar) -> Self {
+        Self {
+            timestamp: cached_bar.timestamp,
+            open: cached_bar.open,
+            high: cached_bar.high,
+            low: cached_bar.low,
+            close: cached_bar.close,
+            volume: cached_bar.volume,
+        }
+    }
+}
+
+/// Size of the cached data of a stock.
+#[derive(Clone, Debug)]
+pub struct CacheStats {
+    pub bar_count: usize,
+    pub size: u64,
+    pub stock_symbol: String,
+}
+
+/// Returns the directory where data is cached across sessions.
+pub fn cache_dir() -> anyhow::Result<PathBuf> {
+    Ok(storage::data_dir()?.join(CACHE_DIR_NAME))
+}
+
+/// Returns whether the stock symbol can safely be used as a file name in the cache directory,
+/// i.e. it can't escape the directory or name a hidden file.
+fn is_valid_file_stem(stock_symbol: &str) -> bool {
+    !stock_symbol.is_empty()
+        && !stock_symbol.starts_with('.')
+        && stock_symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '='))
+}
+
+fn cached_bars_path(stock_symbol: &str) -> anyhow::Result<PathBuf> {
+    if !is_valid_file_stem(stock_symbol) {
+        bail!("invalid stock symbol for the cache: {:?}", stock_symbol);
+    }
+
+    Ok(cache_dir()?.join(format!("{}.json", stock_symbol)))
+}
+
+/// Loads the bars previously cached for the stock, if any.
+pub fn load_bars(stock_symbol: &str) -> anyhow::Result<Option<CachedBars>> {
+    let path = cached_bars_path(stock_symbol)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let cached_bars = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    Ok(Some(cached_bars))
+}
+
+/// Caches the bars of the stock, overwriting any previously cached bars.
+pub fn save_bars(stock_symbol: &str, cached_bars: &CachedBars) -> anyhow::Result<()> {
+    let cache_dir = cache_dir()?;
+    fs::create_dir_all(&cache_dir)
+        .with_context(|| format!("failed to create {}", cache_dir.display()))?;
+
+    let path = cached_bars_path(stock_symbol)?;
+    fs::write(&path, serde_json::to_string(cached_bars)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Returns the stats of every cached stock, sorted by symbol.
+pub fn stats() -> anyhow::Result<Vec<CacheStats>> {
+    let cache_dir = cache_dir()?;
+    if !cache_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut stats = vec![];
+    for entry in fs::read_dir(&cache_dir)
+        .with_context(|| format!("failed to read {}", cache_dir.display()))?
+    {
+        let path = entry?.path();
+        let stock_symbol = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stock_symbol)
+                if path.extension().map_or(false, |ext| ext == "json")
+                    && is_valid_file_stem(stock_symbol) =>
+            {
+                stock_symbol.to_owned()
+            }
+            _ => continue,
+        };
+        let size = fs::metadata(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+            .len();
+        let bar_count = load_bars(&stock_symbol)?.map_or(0, |cached_bars| cached_bars.bars.len());
+
+        stats.push(CacheStats {
+            bar_count,
+            size,
+            stock_symbol,
+        });
+    }
+    stats.sort_by(|a, b| a.stock_symbol.cmp(&b.stock_symbol));
+
+    Ok(stats)
+}
+
+/// Removes all the cached data.
+pub fn clear() -> anyhow::Result<()> {
+    let cache_dir = cache_dir()?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)
+            .with_context(|| format!("failed to remove {}", cache_dir.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_evicts_expired_entries() {
+        let mut cache = TtlCache::new(Duration::from_secs(0));
+        cache.insert("AAPL", 1);
+        cache.insert("MSFT", 2);
+
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.entries.contains_key("MSFT"));
+        assert_eq!(cache.get(&"MSFT"), None);
+    }
+
+    #[test]
+    fn insert_keeps_entries_until_they_expire() {
+        let mut cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("AAPL", 1);
+        cache.insert("MSFT", 2);
+        cache.insert("AAPL", 3);
+
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&"AAPL"), Some(&3));
+        assert_eq!(cache.get(&"MSFT"), Some(&2));
+    }
+
+    #[test]
+    fn accepts_stock_symbols_as_file_stems() {
+        for stock_symbol in &["AAPL", "BRK-B", "D05.SI", "^GSPC", "EURUSD=X"] {
+            assert!(is_valid_file_stem(stock_symbol), "{}", stock_symbol);
+        }
+    }
+
+    #[test]
+    fn rejects_file_stems_escaping_the_cache_dir() {
+        for stock_symbol in &["", ".", "..", "../AAPL", "a/b", "a\\b", ".hidden"] {
+            assert!(!is_valid_file_stem(stock_symbol), "{}", stock_symbol);
+        }
+    }
+}
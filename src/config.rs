@@ -0,0 +1,594 @@
+use crate::{
+    app::LayoutPreset,
+    stock::{MarketQuote, ScreenerUniverse},
+};
+use anyhow::{bail, Context};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::PathBuf, time::Duration};
+
+/// Environment variables with the proxy, in order of precedence.
+const PROXY_ENV_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"];
+
+/// Longest timeout of the requests, in seconds, as the app is blocked while waiting for them.
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Symbols by alias, which can be entered in place of the symbols.
+    pub aliases: SymbolAliases,
+    /// How fired alerts are signaled.
+    pub alerts: AlertsConfig,
+    /// How long fetched data is cached for.
+    pub cache_ttl: CacheTtlConfig,
+    /// API keys of the data providers which require one, by provider name.
+    pub credentials: BTreeMap<String, String>,
+    /// Layout preset to use at startup.
+    pub layout: Option<LayoutPreset>,
+    /// Indices shown in the markets screen, and the region of the trending symbols.
+    pub markets: MarketsConfig,
+    /// Starting cash and order size of paper trading.
+    pub paper_trading: PaperTradingConfig,
+    /// Box size and reversal of the point and figure chart.
+    pub point_and_figure: PointAndFigureConfig,
+    /// Timeouts of the requests to the data provider.
+    pub timeouts: TimeoutConfig,
+    /// Proxy for all requests to the data providers, overriding the `HTTP_PROXY` and `HTTPS_PROXY`
+    /// environment variables.
+    pub proxy: Option<String>,
+    /// How often the latest prices are refreshed while the market is open.
+    pub refresh: RefreshConfig,
+    /// Filters of the stock screener, and the symbols they are evaluated against.
+    pub screener: ScreenerConfig,
+    /// Name of the watchlist shown at startup.
+    pub startup_watchlist: Option<String>,
+    /// Interval between redraws of the screen, in milliseconds.
+    pub tick_rate: Option<u64>,
+    /// Symbols of the default watchlist.
+    pub watchlist: Vec<String>,
+}
+
+impl Config {
+    /// Loads the config file, falling back to the defaults if it does not exist.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        config
+            .validate()
+            .with_context(|| format!("invalid config {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    /// Checks the settings which would otherwise be silently ignored, or leave the app unusable.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.timeouts.validate()?;
+        self.point_and_figure.validate()?;
+        if self.tick_rate == Some(0) {
+            bail!("tick-rate must be at least 1 millisecond, but is 0");
+        }
+
+        Ok(())
+    }
+
+    /// Returns the API key of the provider, falling back to the `STOCKER_<PROVIDER>_API_KEY`
+    /// environment variable.
+    pub fn api_key(&self, provider: &str) -> Option<String> {
+        self.credentials
+            .get(provider)
+            .cloned()
+            .filter(|api_key| !api_key.trim().is_empty())
+            .or_else(|| {
+                env::var(api_key_env_var(provider))
+                    .ok()
+                    .filter(|api_key| !api_key.trim().is_empty())
+            })
+    }
+
+    /// Returns the proxy of the requests of the provider layer: the override if any, falling back
+    /// to the `HTTPS_PROXY` and `HTTP_PROXY` environment variables.
+    pub fn proxy(&self) -> anyhow::Result<Option<Url>> {
+        self.proxy
+            .clone()
+            .or_else(|| {
+                PROXY_ENV_VARS
+                    .iter()
+                    .find_map(|env_var| env::var(env_var).ok())
+            })
+            .filter(|proxy| !proxy.trim().is_empty())
+            .map(|proxy| Url::parse(&proxy).with_context(|| format!("invalid proxy {}", proxy)))
+            .transpose()
+    }
+}
+
+/// Signals of fired alerts, in addition to the toast.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AlertsConfig {
+    /// Whether to ring the terminal bell.
+    pub bell: bool,
+    /// Whether to flash the toast and the header.
+    pub flash: bool,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            bell: true,
+            flash: true,
+        }
+    }
+}
+
+/// Time to live of each type of cached data, in seconds.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CacheTtlConfig {
+    pub news: u64,
+    pub profile: u64,
+    pub quote_summary: u64,
+}
+
+impl CacheTtlConfig {
+    pub fn news(self) -> Duration {
+        Duration::from_secs(self.news)
+    }
+
+    pub fn profile(self) -> Duration {
+        Duration::from_secs(self.profile)
+    }
+
+    pub fn quote_summary(self) -> Duration {
+        Duration::from_secs(self.quote_summary)
+    }
+}
+
+impl Default for CacheTtlConfig {
+    fn default() -> Self {
+        Self {
+            news: 5 * 60,
+            profile: 24 * 60 * 60,
+            quote_summary: 60,
+        }
+    }
+}
+
+/// Symbols of the indices shown in the markets screen, in order.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MarketsConfig {
+    /// Symbol which the beta of the current stock is computed against.
+    pub benchmark: String,
+    pub indices: Vec<String>,
+    /// Region of the trending symbols, e.g. "US" or "GB".
+    pub trending_region: String,
+}
+
+impl Default for MarketsConfig {
+    fn default() -> Self {
+        Self {
+            benchmark: "SPY".to_owned(),
+            indices: vec![
+                "^GSPC".to_owned(),
+                "^IXIC".to_owned(),
+                "^DJI".to_owned(),
+                "^VIX".to_owned(),
+            ],
+            trending_region: "US".to_owned(),
+        }
+    }
+}
+
+/// Settings of paper trading. Orders entered for the current stock are prefilled with the given
+/// quantity.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PaperTradingConfig {
+    pub initial_cash: f64,
+    pub quantity: f64,
+}
+
+impl Default for PaperTradingConfig {
+    fn default() -> Self {
+        Self {
+            initial_cash: 100_000.0,
+            quantity: 1.0,
+        }
+    }
+}
+
+/// Symbols by alias, e.g. `oil` for `CL=F`. The aliases are matched regardless of case.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct SymbolAliases(BTreeMap<String, String>);
+
+impl SymbolAliases {
+    /// Returns the symbol of the alias, or the symbol itself if it is not an alias.
+    pub fn resolve(&self, symbol: &str) -> String {
+        self.0
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(symbol))
+            .map_or_else(
+                || symbol.to_owned(),
+                |(_, symbol)| symbol.to_ascii_uppercase(),
+            )
+    }
+
+    /// Returns the alias of the symbol, if it has one.
+    pub fn alias_of(&self, symbol: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, aliased_symbol)| aliased_symbol.eq_ignore_ascii_case(symbol))
+            .map(|(alias, _)| alias.as_str())
+    }
+}
+
+/// Settings of the point and figure chart.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PointAndFigureConfig {
+    /// Price range of a box, as a percentage of the latest close.
+    pub box_size: f64,
+    /// Number of boxes the price has to move against the column to start a new one.
+    pub reversal: u8,
+}
+
+impl PointAndFigureConfig {
+    /// Checks that the box size is a positive percentage, and that the reversal is at least a box.
+    pub fn validate(self) -> anyhow::Result<()> {
+        if !(self.box_size > 0.0 && self.box_size.is_finite()) {
+            bail!(
+                "point-and-figure.box-size must be a positive percentage, but is {}",
+                self.box_size
+            );
+        }
+        if self.reversal == 0 {
+            bail!("point-and-figure.reversal must be at least 1 box, but is 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PointAndFigureConfig {
+    fn default() -> Self {
+        Self {
+            box_size: 1.0,
+            reversal: 3,
+        }
+    }
+}
+
+/// Interval of refreshing the latest prices, in seconds. Refreshing is disabled if it is 0.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RefreshConfig {
+    pub interval: u64,
+}
+
+impl RefreshConfig {
+    pub fn interval(self) -> Option<Duration> {
+        match self.interval {
+            0 => None,
+            interval => Some(Duration::from_secs(interval)),
+        }
+    }
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self { interval: 60 }
+    }
+}
+
+/// Bounds of a filter of the stock screener, either of which may be left open.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RangeFilter {
+    pub max: Option<f64>,
+    pub min: Option<f64>,
+}
+
+impl RangeFilter {
+    /// Checks that the value is within the bounds. A missing value only passes if the filter is
+    /// not set at all.
+    pub fn matches(self, value: Option<f64>) -> bool {
+        match value {
+            Some(value) => {
+                self.min.map_or(true, |min| value >= min)
+                    && self.max.map_or(true, |max| value <= max)
+            }
+            None => self.min.is_none() && self.max.is_none(),
+        }
+    }
+}
+
+/// Filters of the stock screener. The universe is either `watchlist`, or the ID of one of the
+/// predefined screeners of the provider, e.g. `most_actives`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ScreenerConfig {
+    pub change_percent: RangeFilter,
+    pub market_cap: RangeFilter,
+    pub pe: RangeFilter,
+    pub price: RangeFilter,
+    pub universe: String,
+    pub volume: RangeFilter,
+}
+
+impl ScreenerConfig {
+    pub fn matches(&self, quote: &MarketQuote) -> bool {
+        self.change_percent
+            .matches(quote.regular_market_change_percent)
+            && self.market_cap.matches(quote.market_cap)
+            && self.pe.matches(quote.trailing_pe)
+            && self.price.matches(quote.regular_market_price)
+            && self.volume.matches(quote.regular_market_volume)
+    }
+
+    pub fn to_universe(&self, watchlist: &[String]) -> ScreenerUniverse {
+        if self.universe == "watchlist" {
+            ScreenerUniverse::Symbols(watchlist.to_vec())
+        } else {
+            ScreenerUniverse::Predefined(self.universe.clone())
+        }
+    }
+}
+
+impl Default for ScreenerConfig {
+    fn default() -> Self {
+        Self {
+            change_percent: RangeFilter::default(),
+            market_cap: RangeFilter::default(),
+            pe: RangeFilter::default(),
+            price: RangeFilter::default(),
+            universe: "watchlist".to_owned(),
+            volume: RangeFilter::default(),
+        }
+    }
+}
+
+/// Timeouts of the requests to the data provider, in seconds.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TimeoutConfig {
+    pub connect: u64,
+    pub read: u64,
+}
+
+impl TimeoutConfig {
+    pub fn connect(self) -> Duration {
+        Duration::from_secs(self.connect)
+    }
+
+    pub fn read(self) -> Duration {
+        Duration::from_secs(self.read)
+    }
+
+    /// Checks that each timeout is at least a second, and at most [`MAX_TIMEOUT_SECS`].
+    pub fn validate(self) -> anyhow::Result<()> {
+        for &(name, secs) in &[("connect", self.connect), ("read", self.read)] {
+            if !(1..=MAX_TIMEOUT_SECS).contains(&secs) {
+                bail!(
+                    "timeouts.{} must be between 1 and {} seconds, but is {}",
+                    name,
+                    MAX_TIMEOUT_SECS,
+                    secs
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect: 10,
+            read: 30,
+        }
+    }
+}
+
+pub fn api_key_env_var(provider: &str) -> String {
+    format!("STOCKER_{}_API_KEY", provider.to_ascii_uppercase())
+}
+
+/// Masks all but the last few characters of the API key, so that it can be displayed. Short keys
+/// are masked entirely, as most of the key would be revealed otherwise.
+pub fn mask_api_key(api_key: &str) -> String {
+    const VISIBLE_LEN: usize = 4;
+
+    let len = api_key.chars().count();
+    let visible_len = if len > VISIBLE_LEN * 2 {
+        VISIBLE_LEN
+    } else {
+        0
+    };
+    api_key
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i + visible_len < len { '*' } else { c })
+        .collect()
+}
+
+/// Returns the directory of the app in the config directory of the platform, which the config file
+/// and the other files edited by the user are kept in.
+pub fn config_dir() -> anyhow::Result<PathBuf> {
+    let config_dir = dirs::config_dir().with_context(|| "config directory not found")?;
+
+    Ok(config_dir.join(crate::APP_DIR_NAME))
+}
+
+pub fn config_path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Returns the directory of the plugins, next to the config file.
+pub fn plugins_dir() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("plugins"))
+}
+
+/// Returns the path of the saved chart layouts, next to the config file.
+pub fn chart_layouts_path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("layouts.toml"))
+}
+
+/// Returns the path of the stored watchlists, next to the config file.
+pub fn watchlists_path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("watchlists.toml"))
+}
+
+/// Returns the path of the user script with the event hooks, next to the config file.
+pub fn hooks_path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("hooks.rhai"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_credentials(credentials: &[(&str, &str)]) -> Config {
+        Config {
+            credentials: credentials
+                .iter()
+                .map(|&(provider, api_key)| (provider.to_owned(), api_key.to_owned()))
+                .collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn api_key_prefers_the_config_over_the_environment() {
+        env::set_var("STOCKER_CONFIG_TEST_API_KEY", "from-env");
+        let config = config_with_credentials(&[("config_test", "from-config")]);
+
+        assert_eq!(
+            config.api_key("config_test"),
+            Some("from-config".to_owned())
+        );
+    }
+
+    #[test]
+    fn api_key_falls_back_to_the_environment() {
+        env::set_var("STOCKER_ENV_TEST_API_KEY", "from-env");
+
+        assert_eq!(
+            Config::default().api_key("env_test"),
+            Some("from-env".to_owned())
+        );
+
+        // A blank key in the config does not hide the one in the environment
+        let config = config_with_credentials(&[("env_test", "  ")]);
+        assert_eq!(config.api_key("env_test"), Some("from-env".to_owned()));
+    }
+
+    #[test]
+    fn api_key_is_missing_if_blank_or_not_set() {
+        env::set_var("STOCKER_BLANK_TEST_API_KEY", "");
+        let config = config_with_credentials(&[("blank_test", "")]);
+
+        assert_eq!(config.api_key("blank_test"), None);
+        assert_eq!(Config::default().api_key("missing_test"), None);
+    }
+
+    #[test]
+    fn timeouts_are_valid_by_default() {
+        assert!(TimeoutConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn timeouts_reject_zero_and_absurd_values() {
+        let zero_connect = TimeoutConfig {
+            connect: 0,
+            ..TimeoutConfig::default()
+        };
+        let absurd_read: TimeoutConfig = toml::from_str("read = 86400").unwrap();
+
+        assert_eq!(
+            zero_connect.validate().unwrap_err().to_string(),
+            "timeouts.connect must be between 1 and 300 seconds, but is 0"
+        );
+        assert_eq!(
+            absurd_read.validate().unwrap_err().to_string(),
+            "timeouts.read must be between 1 and 300 seconds, but is 86400"
+        );
+    }
+
+    #[test]
+    fn point_and_figure_rejects_empty_boxes_and_reversals() {
+        let zero_box_size: PointAndFigureConfig = toml::from_str("box-size = 0.0").unwrap();
+        let nan_box_size: PointAndFigureConfig = toml::from_str("box-size = nan").unwrap();
+        let zero_reversal: PointAndFigureConfig = toml::from_str("reversal = 0").unwrap();
+
+        assert!(PointAndFigureConfig::default().validate().is_ok());
+        assert_eq!(
+            zero_box_size.validate().unwrap_err().to_string(),
+            "point-and-figure.box-size must be a positive percentage, but is 0"
+        );
+        assert_eq!(
+            nan_box_size.validate().unwrap_err().to_string(),
+            "point-and-figure.box-size must be a positive percentage, but is NaN"
+        );
+        assert_eq!(
+            zero_reversal.validate().unwrap_err().to_string(),
+            "point-and-figure.reversal must be at least 1 box, but is 0"
+        );
+    }
+
+    #[test]
+    fn tick_rate_rejects_zero() {
+        let config: Config = toml::from_str("tick-rate = 0").unwrap();
+
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "tick-rate must be at least 1 millisecond, but is 0"
+        );
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn api_key_env_var_is_upper_case() {
+        assert_eq!(api_key_env_var("yahoo"), "STOCKER_YAHOO_API_KEY");
+    }
+
+    #[test]
+    fn proxy_prefers_the_override() {
+        let config = Config {
+            proxy: Some("http://proxy.example.com:8080".to_owned()),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.proxy().unwrap().map(|proxy| proxy.to_string()),
+            Some("http://proxy.example.com:8080/".to_owned())
+        );
+    }
+
+    #[test]
+    fn proxy_is_rejected_if_invalid() {
+        let config = Config {
+            proxy: Some("not a proxy".to_owned()),
+            ..Config::default()
+        };
+
+        assert!(config.proxy().is_err());
+    }
+
+    #[test]
+    fn mask_api_key_only_shows_the_last_characters() {
+        assert_eq!(mask_api_key("abcdefghijkl"), "********ijkl");
+        assert_eq!(mask_api_key("abcdefgh"), "********");
+        assert_eq!(mask_api_key("abc"), "***");
+        assert_eq!(mask_api_key(""), "");
+    }
+}
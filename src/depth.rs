@@ -0,0 +1,289 @@
+use crate::{reactive::StreamExt, stock};
+use anyhow::{bail, Context};
+use async_std::{net::TcpStream, task};
+use async_tungstenite::{
+    async_std::{client_async_tls, connect_async, ConnectStream},
+    tungstenite::Message,
+    WebSocketStream,
+};
+use futures::{AsyncReadExt, AsyncWriteExt, StreamExt as FuturesStreamExt};
+use reactive_rs::Stream;
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use tracing::{debug_span, warn};
+
+/// Stream of the top of the order book of each trading pair, refreshed every 100 ms.
+const DEPTH_STREAM_URL: &str = "wss://stream.binance.com:9443/ws";
+const DEPTH_LEVELS: u8 = 20;
+/// Longest response of the proxy to the tunnel request which is read.
+const MAX_PROXY_RESPONSE_LEN: usize = 8 * 1024;
+/// Quote currencies of the crypto symbols of the provider, e.g. `BTC-USD`, which are traded on
+/// the exchange. The exchange quotes in USDT instead of USD.
+const QUOTE_CURRENCIES: &[(&str, &str)] = &[
+    ("BTC", "btc"),
+    ("ETH", "eth"),
+    ("EUR", "eur"),
+    ("USD", "usdt"),
+    ("USDT", "usdt"),
+];
+
+/// Price levels of the order book of a trading pair, best first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderBook {
+    /// Asks as (price, quantity), lowest price first.
+    pub asks: Vec<(f64, f64)>,
+    /// Bids as (price, quantity), highest price first.
+    pub bids: Vec<(f64, f64)>,
+    pub stock_symbol: String,
+}
+
+impl OrderBook {
+    pub fn mid_price(&self) -> Option<f64> {
+        let (best_ask, _) = self.asks.first()?;
+        let (best_bid, _) = self.bids.first()?;
+
+        Some((best_ask + best_bid) / 2.0)
+    }
+
+    /// Returns the quantity available up to each price level of the asks.
+    pub fn cumulative_asks(&self) -> Vec<(f64, f64)> {
+        to_cumulative(&self.asks)
+    }
+
+    /// Returns the quantity available down to each price level of the bids.
+    pub fn cumulative_bids(&self) -> Vec<(f64, f64)> {
+        to_cumulative(&self.bids)
+    }
+}
+
+fn to_cumulative(levels: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    levels
+        .iter()
+        .scan(0.0, |total, &(price, quantity)| {
+            *total += quantity;
+            Some((price, *total))
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct DepthSnapshot {
+    asks: Vec<(String, String)>,
+    bids: Vec<(String, String)>,
+}
+
+impl DepthSnapshot {
+    fn to_order_book(&self, stock_symbol: &str) -> OrderBook {
+        let parse_levels = |levels: &[(String, String)]| {
+            levels
+                .iter()
+                .filter_map(|(price, quantity)| Some((price.parse().ok()?, quantity.parse().ok()?)))
+                .collect()
+        };
+
+        OrderBook {
+            asks: parse_levels(&self.asks),
+            bids: parse_levels(&self.bids),
+            stock_symbol: stock_symbol.to_owned(),
+        }
+    }
+}
+
+/// Returns the symbol of the trading pair on the exchange, e.g. `btcusdt` for `BTC-USD`, or `None`
+/// if the symbol is not a crypto symbol.
+pub fn exchange_symbol(stock_symbol: &str) -> Option<String> {
+    let (base, quote) = {
+        let mut parts = stock_symbol.splitn(2, '-');
+        (parts.next()?, parts.next()?)
+    };
+    if base.is_empty() || !base.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let (_, exchange_quote) = QUOTE_CURRENCIES
+        .iter()
+        .find(|(quote_currency, _)| quote_currency.eq_ignore_ascii_case(quote))?;
+
+    Some(format!("{}{}", base.to_ascii_lowercase(), exchange_quote))
+}
+
+/// Maps each crypto symbol to the latest snapshot of its order book, which is streamed from the
+/// exchange in the background and checked on each tick. `None` symbols close the stream.
+pub fn to_order_books<'a, S, T>(stock_symbols: S, ticks: T) -> ToOrderBooks<S, T>
+where
+    S: Stream<'a, Item = Option<String>>,
+    T: Stream<'a, Context = S::Context>,
+{
+    ToOrderBooks {
+        stock_symbols,
+        ticks,
+    }
+}
+
+pub struct ToOrderBooks<S, T> {
+    stock_symbols: S,
+    ticks: T,
+}
+
+impl<'a, S, T> Stream<'a> for ToOrderBooks<S, T>
+where
+    S: Stream<'a, Item = Option<String>>,
+    T: Stream<'a, Context = S::Context>,
+{
+    type Context = S::Context;
+    type Item = Option<OrderBook>;
+
+    fn subscribe_ctx<O>(self, mut observer: O)
+    where
+        O: 'a + FnMut(&Self::Context, &Self::Item),
+    {
+        let mut order_book_receiver: Option<Receiver<OrderBook>> = None;
+        self.stock_symbols
+            .distinct_until_changed()
+            .map(|stock_symbol| Some(stock_symbol.clone()))
+            .merge(self.ticks.map(|_| None))
+            .subscribe_ctx(move |ctx, stock_symbol| {
+                if let Some(stock_symbol) = stock_symbol {
+                    // Dropping the receiver closes the stream of the previous symbol
+                    order_book_receiver = stock_symbol.as_ref().and_then(|stock_symbol| {
+                        Some(stream_order_books(
+                            stock_symbol.clone(),
+                            exchange_symbol(stock_symbol)?,
+                        ))
+                    });
+
+                    observer(ctx, &None);
+                    return;
+                }
+
+                let receiver = match &order_book_receiver {
+                    Some(receiver) => receiver,
+                    None => return,
+                };
+                let mut order_book = None;
+                loop {
+                    match receiver.try_recv() {
+                        Ok(latest_order_book) => order_book = Some(latest_order_book),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            order_book_receiver = None;
+                            break;
+                        }
+                    }
+                }
+
+                if order_book.is_some() {
+                    observer(ctx, &order_book);
+                }
+            });
+    }
+}
+
+/// Streams the order book from the exchange in a background thread, until the receiver is
+/// dropped. The sender is dropped if the connection fails or is closed by the exchange.
+fn stream_order_books(stock_symbol: String, exchange_symbol: String) -> Receiver<OrderBook> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _span = debug_span!("stream_order_books", %stock_symbol).entered();
+
+        task::block_on(async {
+            let url = format!(
+                "{}/{}@depth{}@100ms",
+                DEPTH_STREAM_URL, exchange_symbol, DEPTH_LEVELS
+            );
+            let mut ws_stream = match connect(&url).await {
+                Ok(ws_stream) => ws_stream,
+                Err(err) => {
+                    warn!("order book stream connection failed: {:?}", err);
+                    return;
+                }
+            };
+
+            while let Some(message) = ws_stream.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Close(_)) => break,
+                    Ok(_) => continue,
+                    Err(err) => {
+                        warn!("order book stream failed: {:?}", err);
+                        break;
+                    }
+                };
+                match serde_json::from_str::<DepthSnapshot>(&text) {
+                    Ok(snapshot) => {
+                        if sender.send(snapshot.to_order_book(&stock_symbol)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        warn!("invalid order book snapshot: {:?}", err);
+                    }
+                }
+            }
+        });
+    });
+
+    receiver
+}
+
+/// Connects to the websocket, through the proxy of the provider layer if there is one.
+async fn connect(url: &str) -> anyhow::Result<WebSocketStream<ConnectStream>> {
+    let proxy = match stock::proxy() {
+        Some(proxy) => proxy,
+        None => {
+            let (ws_stream, _) = connect_async(url).await?;
+            return Ok(ws_stream);
+        }
+    };
+
+    let url = Url::parse(url)?;
+    let host = url.host_str().context("missing host")?;
+    let port = url.port_or_known_default().context("missing port")?;
+    let stream = connect_tunnel(proxy, host, port).await?;
+    let (ws_stream, _) = client_async_tls(url.as_str(), stream).await?;
+
+    Ok(ws_stream)
+}
+
+/// Opens a tunnel to the host through the HTTP proxy, with a `CONNECT` request. Proxies which
+/// require authentication are not supported.
+async fn connect_tunnel(proxy_url: &Url, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    if proxy_url.scheme() != "http" {
+        bail!("unsupported proxy scheme: {}", proxy_url.scheme());
+    }
+    if !proxy_url.username().is_empty() || proxy_url.password().is_some() {
+        bail!("proxy authentication is not supported");
+    }
+    let proxy_host = proxy_url.host_str().context("missing proxy host")?;
+    let proxy_port = proxy_url.port_or_known_default().unwrap_or(80);
+
+    let mut stream = TcpStream::connect(format!("{}:{}", proxy_host, proxy_port)).await?;
+    let request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n\r\n",
+        host = host,
+        port = port
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read a byte at a time, so that nothing sent through the tunnel is consumed
+    let mut response = Vec::new();
+    let mut byte = [0; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_PROXY_RESPONSE_LEN {
+            bail!("proxy response is too long");
+        }
+        stream.read_exact(&mut byte).await?;
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        bail!("proxy refused the tunnel: {}", status_line);
+    }
+
+    Ok(stream)
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("failed to draw the UI")]
+    Draw(#[source] anyhow::Error),
+    #[error("failed to read input events")]
+    InputEvents(#[source] crossterm::ErrorKind),
+    #[error("input events ended unexpectedly")]
+    InputEventsEnded,
+    #[error("failed to write to the terminal")]
+    Terminal(#[source] crossterm::ErrorKind),
+    #[error("failed to restore the terminal")]
+    TerminalCleanup(#[source] crossterm::ErrorKind),
+    #[error("failed to set up the terminal")]
+    TerminalSetup(#[source] crossterm::ErrorKind),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
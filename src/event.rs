@@ -1,24 +1,44 @@
 use crate::{
-    app::UiTarget,
+    app::{Focus, Pane, Screen, UiTarget},
     reactive::{Grouped, StreamExt},
-    widgets::{SelectMenuState, TextFieldState},
+    stock::{ConnectionStatus, DownloadProgress},
+    widgets::{MultiSelectMenuState, PopupState, SelectMenuState, TextFieldState},
 };
 use bimap::BiMap;
-use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use derivative::Derivative;
 use im::{hashmap, hashmap::HashMap};
-use log::debug;
-use reactive_rs::Stream;
-use std::{cell::RefCell, collections::VecDeque, iter, rc::Rc};
+use reactive_rs::{Broadcast, Stream};
+use std::{
+    cell::RefCell,
+    cmp,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
 use tui::layout::Rect;
 
+/// Longest time between the clicks of a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Clone, Copy, Debug)]
 pub enum InputEvent {
     Key(KeyEvent),
     Mouse(MouseEvent),
+    /// Redraws the screen, at the tick rate.
+    Render,
+    /// Polls the data fetched in the background, at a fixed rate.
     Tick,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum AlertEvent {
+    Delete,
+    Snooze,
+    ToggleEnabled,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ChartEvent {
     PanBackward,
@@ -26,6 +46,79 @@ pub enum ChartEvent {
     Reset,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum FinancialsEvent {
+    NextStatement,
+    TogglePeriod,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ScreenEvent {
+    Next,
+    Show(Screen),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TableEvent {
+    Accept,
+    /// A click on one of the visible rows, counting from the first one shown.
+    Click {
+        row: usize,
+        visible_rows: usize,
+    },
+    /// A drag over one of the visible rows, which moves the selected row there.
+    Drag {
+        row: usize,
+        visible_rows: usize,
+    },
+    /// Moves the selected row down, swapping it with the next one.
+    MoveDown,
+    /// Moves the selected row up, swapping it with the previous one.
+    MoveUp,
+    /// A press of the mouse button on one of the visible rows, which selects it to be dragged.
+    Press {
+        row: usize,
+        visible_rows: usize,
+    },
+    SelectNext,
+    SelectPrev,
+    /// Sorts by the column, counting from 0.
+    SortBy(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum StockEvent {
+    RefreshRequested,
+}
+
+/// Change in the fetching of the data for the app.
+#[derive(Clone, Debug)]
+pub enum DataEvent {
+    ConnectionStatus(ConnectionStatus),
+    /// Progress of a large download, or `None` once it is done.
+    DownloadProgress(Option<DownloadProgress>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum HistoryEvent {
+    Back,
+    Forward,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum FocusEvent {
+    Next,
+    Prev,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PaneEvent {
+    GrowBottom,
+    GrowSide,
+    ShrinkBottom,
+    ShrinkSide,
+}
+
 #[derive(Clone, Debug)]
 pub enum TextFieldEvent {
     Accept(String),
@@ -49,8 +142,28 @@ pub enum SelectMenuEvent {
     Toggle,
 }
 
+#[derive(Clone, Debug)]
+pub enum MultiSelectMenuEvent {
+    Accept(Vec<String>),
+    Activate,
+    Deactivate,
+    HighlightIndex(usize),
+    Toggle,
+    ToggleIndex(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PopupEvent {
+    Activate,
+    Deactivate,
+    Scroll(u16),
+    Toggle,
+}
+
 #[derive(Clone, Debug)]
 pub enum OverlayEvent {
+    MultiSelectMenu(MultiSelectMenuEvent),
+    Popup(PopupEvent),
     SelectMenu(SelectMenuEvent),
     TextField(TextFieldEvent),
 }
@@ -63,10 +176,14 @@ pub enum OverlayState {
     Inactive,
 }
 
-pub fn to_grouped_user_input_events<'a, S, U, R, C>(
+/// Groups the user input events by the overlay which they are for, if any. Otherwise, the
+/// navigation keys are grouped by the target of the focused pane, if it has one. Enter on a
+/// focused control is turned into the hotkey of its associated overlay, which opens it.
+pub fn to_grouped_user_input_events<'a, S, U, R, F, C>(
     user_input_events: S,
     ui_target_areas: U,
     active_overlays: R,
+    focuses: F,
     hotkey_overlay_map: BiMap<KeyCode, UiTarget>,
     associated_overlay_map: HashMap<UiTarget, UiTarget>,
 ) -> impl Stream<'a, Item = Grouped<'a, Option<UiTarget>, InputEvent, C>, Context = C>
@@ -74,8 +191,18 @@ where
     S: Stream<'a, Item = InputEvent, Context = C>,
     U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
     R: Stream<'a, Item = Option<UiTarget>>,
+    F: Stream<'a, Item = Focus>,
     C: 'a,
 {
+    let control_hotkey_map: HashMap<UiTarget, KeyCode> = associated_overlay_map
+        .iter()
+        .filter_map(|(ui_target, overlay)| {
+            hotkey_overlay_map
+                .get_by_right(overlay)
+                .map(|hotkey| (*ui_target, *hotkey))
+        })
+        .collect();
+
     user_input_events
         .with_latest_from(
             ui_target_areas
@@ -99,15 +226,39 @@ where
                 (*ev, ui_target_areas.clone(), *active_overlay)
             },
         )
+        .with_latest_from(
+            focuses,
+            move |((ev, ui_target_areas, active_overlay), focus)| {
+                let ev = match (ev, active_overlay, focus.control()) {
+                    (
+                        InputEvent::Key(KeyEvent {
+                            code: KeyCode::Enter,
+                            ..
+                        }),
+                        None,
+                        Some(control),
+                    ) => control_hotkey_map.get(&control).map_or(*ev, |hotkey| {
+                        InputEvent::Key(KeyEvent::new(*hotkey, KeyModifiers::NONE))
+                    }),
+                    _ => *ev,
+                };
+
+                (ev, ui_target_areas.clone(), *active_overlay, *focus)
+            },
+        )
         .group_by(
-            move |(ev, ui_target_areas, active_overlay)| match *ev {
-                InputEvent::Key(KeyEvent { code, .. }) => {
-                    let overlay = match active_overlay {
+            move |(ev, ui_target_areas, active_overlay, focus)| match *ev {
+                InputEvent::Key(KeyEvent { code, modifiers }) => {
+                    let ui_target = match active_overlay {
                         Some(ui_target) => Some(*ui_target),
-                        None => hotkey_overlay_map.get_by_left(&code).copied(),
+                        None => hotkey_overlay_map.get_by_left(&code).copied().or_else(|| {
+                            focus.pane().and_then(Pane::ui_target).filter(|_| {
+                                is_navigation_key(code) && !modifiers.contains(KeyModifiers::ALT)
+                            })
+                        }),
                     };
-                    debug!("key press grouped into overlay: {:?}", overlay);
-                    overlay
+                    debug!("key press grouped into: {:?}", ui_target);
+                    ui_target
                 }
                 InputEvent::Mouse(MouseEvent {
                     kind: MouseEventKind::Up(MouseButton::Left),
@@ -136,26 +287,381 @@ where
         )
 }
 
-pub fn to_chart_events<'a, S, C>(input_events: S) -> impl Stream<'a, Item = ChartEvent, Context = C>
+/// Returns whether the key moves around within a pane, so that it is routed to the focused pane.
+fn is_navigation_key(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Enter
+    )
+}
+
+/// Maps input events to the focus events, which cycle through the focusable panes and controls.
+pub fn to_focus_events<'a, S, C>(input_events: S) -> impl Stream<'a, Item = FocusEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    C: 'a + Clone,
+{
+    input_events.filter_map(|ev| match ev {
+        InputEvent::Key(KeyEvent { code, .. }) => match code {
+            KeyCode::Tab => Some(FocusEvent::Next),
+            KeyCode::BackTab => Some(FocusEvent::Prev),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Maps input events to the history events, which go back and forward through the viewed symbols.
+pub fn to_history_events<'a, S, C>(
+    input_events: S,
+) -> impl Stream<'a, Item = HistoryEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    C: 'a + Clone,
+{
+    input_events.filter_map(|ev| match ev {
+        InputEvent::Key(KeyEvent { code, modifiers }) if modifiers.contains(KeyModifiers::ALT) => {
+            match code {
+                KeyCode::Left => Some(HistoryEvent::Back),
+                KeyCode::Right => Some(HistoryEvent::Forward),
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Maps input events to chart events. Double-clicking the chart resets it, like pressing End.
+pub fn to_chart_events<'a, S, U, C>(
+    input_events: S,
+    ui_target_areas: U,
+) -> impl Stream<'a, Item = ChartEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    input_events
+        .with_latest_from(
+            ui_target_areas.filter(|(ui_target, _)| *ui_target == UiTarget::Chart),
+            |(ev, (_, chart_area))| (*ev, *chart_area),
+        )
+        .fold(
+            (None, None),
+            |(last_click, _): &(Option<(Instant, (u16, u16))>, Option<ChartEvent>),
+             (ev, chart_area)| match ev {
+                // Alt+Left and Alt+Right navigate the symbol history instead
+                InputEvent::Key(KeyEvent { code, modifiers }) => (
+                    *last_click,
+                    match code {
+                        _ if modifiers.contains(KeyModifiers::ALT) => None,
+                        KeyCode::Left => Some(ChartEvent::PanBackward),
+                        KeyCode::Right => Some(ChartEvent::PanForward),
+                        KeyCode::End => Some(ChartEvent::Reset),
+                        KeyCode::PageUp => Some(ChartEvent::PanBackward),
+                        KeyCode::PageDown => Some(ChartEvent::PanForward),
+                        _ => None,
+                    },
+                ),
+                &InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::Up(MouseButton::Left),
+                    column: x,
+                    row: y,
+                    ..
+                }) if chart_area.map_or(false, |area| {
+                    area.left() <= x && area.right() > x && area.top() <= y && area.bottom() > y
+                }) =>
+                {
+                    let now = Instant::now();
+                    match last_click {
+                        // The second click of a double-click must be at the same point as the first
+                        Some((clicked_at, point))
+                            if *point == (x, y) && now - *clicked_at <= DOUBLE_CLICK_INTERVAL =>
+                        {
+                            (None, Some(ChartEvent::Reset))
+                        }
+                        _ => (Some((now, (x, y))), None),
+                    }
+                }
+                _ => (*last_click, None),
+            },
+        )
+        .filter_map(|(_, chart_event)| *chart_event)
+}
+
+pub fn to_pane_events<'a, S, C>(input_events: S) -> impl Stream<'a, Item = PaneEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    C: 'a + Clone,
+{
+    input_events.filter_map(|ev| match ev {
+        InputEvent::Key(KeyEvent { code, .. }) => match code {
+            KeyCode::Char('[') => Some(PaneEvent::ShrinkSide),
+            KeyCode::Char(']') => Some(PaneEvent::GrowSide),
+            KeyCode::Char('{') => Some(PaneEvent::ShrinkBottom),
+            KeyCode::Char('}') => Some(PaneEvent::GrowBottom),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Maps input events to screen events. Clicking the sector in the header shows the sectors
+/// screen.
+pub fn to_screen_events<'a, S, U, C>(
+    input_events: S,
+    ui_target_areas: U,
+) -> impl Stream<'a, Item = ScreenEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    input_events
+        .with_latest_from(
+            ui_target_areas.filter(|(ui_target, _)| *ui_target == UiTarget::SectorButton),
+            |(ev, (_, sector_button_area))| (*ev, *sector_button_area),
+        )
+        .filter_map(|(ev, sector_button_area)| match ev {
+            InputEvent::Key(KeyEvent {
+                code: KeyCode::Char('o'),
+                ..
+            }) => Some(ScreenEvent::Next),
+            &InputEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Left),
+                column: x,
+                row: y,
+                ..
+            }) => sector_button_area
+                .filter(|area| {
+                    area.left() <= x && area.right() > x && area.top() <= y && area.bottom() > y
+                })
+                .map(|_| ScreenEvent::Show(Screen::Sectors)),
+            _ => None,
+        })
+}
+
+/// Maps input events to the actions on the selected alert, while the alerts table is shown.
+pub fn to_alert_events<'a, S, U, C>(
+    input_events: S,
+    ui_target_areas: U,
+) -> impl Stream<'a, Item = AlertEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    input_events
+        .with_latest_from(
+            ui_target_areas.filter(|(ui_target, _)| *ui_target == UiTarget::AlertTable),
+            |(ev, (_, alert_table_area))| (*ev, alert_table_area.is_some()),
+        )
+        .filter_map(|(ev, alert_table_shown)| match ev {
+            InputEvent::Key(KeyEvent { code, .. }) if *alert_table_shown => match code {
+                KeyCode::Delete => Some(AlertEvent::Delete),
+                KeyCode::Char('z') => Some(AlertEvent::Snooze),
+                KeyCode::Char(' ') => Some(AlertEvent::ToggleEnabled),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// Maps input events to the choice of the financial statement, while the financials table is
+/// shown.
+pub fn to_financials_events<'a, S, U, C>(
+    input_events: S,
+    ui_target_areas: U,
+) -> impl Stream<'a, Item = FinancialsEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    input_events
+        .with_latest_from(
+            ui_target_areas.filter(|(ui_target, _)| *ui_target == UiTarget::FinancialsTable),
+            |(ev, (_, financials_table_area))| (*ev, financials_table_area.is_some()),
+        )
+        .filter_map(|(ev, financials_table_shown)| match ev {
+            InputEvent::Key(KeyEvent { code, .. }) if *financials_table_shown => match code {
+                KeyCode::Tab => Some(FinancialsEvent::NextStatement),
+                KeyCode::Char('y') => Some(FinancialsEvent::TogglePeriod),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// Maps table events, along with the number of rows at the time, to the index of the selected
+/// row, if any.
+pub fn to_table_selections<'a, S, C>(
+    table_events: S,
+) -> impl Stream<'a, Item = Option<usize>, Context = C>
+where
+    S: Stream<'a, Item = (TableEvent, usize), Context = C>,
+    C: 'a + Clone,
+{
+    table_events
+        .fold(None, |acc_selection, (ev, row_count)| {
+            select_table_row(*acc_selection, *ev, *row_count)
+        })
+        .distinct_until_changed()
+}
+
+/// Maps table events, along with the number of rows at the time, to the moves of the selected row,
+/// as the indices of the row before and after. The selected row follows the moves, so that it is
+/// the same as with [`to_table_selections`].
+pub fn to_table_row_moves<'a, S, C>(
+    table_events: S,
+) -> impl Stream<'a, Item = (usize, usize), Context = C>
+where
+    S: Stream<'a, Item = (TableEvent, usize), Context = C>,
+    C: 'a + Clone,
+{
+    table_events
+        .fold((None, None), |(acc_selection, _), (ev, row_count)| {
+            let selection = select_table_row(*acc_selection, *ev, *row_count);
+            let row_move = match ev {
+                TableEvent::Drag { .. } | TableEvent::MoveDown | TableEvent::MoveUp => {
+                    acc_selection.zip(selection).filter(|(from, to)| from != to)
+                }
+                _ => None,
+            };
+            (selection, row_move)
+        })
+        .filter_map(|(_, row_move)| *row_move)
+}
+
+fn select_table_row(selection: Option<usize>, ev: TableEvent, row_count: usize) -> Option<usize> {
+    match ev {
+        TableEvent::Accept | TableEvent::SortBy(_) => selection,
+        TableEvent::Click { row, visible_rows }
+        | TableEvent::Drag { row, visible_rows }
+        | TableEvent::Press { row, visible_rows } => {
+            // The rows are scrolled just enough to keep the selection in view
+            let offset = selection.map_or(0, |n| (n + 1).saturating_sub(visible_rows));
+            Some(offset + row).filter(|&n| n < row_count).or(selection)
+        }
+        TableEvent::MoveDown | TableEvent::SelectNext => row_count
+            .checked_sub(1)
+            .map(|last| selection.map_or(0, |n| cmp::min(n + 1, last))),
+        TableEvent::MoveUp | TableEvent::SelectPrev => {
+            selection.map_or_else(|| row_count.checked_sub(1), |n| Some(n.saturating_sub(1)))
+        }
+    }
+}
+
+/// Maps input events to events of the table with the given target. Clicks and drags are resolved
+/// to rows by the caller, which knows how far the table has been scrolled.
+pub fn to_table_events<'a, S, U, C>(
+    input_events: S,
+    ui_target_areas: U,
+    self_ui_target: UiTarget,
+) -> impl Stream<'a, Item = TableEvent, Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    input_events
+        .with_latest_from(
+            ui_target_areas.filter(move |(ui_target, _)| *ui_target == self_ui_target),
+            |(ev, (_, table_area))| (*ev, *table_area),
+        )
+        .filter_map(|(ev, table_area)| {
+            // Nothing to select while the table is not shown
+            let table_area = (*table_area)?;
+
+            match ev {
+                InputEvent::Key(KeyEvent { code, modifiers }) => match code {
+                    KeyCode::Enter => Some(TableEvent::Accept),
+                    KeyCode::Up if modifiers.contains(KeyModifiers::SHIFT) => {
+                        Some(TableEvent::MoveUp)
+                    }
+                    KeyCode::Down if modifiers.contains(KeyModifiers::SHIFT) => {
+                        Some(TableEvent::MoveDown)
+                    }
+                    KeyCode::Up => Some(TableEvent::SelectPrev),
+                    KeyCode::Down => Some(TableEvent::SelectNext),
+                    // The columns are numbered from 1 for sorting
+                    KeyCode::Char(c @ '1'..='9') => {
+                        Some(TableEvent::SortBy(*c as usize - '1' as usize))
+                    }
+                    _ => None,
+                },
+                &InputEvent::Mouse(MouseEvent {
+                    kind,
+                    column: x,
+                    row: y,
+                    ..
+                }) => {
+                    // Skip the top border and the header
+                    let rows_top = table_area.top() + 2;
+                    let rows_bottom = table_area.bottom().saturating_sub(1);
+                    if table_area.left() < x
+                        && table_area.right().saturating_sub(1) > x
+                        && rows_top <= y
+                        && rows_bottom > y
+                    {
+                        let row = (y - rows_top) as usize;
+                        let visible_rows = (rows_bottom - rows_top) as usize;
+                        match kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                Some(TableEvent::Press { row, visible_rows })
+                            }
+                            MouseEventKind::Drag(MouseButton::Left) => {
+                                Some(TableEvent::Drag { row, visible_rows })
+                            }
+                            MouseEventKind::Up(MouseButton::Left) => {
+                                Some(TableEvent::Click { row, visible_rows })
+                            }
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        })
+}
+
+pub fn to_stock_events<'a, S, C>(input_events: S) -> impl Stream<'a, Item = StockEvent, Context = C>
 where
     S: Stream<'a, Item = InputEvent, Context = C>,
     C: 'a + Clone,
 {
     input_events.filter_map(|ev| match ev {
         InputEvent::Key(KeyEvent { code, .. }) => match code {
-            KeyCode::Left => Some(ChartEvent::PanBackward),
-            KeyCode::Right => Some(ChartEvent::PanForward),
-            KeyCode::End => Some(ChartEvent::Reset),
-            KeyCode::PageUp => Some(ChartEvent::PanBackward),
-            KeyCode::PageDown => Some(ChartEvent::PanForward),
+            KeyCode::Char('r') => Some(StockEvent::RefreshRequested),
             _ => None,
         },
         _ => None,
     })
 }
 
-pub fn to_text_field_events<'a, S, O, U, F, C>(
+/// Input of a text field, which is either an input event or a request to start editing a value.
+#[derive(Clone, Debug)]
+enum TextFieldInput {
+    Edit(String),
+    Event(InputEvent),
+}
+
+/// Maps input events to text field events. Each of the values requested activates the text
+/// field with that value, so that it can be edited.
+#[allow(clippy::too_many_arguments)]
+pub fn to_text_field_events<'a, S, V, O, U, F, P, C>(
     input_events: S,
+    value_requests: V,
     init_text_field_state: TextFieldState,
     overlay_states: O,
     activation_hotkey: KeyCode,
@@ -163,12 +669,15 @@ pub fn to_text_field_events<'a, S, O, U, F, C>(
     self_ui_target: UiTarget,
     text_field_event_map: HashMap<Option<UiTarget>, TextFieldEvent>,
     map_value_func: F,
+    is_valid_value_func: P,
 ) -> impl Stream<'a, Item = (TextFieldEvent, TextFieldState), Context = C>
 where
     S: Stream<'a, Item = InputEvent, Context = C>,
+    V: Stream<'a, Item = String, Context = C>,
     O: Stream<'a, Item = OverlayState>,
     U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
     F: 'a + Clone + FnOnce(String) -> String,
+    P: 'a + Clone + FnOnce(&str) -> bool,
     C: 'a + Clone,
 {
     let text_field_event_map = text_field_event_map.without(&Some(self_ui_target));
@@ -190,13 +699,17 @@ where
         });
 
     input_events
+        .map(|ev| TextFieldInput::Event(*ev))
+        .merge(value_requests.map(|value| TextFieldInput::Edit(value.clone())))
         .combine_latest(
             overlay_states.distinct_until_changed(),
-            |(ev, overlay_state)| (*ev, *overlay_state),
+            |(input, overlay_state)| (input.clone(), *overlay_state),
         )
         .with_latest_from(
             ui_target_area_bufs,
-            |((ev, overlay_state), ui_target_areas)| (*ev, *overlay_state, ui_target_areas.clone()),
+            |((input, overlay_state), ui_target_areas)| {
+                (input.clone(), *overlay_state, ui_target_areas.clone())
+            },
         )
         .fold(
             (
@@ -206,7 +719,7 @@ where
                 OverlayState::default(),
             ),
             move |(_, acc_text_field_state, acc_saved_text_field_state, acc_overlay_state),
-                  (ev, overlay_state, ui_target_areas)| {
+                  (input, overlay_state, ui_target_areas)| {
                 let noop = || {
                     (
                         None,
@@ -248,18 +761,40 @@ where
                     };
                 }
 
+                let ev = match input {
+                    TextFieldInput::Edit(value) => {
+                        return (
+                            Some(TextFieldEvent::Activate),
+                            TextFieldState {
+                                active: true,
+                                cursor_offset: value.chars().count(),
+                                history_index: None,
+                                value: value.clone(),
+                                ..acc_text_field_state.clone()
+                            },
+                            acc_saved_text_field_state.clone(),
+                            *overlay_state,
+                        );
+                    }
+                    TextFieldInput::Event(ev) => ev,
+                };
+
                 match ev {
-                    InputEvent::Key(KeyEvent { code, .. }) => match code {
+                    InputEvent::Key(KeyEvent { code, modifiers }) => match code {
                         KeyCode::Enter
                             if acc_text_field_state.active
-                                && !acc_text_field_state.value.is_empty() =>
+                                && !acc_text_field_state.value.is_empty()
+                                && is_valid_value_func.clone()(
+                                    acc_text_field_state.value.trim(),
+                                ) =>
                         {
+                            let value = acc_text_field_state.value.trim().to_owned();
+                            let mut saved_text_field_state = acc_saved_text_field_state.clone();
+                            saved_text_field_state.push_history(value.clone());
                             (
-                                Some(TextFieldEvent::Accept(
-                                    acc_text_field_state.value.trim().to_owned(),
-                                )),
-                                acc_saved_text_field_state.clone(),
-                                acc_saved_text_field_state.clone(),
+                                Some(TextFieldEvent::Accept(value)),
+                                saved_text_field_state.clone(),
+                                saved_text_field_state,
                                 *overlay_state,
                             )
                         }
@@ -269,185 +804,42 @@ where
                             acc_saved_text_field_state.clone(),
                             *overlay_state,
                         ),
-                        KeyCode::Backspace
-                            if acc_text_field_state.active
-                                && acc_text_field_state.cursor_offset == 0 =>
+                        &key_code
+                            if key_code == activation_hotkey && !acc_text_field_state.active =>
                         {
                             (
-                                Some(TextFieldEvent::BackspacePastStart),
-                                acc_text_field_state.clone(),
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Backspace if acc_text_field_state.active => {
-                            let mut value = acc_text_field_state.value.clone();
-                            let cursor_offset = acc_text_field_state.cursor_offset;
-                            debug_assert!(cursor_offset > 0);
-                            if cursor_offset == value.chars().count() {
-                                value.pop();
-                            } else {
-                                value = value
-                                    .chars()
-                                    .take(cursor_offset - 1)
-                                    .chain(value.chars().skip(cursor_offset))
-                                    .collect();
-                            }
-                            let map_value_func = map_value_func.clone();
-                            let value = map_value_func(value);
-                            (
-                                Some(TextFieldEvent::Input(value.clone())),
+                                Some(TextFieldEvent::Activate),
                                 TextFieldState {
-                                    cursor_offset: cursor_offset - 1,
-                                    value,
-                                    ..*acc_text_field_state
+                                    active: true,
+                                    ..acc_text_field_state.clone()
                                 },
                                 acc_saved_text_field_state.clone(),
                                 *overlay_state,
                             )
                         }
-                        KeyCode::Delete
-                            if acc_text_field_state.active
-                                && acc_text_field_state.cursor_offset
-                                    == acc_text_field_state.value.chars().count() =>
-                        {
-                            (
-                                Some(TextFieldEvent::DeletePastEnd),
-                                acc_text_field_state.clone(),
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Delete if acc_text_field_state.active => {
-                            let mut value = acc_text_field_state.value.clone();
-                            let cursor_offset = acc_text_field_state.cursor_offset;
-                            debug_assert!(cursor_offset < value.chars().count());
-                            value = value
-                                .chars()
-                                .take(cursor_offset)
-                                .chain(value.chars().skip(cursor_offset + 1))
-                                .collect();
-                            let map_value_func = map_value_func.clone();
-                            let value = map_value_func(value);
-                            (
-                                Some(TextFieldEvent::Input(value.clone())),
-                                TextFieldState {
-                                    value,
-                                    ..*acc_text_field_state
-                                },
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Left
-                            if acc_text_field_state.active
-                                && acc_text_field_state.cursor_offset == 0 =>
-                        {
-                            (
-                                Some(TextFieldEvent::MoveCursorPastStart),
-                                acc_text_field_state.clone(),
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Left if acc_text_field_state.active => {
-                            let cursor_offset = acc_text_field_state.cursor_offset;
-                            debug_assert!(cursor_offset > 0);
-                            (
-                                Some(TextFieldEvent::MoveCursor(cursor_offset - 1)),
-                                TextFieldState {
-                                    cursor_offset: cursor_offset - 1,
-                                    ..acc_text_field_state.clone()
-                                },
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Right
-                            if acc_text_field_state.active
-                                && acc_text_field_state.cursor_offset
-                                    == acc_text_field_state.value.chars().count() =>
-                        {
-                            (
-                                Some(TextFieldEvent::MoveCursorPastEnd),
-                                acc_text_field_state.clone(),
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Right if acc_text_field_state.active => {
-                            let cursor_offset = acc_text_field_state.cursor_offset;
-                            debug_assert!(
-                                cursor_offset < acc_text_field_state.value.chars().count()
-                            );
-                            (
-                                Some(TextFieldEvent::MoveCursor(cursor_offset + 1)),
-                                TextFieldState {
-                                    cursor_offset: cursor_offset + 1,
-                                    ..acc_text_field_state.clone()
-                                },
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Home if acc_text_field_state.active => (
-                            Some(TextFieldEvent::MoveCursor(0)),
-                            TextFieldState {
-                                cursor_offset: 0,
-                                ..acc_text_field_state.clone()
-                            },
-                            acc_saved_text_field_state.clone(),
-                            *overlay_state,
-                        ),
-                        KeyCode::End if acc_text_field_state.active => (
-                            Some(TextFieldEvent::MoveCursor(
-                                acc_text_field_state.value.chars().count(),
-                            )),
-                            TextFieldState {
-                                cursor_offset: acc_text_field_state.value.chars().count(),
-                                ..acc_text_field_state.clone()
-                            },
-                            acc_saved_text_field_state.clone(),
-                            *overlay_state,
-                        ),
-                        &key_code
-                            if key_code == activation_hotkey && !acc_text_field_state.active =>
-                        {
-                            (
-                                Some(TextFieldEvent::Activate),
-                                TextFieldState {
-                                    active: true,
-                                    ..acc_text_field_state.clone()
-                                },
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
-                        }
-                        KeyCode::Char(c) if acc_text_field_state.active => {
-                            let mut value = acc_text_field_state.value.clone();
-                            let cursor_offset = acc_text_field_state.cursor_offset;
-                            if cursor_offset == value.chars().count() {
-                                value.push(*c);
-                            } else {
-                                value = value
-                                    .chars()
-                                    .take(cursor_offset)
-                                    .chain(iter::once(*c))
-                                    .chain(value.chars().skip(cursor_offset))
-                                    .collect();
+                        _ if acc_text_field_state.active => {
+                            let edit = match code {
+                                KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+                                    let pasted_text = read_clipboard_text();
+                                    if pasted_text.is_empty() {
+                                        return noop();
+                                    }
+                                    Some(TextFieldEdit::Insert(pasted_text))
+                                }
+                                _ => TextFieldEdit::from_key_code(*code),
+                            };
+
+                            match edit.and_then(|edit| {
+                                edit_text_field(acc_text_field_state, &edit, map_value_func.clone())
+                            }) {
+                                Some((ev, text_field_state)) => (
+                                    Some(ev),
+                                    text_field_state,
+                                    acc_saved_text_field_state.clone(),
+                                    *overlay_state,
+                                ),
+                                None => noop(),
                             }
-                            let map_value_func = map_value_func.clone();
-                            let value = map_value_func(value);
-                            (
-                                Some(TextFieldEvent::Input(value.clone())),
-                                TextFieldState {
-                                    cursor_offset: cursor_offset + 1,
-                                    value,
-                                    ..*acc_text_field_state
-                                },
-                                acc_saved_text_field_state.clone(),
-                                *overlay_state,
-                            )
                         }
                         _ => noop(),
                     },
@@ -528,6 +920,163 @@ where
         })
 }
 
+/// Edit of the value or the cursor position of an active text field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextFieldEdit {
+    /// Deletes the character before the cursor.
+    Backspace,
+    /// Deletes the character at the cursor.
+    Delete,
+    /// Inserts the text at the cursor, moving the cursor past it.
+    Insert(String),
+    MoveCursorEnd,
+    MoveCursorHome,
+    MoveCursorLeft,
+    MoveCursorRight,
+    /// Recalls the next value in the history, or an empty value past the most recent one.
+    RecallNext,
+    /// Recalls the previous value in the history, starting from the most recent one.
+    RecallPrevious,
+}
+
+impl TextFieldEdit {
+    /// Returns the edit of the key, if any. Pasting is left out, as it reads the clipboard.
+    pub fn from_key_code(code: KeyCode) -> Option<Self> {
+        match code {
+            KeyCode::Backspace => Some(Self::Backspace),
+            KeyCode::Char(c) => Some(Self::Insert(c.to_string())),
+            KeyCode::Delete => Some(Self::Delete),
+            KeyCode::Down => Some(Self::RecallNext),
+            KeyCode::End => Some(Self::MoveCursorEnd),
+            KeyCode::Home => Some(Self::MoveCursorHome),
+            KeyCode::Left => Some(Self::MoveCursorLeft),
+            KeyCode::Right => Some(Self::MoveCursorRight),
+            KeyCode::Up => Some(Self::RecallPrevious),
+            _ => None,
+        }
+    }
+}
+
+/// Applies the edit to the state of an active text field, returning the text field event and the
+/// state after the edit. The value is mapped by the function whenever it is changed, with the
+/// cursor kept within the mapped value. Returns `None` if there is no history to recall.
+pub fn edit_text_field<F>(
+    text_field_state: &TextFieldState,
+    edit: &TextFieldEdit,
+    map_value_func: F,
+) -> Option<(TextFieldEvent, TextFieldState)>
+where
+    F: FnOnce(String) -> String,
+{
+    let value = &text_field_state.value;
+    let char_count = value.chars().count();
+    let cursor_offset = text_field_state.cursor_offset;
+
+    let with_value = |value: String, cursor_offset: usize| {
+        let value = map_value_func(value);
+        (
+            TextFieldEvent::Input(value.clone()),
+            TextFieldState {
+                cursor_offset: cmp::min(cursor_offset, value.chars().count()),
+                value,
+                ..text_field_state.clone()
+            },
+        )
+    };
+    let with_cursor_offset = |cursor_offset: usize| {
+        (
+            TextFieldEvent::MoveCursor(cursor_offset),
+            TextFieldState {
+                cursor_offset,
+                ..text_field_state.clone()
+            },
+        )
+    };
+
+    match edit {
+        TextFieldEdit::Backspace if cursor_offset == 0 => {
+            Some((TextFieldEvent::BackspacePastStart, text_field_state.clone()))
+        }
+        TextFieldEdit::Backspace => Some(with_value(
+            value
+                .chars()
+                .take(cursor_offset - 1)
+                .chain(value.chars().skip(cursor_offset))
+                .collect(),
+            cursor_offset - 1,
+        )),
+        TextFieldEdit::Delete if cursor_offset >= char_count => {
+            Some((TextFieldEvent::DeletePastEnd, text_field_state.clone()))
+        }
+        TextFieldEdit::Delete => Some(with_value(
+            value
+                .chars()
+                .take(cursor_offset)
+                .chain(value.chars().skip(cursor_offset + 1))
+                .collect(),
+            cursor_offset,
+        )),
+        TextFieldEdit::Insert(text) => Some(with_value(
+            text_field_state.value_with_inserted(text),
+            cursor_offset + text.chars().count(),
+        )),
+        TextFieldEdit::MoveCursorEnd => Some(with_cursor_offset(char_count)),
+        TextFieldEdit::MoveCursorHome => Some(with_cursor_offset(0)),
+        TextFieldEdit::MoveCursorLeft if cursor_offset == 0 => Some((
+            TextFieldEvent::MoveCursorPastStart,
+            text_field_state.clone(),
+        )),
+        TextFieldEdit::MoveCursorLeft => Some(with_cursor_offset(cursor_offset - 1)),
+        TextFieldEdit::MoveCursorRight if cursor_offset >= char_count => {
+            Some((TextFieldEvent::MoveCursorPastEnd, text_field_state.clone()))
+        }
+        TextFieldEdit::MoveCursorRight => Some(with_cursor_offset(cursor_offset + 1)),
+        TextFieldEdit::RecallNext => {
+            let history_index = text_field_state
+                .history_index?
+                .checked_add(1)
+                .filter(|&i| i < text_field_state.history.len());
+            let text_field_state = text_field_state.recall_history(history_index);
+
+            Some((
+                TextFieldEvent::Input(text_field_state.value.clone()),
+                text_field_state,
+            ))
+        }
+        TextFieldEdit::RecallPrevious => {
+            let history_index = match text_field_state.history_index {
+                Some(i) => i.checked_sub(1)?,
+                None => text_field_state.history.len().checked_sub(1)?,
+            };
+            let text_field_state = text_field_state.recall_history(Some(history_index));
+
+            Some((
+                TextFieldEvent::Input(text_field_state.value.clone()),
+                text_field_state,
+            ))
+        }
+    }
+}
+
+/// Reads the first line of text from the system clipboard, without any control characters.
+fn read_clipboard_text() -> String {
+    let text = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text());
+
+    match text {
+        Ok(text) => text
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect(),
+        Err(err) => {
+            warn!("clipboard read failed: {:?}", err);
+            String::new()
+        }
+    }
+}
+
 pub fn to_select_menu_events<'a, S, V, O, U, C>(
     input_events: S,
     init_select_menu_state: SelectMenuState<V>,
@@ -544,6 +1093,48 @@ where
     U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
     C: 'a + Clone,
 {
+    to_dynamic_select_menu_events(
+        input_events,
+        init_select_menu_state,
+        Broadcast::<(), Vec<V>>::new(),
+        overlay_states,
+        activation_hotkey,
+        ui_target_areas,
+        self_ui_target,
+        select_menu_event_map,
+    )
+}
+
+/// Like [`to_select_menu_events`], but the menu items are replaced with the latest item list
+/// whenever the menu is activated.
+#[allow(clippy::too_many_arguments)]
+pub fn to_dynamic_select_menu_events<'a, S, V, I, O, U, C>(
+    input_events: S,
+    init_select_menu_state: SelectMenuState<V>,
+    item_lists: I,
+    overlay_states: O,
+    activation_hotkey: KeyCode,
+    ui_target_areas: U,
+    self_ui_target: UiTarget,
+    select_menu_event_map: HashMap<Option<UiTarget>, SelectMenuEvent>,
+) -> impl Stream<'a, Item = (SelectMenuEvent, SelectMenuState<V>), Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    V: 'a + Clone + PartialEq + ToString,
+    I: Stream<'a, Item = Vec<V>>,
+    O: Stream<'a, Item = OverlayState>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    let latest_items: Rc<RefCell<Option<Vec<V>>>> = Rc::new(RefCell::new(None));
+
+    item_lists.subscribe({
+        let latest_items = latest_items.clone();
+        move |items| {
+            latest_items.borrow_mut().replace(items.clone());
+        }
+    });
+
     let select_menu_event_map = select_menu_event_map.without(&Some(self_ui_target));
 
     let ui_target_area_bufs = ui_target_areas
@@ -590,6 +1181,15 @@ where
                     )
                 };
 
+                let activated = || {
+                    let mut select_menu_state = acc_saved_select_menu_state.clone();
+                    if let Some(items) = latest_items.borrow().as_ref() {
+                        select_menu_state.set_items(items.clone());
+                    }
+                    select_menu_state.active = true;
+                    select_menu_state
+                };
+
                 let overlay_state_transitioned = acc_overlay_state != overlay_state;
                 if overlay_state_transitioned {
                     let overlay_state_changed = match overlay_state {
@@ -603,11 +1203,7 @@ where
                     return match (acc_overlay_state, overlay_state) {
                         (OverlayState::Inactive, OverlayState::Active) => (
                             Some(SelectMenuEvent::Activate),
-                            {
-                                let mut select_menu_state = acc_saved_select_menu_state.clone();
-                                select_menu_state.active = true;
-                                select_menu_state
-                            },
+                            activated(),
                             acc_saved_select_menu_state.clone(),
                             *overlay_state,
                         ),
@@ -681,11 +1277,7 @@ where
                         {
                             (
                                 Some(SelectMenuEvent::Activate),
-                                {
-                                    let mut select_menu_state = acc_saved_select_menu_state.clone();
-                                    select_menu_state.active = true;
-                                    select_menu_state
-                                },
+                                activated(),
                                 acc_saved_select_menu_state.clone(),
                                 *overlay_state,
                             )
@@ -745,12 +1337,7 @@ where
                                 {
                                     (
                                         Some(SelectMenuEvent::Activate),
-                                        {
-                                            let mut select_menu_state =
-                                                acc_saved_select_menu_state.clone();
-                                            select_menu_state.active = true;
-                                            select_menu_state
-                                        },
+                                        activated(),
                                         acc_saved_select_menu_state.clone(),
                                         *overlay_state,
                                     )
@@ -776,12 +1363,7 @@ where
                                 }
                                 Some(SelectMenuEvent::Toggle) if !acc_select_menu_state.active => (
                                     Some(SelectMenuEvent::Activate),
-                                    {
-                                        let mut select_menu_state =
-                                            acc_saved_select_menu_state.clone();
-                                        select_menu_state.active = true;
-                                        select_menu_state
-                                    },
+                                    activated(),
                                     acc_saved_select_menu_state.clone(),
                                     *overlay_state,
                                 ),
@@ -803,9 +1385,427 @@ where
         })
 }
 
+/// Maps input events to multi-select menu events.
+///
+/// Space toggles the highlighted item, and Enter accepts the checked items. As there is no other
+/// way to accept with the mouse, clicking outside of an active menu accepts the checked items
+/// instead of discarding them.
+pub fn to_multi_select_menu_events<'a, S, V, O, U, C>(
+    input_events: S,
+    init_multi_select_menu_state: MultiSelectMenuState<V>,
+    overlay_states: O,
+    activation_hotkey: KeyCode,
+    ui_target_areas: U,
+    self_ui_target: UiTarget,
+    multi_select_menu_event_map: HashMap<Option<UiTarget>, MultiSelectMenuEvent>,
+) -> impl Stream<'a, Item = (MultiSelectMenuEvent, MultiSelectMenuState<V>), Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    V: 'a + Clone + PartialEq + ToString,
+    O: Stream<'a, Item = OverlayState>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    let multi_select_menu_event_map = multi_select_menu_event_map.without(&Some(self_ui_target));
+
+    let ui_target_area_bufs = ui_target_areas
+        .filter({
+            let multi_select_menu_event_map = multi_select_menu_event_map.clone();
+            move |(ui_target, _)| {
+                *ui_target == self_ui_target
+                    || multi_select_menu_event_map.contains_key(&Some(*ui_target))
+            }
+        })
+        .buffer(multi_select_menu_event_map.without(&None).len() + 1)
+        .map(move |ui_target_areas| {
+            ui_target_areas
+                .iter()
+                .filter_map(|(ui_target, area)| area.map(|area| (*ui_target, area)))
+                .rev()
+                .collect::<Vec<_>>()
+        });
+
+    input_events
+        .combine_latest(
+            overlay_states.distinct_until_changed(),
+            |(ev, overlay_state)| (*ev, *overlay_state),
+        )
+        .with_latest_from(
+            ui_target_area_bufs,
+            |((ev, overlay_state), ui_target_areas)| (*ev, *overlay_state, ui_target_areas.clone()),
+        )
+        .fold(
+            (
+                None,
+                init_multi_select_menu_state.clone(),
+                init_multi_select_menu_state,
+                OverlayState::default(),
+            ),
+            move |(_, acc_menu_state, acc_saved_menu_state, acc_overlay_state),
+                  (ev, overlay_state, ui_target_areas)| {
+                let noop = || {
+                    (
+                        None,
+                        acc_menu_state.clone(),
+                        acc_saved_menu_state.clone(),
+                        *overlay_state,
+                    )
+                };
+
+                let activated = || {
+                    let mut menu_state = acc_saved_menu_state.clone();
+                    menu_state.active = true;
+                    menu_state
+                };
+
+                let accepted = || {
+                    let menu_state = {
+                        let mut menu_state = acc_menu_state.clone();
+                        menu_state.active = false;
+                        menu_state
+                    };
+                    (
+                        Some(MultiSelectMenuEvent::Accept(
+                            menu_state
+                                .selected()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        )),
+                        menu_state.clone(),
+                        menu_state,
+                        *overlay_state,
+                    )
+                };
+
+                let overlay_state_transitioned = acc_overlay_state != overlay_state;
+                if overlay_state_transitioned {
+                    let overlay_state_changed = match overlay_state {
+                        OverlayState::Active => !acc_menu_state.active,
+                        OverlayState::Inactive => acc_menu_state.active,
+                    };
+                    if !overlay_state_changed {
+                        return noop();
+                    }
+
+                    return match (acc_overlay_state, overlay_state) {
+                        (OverlayState::Inactive, OverlayState::Active) => (
+                            Some(MultiSelectMenuEvent::Activate),
+                            activated(),
+                            acc_saved_menu_state.clone(),
+                            *overlay_state,
+                        ),
+                        (OverlayState::Active, OverlayState::Inactive) => (
+                            Some(MultiSelectMenuEvent::Deactivate),
+                            acc_saved_menu_state.clone(),
+                            acc_saved_menu_state.clone(),
+                            *overlay_state,
+                        ),
+                        _ => {
+                            unreachable!();
+                        }
+                    };
+                }
+
+                match ev {
+                    InputEvent::Key(KeyEvent { code, .. }) => match code {
+                        KeyCode::Enter if acc_menu_state.active => accepted(),
+                        KeyCode::Esc if acc_menu_state.active => (
+                            Some(MultiSelectMenuEvent::Deactivate),
+                            acc_saved_menu_state.clone(),
+                            acc_saved_menu_state.clone(),
+                            *overlay_state,
+                        ),
+                        KeyCode::Up if acc_menu_state.active => {
+                            let menu_state = {
+                                let mut menu_state = acc_menu_state.clone();
+                                menu_state.highlight_prev().unwrap();
+                                menu_state
+                            };
+                            (
+                                Some(MultiSelectMenuEvent::HighlightIndex(
+                                    menu_state.highlighted_index().unwrap(),
+                                )),
+                                menu_state,
+                                acc_saved_menu_state.clone(),
+                                *overlay_state,
+                            )
+                        }
+                        KeyCode::Down if acc_menu_state.active => {
+                            let menu_state = {
+                                let mut menu_state = acc_menu_state.clone();
+                                menu_state.highlight_next().unwrap();
+                                menu_state
+                            };
+                            (
+                                Some(MultiSelectMenuEvent::HighlightIndex(
+                                    menu_state.highlighted_index().unwrap(),
+                                )),
+                                menu_state,
+                                acc_saved_menu_state.clone(),
+                                *overlay_state,
+                            )
+                        }
+                        KeyCode::Char(' ') if acc_menu_state.active => {
+                            if let Some(n) = acc_menu_state.highlighted_index() {
+                                let menu_state = {
+                                    let mut menu_state = acc_menu_state.clone();
+                                    menu_state.toggle_index(n).unwrap();
+                                    menu_state
+                                };
+                                (
+                                    Some(MultiSelectMenuEvent::ToggleIndex(n)),
+                                    menu_state,
+                                    acc_saved_menu_state.clone(),
+                                    *overlay_state,
+                                )
+                            } else {
+                                noop()
+                            }
+                        }
+                        &key_code if key_code == activation_hotkey && !acc_menu_state.active => (
+                            Some(MultiSelectMenuEvent::Activate),
+                            activated(),
+                            acc_saved_menu_state.clone(),
+                            *overlay_state,
+                        ),
+                        _ => noop(),
+                    },
+                    &InputEvent::Mouse(MouseEvent {
+                        kind: MouseEventKind::Up(MouseButton::Left),
+                        column: x,
+                        row: y,
+                        ..
+                    }) => {
+                        let point = (x, y);
+                        let hit = ui_target_areas.iter().find(|(_, area)| {
+                            area.left() <= x
+                                && area.right() > x
+                                && area.top() <= y
+                                && area.bottom() > y
+                        });
+
+                        match hit {
+                            Some(&(ui_target, area))
+                                if ui_target == self_ui_target && acc_menu_state.active =>
+                            {
+                                if let Some(n) = acc_menu_state.point_to_index(area, point) {
+                                    let menu_state = {
+                                        let mut menu_state = acc_menu_state.clone();
+                                        menu_state.highlight_index(n).unwrap();
+                                        menu_state.toggle_index(n).unwrap();
+                                        menu_state
+                                    };
+                                    (
+                                        Some(MultiSelectMenuEvent::ToggleIndex(n)),
+                                        menu_state,
+                                        acc_saved_menu_state.clone(),
+                                        *overlay_state,
+                                    )
+                                } else {
+                                    noop()
+                                }
+                            }
+                            _ => match multi_select_menu_event_map
+                                .get(&hit.map(|(ui_target, _)| *ui_target))
+                            {
+                                Some(MultiSelectMenuEvent::Activate)
+                                | Some(MultiSelectMenuEvent::Toggle)
+                                    if !acc_menu_state.active =>
+                                {
+                                    (
+                                        Some(MultiSelectMenuEvent::Activate),
+                                        activated(),
+                                        acc_saved_menu_state.clone(),
+                                        *overlay_state,
+                                    )
+                                }
+                                Some(MultiSelectMenuEvent::Activate) if acc_menu_state.active => {
+                                    noop()
+                                }
+                                Some(MultiSelectMenuEvent::Deactivate)
+                                | Some(MultiSelectMenuEvent::Toggle)
+                                    if acc_menu_state.active =>
+                                {
+                                    accepted()
+                                }
+                                Some(MultiSelectMenuEvent::Deactivate)
+                                    if !acc_menu_state.active =>
+                                {
+                                    noop()
+                                }
+                                Some(ev) => {
+                                    warn!("unhandled multi-select menu event: {:?}", ev);
+                                    noop()
+                                }
+                                None => noop(),
+                            },
+                        }
+                    }
+                    _ => noop(),
+                }
+            },
+        )
+        .filter_map(|(ev, menu_state, ..)| ev.as_ref().cloned().map(|ev| (ev, menu_state.clone())))
+}
+
 /// Queues the overlay states to send on next tick.
 ///
 /// This is necessary to prevent a cycle.
+/// Maps input events to popup events.
+///
+/// The popup is read-only, so Up and Down scroll its contents, and Esc or clicking outside of it
+/// closes it.
+pub fn to_popup_events<'a, S, O, U, C>(
+    input_events: S,
+    init_popup_state: PopupState,
+    overlay_states: O,
+    activation_hotkey: KeyCode,
+    ui_target_areas: U,
+    self_ui_target: UiTarget,
+    popup_event_map: HashMap<Option<UiTarget>, PopupEvent>,
+) -> impl Stream<'a, Item = (PopupEvent, PopupState), Context = C>
+where
+    S: Stream<'a, Item = InputEvent, Context = C>,
+    O: Stream<'a, Item = OverlayState>,
+    U: Stream<'a, Item = (UiTarget, Option<Rect>)>,
+    C: 'a + Clone,
+{
+    let popup_event_map = popup_event_map.without(&Some(self_ui_target));
+
+    let ui_target_area_bufs = ui_target_areas
+        .filter({
+            let popup_event_map = popup_event_map.clone();
+            move |(ui_target, _)| {
+                *ui_target == self_ui_target || popup_event_map.contains_key(&Some(*ui_target))
+            }
+        })
+        .buffer(popup_event_map.without(&None).len() + 1)
+        .map(move |ui_target_areas| {
+            ui_target_areas
+                .iter()
+                .filter_map(|(ui_target, area)| area.map(|area| (*ui_target, area)))
+                .rev()
+                .collect::<Vec<_>>()
+        });
+
+    input_events
+        .combine_latest(
+            overlay_states.distinct_until_changed(),
+            |(ev, overlay_state)| (*ev, *overlay_state),
+        )
+        .with_latest_from(
+            ui_target_area_bufs,
+            |((ev, overlay_state), ui_target_areas)| (*ev, *overlay_state, ui_target_areas.clone()),
+        )
+        .fold(
+            (None, init_popup_state, OverlayState::default()),
+            move |(_, acc_popup_state, acc_overlay_state), (ev, overlay_state, ui_target_areas)| {
+                let noop = || (None, *acc_popup_state, *overlay_state);
+                let activated = || {
+                    (
+                        Some(PopupEvent::Activate),
+                        PopupState {
+                            active: true,
+                            scroll: 0,
+                        },
+                        *overlay_state,
+                    )
+                };
+                let deactivated = || {
+                    (
+                        Some(PopupEvent::Deactivate),
+                        PopupState {
+                            active: false,
+                            ..*acc_popup_state
+                        },
+                        *overlay_state,
+                    )
+                };
+
+                let overlay_state_transitioned = acc_overlay_state != overlay_state;
+                if overlay_state_transitioned {
+                    return match overlay_state {
+                        OverlayState::Active if !acc_popup_state.active => activated(),
+                        OverlayState::Inactive if acc_popup_state.active => deactivated(),
+                        _ => noop(),
+                    };
+                }
+
+                match ev {
+                    InputEvent::Key(KeyEvent { code, .. }) => match code {
+                        KeyCode::Esc if acc_popup_state.active => deactivated(),
+                        KeyCode::Up if acc_popup_state.active => {
+                            let scroll = acc_popup_state.scroll.saturating_sub(1);
+                            (
+                                Some(PopupEvent::Scroll(scroll)),
+                                PopupState {
+                                    scroll,
+                                    ..*acc_popup_state
+                                },
+                                *overlay_state,
+                            )
+                        }
+                        KeyCode::Down if acc_popup_state.active => {
+                            let scroll = acc_popup_state.scroll.saturating_add(1);
+                            (
+                                Some(PopupEvent::Scroll(scroll)),
+                                PopupState {
+                                    scroll,
+                                    ..*acc_popup_state
+                                },
+                                *overlay_state,
+                            )
+                        }
+                        &key_code if key_code == activation_hotkey => {
+                            if acc_popup_state.active {
+                                deactivated()
+                            } else {
+                                activated()
+                            }
+                        }
+                        _ => noop(),
+                    },
+                    &InputEvent::Mouse(MouseEvent {
+                        kind: MouseEventKind::Up(MouseButton::Left),
+                        column: x,
+                        row: y,
+                        ..
+                    }) => {
+                        let hit = ui_target_areas.iter().find(|(_, area)| {
+                            area.left() <= x
+                                && area.right() > x
+                                && area.top() <= y
+                                && area.bottom() > y
+                        });
+
+                        match hit {
+                            Some(&(ui_target, _)) if ui_target == self_ui_target => noop(),
+                            _ => match popup_event_map.get(&hit.map(|(ui_target, _)| *ui_target)) {
+                                Some(PopupEvent::Activate) if !acc_popup_state.active => {
+                                    activated()
+                                }
+                                Some(PopupEvent::Deactivate) | Some(PopupEvent::Toggle)
+                                    if acc_popup_state.active =>
+                                {
+                                    deactivated()
+                                }
+                                Some(PopupEvent::Toggle) => activated(),
+                                // Clicking never scrolls the popup
+                                Some(PopupEvent::Activate)
+                                | Some(PopupEvent::Deactivate)
+                                | Some(PopupEvent::Scroll(_))
+                                | None => noop(),
+                            },
+                        }
+                    }
+                    _ => noop(),
+                }
+            },
+        )
+        .filter_map(|(ev, popup_state, ..)| ev.map(|ev| (ev, *popup_state)))
+}
+
 pub fn queue_overlay_states_for_next_tick<'a, S>(
     overlay_events: S,
     overlay_state_queue: Rc<RefCell<VecDeque<(UiTarget, OverlayState)>>>,
@@ -835,6 +1835,34 @@ pub fn queue_overlay_states_for_next_tick<'a, S>(
                         }
                         _ => acc_overlay_state,
                     },
+                    OverlayEvent::MultiSelectMenu(ev) => match ev {
+                        MultiSelectMenuEvent::Activate => OverlayState::Active,
+                        MultiSelectMenuEvent::Accept(_) | MultiSelectMenuEvent::Deactivate => {
+                            OverlayState::Inactive
+                        }
+                        MultiSelectMenuEvent::Toggle
+                            if acc_overlay_state == OverlayState::Active =>
+                        {
+                            OverlayState::Inactive
+                        }
+                        MultiSelectMenuEvent::Toggle
+                            if acc_overlay_state == OverlayState::Inactive =>
+                        {
+                            OverlayState::Active
+                        }
+                        _ => acc_overlay_state,
+                    },
+                    OverlayEvent::Popup(ev) => match ev {
+                        PopupEvent::Activate => OverlayState::Active,
+                        PopupEvent::Deactivate => OverlayState::Inactive,
+                        PopupEvent::Toggle if acc_overlay_state == OverlayState::Active => {
+                            OverlayState::Inactive
+                        }
+                        PopupEvent::Toggle if acc_overlay_state == OverlayState::Inactive => {
+                            OverlayState::Active
+                        }
+                        _ => acc_overlay_state,
+                    },
                     OverlayEvent::SelectMenu(ev) => match ev {
                         SelectMenuEvent::Activate => OverlayState::Active,
                         SelectMenuEvent::Accept(_) | SelectMenuEvent::Deactivate => {
@@ -916,3 +1944,227 @@ where
             debug!("active overlay: {:?}", active_overlay);
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{prelude::*, sample::Index};
+    use std::iter;
+
+    /// Values with any printable characters, including multi-byte and wide ones.
+    const VALUE_REGEX: &str = "\\PC{0,16}";
+
+    fn text_field_state() -> impl Strategy<Value = TextFieldState> {
+        (
+            VALUE_REGEX,
+            any::<Index>(),
+            prop::collection::vec("\\PC{1,8}", 0..4),
+        )
+            .prop_map(|(value, cursor_index, history)| TextFieldState {
+                active: true,
+                cursor_offset: cursor_index.index(value.chars().count() + 1),
+                history,
+                history_index: None,
+                value,
+            })
+    }
+
+    fn text_field_edit() -> impl Strategy<Value = TextFieldEdit> {
+        prop_oneof![
+            Just(TextFieldEdit::Backspace),
+            Just(TextFieldEdit::Delete),
+            "\\PC{0,4}".prop_map(TextFieldEdit::Insert),
+            Just(TextFieldEdit::MoveCursorEnd),
+            Just(TextFieldEdit::MoveCursorHome),
+            Just(TextFieldEdit::MoveCursorLeft),
+            Just(TextFieldEdit::MoveCursorRight),
+            Just(TextFieldEdit::RecallNext),
+            Just(TextFieldEdit::RecallPrevious),
+        ]
+    }
+
+    fn apply_edits(text_field_state: &TextFieldState, edits: &[TextFieldEdit]) -> TextFieldState {
+        edits
+            .iter()
+            .fold(text_field_state.clone(), |text_field_state, edit| {
+                edit_text_field(&text_field_state, edit, |value| value)
+                    .map(|(_, text_field_state)| text_field_state)
+                    .unwrap_or(text_field_state)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn cursor_stays_within_value(
+            text_field_state in text_field_state(),
+            edits in prop::collection::vec(text_field_edit(), 0..32)
+        ) {
+            let mut text_field_state = text_field_state;
+            for edit in &edits {
+                if let Some((_, next_text_field_state)) =
+                    edit_text_field(&text_field_state, edit, |value| value)
+                {
+                    text_field_state = next_text_field_state;
+                }
+                prop_assert!(
+                    text_field_state.cursor_offset <= text_field_state.value.chars().count()
+                );
+            }
+        }
+
+        #[test]
+        fn cursor_stays_within_mapped_value(
+            text_field_state in text_field_state(),
+            edits in prop::collection::vec(text_field_edit(), 0..32)
+        ) {
+            let mut text_field_state = text_field_state;
+            for edit in &edits {
+                // Mapping which shortens the value, as the symbol fields do by trimming
+                if let Some((_, next_text_field_state)) =
+                    edit_text_field(&text_field_state, edit, |value| value.trim().to_owned())
+                {
+                    text_field_state = next_text_field_state;
+                }
+                prop_assert!(
+                    text_field_state.cursor_offset <= text_field_state.value.chars().count()
+                );
+            }
+        }
+
+        #[test]
+        fn insert_counts_chars(text_field_state in text_field_state(), text in "\\PC{0,8}") {
+            let (ev, edited_text_field_state) = edit_text_field(
+                &text_field_state,
+                &TextFieldEdit::Insert(text.clone()),
+                |value| value,
+            )
+            .unwrap();
+
+            let expected_value: String = text_field_state
+                .value
+                .chars()
+                .take(text_field_state.cursor_offset)
+                .chain(text.chars())
+                .chain(text_field_state.value.chars().skip(text_field_state.cursor_offset))
+                .collect();
+            prop_assert!(matches!(ev, TextFieldEvent::Input(value) if value == expected_value));
+            prop_assert_eq!(edited_text_field_state.value, expected_value);
+            prop_assert_eq!(
+                edited_text_field_state.cursor_offset,
+                text_field_state.cursor_offset + text.chars().count()
+            );
+        }
+
+        #[test]
+        fn insert_then_backspace_round_trips(
+            text_field_state in text_field_state(),
+            text in "\\PC{0,8}"
+        ) {
+            let edits: Vec<_> = iter::once(TextFieldEdit::Insert(text.clone()))
+                .chain(iter::repeat(TextFieldEdit::Backspace).take(text.chars().count()))
+                .collect();
+            let edited_text_field_state = apply_edits(&text_field_state, &edits);
+
+            prop_assert_eq!(edited_text_field_state.value, text_field_state.value);
+            prop_assert_eq!(
+                edited_text_field_state.cursor_offset,
+                text_field_state.cursor_offset
+            );
+        }
+
+        #[test]
+        fn insert_then_delete_round_trips(
+            text_field_state in text_field_state(),
+            text in "\\PC{0,8}"
+        ) {
+            let char_count = text.chars().count();
+            let edits: Vec<_> = iter::once(TextFieldEdit::Insert(text.clone()))
+                .chain(iter::repeat(TextFieldEdit::MoveCursorLeft).take(char_count))
+                .chain(iter::repeat(TextFieldEdit::Delete).take(char_count))
+                .collect();
+            let edited_text_field_state = apply_edits(&text_field_state, &edits);
+
+            prop_assert_eq!(edited_text_field_state.value, text_field_state.value);
+            prop_assert_eq!(
+                edited_text_field_state.cursor_offset,
+                text_field_state.cursor_offset
+            );
+        }
+
+        #[test]
+        fn home_and_end_move_cursor_to_bounds(text_field_state in text_field_state()) {
+            let (ev, home_text_field_state) =
+                edit_text_field(&text_field_state, &TextFieldEdit::MoveCursorHome, |value| value)
+                    .unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::MoveCursor(0)));
+            prop_assert_eq!(home_text_field_state.cursor_offset, 0);
+
+            let char_count = text_field_state.value.chars().count();
+            let (ev, end_text_field_state) =
+                edit_text_field(&text_field_state, &TextFieldEdit::MoveCursorEnd, |value| value)
+                    .unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::MoveCursor(offset) if offset == char_count));
+            prop_assert_eq!(end_text_field_state.cursor_offset, char_count);
+        }
+
+        #[test]
+        fn edits_past_bounds_leave_state_unchanged(text_field_state in text_field_state()) {
+            let char_count = text_field_state.value.chars().count();
+            let at_start = TextFieldState {
+                cursor_offset: 0,
+                ..text_field_state.clone()
+            };
+            let at_end = TextFieldState {
+                cursor_offset: char_count,
+                ..text_field_state
+            };
+
+            let (ev, edited_text_field_state) =
+                edit_text_field(&at_start, &TextFieldEdit::Backspace, |value| value).unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::BackspacePastStart));
+            prop_assert_eq!(&edited_text_field_state.value, &at_start.value);
+            prop_assert_eq!(edited_text_field_state.cursor_offset, 0);
+
+            let (ev, edited_text_field_state) =
+                edit_text_field(&at_start, &TextFieldEdit::MoveCursorLeft, |value| value).unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::MoveCursorPastStart));
+            prop_assert_eq!(edited_text_field_state.cursor_offset, 0);
+
+            let (ev, edited_text_field_state) =
+                edit_text_field(&at_end, &TextFieldEdit::Delete, |value| value).unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::DeletePastEnd));
+            prop_assert_eq!(&edited_text_field_state.value, &at_end.value);
+            prop_assert_eq!(edited_text_field_state.cursor_offset, char_count);
+
+            let (ev, edited_text_field_state) =
+                edit_text_field(&at_end, &TextFieldEdit::MoveCursorRight, |value| value).unwrap();
+            prop_assert!(matches!(ev, TextFieldEvent::MoveCursorPastEnd));
+            prop_assert_eq!(edited_text_field_state.cursor_offset, char_count);
+        }
+
+        #[test]
+        fn recall_previous_then_next_clears_value(text_field_state in text_field_state()) {
+            let recalled =
+                edit_text_field(&text_field_state, &TextFieldEdit::RecallPrevious, |value| value);
+            prop_assert_eq!(recalled.is_some(), !text_field_state.history.is_empty());
+            let (_, recalled_text_field_state) = match recalled {
+                Some(recalled) => recalled,
+                None => return Ok(()),
+            };
+            prop_assert_eq!(
+                Some(&recalled_text_field_state.value),
+                text_field_state.history.last()
+            );
+
+            let (_, text_field_state) = edit_text_field(
+                &recalled_text_field_state,
+                &TextFieldEdit::RecallNext,
+                |value| value,
+            )
+            .unwrap();
+            prop_assert_eq!(text_field_state.history_index, None);
+            prop_assert_eq!(text_field_state.value, "");
+            prop_assert_eq!(text_field_state.cursor_offset, 0);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{alert::Alert, annotation::Annotation};
+use anyhow::{anyhow, Context};
+use chrono::{TimeZone, Utc};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope, AST};
+use std::{cell::RefCell, path::Path, rc::Rc};
+use tracing::{debug_span, warn};
+use yahoo_finance::{Bar, Timestamped};
+
+/// Number of operations which a call of a hook may run, so that a hook which loops forever is
+/// stopped instead of hanging the app.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+const MAX_CALL_LEVELS: usize = 32;
+
+const MAX_STRING_SIZE: usize = 64 * 1024;
+
+/// Something requested by a hook of the user script, on behalf of the stock of the event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookAction {
+    /// Marks the chart of the stock.
+    Annotate {
+        annotation: Annotation,
+        stock_symbol: String,
+    },
+    /// Shows a toast.
+    Notify(String),
+}
+
+/// Hooks of the user script, which are called on the events of the same name:
+///
+/// - `on_quote(symbol, price)` when the live quote of the current stock is refreshed
+/// - `on_bars_loaded(symbol, bars)` when the bars of the current stock are loaded or refreshed,
+///   with each bar as a map of `time` (Unix timestamp), `open`, `high`, `low`, `close` and `volume`
+/// - `on_alert(symbol, condition)` when an alert fires
+///
+/// The hooks may call `annotate(time, label, detail)` to mark the chart of the stock at the Unix
+/// timestamp, and `notify(message)` to show a toast. Hooks which are not defined are skipped, and
+/// hooks which run for too long are stopped.
+pub struct Hooks {
+    actions: Rc<RefCell<Vec<HookAction>>>,
+    ast: Option<AST>,
+    engine: Engine,
+    stock_symbol: Rc<RefCell<String>>,
+}
+
+impl Hooks {
+    /// Compiles the user script at the path, if there is one.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut hooks = Self::default();
+        if path.exists() {
+            let ast = hooks
+                .engine
+                .compile_file(path.to_path_buf())
+                .map_err(|err| anyhow!("{}", err))
+                .with_context(|| format!("failed to compile {}", path.display()))?;
+            hooks.ast = Some(ast);
+        }
+
+        Ok(hooks)
+    }
+
+    pub fn on_alert(&self, alert: &Alert) -> Vec<HookAction> {
+        self.call(
+            "on_alert",
+            &alert.symbol,
+            vec![
+                Dynamic::from(ImmutableString::from(alert.symbol.as_str())),
+                Dynamic::from(ImmutableString::from(alert.condition.to_string())),
+            ],
+        )
+    }
+
+    pub fn on_bars_loaded<'a, I>(&self, stock_symbol: &str, bars: I) -> Vec<HookAction>
+    where
+        I: IntoIterator<Item = &'a Bar>,
+    {
+        let bars: Array = bars
+            .into_iter()
+            .map(|bar| {
+                let mut map = Map::new();
+                map.insert("time".into(), Dynamic::from(bar.timestamp_seconds() as i64));
+                map.insert("open".into(), Dynamic::from(bar.open));
+                map.insert("high".into(), Dynamic::from(bar.high));
+                map.insert("low".into(), Dynamic::from(bar.low));
+                map.insert("close".into(), Dynamic::from(bar.close));
+                map.insert(
+                    "volume".into(),
+                    bar.volume
+                        .map_or(Dynamic::UNIT, |volume| Dynamic::from(volume as i64)),
+                );
+                Dynamic::from(map)
+            })
+            .collect();
+
+        self.call(
+            "on_bars_loaded",
+            stock_symbol,
+            vec![
+                Dynamic::from(ImmutableString::from(stock_symbol)),
+                Dynamic::from(bars),
+            ],
+        )
+    }
+
+    pub fn on_quote(&self, stock_symbol: &str, price: f64) -> Vec<HookAction> {
+        self.call(
+            "on_quote",
+            stock_symbol,
+            vec![
+                Dynamic::from(ImmutableString::from(stock_symbol)),
+                Dynamic::from(price),
+            ],
+        )
+    }
+
+    /// Calls the hook on behalf of the stock, returning the actions requested by it.
+    fn call(&self, name: &str, stock_symbol: &str, mut args: Vec<Dynamic>) -> Vec<HookAction> {
+        let ast = match &self.ast {
+            Some(ast) => ast,
+            None => return vec![],
+        };
+        let _span = debug_span!("call_hook", name, %stock_symbol).entered();
+
+        *self.stock_symbol.borrow_mut() = stock_symbol.to_owned();
+        if let Err(err) = self
+            .engine
+            .call_fn_dynamic(&mut Scope::new(), ast, name, None, &mut args)
+        {
+            // The hooks which are not defined are not an error
+            if !matches!(
+                *err,
+                EvalAltResult::ErrorFunctionNotFound(ref fn_sig, _) if fn_sig.starts_with(name)
+            ) {
+                warn!("hook {} failed: {}", name, err);
+            }
+        }
+
+        self.actions.borrow_mut().drain(..).collect()
+    }
+}
+
+/// Without a script, so none of the hooks are defined.
+impl Default for Hooks {
+    fn default() -> Self {
+        let actions: Rc<RefCell<Vec<HookAction>>> = Rc::new(RefCell::new(vec![]));
+        let stock_symbol = Rc::new(RefCell::new(String::new()));
+
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(MAX_CALL_LEVELS)
+            .set_max_string_size(MAX_STRING_SIZE);
+        engine.register_result_fn("annotate", {
+            let actions = actions.clone();
+            let stock_symbol = stock_symbol.clone();
+            move |time: i64,
+                  label: ImmutableString,
+                  detail: ImmutableString|
+                  -> Result<Dynamic, Box<EvalAltResult>> {
+                let datetime = Utc
+                    .timestamp_opt(time, 0)
+                    .single()
+                    .ok_or_else(|| format!("invalid annotation time {}", time))?;
+                actions.borrow_mut().push(HookAction::Annotate {
+                    annotation: Annotation::script(datetime, &label, &detail),
+                    stock_symbol: stock_symbol.borrow().clone(),
+                });
+
+                Ok(Dynamic::UNIT)
+            }
+        });
+        engine.register_fn("notify", {
+            let actions = actions.clone();
+            move |message: ImmutableString| {
+                actions
+                    .borrow_mut()
+                    .push(HookAction::Notify(message.to_string()));
+            }
+        });
+
+        Self {
+            actions,
+            ast: None,
+            engine,
+            stock_symbol,
+        }
+    }
+}
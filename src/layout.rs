@@ -0,0 +1,112 @@
+use crate::app::{ChartType, Indicator, LayoutPreset, TimeFrame};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+use thiserror::Error;
+
+/// Combination of the stock, the chart and the panes shown, which is saved under a name to be
+/// recalled later.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChartLayout {
+    pub chart_type: ChartType,
+    pub indicators: Vec<Indicator>,
+    pub layout: LayoutPreset,
+    pub stock_symbol: String,
+    pub time_frame: TimeFrame,
+    pub volume_profile: bool,
+}
+
+/// Named chart layouts, by name.
+pub type ChartLayouts = BTreeMap<String, ChartLayout>;
+
+/// Loads the chart layouts from the TOML file at the path, with a table per layout. There are no
+/// layouts if it does not exist.
+pub fn load_chart_layouts<P>(path: P) -> anyhow::Result<ChartLayouts>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(ChartLayouts::new());
+    }
+
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let chart_layouts =
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    Ok(chart_layouts)
+}
+
+/// Saves the chart layouts to the TOML file at the path, overwriting it.
+pub fn save_chart_layouts<P>(path: P, chart_layouts: &ChartLayouts) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+
+    fs::write(path, toml::to_string_pretty(chart_layouts)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Command entered in the layout field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutCommand {
+    /// `delete NAME` forgets the layout.
+    Delete(String),
+    /// `load NAME` recalls the layout.
+    Load(String),
+    /// `save NAME` saves the current layout, replacing any layout of the same name.
+    Save(String),
+}
+
+impl LayoutCommand {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Delete(name) | Self::Load(name) | Self::Save(name) => name,
+        }
+    }
+}
+
+impl FromStr for LayoutCommand {
+    type Err = ParseLayoutCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut words = s.splitn(2, char::is_whitespace);
+        let command = words
+            .next()
+            .filter(|command| !command.is_empty())
+            .ok_or(ParseLayoutCommandError::Empty)?;
+        // The name is the rest of the line, so that it may contain spaces
+        let name = words
+            .next()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or(ParseLayoutCommandError::MissingName)?
+            .to_owned();
+
+        match command {
+            "delete" => Ok(Self::Delete(name)),
+            "load" => Ok(Self::Load(name)),
+            "save" => Ok(Self::Save(name)),
+            _ => Err(ParseLayoutCommandError::UnknownCommand(command.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseLayoutCommandError {
+    #[error("empty command")]
+    Empty,
+    #[error("missing layout name")]
+    MissingName,
+    #[error("unknown command: {0}")]
+    UnknownCommand(String),
+}
@@ -1,65 +1,448 @@
 use crate::{
-    app::{App, Indicator, TimeFrame, UiState, UiTarget},
-    event::{ChartEvent, InputEvent, OverlayEvent, OverlayState, SelectMenuEvent, TextFieldEvent},
-    reactive::StreamExt as ReactiveStreamExt,
-    stock::Stock,
-    widgets::{SelectMenuState, TextFieldState},
+    alert::{Alert, AlertEdit},
+    app::{
+        self, App, BarSize, ChartType, DateRange, DateRangeSpec, EventLog, Indicator, LayoutPreset,
+        Pane, PaneSizes, Screen, TimeFrame, Toast, UiState, UiStateUpdate, UiTarget,
+    },
+    bus::EventBus,
+    cache::CacheStats,
+    config::Config,
+    error::AppError,
+    event::{
+        AlertEvent, DataEvent, FinancialsEvent, InputEvent, MultiSelectMenuEvent, OverlayEvent,
+        OverlayState, PopupEvent, ScreenEvent, SelectMenuEvent, TableEvent, TextFieldEvent,
+    },
+    hook::{HookAction, Hooks},
+    layout::{ChartLayout, ChartLayouts, LayoutCommand},
+    market::MarketSession,
+    paper::{
+        self, JournalFormat, Order, PaperTradingError, Portfolio, PortfolioEvent, Side, Trade,
+    },
+    reactive::{StreamExt as ReactiveStreamExt, Topic},
+    remote::RemoteCommand,
+    series::CustomSeries,
+    server::ApiServer,
+    stock::{ApiKeyError, ConnectionStatus, Stock},
+    store::{self, Action, State, Store},
+    watchlist::DEFAULT_WATCHLIST_NAME,
+    widgets::{CaptureFrame, MultiSelectMenuState, PopupState, SelectMenuState, TextFieldState},
 };
+use anyhow::{anyhow, bail, Context};
 use argh::FromArgs;
 use async_std::stream::{self, StreamExt};
 use bimap::BiMap;
+use chrono::Utc;
 use crossterm::{
     cursor,
-    event::{Event, EventStream, KeyCode, KeyEvent},
+    event::{Event, EventStream, KeyCode, KeyEvent, MouseEvent, MouseEventKind},
     execute, terminal,
 };
-use im::hashmap;
-use log::debug;
-use reactive_rs::{Broadcast, Stream};
-use simplelog::{Config as LoggerConfig, LevelFilter, WriteLogger};
+use im::{hashmap, HashMap};
+use itertools::Itertools;
+use reactive_rs::Stream;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::VecDeque,
     fs::File,
-    io, panic,
+    io, iter,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
     rc::Rc,
     sync::atomic::{self, AtomicBool},
     time,
 };
 use strum::IntoEnumIterator;
-use tui::{backend::CrosstermBackend, layout::Rect, Terminal};
+use tracing::{debug, debug_span, error, warn};
+use tracing_subscriber::{
+    filter::{Directive, EnvFilter, LevelFilter},
+    fmt::format::FmtSpan,
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::Rect,
+    Terminal,
+};
+use yahoo_finance::Timestamped;
 
+mod alert;
+mod annotation;
 mod app;
+mod bus;
+mod cache;
+mod config;
+mod depth;
+mod error;
 mod event;
+mod hook;
+mod layout;
+mod market;
+mod paper;
+mod plugin;
 mod reactive;
+mod regression;
+mod remote;
+mod series;
+mod server;
+mod stats;
 mod stock;
+mod storage;
+mod store;
 mod ui;
+mod watchlist;
 mod widgets;
 
+const APP_DIR_NAME: &str = "stocker";
 const DEFAULT_SYMBOL: &str = "TSLA";
-const TICK_RATE: u64 = 100;
+const ALERTS_STORAGE_NAME: &str = "alerts";
+const FAVORITE_STOCK_SYMBOLS_STORAGE_NAME: &str = "favorite_stock_symbols";
+const PANE_SIZES_STORAGE_NAME: &str = "pane_sizes";
+const PORTFOLIO_STORAGE_NAME: &str = "portfolio";
+const STOCK_SYMBOL_HISTORY_STORAGE_NAME: &str = "stock_symbol_history";
+const WORKSPACE_STORAGE_NAME: &str = "workspace";
+const DEFAULT_TICK_RATE: u64 = 100;
+/// Interval between the polls of the data fetched in the background, in milliseconds. It is fixed,
+/// so that a slower tick rate does not slow down the data updates.
+const POLL_RATE: u64 = 100;
+
+thread_local! {
+    static CATCHING_PANICS: Cell<bool> = Cell::new(false);
+    static PANIC_MESSAGE: RefCell<Option<String>> = RefCell::new(None);
+}
+
+#[global_allocator]
+static ALLOCATOR: stats::CountingAllocator = stats::CountingAllocator;
 
 /// Stocks dashboard
 #[derive(Debug, FromArgs)]
 struct Args {
+    #[argh(subcommand)]
+    command: Option<Command>,
+    /// size of the bars on the chart (1D, 1W, 1M)
+    #[argh(option, short = 'b', default = "BarSize::default()")]
+    bar_size: BarSize,
+    /// type of the chart (line, candles, pnf, baseline) (default: line)
+    #[argh(option, long = "chart")]
+    chart_type: Option<ChartType>,
+    /// skip the days without trading on the time axis
+    #[argh(switch)]
+    compressed_time_axis: bool,
     /// debug draw
     #[argh(switch)]
     debug_draw: bool,
-    /// indicator for technical analysis
+    /// start with the default view instead of restoring the workspace of the last session
+    #[argh(switch)]
+    fresh: bool,
+    /// indicator for technical analysis (may be repeated)
     #[argh(option, short = 'i')]
-    indicator: Option<Indicator>,
+    indicator: Vec<Indicator>,
+    /// layout preset (chart-only, chart-watchlist, chart-news-fundamentals, chart-heatmap)
+    #[argh(option)]
+    layout: Option<LayoutPreset>,
     /// path to log file
     #[argh(option)]
     log_file: Option<String>,
-    /// stock symbol
-    #[argh(option, short = 's', default = "DEFAULT_SYMBOL.to_owned()")]
-    symbol: String,
+    /// log level (off, error, warn, info, debug, trace) (default: info)
+    #[argh(option, default = "LevelFilter::INFO")]
+    log_level: LevelFilter,
+    /// only log the module, e.g. stocker::stock (may be repeated)
+    #[argh(option)]
+    log_module: Vec<String>,
+    /// mark the news headlines on the chart
+    #[argh(switch)]
+    news_markers: bool,
+    /// date range shown on the chart, e.g. 2020-01-01..2020-12-31, instead of the latest of the
+    /// time frame
+    #[argh(option)]
+    range: Option<DateRangeSpec>,
+    /// path of a Unix socket to accept remote control commands on
+    #[argh(option)]
+    remote_socket: Option<PathBuf>,
+    /// serve the quote, bars and portfolio as JSON on the address, e.g. 127.0.0.1:8080
+    #[argh(option)]
+    serve: Option<SocketAddr>,
+    /// origin of the web pages allowed to read the API from the browser, e.g.
+    /// http://localhost:3000 (default: none)
+    #[argh(option)]
+    serve_allow_origin: Option<String>,
+    /// allow serving the API on an address other than loopback, and for any host, where it is
+    /// reachable by other machines without any authentication
+    #[argh(switch)]
+    serve_public: bool,
+    /// show a text summary instead of the chart
+    #[argh(switch)]
+    summary: bool,
+    /// stock symbol, with the exchange suffix outside the US, e.g. SHOP.TO (default: TSLA)
+    #[argh(option, short = 's')]
+    symbol: Option<String>,
+    /// interval between redraws of the screen, in milliseconds (default: 100)
+    #[argh(option, from_str_fn(parse_tick_rate))]
+    tick_rate: Option<u64>,
+    /// time frame for historical prices (default: 1M)
+    #[argh(option, short = 't')]
+    time_frame: Option<TimeFrame>,
+    /// show the volume at each price level alongside the chart
+    #[argh(switch)]
+    volume_profile: bool,
+    /// name of the watchlist shown at startup
+    #[argh(option, short = 'w')]
+    watchlist: Option<String>,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Cache(CacheCommand),
+    Indicator(IndicatorCommand),
+    Journal(JournalCommand),
+    Watchlist(WatchlistCommand),
+}
+
+/// Manage the local data cache
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "cache")]
+struct CacheCommand {
+    #[argh(subcommand)]
+    command: CacheSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum CacheSubcommand {
+    Clear(CacheClearCommand),
+    Stats(CacheStatsCommand),
+    Warm(CacheWarmCommand),
+}
+
+/// Remove all the cached data
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "clear")]
+struct CacheClearCommand {}
+
+/// Show the size of the cached data of each stock
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "stats")]
+struct CacheStatsCommand {}
+
+/// Download the whole history of the stocks into the cache
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "warm")]
+struct CacheWarmCommand {
+    /// stock symbols
+    #[argh(positional)]
+    symbols: Vec<String>,
+}
+
+/// Compute indicators outside of the dashboard
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "indicator")]
+struct IndicatorCommand {
+    #[argh(subcommand)]
+    command: IndicatorSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum IndicatorSubcommand {
+    Export(IndicatorExportCommand),
+}
+
+/// Print the values of the indicators as CSV, one row per bar
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "export")]
+struct IndicatorExportCommand {
+    /// size of the bars (1D, 1W, 1M)
+    #[argh(option, short = 'b', default = "BarSize::default()")]
+    bar_size: BarSize,
+    /// indicator to compute (may be repeated)
+    #[argh(option, short = 'i')]
+    indicator: Vec<Indicator>,
     /// time frame for historical prices
     #[argh(option, short = 't', default = "TimeFrame::default()")]
     time_frame: TimeFrame,
+    /// stock symbol
+    #[argh(positional)]
+    symbol: String,
+}
+
+/// Manage the paper trading journal
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "journal")]
+struct JournalCommand {
+    #[argh(subcommand)]
+    command: JournalSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum JournalSubcommand {
+    Export(JournalExportCommand),
+}
+
+/// Print the trades and notes of the journal
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "export")]
+struct JournalExportCommand {
+    /// format of the journal (csv, json)
+    #[argh(option, default = "JournalFormat::Csv")]
+    format: JournalFormat,
+}
+
+/// Manage the watchlists
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "watchlist")]
+struct WatchlistCommand {
+    #[argh(subcommand)]
+    command: WatchlistSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum WatchlistSubcommand {
+    Import(WatchlistImportCommand),
+}
+
+/// Create a watchlist from a list of symbols or the CSV export of a portfolio, replacing any
+/// watchlist of the same name
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "import")]
+struct WatchlistImportCommand {
+    /// name of the watchlist (default: the file name without the extension)
+    #[argh(option)]
+    name: Option<String>,
+    /// path to the file
+    #[argh(positional)]
+    path: PathBuf,
+}
+
+fn parse_tick_rate(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("tick rate must be at least 1 millisecond".to_owned()),
+        Ok(tick_rate) => Ok(tick_rate),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn run_cache_command(CacheCommand { command }: CacheCommand) -> anyhow::Result<()> {
+    match command {
+        CacheSubcommand::Clear(_) => {
+            cache::clear()?;
+        }
+        CacheSubcommand::Stats(_) => {
+            for CacheStats {
+                bar_count,
+                size,
+                stock_symbol,
+            } in cache::stats()?
+            {
+                println!("{}\t{} bars\t{} bytes", stock_symbol, bar_count, size);
+            }
+        }
+        CacheSubcommand::Warm(CacheWarmCommand { symbols }) => {
+            for symbol in symbols {
+                let symbol = symbol.to_ascii_uppercase();
+                let bar_count = stock::warm_cache(&symbol)?;
+                println!("{}\t{} bars", symbol, bar_count);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_indicator_command(IndicatorCommand { command }: IndicatorCommand) -> anyhow::Result<()> {
+    match command {
+        IndicatorSubcommand::Export(IndicatorExportCommand {
+            bar_size,
+            indicator: indicators,
+            symbol,
+            time_frame,
+        }) => {
+            let symbol = symbol.to_ascii_uppercase();
+            let stock = Stock {
+                bars: stock::retrieve_bars(&symbol, time_frame)?,
+                symbol,
+                ..Stock::default()
+            };
+
+            // Indicators with several series get a column for each of them
+            let mut columns = vec!["time".to_owned(), "close".to_owned()];
+            let mut series_maps = vec![];
+            for indicator in indicators {
+                let indicator_series = ui::to_indicator_series(indicator, &stock, bar_size, None)?;
+                let series_count = indicator_series.len();
+                for (name, _, data) in indicator_series {
+                    columns.push(if series_count > 1 {
+                        format!("{} {}", indicator, name)
+                    } else {
+                        indicator.to_string()
+                    });
+                    series_maps.push(
+                        data.into_iter()
+                            .map(|(timestamp, value)| (timestamp as i64, value))
+                            .collect::<HashMap<_, _>>(),
+                    );
+                }
+            }
+
+            println!(
+                "{}",
+                columns
+                    .iter()
+                    .map(|column| paper::escape_csv_field(column))
+                    .join(",")
+            );
+            for bar in bar_size.aggregate(&stock.bars) {
+                let timestamp = bar.timestamp_seconds() as i64;
+                let fields = vec![bar.datetime().to_rfc3339(), bar.close.to_string()]
+                    .into_iter()
+                    .chain(series_maps.iter().map(|series_map| {
+                        series_map
+                            .get(&timestamp)
+                            .map_or_else(String::new, |value| value.to_string())
+                    }));
+                println!("{}", fields.format(","));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_journal_command(JournalCommand { command }: JournalCommand) -> anyhow::Result<()> {
+    match command {
+        JournalSubcommand::Export(JournalExportCommand { format }) => {
+            let journal = storage::load::<Portfolio>(PORTFOLIO_STORAGE_NAME)?
+                .map(|portfolio| portfolio.journal())
+                .unwrap_or_default();
+            print!("{}", format.format(&journal)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_watchlist_command(
+    WatchlistCommand { command }: WatchlistCommand,
+    config: &Config,
+) -> anyhow::Result<()> {
+    match command {
+        WatchlistSubcommand::Import(WatchlistImportCommand { name, path }) => {
+            let (file_name, symbols) = watchlist::import_watchlist(path)?;
+            let name = name.unwrap_or(file_name);
+            let watchlists_path = config::watchlists_path()?;
+            let mut watchlists = watchlist::load_watchlists(&watchlists_path, &config.watchlist)?;
+            println!("{}\t{} symbols", name, symbols.len());
+            watchlists.insert(name, symbols);
+            watchlist::save_watchlists(&watchlists_path, &watchlists)?;
+        }
+    }
+
+    Ok(())
 }
 
-fn setup_terminal() {
+fn setup_terminal() -> Result<(), AppError> {
     let mut stdout = io::stdout();
 
     execute!(
@@ -69,7 +452,7 @@ fn setup_terminal() {
         cursor::DisableBlinking,
         crossterm::event::EnableMouseCapture
     )
-    .unwrap();
+    .map_err(AppError::TerminalSetup)?;
 
     // Needed for when run in a TTY since TTYs don't actually have an alternate screen.
     //
@@ -78,13 +461,13 @@ fn setup_terminal() {
     //
     // If not running in a TTY, then we just end up clearing the alternate screen which should have
     // no effect.
-    execute!(stdout, terminal::Clear(terminal::ClearType::All)).unwrap();
+    execute!(stdout, terminal::Clear(terminal::ClearType::All)).map_err(AppError::TerminalSetup)?;
 
-    terminal::enable_raw_mode().unwrap();
+    terminal::enable_raw_mode().map_err(AppError::TerminalSetup)
 }
 
 // Adapted from https://github.com/cjbassi/ytop/blob/89a210f0e5e2de6aa0e8d7a153a21f959d77607e/src/main.rs#L51-L66
-fn cleanup_terminal() {
+fn cleanup_terminal() -> Result<(), AppError> {
     let mut stdout = io::stdout();
 
     // Needed for when run in a TTY since TTYs don't actually have an alternate screen.
@@ -99,7 +482,7 @@ fn cleanup_terminal() {
         cursor::MoveTo(0, 0),
         terminal::Clear(terminal::ClearType::All)
     )
-    .unwrap();
+    .map_err(AppError::TerminalCleanup)?;
 
     execute!(
         stdout,
@@ -108,146 +491,637 @@ fn cleanup_terminal() {
         cursor::EnableBlinking,
         crossterm::event::DisableMouseCapture
     )
-    .unwrap();
+    .map_err(AppError::TerminalCleanup)?;
+
+    terminal::disable_raw_mode().map_err(AppError::TerminalCleanup)
+}
+
+fn draw<B: Backend>(
+    terminal: &mut Terminal<B>,
+    last_frame: &RefCell<Buffer>,
+    app: &App,
+    cursor_point: Option<(u16, u16)>,
+) -> Result<(), AppError> {
+    let _span = debug_span!("draw").entered();
+
+    let mut draw_result = Ok(());
+
+    terminal
+        .draw(|f| {
+            draw_result = ui::draw(f, app);
+            f.render_widget(CaptureFrame(last_frame), f.size());
+
+            if let Some((cx, cy)) = cursor_point {
+                f.set_cursor(cx, cy);
+            }
+        })
+        .map_err(|err| AppError::Draw(err.into()))?;
 
-    terminal::disable_raw_mode().unwrap();
+    draw_result.map_err(AppError::Draw)
 }
 
 // Adapted from https://github.com/cjbassi/ytop/blob/89a210f0e5e2de6aa0e8d7a153a21f959d77607e/src/main.rs#L113-L120
 //
 // We need to catch panics since we need to close the UI and cleanup the terminal before logging any
 // error messages to the screen.
+//
+// Panics caught by `catch_panic` are recovered from instead, so the UI is kept.
 fn setup_panic_hook() {
     panic::set_hook(Box::new(|panic_info| {
-        cleanup_terminal();
+        if CATCHING_PANICS.with(Cell::get) {
+            error!("recovering from panic: {}", panic_info);
+            PANIC_MESSAGE.with(|panic_message| {
+                panic_message.replace(Some(panic_info.to_string()));
+            });
+            return;
+        }
+
+        cleanup_terminal().ok();
         better_panic::Settings::auto().create_panic_handler()(panic_info);
     }));
 }
 
+/// Runs the function, returning the panic message if it panics.
+fn catch_panic<F>(f: F) -> Result<(), String>
+where
+    F: FnOnce(),
+{
+    CATCHING_PANICS.with(|catching_panics| catching_panics.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING_PANICS.with(|catching_panics| catching_panics.set(false));
+
+    result.map_err(|_| {
+        PANIC_MESSAGE
+            .with(|panic_message| panic_message.borrow_mut().take())
+            .unwrap_or_else(|| "unknown panic".to_owned())
+    })
+}
+
 #[smol_potat::main]
 async fn main() -> anyhow::Result<()> {
     better_panic::install();
 
-    let args: Args = argh::from_env();
+    let mut args: Args = argh::from_env();
 
-    if let Some(log_file) = args.log_file {
-        WriteLogger::init(
-            LevelFilter::Debug,
-            LoggerConfig::default(),
-            File::create(log_file)?,
-        )?;
+    let config = Config::load()?;
+    if let Some(proxy) = config.proxy()? {
+        stock::set_proxy(proxy);
     }
+    stock::set_timeouts(config.timeouts);
 
-    let backend = CrosstermBackend::new(io::stdout());
-    let mut terminal = Terminal::new(backend)?;
+    // Must be kept alive until exit, so that the buffered log lines are flushed
+    let _log_guard = if let Some(log_file) = &args.log_file {
+        let log_filter = if args.log_module.is_empty() {
+            EnvFilter::default().add_directive(args.log_level.into())
+        } else {
+            args.log_module
+                .iter()
+                .try_fold(EnvFilter::default(), |log_filter, log_module| {
+                    let directive = format!("{}={}", log_module, args.log_level)
+                        .parse::<Directive>()
+                        .with_context(|| format!("invalid log module: {}", log_module))?;
+
+                    Ok::<_, anyhow::Error>(log_filter.add_directive(directive))
+                })?
+        };
+        let (log_writer, log_guard) = tracing_appender::non_blocking(File::create(log_file)?);
+
+        // Closed spans are logged along with their timings
+        tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_env_filter(log_filter)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(log_writer)
+            .try_init()
+            .map_err(|err| anyhow!(err))?;
+
+        Some(log_guard)
+    } else {
+        None
+    };
+
+    // A missing or rejected key is shown in the app, so that it can be told apart from other errors
+    if let Some(api_key) = config.api_key(stock::PROVIDER_NAME) {
+        stock::set_api_key(api_key);
+    }
+
+    // The API is not authenticated, so it is only reachable from this machine unless allowed
+    if let Some(addr) = args.serve {
+        if !addr.ip().is_loopback() && !args.serve_public {
+            bail!(
+                "refusing to serve the API on {}, which is reachable by other machines: use a \
+                 loopback address, e.g. 127.0.0.1:8080, or pass --serve-public",
+                addr
+            );
+        }
+    }
+
+    match args.command.take() {
+        Some(Command::Cache(cache_command)) => return run_cache_command(cache_command),
+        Some(Command::Indicator(indicator_command)) => {
+            return run_indicator_command(indicator_command)
+        }
+        Some(Command::Journal(journal_command)) => return run_journal_command(journal_command),
+        Some(Command::Watchlist(watchlist_command)) => {
+            return run_watchlist_command(watchlist_command, &config)
+        }
+        None => {}
+    }
 
     setup_panic_hook();
-    setup_terminal();
+
+    let result = match setup_terminal() {
+        Ok(()) => run(args, config).await,
+        Err(err) => Err(err),
+    };
+
+    // The terminal must be restored before the error is printed, or it would not be readable
+    let cleanup_result = cleanup_terminal();
+
+    result.and(cleanup_result).map_err(|err| {
+        error!("fatal error: {:?}", err);
+        anyhow::Error::new(err)
+    })
+}
+
+async fn run(args: Args, config: Config) -> Result<(), AppError> {
+    let backend = CrosstermBackend::new(io::stdout());
+    let terminal =
+        Rc::new(RefCell::new(Terminal::new(backend).map_err(|err| {
+            AppError::TerminalSetup(crossterm::ErrorKind::IoError(err))
+        })?));
+    // The last frame drawn, so that it can be drawn again when the app state is not at hand
+    let last_frame: Rc<RefCell<Buffer>> = Rc::new(RefCell::new(Buffer::default()));
+
+    // Requests to the provider block the event loop, so the loading indicator must be drawn before
+    // each of them, or the UI would seem frozen until the request is done
+    stock::set_request_start_hook({
+        let terminal = Rc::clone(&terminal);
+        let last_frame = Rc::clone(&last_frame);
+        move || {
+            // Requests started while drawing are skipped, as the terminal is already borrowed
+            if let (Ok(mut terminal), Ok(last_frame)) =
+                (terminal.try_borrow_mut(), last_frame.try_borrow())
+            {
+                let mut draw_result = Ok(());
+                if let Err(err) = terminal.draw(|f| {
+                    draw_result = ui::draw_loading(f, &last_frame);
+                }) {
+                    warn!("loading indicator draw failed: {:?}", err);
+                }
+                if let Err(err) = draw_result {
+                    warn!("loading indicator draw failed: {:?}", err);
+                }
+            }
+        }
+    });
 
     let should_quit = AtomicBool::new(false);
+    // Errors inside the subscribers cannot be returned, so the first one is kept here to end the
+    // event loop with
+    let fatal_error: RefCell<Option<AppError>> = RefCell::new(None);
+    let fail = |err: AppError| {
+        fatal_error.borrow_mut().get_or_insert(err);
+        should_quit.store(true, atomic::Ordering::Relaxed);
+    };
+
+    // The events are logged for the event inspector as soon as they are sent, as they are not
+    // replayed to the subscribers set up later
+    let event_log = Rc::new(RefCell::new(EventLog::default()));
 
-    let ui_target_areas: Broadcast<(), (UiTarget, Option<Rect>)> = Broadcast::new();
+    let bus = EventBus::new();
+    bus.subscribe_all({
+        let event_log = event_log.clone();
+        move |topic, ev| event_log.borrow_mut().push(topic, format!("{:?}", ev))
+    });
 
     let overlay_state_queue = Rc::new(RefCell::new(VecDeque::new()));
 
-    let overlay_states: Broadcast<(), (UiTarget, OverlayState)> = Broadcast::new();
+    // Going back or forward changes the symbol which the history is recorded from, so the
+    // navigations are sent on the next tick
+    let symbol_history_navigation_queue = Rc::new(RefCell::new(VecDeque::new()));
 
-    let grouped_overlay_states = overlay_states
-        .clone()
-        .group_by(
-            |(ui_target, _)| *ui_target,
-            |(_, overlay_state)| *overlay_state,
-        )
-        .broadcast();
+    // The date range given on the command line is restored once the stock symbol and the time frame
+    // are known, as they reset it
+    let init_date_ranges: Topic<Option<DateRange>> = Topic::new();
+    if let Some(DateRangeSpec(date_range)) = &args.range {
+        init_date_ranges.send(Some(date_range.clone()));
+    }
+
+    // Each overlay starts out inactive, however late its stream is set up
+    let overlay_states_of = |ui_target: UiTarget| {
+        bus.overlay_state
+            .clone()
+            .filter(move |(overlay_target, _)| *overlay_target == ui_target)
+            .map(|(_, overlay_state)| *overlay_state)
+            .start_with(OverlayState::default())
+    };
 
-    let active_overlays = event::to_active_overlays(overlay_states.clone()).broadcast();
+    // Some text fields are opened from elsewhere with a value to fill them with, e.g. the alert
+    // field from the alerts table
+    let field_values_of = |ui_target: UiTarget| {
+        bus.field_value
+            .clone()
+            .filter(move |(field_target, _)| *field_target == ui_target)
+            .map(|(_, value)| value.clone())
+    };
 
-    let input_events: Broadcast<(), InputEvent> = Broadcast::new();
+    let active_overlays = event::to_active_overlays(bus.overlay_state.clone())
+        .start_with(None)
+        .topic();
 
-    let grouped_input_events = input_events
+    let grouped_input_events = bus
+        .input
         .clone()
-        .group_by(|ev| !matches!(ev, InputEvent::Tick), |ev| *ev)
-        .broadcast();
+        .group_by(
+            |ev| matches!(ev, InputEvent::Key(..) | InputEvent::Mouse(..)),
+            |ev| *ev,
+        )
+        .topic();
 
     let user_input_events = grouped_input_events
         .clone()
         .filter(|grouped| grouped.key)
         .switch()
-        .broadcast();
+        .topic();
 
     let tick_input_events = grouped_input_events
         .clone()
         .filter(|grouped| !grouped.key)
         .switch()
-        .broadcast();
+        .filter(|ev| matches!(ev, InputEvent::Tick))
+        .topic();
+
+    let render_input_events = grouped_input_events
+        .clone()
+        .filter(|grouped| !grouped.key)
+        .switch()
+        .filter(|ev| matches!(ev, InputEvent::Render));
+
+    // Scripts and window managers may drive the app through the remote control socket, if enabled
+    if let Some(remote_socket) = args.remote_socket.clone() {
+        // The symbols are checked like those entered in the stock symbol field
+        bus.remote.feed_from(remote::to_remote_commands(
+            remote_socket,
+            tick_input_events.clone(),
+            {
+                let aliases = config.aliases.clone();
+                move |symbol: &str| {
+                    let symbol = aliases.resolve(symbol);
+                    stock::validate_stock_symbol(&symbol)
+                        .and_then(|()| stock::lookup_stock_symbol(&symbol))
+                        .map(|()| symbol)
+                }
+            },
+        ));
+    }
 
     let hotkey_overlay_map = {
         let mut bimap = BiMap::new();
+        bimap.insert(KeyCode::Char('`'), UiTarget::RecentStockSymbolMenu);
+        bimap.insert(KeyCode::Char('a'), UiTarget::AlertField);
+        bimap.insert(KeyCode::Char('d'), UiTarget::CompanyProfilePopup);
         bimap.insert(KeyCode::Char('i'), UiTarget::IndicatorMenu);
+        bimap.insert(KeyCode::Char('j'), UiTarget::NoteField);
+        bimap.insert(KeyCode::Char('L'), UiTarget::LayoutField);
+        bimap.insert(KeyCode::Char('m'), UiTarget::CompareMenu);
+        bimap.insert(KeyCode::Char('n'), UiTarget::OrderField);
+        bimap.insert(KeyCode::Char('O'), UiTarget::SeriesField);
         bimap.insert(KeyCode::Char('s'), UiTarget::StockSymbolField);
         bimap.insert(KeyCode::Char('t'), UiTarget::TimeFrameMenu);
+        bimap.insert(KeyCode::Char('w'), UiTarget::WatchlistMenu);
+        bimap.insert(KeyCode::Char('W'), UiTarget::WatchlistImportField);
         bimap
     };
 
     let associated_overlay_map = hashmap! {
+        UiTarget::AlertField => UiTarget::AlertField,
+        UiTarget::CompanyProfilePopup => UiTarget::CompanyProfilePopup,
+        UiTarget::CompareMenu => UiTarget::CompareMenu,
         UiTarget::IndicatorBox => UiTarget::IndicatorMenu,
         UiTarget::IndicatorMenu => UiTarget::IndicatorMenu,
-        UiTarget::StockNameButton => UiTarget::StockSymbolField,
+        UiTarget::LayoutField => UiTarget::LayoutField,
+        UiTarget::NoteField => UiTarget::NoteField,
+        UiTarget::OrderField => UiTarget::OrderField,
+        UiTarget::RecentStockSymbolMenu => UiTarget::RecentStockSymbolMenu,
+        UiTarget::SeriesField => UiTarget::SeriesField,
+        UiTarget::StockNameButton => UiTarget::CompanyProfilePopup,
         UiTarget::StockSymbolButton => UiTarget::StockSymbolField,
         UiTarget::StockSymbolField => UiTarget::StockSymbolField,
         UiTarget::TimeFrameBox => UiTarget::TimeFrameMenu,
         UiTarget::TimeFrameMenu => UiTarget::TimeFrameMenu,
+        UiTarget::WatchlistImportField => UiTarget::WatchlistImportField,
+        UiTarget::WatchlistMenu => UiTarget::WatchlistMenu,
+    };
+
+    // The workspace of the last session is restored, except for the parts given on the command line
+    let workspace: Option<ChartLayout> = if args.fresh {
+        None
+    } else {
+        storage::load(WORKSPACE_STORAGE_NAME).unwrap_or_else(|err| {
+            warn!("failed to load workspace: {:?}", err);
+            None
+        })
+    };
+
+    let init_stock_symbol = args
+        .symbol
+        .as_deref()
+        .map(|symbol| config.aliases.resolve(symbol))
+        .or_else(|| {
+            workspace
+                .as_ref()
+                .map(|workspace| workspace.stock_symbol.clone())
+        })
+        .unwrap_or_else(|| DEFAULT_SYMBOL.to_owned());
+    let init_time_frame = args
+        .time_frame
+        .or_else(|| workspace.as_ref().map(|workspace| workspace.time_frame))
+        .unwrap_or_default();
+    let init_indicators = if args.indicator.is_empty() {
+        workspace
+            .as_ref()
+            .map(|workspace| workspace.indicators.clone())
+            .unwrap_or_default()
+    } else {
+        args.indicator.clone()
+    };
+    let init_chart_type = args
+        .chart_type
+        .or_else(|| workspace.as_ref().map(|workspace| workspace.chart_type))
+        .unwrap_or_default();
+    let init_volume_profile = args.volume_profile
+        || workspace
+            .as_ref()
+            .map_or(false, |workspace| workspace.volume_profile);
+    let init_layout_preset = args
+        .layout
+        .or_else(|| workspace.as_ref().map(|workspace| workspace.layout))
+        .or(config.layout)
+        .unwrap_or_default();
+
+    // Redrawing less often lowers the CPU usage, as the data is polled at its own rate
+    let tick_rate = args
+        .tick_rate
+        .or(config.tick_rate)
+        .unwrap_or(DEFAULT_TICK_RATE);
+
+    let init_stock_symbol_field_state = TextFieldState {
+        history: storage::load(STOCK_SYMBOL_HISTORY_STORAGE_NAME)
+            .unwrap_or_else(|err| {
+                warn!("failed to load stock symbol history: {:?}", err);
+                None
+            })
+            .unwrap_or_default(),
+        ..TextFieldState::default()
+    };
+
+    let init_time_frame_menu_state = {
+        let mut select_menu_state = SelectMenuState::new(TimeFrame::iter());
+        select_menu_state.select(Some(init_time_frame))?;
+        select_menu_state
+    };
+
+    let init_indicator_menu_state = {
+        let mut multi_select_menu_state = MultiSelectMenuState::new(Indicator::iter());
+        multi_select_menu_state.select(&init_indicators)?;
+        multi_select_menu_state
+    };
+
+    let init_watchlists = config::watchlists_path()
+        .and_then(|path| watchlist::load_watchlists(path, &config.watchlist))
+        .unwrap_or_else(|err| {
+            warn!("failed to load watchlists: {:?}", err);
+            iter::once((DEFAULT_WATCHLIST_NAME.to_owned(), config.watchlist.clone())).collect()
+        });
+
+    let init_watchlist_name = args
+        .watchlist
+        .clone()
+        .or_else(|| config.startup_watchlist.clone())
+        .filter(|name| {
+            let exists = init_watchlists.contains_key(name);
+            if !exists {
+                warn!("no watchlist named {}", name);
+            }
+            exists
+        })
+        .unwrap_or_else(|| DEFAULT_WATCHLIST_NAME.to_owned());
+
+    let init_compare_menu_state =
+        MultiSelectMenuState::new(init_watchlists[&init_watchlist_name].clone());
+
+    let init_recent_stock_symbol_menu_state = {
+        let mut select_menu_state = SelectMenuState::new(vec![init_stock_symbol.clone()]);
+        select_menu_state.select(Some(init_stock_symbol.clone()))?;
+        select_menu_state
+    };
+
+    let init_watchlist_menu_state = {
+        let mut select_menu_state = SelectMenuState::new(init_watchlists.keys().cloned());
+        select_menu_state.select(Some(init_watchlist_name.clone()))?;
+        select_menu_state
+    };
+
+    let init_favorite_stock_symbols: Vec<String> =
+        storage::load(FAVORITE_STOCK_SYMBOLS_STORAGE_NAME)
+            .unwrap_or_else(|err| {
+                warn!("failed to load favorite stock symbols: {:?}", err);
+                None
+            })
+            .unwrap_or_default();
+
+    let init_pane_sizes: PaneSizes = storage::load(PANE_SIZES_STORAGE_NAME)
+        .unwrap_or_else(|err| {
+            warn!("failed to load pane sizes: {:?}", err);
+            None
+        })
+        .unwrap_or_default()
+        .clamp();
+
+    let init_portfolio = storage::load(PORTFOLIO_STORAGE_NAME)
+        .unwrap_or_else(|err| {
+            warn!("failed to load portfolio: {:?}", err);
+            None
+        })
+        .unwrap_or_else(|| Portfolio::new(config.paper_trading.initial_cash));
+
+    let init_chart_layouts = config::chart_layouts_path()
+        .and_then(layout::load_chart_layouts)
+        .unwrap_or_else(|err| {
+            warn!("failed to load chart layouts: {:?}", err);
+            ChartLayouts::new()
+        });
+
+    // The saved layouts can be recalled from the history of the layout field
+    let init_layout_field_state = TextFieldState {
+        history: init_chart_layouts
+            .keys()
+            .map(|name| format!("load {}", name))
+            .collect(),
+        ..TextFieldState::default()
+    };
+
+    let init_ui_state = UiState {
+        bar_size: args.bar_size,
+        chart_type: init_chart_type,
+        compare_menu_state: Rc::new(RefCell::new(init_compare_menu_state.clone())),
+        compressed_time_axis: args.compressed_time_axis,
+        date_range: init_time_frame.now_date_range(&MarketSession::default()),
+        debug_draw: args.debug_draw,
+        event_log: event_log.clone(),
+        indicator_menu_state: Rc::new(RefCell::new(init_indicator_menu_state.clone())),
+        indicators: init_indicators.clone(),
+        layout_field_state: Rc::new(RefCell::new(init_layout_field_state.clone())),
+        layout_preset: init_layout_preset,
+        masked_api_key: stock::api_key().map(config::mask_api_key),
+        news_markers: args.news_markers,
+        favorite_stock_symbols: Rc::new(init_favorite_stock_symbols.clone()),
+        pane_sizes: init_pane_sizes,
+        point_and_figure: config.point_and_figure,
+        recent_stock_symbol_menu_state: Rc::new(RefCell::new(
+            init_recent_stock_symbol_menu_state.clone(),
+        )),
+        stock_symbol_field_state: Rc::new(RefCell::new(init_stock_symbol_field_state.clone())),
+        summary_view: args.summary,
+        symbol_aliases: Rc::new(config.aliases.clone()),
+        tick_rate,
+        time_frame: init_time_frame,
+        time_frame_menu_state: Rc::new(RefCell::new(init_time_frame_menu_state.clone())),
+        ui_target_areas: bus.ui_target_area.clone(),
+        volume_profile: init_volume_profile,
+        watchlist_menu_state: Rc::new(RefCell::new(init_watchlist_menu_state.clone())),
+        ..UiState::default()
     };
 
+    // The parts of the state which are changed by the user are reduced from their previous values
+    // in the store, and the streams of those parts are selected from its states
+    let store = Store::new(State {
+        connection_status: if stock::api_key().is_none() && stock::PROVIDER_REQUIRES_API_KEY {
+            ConnectionStatus::Unauthorized(ApiKeyError::Missing)
+        } else {
+            ConnectionStatus::Connected
+        },
+        ..State::new(
+            init_ui_state,
+            init_stock_symbol.clone(),
+            init_portfolio.clone(),
+            config.markets.indices.clone(),
+        )
+    });
+
+    store.dispatch_from(bus.data.clone().map(|ev| match ev {
+        DataEvent::ConnectionStatus(connection_status) => {
+            Action::ConnectionStatus(connection_status.clone())
+        }
+        DataEvent::DownloadProgress(download_progress) => {
+            Action::DownloadProgress(download_progress.clone())
+        }
+    }));
+
+    // The focus is reduced from the focus events, which depend on the grouped user input events
+    let focuses = store.select(|state| state.ui_state.focus);
+
     let grouped_user_input_events = event::to_grouped_user_input_events(
         user_input_events.clone(),
-        ui_target_areas.clone(),
+        bus.ui_target_area.clone(),
         active_overlays.clone(),
+        focuses.clone(),
         hotkey_overlay_map.clone(),
         associated_overlay_map,
     )
-    .broadcast();
+    .topic();
 
     let non_overlay_user_input_events = grouped_user_input_events
         .clone()
         .filter(|grouped| grouped.key == None)
         .switch()
-        .broadcast();
+        .topic();
+
+    bus.chart.feed_from(event::to_chart_events(
+        non_overlay_user_input_events.clone(),
+        bus.ui_target_area.clone(),
+    ));
+
+    let pane_events = event::to_pane_events(non_overlay_user_input_events.clone()).topic();
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('l'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::NextLayoutPreset),
+    );
+
+    // Each part of the recalled layout replaces its part of the view
+    store.dispatch_from(
+        bus.layout
+            .clone()
+            .map(|chart_layout| Action::ChartLayout(chart_layout.clone())),
+    );
 
-    let chart_events = event::to_chart_events(non_overlay_user_input_events.clone()).broadcast();
+    store
+        .select(|state| state.ui_state.layout_preset)
+        .subscribe(|layout_preset| {
+            debug!("selected layout preset: {:?}", layout_preset);
+        });
 
-    let init_stock_symbol_field_state = TextFieldState::default();
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('h'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::ToggleNewsMarkers),
+    );
 
     let stock_symbol_text_field_events = event::to_text_field_events(
         grouped_user_input_events
             .clone()
             .filter(|grouped| grouped.key == Some(UiTarget::StockSymbolField))
             .switch(),
+        field_values_of(UiTarget::StockSymbolField),
         init_stock_symbol_field_state.clone(),
-        grouped_overlay_states
-            .clone()
-            .filter(|grouped| grouped.key == UiTarget::StockSymbolField)
-            .switch(),
+        overlay_states_of(UiTarget::StockSymbolField),
         hotkey_overlay_map
             .get_by_right(&UiTarget::StockSymbolField)
             .copied()
             .unwrap(),
-        ui_target_areas.clone(),
+        bus.ui_target_area.clone(),
         UiTarget::StockSymbolField,
         hashmap! {
             Some(UiTarget::StockSymbolButton) => TextFieldEvent::Toggle,
-            Some(UiTarget::StockNameButton) => TextFieldEvent::Toggle,
             None => TextFieldEvent::Deactivate,
         },
         |v| v.to_ascii_uppercase(),
+        {
+            let aliases = config.aliases.clone();
+            move |v: &str| stock::validate_stock_symbol(&aliases.resolve(v)).is_ok()
+        },
     )
-    .broadcast();
-
-    let init_time_frame_menu_state = {
-        let mut select_menu_state = SelectMenuState::new(TimeFrame::iter());
-        select_menu_state.select(Some(args.time_frame))?;
-        select_menu_state
-    };
+    .inspect(|(ev, text_field_state)| {
+        if let TextFieldEvent::Accept(_) = ev {
+            if let Err(err) =
+                storage::save(STOCK_SYMBOL_HISTORY_STORAGE_NAME, &text_field_state.history)
+            {
+                warn!("failed to save stock symbol history: {:?}", err);
+            }
+        }
+    })
+    .topic();
 
     let time_frame_select_menu_events = event::to_select_menu_events(
         grouped_user_input_events
@@ -255,401 +1129,2259 @@ async fn main() -> anyhow::Result<()> {
             .filter(|grouped| grouped.key == Some(UiTarget::TimeFrameMenu))
             .switch(),
         init_time_frame_menu_state.clone(),
-        grouped_overlay_states
-            .clone()
-            .filter(|grouped| grouped.key == UiTarget::TimeFrameMenu)
-            .switch(),
+        overlay_states_of(UiTarget::TimeFrameMenu),
         hotkey_overlay_map
             .get_by_right(&UiTarget::TimeFrameMenu)
             .copied()
             .unwrap(),
-        ui_target_areas.clone(),
+        bus.ui_target_area.clone(),
         UiTarget::TimeFrameMenu,
         hashmap! {
             Some(UiTarget::TimeFrameBox) => SelectMenuEvent::Toggle,
             None => SelectMenuEvent::Deactivate,
         },
     )
-    .broadcast();
+    .topic();
 
-    let init_indicator_menu_state = {
-        let mut select_menu_state = SelectMenuState::new(Indicator::iter());
-        select_menu_state.allow_empty_selection = true;
-        select_menu_state.select(args.indicator)?;
-        select_menu_state
-    };
+    let indicator_multi_select_menu_events = event::to_multi_select_menu_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::IndicatorMenu))
+            .switch(),
+        init_indicator_menu_state.clone(),
+        overlay_states_of(UiTarget::IndicatorMenu),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::IndicatorMenu)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::IndicatorMenu,
+        hashmap! {
+            Some(UiTarget::IndicatorBox) => MultiSelectMenuEvent::Toggle,
+            None => MultiSelectMenuEvent::Deactivate,
+        },
+    )
+    .topic();
+
+    let compare_multi_select_menu_events = event::to_multi_select_menu_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::CompareMenu))
+            .switch(),
+        init_compare_menu_state.clone(),
+        overlay_states_of(UiTarget::CompareMenu),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::CompareMenu)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::CompareMenu,
+        hashmap! {
+            None => MultiSelectMenuEvent::Deactivate,
+        },
+    )
+    .topic();
+
+    let recent_stock_symbol_lists = store.select(store::select_recent_stock_symbols);
+
+    let recent_stock_symbol_select_menu_events = event::to_dynamic_select_menu_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::RecentStockSymbolMenu))
+            .switch(),
+        init_recent_stock_symbol_menu_state.clone(),
+        recent_stock_symbol_lists.clone(),
+        overlay_states_of(UiTarget::RecentStockSymbolMenu),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::RecentStockSymbolMenu)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::RecentStockSymbolMenu,
+        hashmap! {
+            None => SelectMenuEvent::Deactivate,
+        },
+    )
+    .topic();
+
+    // Each edit replaces the symbols of the watchlist of the name, e.g. when they are reordered
+    let watchlist_maps = bus
+        .watchlist
+        .clone()
+        .fold(
+            init_watchlists.clone(),
+            |acc_watchlists, (name, symbols)| {
+                let mut watchlists = acc_watchlists.clone();
+                watchlists.insert(name.clone(), symbols.clone());
+                watchlists
+            },
+        )
+        .distinct_until_changed()
+        .inspect(|watchlists| {
+            if let Err(err) = config::watchlists_path()
+                .and_then(|path| watchlist::save_watchlists(path, watchlists))
+            {
+                warn!("failed to save watchlists: {:?}", err);
+            }
+        })
+        .start_with(init_watchlists.clone())
+        .topic();
+
+    let watchlist_select_menu_events = event::to_dynamic_select_menu_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::WatchlistMenu))
+            .switch(),
+        init_watchlist_menu_state.clone(),
+        watchlist_maps
+            .clone()
+            .map(|watchlists| watchlists.keys().cloned().collect::<Vec<_>>()),
+        overlay_states_of(UiTarget::WatchlistMenu),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::WatchlistMenu)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::WatchlistMenu,
+        hashmap! {
+            None => SelectMenuEvent::Deactivate,
+        },
+    )
+    .topic();
+
+    let watchlist_import_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::WatchlistImportField))
+            .switch(),
+        field_values_of(UiTarget::WatchlistImportField),
+        TextFieldState::default(),
+        overlay_states_of(UiTarget::WatchlistImportField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::WatchlistImportField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::WatchlistImportField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |v| !v.trim().is_empty(),
+    )
+    .topic();
+
+    // Importing a file creates a watchlist named after it, replacing any watchlist of the same
+    // name, and switches to it
+    let watchlist_imports = watchlist_import_text_field_events
+        .clone()
+        .filter_map(|(ev, _)| {
+            if let TextFieldEvent::Accept(path) = ev {
+                Some(watchlist::import_watchlist(path.trim()).map_err(|err| err.to_string()))
+            } else {
+                None
+            }
+        })
+        .topic();
+
+    bus.watchlist.feed_from(
+        watchlist_imports
+            .clone()
+            .filter_map(|import| import.as_ref().ok().cloned()),
+    );
+
+    store.dispatch_from(
+        watchlist_maps
+            .clone()
+            .map(|watchlists| Action::Watchlists(watchlists.clone())),
+    );
+
+    store.dispatch_from(
+        watchlist_select_menu_events
+            .clone()
+            .filter_map(|(ev, ..)| {
+                if let SelectMenuEvent::Accept(name) = ev {
+                    name.clone()
+                } else {
+                    None
+                }
+            })
+            .merge(
+                watchlist_imports
+                    .clone()
+                    .filter_map(|import| import.as_ref().ok().map(|(name, _)| name.clone())),
+            )
+            .start_with(init_watchlist_name)
+            .map(|name| Action::SelectWatchlist(name.clone())),
+    );
+
+    // Name and symbols of the watchlist which is shown
+    let watchlists = store.select(|state| (state.watchlist_name.clone(), state.watchlist.clone()));
+
+    let company_profile_popup_events = event::to_popup_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::CompanyProfilePopup))
+            .switch(),
+        PopupState::default(),
+        overlay_states_of(UiTarget::CompanyProfilePopup),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::CompanyProfilePopup)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::CompanyProfilePopup,
+        hashmap! {
+            Some(UiTarget::StockNameButton) => PopupEvent::Toggle,
+            None => PopupEvent::Deactivate,
+        },
+    )
+    .topic();
+
+    // The alert field is also opened from the alerts table, to edit the selected alert
+    let alert_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::AlertField))
+            .switch(),
+        field_values_of(UiTarget::AlertField),
+        TextFieldState::default(),
+        overlay_states_of(UiTarget::AlertField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::AlertField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::AlertField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |v| v.parse::<Alert>().is_ok(),
+    )
+    .topic();
+
+    // The order field is opened prefilled with an order for the current stock
+    let order_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::OrderField))
+            .switch(),
+        field_values_of(UiTarget::OrderField),
+        TextFieldState::default(),
+        overlay_states_of(UiTarget::OrderField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::OrderField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::OrderField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |v| v.parse::<Order>().is_ok(),
+    )
+    .topic();
+
+    let note_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::NoteField))
+            .switch(),
+        field_values_of(UiTarget::NoteField),
+        TextFieldState::default(),
+        overlay_states_of(UiTarget::NoteField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::NoteField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::NoteField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |_| true,
+    )
+    .topic();
+
+    let series_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::SeriesField))
+            .switch(),
+        field_values_of(UiTarget::SeriesField),
+        TextFieldState::default(),
+        overlay_states_of(UiTarget::SeriesField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::SeriesField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::SeriesField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |_| true,
+    )
+    .topic();
+
+    let layout_text_field_events = event::to_text_field_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::LayoutField))
+            .switch(),
+        field_values_of(UiTarget::LayoutField),
+        init_layout_field_state.clone(),
+        overlay_states_of(UiTarget::LayoutField),
+        hotkey_overlay_map
+            .get_by_right(&UiTarget::LayoutField)
+            .copied()
+            .unwrap(),
+        bus.ui_target_area.clone(),
+        UiTarget::LayoutField,
+        hashmap! {
+            None => TextFieldEvent::Deactivate,
+        },
+        |v| v,
+        |v| v.parse::<LayoutCommand>().is_ok(),
+    )
+    .topic();
+
+    bus.overlay.feed_from(
+        stock_symbol_text_field_events
+            .clone()
+            .map(|(ev, ..)| {
+                (
+                    UiTarget::StockSymbolField,
+                    OverlayEvent::TextField(ev.clone()),
+                )
+            })
+            .merge(
+                alert_text_field_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::AlertField, OverlayEvent::TextField(ev.clone()))),
+            )
+            .merge(
+                order_text_field_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::OrderField, OverlayEvent::TextField(ev.clone()))),
+            )
+            .merge(
+                note_text_field_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::NoteField, OverlayEvent::TextField(ev.clone()))),
+            )
+            .merge(
+                series_text_field_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::SeriesField, OverlayEvent::TextField(ev.clone()))),
+            )
+            .merge(
+                layout_text_field_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::LayoutField, OverlayEvent::TextField(ev.clone()))),
+            )
+            .merge(time_frame_select_menu_events.clone().map(|(ev, ..)| {
+                (
+                    UiTarget::TimeFrameMenu,
+                    OverlayEvent::SelectMenu(ev.clone()),
+                )
+            }))
+            .merge(indicator_multi_select_menu_events.clone().map(|(ev, ..)| {
+                (
+                    UiTarget::IndicatorMenu,
+                    OverlayEvent::MultiSelectMenu(ev.clone()),
+                )
+            }))
+            .merge(compare_multi_select_menu_events.clone().map(|(ev, ..)| {
+                (
+                    UiTarget::CompareMenu,
+                    OverlayEvent::MultiSelectMenu(ev.clone()),
+                )
+            }))
+            .merge(
+                company_profile_popup_events
+                    .clone()
+                    .map(|(ev, ..)| (UiTarget::CompanyProfilePopup, OverlayEvent::Popup(*ev))),
+            )
+            .merge(
+                recent_stock_symbol_select_menu_events
+                    .clone()
+                    .map(|(ev, ..)| {
+                        (
+                            UiTarget::RecentStockSymbolMenu,
+                            OverlayEvent::SelectMenu(ev.clone()),
+                        )
+                    }),
+            )
+            .merge(watchlist_import_text_field_events.clone().map(|(ev, ..)| {
+                (
+                    UiTarget::WatchlistImportField,
+                    OverlayEvent::TextField(ev.clone()),
+                )
+            }))
+            .merge(watchlist_select_menu_events.clone().map(|(ev, ..)| {
+                (
+                    UiTarget::WatchlistMenu,
+                    OverlayEvent::SelectMenu(ev.clone()),
+                )
+            }))
+            .inspect(|(ui_target, ev)| {
+                debug!("overlay event: {:?}", (ui_target, ev));
+            }),
+    );
+
+    event::queue_overlay_states_for_next_tick(bus.overlay.clone(), overlay_state_queue.clone());
+
+    let layout_commands = layout_text_field_events
+        .clone()
+        .filter_map(|(ev, _)| {
+            if let TextFieldEvent::Accept(command) = ev {
+                command.parse::<LayoutCommand>().ok()
+            } else {
+                None
+            }
+        })
+        .topic();
+
+    let stock_symbol_lookups = stock_symbol_text_field_events
+        .clone()
+        .filter_map(|(ev, ..)| {
+            if let TextFieldEvent::Accept(symbol) = ev {
+                Some(symbol.clone())
+            } else {
+                None
+            }
+        })
+        .map({
+            let aliases = config.aliases.clone();
+            move |symbol| {
+                let symbol = aliases.resolve(symbol);
+                stock::lookup_stock_symbol(&symbol).map(|()| symbol)
+            }
+        })
+        .topic();
+
+    store.dispatch_from(
+        stock_symbol_lookups
+            .clone()
+            .filter_map(|lookup| lookup.as_ref().ok().cloned())
+            .merge(
+                recent_stock_symbol_select_menu_events
+                    .clone()
+                    .filter_map(|(ev, ..)| {
+                        if let SelectMenuEvent::Accept(symbol) = ev {
+                            symbol.clone()
+                        } else {
+                            None
+                        }
+                    }),
+            )
+            .merge(
+                bus.stock_symbol
+                    .clone()
+                    .map(|(_, stock_symbol)| stock_symbol.clone()),
+            )
+            .merge(
+                bus.symbol_history
+                    .clone()
+                    .map(|entry| entry.stock_symbol.clone()),
+            )
+            .merge(bus.remote.clone().filter_map(|command| {
+                if let RemoteCommand::Symbol(symbol) = command {
+                    Some(symbol.clone())
+                } else {
+                    None
+                }
+            }))
+            .map(|stock_symbol| Action::StockSymbol(stock_symbol.clone())),
+    );
+
+    let stock_symbols = store.select(|state| state.stock_symbol.clone());
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('*'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::ToggleFavoriteStockSymbol),
+    );
+
+    let favorite_stock_symbol_lists = store
+        .select(|state| state.ui_state.favorite_stock_symbols.clone())
+        .inspect(|favorite_stock_symbols| {
+            if let Err(err) = storage::save(
+                FAVORITE_STOCK_SYMBOLS_STORAGE_NAME,
+                &**favorite_stock_symbols,
+            ) {
+                warn!("failed to save favorite stock symbols: {:?}", err);
+            }
+        })
+        .topic();
+
+    let watchlist_table_events = event::to_table_events(
+        grouped_user_input_events
+            .clone()
+            .filter(|grouped| grouped.key == Some(UiTarget::WatchlistTable))
+            .switch(),
+        bus.ui_target_area.clone(),
+        UiTarget::WatchlistTable,
+    )
+    .topic();
+
+    let watchlist_stock_symbol_lists = store.select(store::select_watchlist_stock_symbols);
+
+    let watchlist_selections =
+        event::to_table_selections(watchlist_table_events.clone().with_latest_from(
+            watchlist_stock_symbol_lists.clone(),
+            |(ev, watchlist_stock_symbols)| (*ev, watchlist_stock_symbols.len()),
+        ))
+        .start_with(None)
+        .topic();
+
+    bus.stock_symbol.feed_from(
+        watchlist_table_events
+            .clone()
+            .filter(|ev| matches!(ev, TableEvent::Accept))
+            .with_latest_from(watchlist_selections.clone(), |(_, selection)| *selection)
+            .with_latest_from(
+                watchlist_stock_symbol_lists.clone(),
+                |(selection, watchlist_stock_symbols)| {
+                    selection.and_then(|n| watchlist_stock_symbols.get(n).cloned())
+                },
+            )
+            .filter_map(|stock_symbol| stock_symbol.clone())
+            .map(|stock_symbol| (UiTarget::WatchlistTable, stock_symbol.clone())),
+    );
+
+    // Only the entries of the watchlist itself are moved, as the favorites are pinned above them
+    bus.watchlist.feed_from(
+        event::to_table_row_moves(watchlist_table_events.clone().with_latest_from(
+            watchlist_stock_symbol_lists.clone(),
+            |(ev, watchlist_stock_symbols)| (*ev, watchlist_stock_symbols.len()),
+        ))
+        .with_latest_from(
+            favorite_stock_symbol_lists.clone(),
+            |((from, to), favorite_stock_symbols)| {
+                Some((*from, *to)).filter(|(from, to)| {
+                    *from >= favorite_stock_symbols.len() && *to >= favorite_stock_symbols.len()
+                })
+            },
+        )
+        .filter_map(|row_move| *row_move)
+        .with_latest_from(
+            watchlist_stock_symbol_lists.clone(),
+            |((from, to), watchlist_stock_symbols)| {
+                (
+                    watchlist_stock_symbols[*from].clone(),
+                    watchlist_stock_symbols[*to].clone(),
+                )
+            },
+        )
+        .with_latest_from(
+            watchlists.clone(),
+            |((from_symbol, to_symbol), (name, watchlist))| {
+                let from = watchlist.iter().position(|s| s == from_symbol)?;
+                let to = watchlist.iter().position(|s| s == to_symbol)?;
+                let mut watchlist = watchlist.to_vec();
+                let symbol = watchlist.remove(from);
+                watchlist.insert(to, symbol);
+                Some((name.clone(), watchlist))
+            },
+        )
+        .filter_map(|watchlist_edit| watchlist_edit.clone()),
+    );
+
+    store.dispatch_from(
+        time_frame_select_menu_events
+            .clone()
+            .filter_map(|(ev, ..)| {
+                if let SelectMenuEvent::Accept(time_frame) = ev {
+                    Some(time_frame.as_ref().unwrap().parse::<TimeFrame>().unwrap())
+                } else {
+                    None
+                }
+            })
+            .merge(bus.remote.clone().filter_map(|command| {
+                if let RemoteCommand::TimeFrame(time_frame) = command {
+                    Some(*time_frame)
+                } else {
+                    None
+                }
+            }))
+            .merge(bus.symbol_history.clone().map(|entry| entry.time_frame))
+            .map(|time_frame| Action::UiState(UiStateUpdate::TimeFrame(*time_frame))),
+    );
+
+    let time_frames = store
+        .select(|state| state.ui_state.time_frame)
+        .inspect(|time_frame| {
+            debug!("selected time frame: {:?}", time_frame);
+        })
+        .topic();
+
+    let date_ranges = app::to_date_ranges(
+        bus.chart.clone(),
+        bus.symbol_history
+            .clone()
+            .map(|entry| entry.date_range.clone())
+            .merge(init_date_ranges.clone()),
+        stock_symbols.clone(),
+        init_stock_symbol.clone(),
+        time_frames.clone(),
+        init_time_frame,
+    )
+    .topic();
+
+    app::to_symbol_history_navigations(
+        event::to_history_events(non_overlay_user_input_events.clone()),
+        stock_symbols.clone(),
+        time_frames.clone(),
+        date_ranges.clone(),
+    )
+    .subscribe({
+        let symbol_history_navigation_queue = symbol_history_navigation_queue.clone();
+        move |entry| {
+            symbol_history_navigation_queue
+                .borrow_mut()
+                .push_back(entry.clone())
+        }
+    });
+
+    store.dispatch_from(
+        indicator_multi_select_menu_events
+            .clone()
+            .filter_map(|(ev, ..)| {
+                if let MultiSelectMenuEvent::Accept(indicators) = ev {
+                    Some(
+                        indicators
+                            .iter()
+                            .map(|s| s.parse().unwrap())
+                            .collect::<Vec<Indicator>>(),
+                    )
+                } else {
+                    None
+                }
+            })
+            .map(|indicators| Action::UiState(UiStateUpdate::Indicators(indicators.clone()))),
+    );
+
+    let indicators = store.select(|state| state.ui_state.indicators.clone());
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('c'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::NextChartType),
+    );
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('p'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::ToggleVolumeProfile),
+    );
+
+    // The layouts are saved from the latest of each part of the view
+    let current_chart_layouts = store.select(store::select_chart_layout);
+
+    // The workspace is saved on exit, to be restored in the next session
+    let latest_workspace = Rc::new(RefCell::new(None));
+    current_chart_layouts.clone().subscribe({
+        let latest_workspace = latest_workspace.clone();
+        move |chart_layout| {
+            latest_workspace.borrow_mut().replace(chart_layout.clone());
+        }
+    });
+
+    let chart_layout_maps = layout_commands
+        .clone()
+        .with_latest_from(current_chart_layouts.clone(), |(command, chart_layout)| {
+            (command.clone(), chart_layout.clone())
+        })
+        .fold(
+            init_chart_layouts.clone(),
+            |acc_chart_layouts, (command, chart_layout)| {
+                let mut chart_layouts = acc_chart_layouts.clone();
+                match command {
+                    LayoutCommand::Delete(name) => {
+                        chart_layouts.remove(name);
+                    }
+                    LayoutCommand::Load(_) => {}
+                    LayoutCommand::Save(name) => {
+                        chart_layouts.insert(name.clone(), chart_layout.clone());
+                    }
+                }
+                chart_layouts
+            },
+        )
+        .distinct_until_changed()
+        .inspect(|chart_layouts| {
+            if let Err(err) = config::chart_layouts_path()
+                .and_then(|path| layout::save_chart_layouts(path, chart_layouts))
+            {
+                warn!("failed to save chart layouts: {:?}", err);
+            }
+        })
+        .start_with(init_chart_layouts)
+        .topic();
+
+    let chart_layout_loads = layout_commands
+        .clone()
+        .filter_map(|command| {
+            if let LayoutCommand::Load(name) = command {
+                Some(name.clone())
+            } else {
+                None
+            }
+        })
+        .with_latest_from(chart_layout_maps.clone(), |(name, chart_layouts)| {
+            chart_layouts.get(name).cloned().ok_or_else(|| name.clone())
+        })
+        .topic();
+
+    bus.layout.feed_from(
+        chart_layout_loads
+            .clone()
+            .filter_map(|load| load.as_ref().ok().cloned()),
+    );
+
+    let compare_stock_symbol_lists = compare_multi_select_menu_events
+        .clone()
+        .fold(vec![], |acc_stock_symbols, (ev, ..)| {
+            if let MultiSelectMenuEvent::Accept(stock_symbols) = ev {
+                stock_symbols.clone()
+            } else {
+                acc_stock_symbols.clone()
+            }
+        })
+        .distinct_until_changed()
+        .start_with(vec![])
+        .topic();
+
+    let stock_profiles = stock::to_stock_profiles(
+        stock_symbols.clone(),
+        tick_input_events.clone(),
+        config.cache_ttl.profile(),
+    )
+    .topic();
+
+    let stock_bar_sets = stock::to_stock_bar_sets(
+        stock_symbols.clone(),
+        time_frames.clone(),
+        date_ranges.clone(),
+        indicators.clone(),
+        tick_input_events.clone(),
+        event::to_stock_events(non_overlay_user_input_events.clone()),
+        config.refresh.interval(),
+        bus.data.clone(),
+    )
+    .topic();
+
+    let stock_quote_summaries = stock::to_stock_quote_summaries(
+        store.select(|state| {
+            let layout_preset = state.ui_state.layout_preset;
+            if layout_preset.has_pane(Pane::Fundamentals)
+                || layout_preset.has_pane(Pane::KeyRatios)
+                || layout_preset.has_pane(Pane::EsgScores)
+                || layout_preset.has_pane(Pane::AnalystRatings)
+            {
+                Some(state.stock_symbol.clone())
+            } else {
+                None
+            }
+        }),
+        config.cache_ttl.quote_summary(),
+    )
+    .topic();
+
+    let stock_asset_profiles =
+        stock::to_stock_asset_profiles(stock_symbols.clone(), config.cache_ttl.profile()).topic();
+
+    let stock_annotations =
+        stock::to_stock_annotations(stock_symbols.clone(), config.cache_ttl.profile()).topic();
+
+    // The live quote is checked again whenever the bars are refreshed
+    let stock_live_quotes = stock::to_stock_live_quotes(
+        stock_symbols
+            .clone()
+            .combine_latest(stock_bar_sets.clone(), |(stock_symbol, _)| {
+                stock_symbol.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .topic();
+
+    // The heatmap shows the whole watchlist, so only the recent closes of each symbol are loaded,
+    // and checked again whenever the bars are refreshed
+    let watchlist_daily_closes_maps = stock::to_daily_closes_maps(
+        store
+            .select(|state| {
+                if state.ui_state.layout_preset.has_pane(Pane::Heatmap) {
+                    store::select_watchlist_stock_symbols(state)
+                } else {
+                    vec![]
+                }
+            })
+            .combine_latest(stock_bar_sets.clone(), |(stock_symbols, _)| {
+                stock_symbols.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .start_with(hashmap! {})
+    .topic();
+
+    // The news is also needed when the headlines are marked on the chart
+    let stock_news = stock::to_stock_news(
+        store.select(|state| {
+            if state.ui_state.layout_preset.has_pane(Pane::News) || state.ui_state.news_markers {
+                Some(state.stock_symbol.clone())
+            } else {
+                None
+            }
+        }),
+        config.cache_ttl.news(),
+    )
+    .topic();
+
+    let comparison_bar_sets = stock::to_comparison_bar_sets(
+        compare_stock_symbol_lists.clone(),
+        time_frames.clone(),
+        date_ranges.clone(),
+    )
+    .topic();
+
+    // The benchmark is fetched for the same time frame as the current stock, to compute its beta
+    let benchmark_bar_sets = stock::to_comparison_bar_sets(
+        stock_symbols.clone().map({
+            let benchmark = config.markets.benchmark.clone();
+            move |_| vec![benchmark.clone()]
+        }),
+        time_frames.clone(),
+        date_ranges.clone(),
+    )
+    .map(|bar_sets| bar_sets.first().cloned())
+    .topic();
+
+    let recent_closes_maps =
+        stock::to_recent_closes_maps(stock_symbols.clone(), stock_bar_sets.clone())
+            .start_with(hashmap! {})
+            .topic();
+
+    // The stock is assembled in the store from its parts, as each of them is loaded
+    store.dispatch_from(
+        stock_profiles
+            .clone()
+            .map(|stock_profile| Action::StockProfile(stock_profile.clone()))
+            .merge(
+                stock_bar_sets
+                    .clone()
+                    .map(|stock_bar_set| Action::StockBars(stock_bar_set.clone())),
+            )
+            .merge(
+                stock_quote_summaries.clone().map(|stock_quote_summary| {
+                    Action::StockQuoteSummary(stock_quote_summary.clone())
+                }),
+            )
+            .merge(
+                stock_news
+                    .clone()
+                    .map(|stock_news| Action::StockNews(stock_news.clone())),
+            )
+            .merge(
+                stock_asset_profiles.clone().map(|stock_asset_profile| {
+                    Action::StockAssetProfile(stock_asset_profile.clone())
+                }),
+            )
+            .merge(
+                stock_live_quotes
+                    .clone()
+                    .map(|stock_live_quote| Action::StockLiveQuote(stock_live_quote.clone())),
+            )
+            .merge(
+                stock_annotations
+                    .clone()
+                    .map(|stock_annotations| Action::StockAnnotations(stock_annotations.clone())),
+            ),
+    );
+
+    let stocks = store.select_shared(|state| state.stock.clone());
+
+    // Importing a CSV file overlays its series on the chart, replacing any series of the same
+    // name, and importing nothing clears them
+    let custom_series_loads = series_text_field_events
+        .clone()
+        .filter_map(|(ev, _)| match ev {
+            TextFieldEvent::Accept(path) if path.trim().is_empty() => Some(None),
+            TextFieldEvent::Accept(path) => Some(Some(CustomSeries::load(path.trim()))),
+            _ => None,
+        })
+        .topic();
+
+    let custom_series_lists = custom_series_loads
+        .clone()
+        .fold(
+            vec![],
+            |acc_custom_series: &Vec<CustomSeries>, load| match load {
+                Some(Ok(custom_series)) => acc_custom_series
+                    .iter()
+                    .filter(|acc_custom_series| acc_custom_series.name != custom_series.name)
+                    .cloned()
+                    .chain(iter::once(custom_series.clone()))
+                    .collect(),
+                Some(Err(_)) => acc_custom_series.clone(),
+                None => vec![],
+            },
+        )
+        .start_with(vec![])
+        .topic();
+
+    store.dispatch_from(pane_events.clone().map(|ev| Action::ResizePanes(*ev)));
+
+    store
+        .select(|state| state.ui_state.pane_sizes)
+        .subscribe(|pane_sizes| {
+            if let Err(err) = storage::save(PANE_SIZES_STORAGE_NAME, pane_sizes) {
+                warn!("failed to save pane sizes: {:?}", err);
+            }
+        });
+
+    // F12 works even with an overlay open, since the overlays do not use it
+    store.dispatch_from(
+        user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::F(12),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::ToggleDebugDraw),
+    );
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('f'),
+                        ..
+                    })
+                )
+            })
+            .map(|_| Action::ToggleFullScreen),
+    );
+
+    // Choosing a trending symbol jumps straight into its chart
+    store.dispatch_from(
+        event::to_screen_events(
+            non_overlay_user_input_events.clone(),
+            bus.ui_target_area.clone(),
+        )
+        .merge(
+            bus.stock_symbol
+                .clone()
+                .filter(|(ui_target, _)| *ui_target == UiTarget::TrendingTable)
+                .map(|_| ScreenEvent::Show(Screen::Chart)),
+        )
+        .map(|ev| match ev {
+            ScreenEvent::Next => Action::NextScreen,
+            ScreenEvent::Show(screen) => Action::UiState(UiStateUpdate::Screen(*screen)),
+        }),
+    );
+
+    let screens = store.select(|state| state.ui_state.screen);
+
+    store.dispatch_from(
+        event::to_focus_events(non_overlay_user_input_events.clone())
+            .map(|ev| Action::CycleFocus(*ev)),
+    );
+
+    let trending_stock_symbol_lists = stock::to_trending_stock_symbol_lists(
+        screens
+            .clone()
+            .filter(|screen| *screen == Screen::Trending)
+            .map({
+                let trending_region = config.markets.trending_region.clone();
+                move |_| trending_region.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .start_with(vec![])
+    .topic();
+
+    // The sector ETFs, the market indices and the trending symbols are only loaded while their
+    // screens are shown, and checked again whenever the bars are refreshed
+    let screen_daily_closes_maps = stock::to_daily_closes_maps(
+        store
+            .select(|state| match state.ui_state.screen {
+                Screen::Alerts
+                | Screen::Chart
+                | Screen::Depth
+                | Screen::Financials
+                | Screen::Journal
+                | Screen::Performance
+                | Screen::Plugins
+                | Screen::Portfolio
+                | Screen::Screener => {
+                    vec![]
+                }
+                Screen::Markets => (*state.market_indices).clone(),
+                Screen::Sectors => stock::SECTOR_ETFS
+                    .iter()
+                    .map(|(_, symbol)| (*symbol).to_owned())
+                    .collect(),
+                Screen::Trending => (*state.trending_stock_symbols).clone(),
+            })
+            .combine_latest(stock_bar_sets.clone(), |(stock_symbols, _)| {
+                stock_symbols.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .start_with(hashmap! {})
+    .topic();
+
+    // The returns of the current stock and the watchlist are computed from their daily bars, which
+    // are only loaded while the performance screen is shown
+    let performance_daily_bars_maps = stock::to_daily_bars_maps(
+        store
+            .select(|state| {
+                if state.ui_state.screen == Screen::Performance {
+                    iter::once(state.stock_symbol.clone())
+                        .chain(store::select_watchlist_stock_symbols(state))
+                        .unique()
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![]
+                }
+            })
+            .combine_latest(stock_bar_sets.clone(), |(stock_symbols, _)| {
+                stock_symbols.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .start_with(hashmap! {})
+    .topic();
+
+    // The plugins provide indicators, which are overlaid on the chart like the imported series, and
+    // panels, which are only rendered while the plugins screen is shown
+    let plugins = Rc::new(
+        config::plugins_dir()
+            .map(plugin::load_plugins)
+            .unwrap_or_else(|err| {
+                warn!("failed to load plugins: {:?}", err);
+                vec![]
+            }),
+    );
+
+    let plugin_series_lists = stock_bar_sets
+        .clone()
+        .with_latest_from(stock_symbols.clone(), {
+            let plugins = plugins.clone();
+            move |(stock_bar_set, stock_symbol)| {
+                let price = stock_bar_set.iter().next_back().map(|bar| bar.close);
+                plugins
+                    .iter()
+                    .flat_map(|plugin| {
+                        plugin
+                            .manifest
+                            .indicators
+                            .iter()
+                            .filter_map(move |name| {
+                                plugin
+                                    .indicator(name, stock_symbol, stock_bar_set, price)
+                                    .map_err(|err| {
+                                        warn!("plugin indicator {} failed: {:?}", name, err);
+                                    })
+                                    .ok()
+                            })
+                            .flatten()
+                    })
+                    .collect::<Vec<_>>()
+            }
+        })
+        .start_with(vec![])
+        .topic();
+
+    let plugin_panel_lists = screens
+        .clone()
+        .combine_latest(stocks.clone(), {
+            let plugins = plugins.clone();
+            move |(screen, stock)| {
+                if *screen != Screen::Plugins {
+                    return vec![];
+                }
+
+                plugins
+                    .iter()
+                    .flat_map(|plugin| {
+                        plugin.manifest.panels.iter().filter_map(move |name| {
+                            plugin
+                                .panel(name, &stock.symbol, &stock.bars, stock.latest_price())
+                                .map_err(|err| {
+                                    warn!("plugin panel {} failed: {:?}", name, err);
+                                })
+                                .ok()
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            }
+        })
+        .distinct_until_changed()
+        .topic();
+
+    let stock_financials = stock::to_stock_financials(
+        store.select(|state| {
+            if state.ui_state.screen == Screen::Financials {
+                Some(state.stock_symbol.clone())
+            } else {
+                None
+            }
+        }),
+        config.cache_ttl.profile(),
+    )
+    .topic();
+
+    let order_books = depth::to_order_books(
+        store.select(|state| {
+            if state.ui_state.screen == Screen::Depth {
+                Some(state.stock_symbol.clone())
+            } else {
+                None
+            }
+        }),
+        tick_input_events.clone(),
+    )
+    .topic();
+
+    store.dispatch_from(
+        event::to_financials_events(
+            non_overlay_user_input_events.clone(),
+            bus.ui_target_area.clone(),
+        )
+        .map(|ev| match ev {
+            FinancialsEvent::NextStatement => Action::NextFinancialStatement,
+            FinancialsEvent::TogglePeriod => Action::ToggleFinancialPeriod,
+        }),
+    );
+
+    let trending_table_events = event::to_table_events(
+        non_overlay_user_input_events.clone(),
+        bus.ui_target_area.clone(),
+        UiTarget::TrendingTable,
+    )
+    .topic();
+
+    let trending_selections =
+        event::to_table_selections(trending_table_events.clone().with_latest_from(
+            trending_stock_symbol_lists.clone(),
+            |(ev, trending_stock_symbols)| (*ev, trending_stock_symbols.len()),
+        ))
+        .start_with(None)
+        .topic();
+
+    bus.stock_symbol.feed_from(
+        trending_table_events
+            .clone()
+            .filter(|ev| matches!(ev, TableEvent::Accept | TableEvent::Click { .. }))
+            .with_latest_from(trending_selections.clone(), |(_, selection)| *selection)
+            .with_latest_from(
+                trending_stock_symbol_lists.clone(),
+                |(selection, trending_stock_symbols)| {
+                    selection.and_then(|n| trending_stock_symbols.get(n).cloned())
+                },
+            )
+            .filter_map(|stock_symbol| stock_symbol.clone())
+            .map(|stock_symbol| (UiTarget::TrendingTable, stock_symbol.clone())),
+    );
+
+    let news_selections = event::to_table_selections(
+        event::to_table_events(
+            grouped_user_input_events
+                .clone()
+                .filter(|grouped| grouped.key == Some(UiTarget::NewsList))
+                .switch(),
+            bus.ui_target_area.clone(),
+            UiTarget::NewsList,
+        )
+        .with_latest_from(stocks.clone(), |(ev, stock)| (*ev, stock.news.len())),
+    )
+    .start_with(None)
+    .topic();
+
+    let screener_quote_lists = stock::to_screener_quote_lists(
+        screens
+            .clone()
+            .filter(|screen| *screen == Screen::Screener)
+            .with_latest_from(watchlists.clone(), {
+                let screener_config = config.screener.clone();
+                move |(_, (_, watchlist))| screener_config.to_universe(watchlist)
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .map({
+        let screener_config = config.screener.clone();
+        move |quotes| {
+            quotes
+                .iter()
+                .filter(|quote| screener_config.matches(quote))
+                .cloned()
+                .collect::<Vec<_>>()
+        }
+    })
+    .start_with(vec![])
+    .topic();
+
+    store.dispatch_from(
+        event::to_table_events(
+            non_overlay_user_input_events.clone(),
+            bus.ui_target_area.clone(),
+            UiTarget::ScreenerTable,
+        )
+        .filter_map(|ev| match *ev {
+            TableEvent::SortBy(column) => Some(Action::SortScreener(column)),
+            _ => None,
+        }),
+    );
+
+    let init_alerts: Vec<Alert> = storage::load(ALERTS_STORAGE_NAME)
+        .unwrap_or_else(|err| {
+            warn!("failed to load alerts: {:?}", err);
+            None
+        })
+        .unwrap_or_default();
 
-    let indicator_select_menu_events = event::to_select_menu_events(
-        grouped_user_input_events
-            .clone()
-            .filter(|grouped| grouped.key == Some(UiTarget::IndicatorMenu))
-            .switch(),
-        init_indicator_menu_state.clone(),
-        grouped_overlay_states
+    let alert_lists = Topic::with_value(init_alerts.clone());
+
+    let alert_table_events = event::to_table_events(
+        non_overlay_user_input_events.clone(),
+        bus.ui_target_area.clone(),
+        UiTarget::AlertTable,
+    )
+    .topic();
+
+    let alert_selections = event::to_table_selections(
+        alert_table_events
             .clone()
-            .filter(|grouped| grouped.key == UiTarget::IndicatorMenu)
-            .switch(),
-        hotkey_overlay_map
-            .get_by_right(&UiTarget::IndicatorMenu)
-            .copied()
-            .unwrap(),
-        ui_target_areas.clone(),
-        UiTarget::IndicatorMenu,
-        hashmap! {
-            Some(UiTarget::IndicatorBox) => SelectMenuEvent::Toggle,
-            None => SelectMenuEvent::Deactivate,
-        },
+            .with_latest_from(alert_lists.clone(), |(ev, alerts)| (*ev, alerts.len())),
     )
-    .broadcast();
+    .start_with(None)
+    .topic();
 
-    let overlay_events = stock_symbol_text_field_events
+    let alert_edit_requests = alert_table_events
         .clone()
-        .map(|(ev, ..)| {
-            (
-                UiTarget::StockSymbolField,
-                OverlayEvent::TextField(ev.clone()),
-            )
-        })
-        .merge(time_frame_select_menu_events.clone().map(|(ev, ..)| {
-            (
-                UiTarget::TimeFrameMenu,
-                OverlayEvent::SelectMenu(ev.clone()),
-            )
-        }))
-        .merge(indicator_select_menu_events.clone().map(|(ev, ..)| {
-            (
-                UiTarget::IndicatorMenu,
-                OverlayEvent::SelectMenu(ev.clone()),
-            )
-        }))
-        .inspect(|(ui_target, ev)| {
-            debug!("overlay event: {:?}", (ui_target, ev));
+        .filter(|ev| matches!(ev, TableEvent::Accept))
+        .with_latest_from(alert_selections.clone(), |(_, selection)| *selection)
+        .with_latest_from(alert_lists.clone(), |(selection, alerts)| {
+            selection.and_then(|n| alerts.get(n).map(|alert| (n, alert.to_string())))
         })
-        .broadcast();
+        .filter_map(|edit_request| edit_request.clone())
+        .topic();
 
-    event::queue_overlay_states_for_next_tick(overlay_events.clone(), overlay_state_queue.clone());
+    bus.field_value.feed_from(
+        alert_edit_requests
+            .clone()
+            .map(|(_, value)| (UiTarget::AlertField, value.clone())),
+    );
 
-    let stock_symbols = stock_symbol_text_field_events
+    // Accepting the alert field replaces the alert being edited, if any, or adds a new alert
+    let alert_field_edits = alert_edit_requests
         .clone()
-        .fold(args.symbol.clone(), |acc_symbol, (ev, ..)| {
-            if let TextFieldEvent::Accept(symbol) = ev {
-                symbol.clone()
-            } else {
-                acc_symbol.clone()
-            }
-        })
-        .distinct_until_changed()
-        .broadcast();
+        .map(|(n, _)| (Some(*n), None))
+        .merge(
+            alert_text_field_events
+                .clone()
+                .map(|(ev, _)| (None, Some(ev.clone()))),
+        )
+        .fold(
+            (None, None),
+            |(acc_edited_alert_index, _), (edit_request, ev)| match (edit_request, ev) {
+                (Some(n), _) => (Some(*n), None),
+                (None, Some(TextFieldEvent::Accept(value))) => (
+                    None,
+                    value
+                        .parse()
+                        .ok()
+                        .map(|alert| match acc_edited_alert_index {
+                            Some(n) => AlertEdit::Replace(*n, alert),
+                            None => AlertEdit::Add(alert),
+                        }),
+                ),
+                (None, Some(TextFieldEvent::Deactivate)) => (None, None),
+                _ => (*acc_edited_alert_index, None),
+            },
+        )
+        .filter_map(|(_, alert_edit)| alert_edit.clone());
+
+    bus.alert.feed_from(event::to_alert_events(
+        non_overlay_user_input_events.clone(),
+        bus.ui_target_area.clone(),
+    ));
 
-    let time_frames = time_frame_select_menu_events
+    let alert_table_edits = bus
+        .alert
         .clone()
-        .fold(args.time_frame, |acc_time_frame, (ev, ..)| {
-            if let SelectMenuEvent::Accept(time_frame) = ev {
-                time_frame.as_ref().unwrap().parse().unwrap()
-            } else {
-                *acc_time_frame
-            }
+        .with_latest_from(alert_selections.clone(), |(ev, selection)| {
+            selection.map(|n| match ev {
+                AlertEvent::Delete => AlertEdit::Delete(n),
+                AlertEvent::Snooze => AlertEdit::Snooze(n),
+                AlertEvent::ToggleEnabled => AlertEdit::ToggleEnabled(n),
+            })
         })
-        .distinct_until_changed()
-        .inspect(|time_frame| {
-            debug!("selected time frame: {:?}", time_frame);
+        .filter_map(|alert_edit| alert_edit.clone());
+
+    alert_field_edits
+        .merge(alert_table_edits)
+        .fold(init_alerts, |acc_alerts, alert_edit| {
+            let mut alerts = acc_alerts.clone();
+            alert_edit.apply(&mut alerts, Utc::now());
+            alerts
+        })
+        .inspect(|alerts| {
+            if let Err(err) = storage::save(ALERTS_STORAGE_NAME, alerts) {
+                warn!("failed to save alerts: {:?}", err);
+            }
         })
-        .broadcast();
+        .subscribe({
+            let alert_lists = alert_lists.clone();
+            move |alerts| alert_lists.send(alerts.clone())
+        });
 
-    let date_ranges = app::to_date_ranges(
-        chart_events.clone(),
-        stock_symbols.clone(),
-        args.symbol.clone(),
-        time_frames.clone(),
-        args.time_frame,
+    // Alerts are evaluated against the daily bars of their symbols, which are checked again
+    // whenever the bars of the current stock are refreshed
+    let alert_daily_bars_maps = stock::to_daily_bars_maps(
+        alert_lists
+            .clone()
+            .combine_latest(stock_bar_sets.clone(), |(alerts, _)| {
+                alerts
+                    .iter()
+                    .map(|alert| alert.symbol.clone())
+                    .unique()
+                    .collect()
+            }),
+        config.cache_ttl.quote_summary(),
     )
-    .broadcast();
+    .topic();
 
-    let indicators = indicator_select_menu_events
+    let alert_evaluation_lists = alert_lists
         .clone()
-        .fold(args.indicator, |acc_indicator, (ev, ..)| {
-            if let SelectMenuEvent::Accept(indicator) = ev {
-                indicator.as_ref().map(|s| s.parse().unwrap())
-            } else {
-                *acc_indicator
-            }
+        .combine_latest(alert_daily_bars_maps.clone(), |(alerts, daily_bars_map)| {
+            alerts
+                .iter()
+                .map(|alert| {
+                    let triggered = daily_bars_map
+                        .get(&alert.symbol)
+                        .map_or(false, |daily_bars| alert.condition.evaluate(daily_bars));
+                    (alert.clone(), triggered)
+                })
+                .collect::<Vec<_>>()
         })
-        .distinct_until_changed()
-        .broadcast();
+        .topic();
 
-    let stock_profiles = stock::to_stock_profiles(stock_symbols.clone())
-        .map(|stock_profile| Some(stock_profile.clone()))
-        .broadcast();
+    // Disabled and snoozed alerts never fire, but they still fire once they are armed again if
+    // their condition is true
+    let fired_alert_lists =
+        alert::to_fired_alert_lists(alert_evaluation_lists.clone().map(|alert_evaluations| {
+            let now = Utc::now();
+            alert_evaluations
+                .iter()
+                .map(|(alert, triggered)| (alert.clone(), alert.is_armed(now) && *triggered))
+                .collect::<Vec<_>>()
+        }))
+        .inspect(|fired_alerts| {
+            debug!("fired alerts: {:?}", fired_alerts);
+        })
+        .topic();
 
-    let stock_bar_sets = stock::to_stock_bar_sets(
-        stock_symbols.clone(),
-        time_frames.clone(),
-        date_ranges.clone(),
-        indicators.clone(),
-    )
-    .broadcast();
+    // The hooks of the user script are called on the events, and may annotate the chart of the
+    // stock or show a toast
+    let hooks = Rc::new(
+        config::hooks_path()
+            .and_then(Hooks::load)
+            .unwrap_or_else(|err| {
+                warn!("failed to load hooks: {:?}", err);
+                Hooks::default()
+            }),
+    );
 
-    let stocks = stock_symbols
+    let hook_action_lists = stock_bar_sets
         .clone()
-        .combine_latest(stock_profiles.clone(), |(stock_symbol, stock_profile)| {
-            (stock_symbol.clone(), stock_profile.clone())
+        .with_latest_from(stock_symbols.clone(), {
+            let hooks = hooks.clone();
+            move |(stock_bar_set, stock_symbol)| hooks.on_bars_loaded(stock_symbol, stock_bar_set)
         })
-        .combine_latest(
-            stock_bar_sets.clone(),
-            |((stock_symbol, stock_profile), stock_bar_set)| Stock {
-                bars: stock_bar_set.clone(),
-                profile: stock_profile.clone(),
-                symbol: stock_symbol.clone(),
-                ..Stock::default()
-            },
+        .merge(
+            stock_live_quotes
+                .clone()
+                .with_latest_from(stock_symbols.clone(), |(live_quote, stock_symbol)| {
+                    (live_quote.clone(), stock_symbol.clone())
+                })
+                .filter_map({
+                    let hooks = hooks.clone();
+                    move |(live_quote, stock_symbol)| {
+                        let price = live_quote
+                            .as_ref()?
+                            .price
+                            .as_ref()?
+                            .regular_market_price
+                            .as_ref()?
+                            .raw?;
+                        Some(hooks.on_quote(stock_symbol, price))
+                    }
+                }),
         )
-        .broadcast();
+        .merge(fired_alert_lists.clone().map({
+            let hooks = hooks.clone();
+            move |fired_alerts| {
+                fired_alerts
+                    .iter()
+                    .flat_map(|alert| hooks.on_alert(alert))
+                    .collect()
+            }
+        }))
+        .filter(|hook_actions| !hook_actions.is_empty())
+        .topic();
+
+    // Pressing B or S opens the order field, prefilled with an order for the current stock
+    bus.field_value.feed_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter_map(|ev| match ev {
+                InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char('B'),
+                    ..
+                }) => Some(Side::Buy),
+                InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char('S'),
+                    ..
+                }) => Some(Side::Sell),
+                _ => None,
+            })
+            .with_latest_from(stocks.clone(), {
+                let quantity = config.paper_trading.quantity;
+                move |(side, stock)| {
+                    (
+                        UiTarget::OrderField,
+                        format!("{} {} {}", side, quantity, stock.symbol),
+                    )
+                }
+            }),
+    );
 
-    let stock_symbol_field_states = stock_symbol_text_field_events
+    // Orders for the current stock are filled right away if its latest price satisfies them
+    let paper_orders = order_text_field_events
         .clone()
-        .map(|(_, text_field_state)| text_field_state.clone())
-        .broadcast();
+        .filter_map(|(ev, _)| match ev {
+            TextFieldEvent::Accept(value) => value.parse::<Order>().ok(),
+            _ => None,
+        })
+        .with_latest_from(stocks.clone(), |(order, stock)| {
+            let price = if order.symbol == stock.symbol {
+                stock.latest_price()
+            } else {
+                None
+            };
+            (order.clone(), price)
+        })
+        .topic();
 
-    let time_frame_menu_states = time_frame_select_menu_events
+    let init_portfolio_stock_symbols: Vec<_> = init_portfolio
+        .positions
+        .keys()
+        .chain(init_portfolio.orders.iter().map(|order| &order.symbol))
+        .unique()
+        .cloned()
+        .collect();
+
+    let portfolio_stock_symbol_lists = paper_orders
         .clone()
-        .map(|(_, select_menu_state)| select_menu_state.clone())
-        .broadcast();
+        .fold(
+            init_portfolio_stock_symbols.clone(),
+            |acc_stock_symbols, (order, _)| {
+                let mut stock_symbols = acc_stock_symbols.clone();
+                if !stock_symbols.contains(&order.symbol) {
+                    stock_symbols.push(order.symbol.clone());
+                }
+                stock_symbols
+            },
+        )
+        .distinct_until_changed()
+        .start_with(init_portfolio_stock_symbols)
+        .topic();
+
+    // The positions are marked and the orders are filled at the daily closes of their symbols,
+    // which are checked again whenever the bars of the current stock are refreshed
+    let portfolio_daily_closes_maps = stock::to_daily_closes_maps(
+        portfolio_stock_symbol_lists
+            .clone()
+            .combine_latest(stock_bar_sets.clone(), |(stock_symbols, _)| {
+                stock_symbols.clone()
+            }),
+        config.cache_ttl.quote_summary(),
+    )
+    .topic();
 
-    let indicator_menu_states = indicator_select_menu_events
+    let portfolio_updates = paper_orders
         .clone()
-        .map(|(_, select_menu_state)| select_menu_state.clone())
-        .broadcast();
+        .map(|(order, price)| PortfolioEvent::Place {
+            order: order.clone(),
+            price: *price,
+        })
+        // Notes are written about the current stock
+        .merge(
+            note_text_field_events
+                .clone()
+                .filter_map(|(ev, _)| match ev {
+                    TextFieldEvent::Accept(text) => Some(text.clone()),
+                    _ => None,
+                })
+                .with_latest_from(stocks.clone(), |(text, stock)| PortfolioEvent::Note {
+                    symbol: stock.symbol.clone(),
+                    text: text.clone(),
+                }),
+        )
+        .merge(portfolio_daily_closes_maps.clone().map(|daily_closes_map| {
+            PortfolioEvent::Mark(
+                daily_closes_map
+                    .iter()
+                    .filter_map(|(stock_symbol, daily_closes)| {
+                        daily_closes
+                            .last()
+                            .map(|close| (stock_symbol.clone(), *close))
+                    })
+                    .collect(),
+            )
+        }))
+        .merge(stocks.clone().filter_map(|stock| {
+            stock
+                .latest_price()
+                .map(|price| PortfolioEvent::Mark(vec![(stock.symbol.clone(), price)]))
+        }))
+        .fold(
+            (init_portfolio.clone(), vec![]),
+            |(acc_portfolio, _): &(Portfolio, Vec<Result<Trade, PaperTradingError>>), ev| {
+                let mut portfolio = acc_portfolio.clone();
+                let fills = portfolio.apply(ev, Utc::now());
+                (portfolio, fills)
+            },
+        )
+        .topic();
 
-    let debug_draws: Broadcast<(), bool> = Broadcast::new();
+    let portfolios = portfolio_updates
+        .clone()
+        .map(|(portfolio, _)| portfolio.clone())
+        .distinct_until_changed()
+        .inspect(|portfolio| {
+            if let Err(err) = storage::save(PORTFOLIO_STORAGE_NAME, portfolio) {
+                warn!("failed to save portfolio: {:?}", err);
+            }
+        })
+        .start_with(init_portfolio.clone())
+        .topic();
 
-    let init_ui_state = UiState {
-        date_range: args.time_frame.now_date_range(),
-        debug_draw: args.debug_draw,
-        indicator: args.indicator,
-        indicator_menu_state: Rc::new(RefCell::new(init_indicator_menu_state.clone())),
-        stock_symbol_field_state: Rc::new(RefCell::new(init_stock_symbol_field_state.clone())),
-        time_frame: args.time_frame,
-        time_frame_menu_state: Rc::new(RefCell::new(init_time_frame_menu_state.clone())),
-        ..UiState::default()
-    };
+    // Other dashboards may consume the data of the app through the HTTP API, if enabled
+    if let Some(addr) = args.serve {
+        let api_server = Rc::new(ApiServer::serve(
+            addr,
+            args.serve_allow_origin.clone(),
+            args.serve_public,
+        )?);
+        stocks.clone().subscribe({
+            let api_server = api_server.clone();
+            move |stock| api_server.set_stock(stock)
+        });
+        portfolios
+            .clone()
+            .subscribe(move |portfolio| api_server.set_portfolio(portfolio));
+    }
 
-    let ui_states = time_frames
+    let remote_exports = bus
+        .remote
         .clone()
-        .combine_latest(date_ranges.clone(), |(time_frame, date_range)| {
-            (*time_frame, date_range.clone())
+        .filter_map(|command| {
+            if let RemoteCommand::Export(path) = command {
+                Some(path.clone())
+            } else {
+                None
+            }
         })
-        .combine_latest(
-            indicators.clone(),
-            |((time_frame, date_range), indicator)| (*time_frame, date_range.clone(), *indicator),
+        .with_latest_from(stocks.clone(), |(path, stock)| {
+            remote::export_bars_csv(path, &stock.bars)
+                .map(|()| (stock.symbol.clone(), path.clone()))
+                .map_err(|err| format!("Export failed: {}", err))
+        })
+        .topic();
+
+    let toasts = stock_symbol_lookups
+        .clone()
+        .filter_map(|lookup| lookup.as_ref().err().map(|err| Toast::new(err.to_string())))
+        .merge(
+            portfolio_updates
+                .clone()
+                .filter(|(_, fills)| !fills.is_empty())
+                .map(|(_, fills)| {
+                    Toast::new(
+                        fills
+                            .iter()
+                            .map(|fill| match fill {
+                                Ok(trade) => trade.to_string(),
+                                Err(err) => format!("Order rejected: {}", err),
+                            })
+                            .join("; "),
+                    )
+                }),
         )
-        .combine_latest(
-            stock_symbol_field_states.clone(),
-            |((time_frame, date_range, indicator), stock_symbol_field_state)| {
-                (
-                    *time_frame,
-                    date_range.clone(),
-                    *indicator,
-                    stock_symbol_field_state.clone(),
+        .merge(fired_alert_lists.clone().map({
+            let flash = config.alerts.flash;
+            move |fired_alerts| {
+                Toast::new(format!(
+                    "Alert: {}",
+                    fired_alerts
+                        .iter()
+                        .map(|alert| alert.to_string())
+                        .join("; ")
+                ))
+                .with_flash(flash)
+            }
+        }))
+        .merge(remote_exports.clone().map(|export| match export {
+            Ok((stock_symbol, path)) => {
+                Toast::new(format!("Exported {} to {}", stock_symbol, path.display()))
+            }
+            Err(message) => Toast::new(message.clone()),
+        }))
+        .merge(custom_series_loads.clone().filter_map(|load| match load {
+            Some(Err(err)) => Some(Toast::new(err.to_string())),
+            _ => None,
+        }))
+        .merge(layout_commands.clone().filter_map(|command| match command {
+            LayoutCommand::Delete(name) => Some(Toast::new(format!("Deleted layout {}", name))),
+            LayoutCommand::Load(_) => None,
+            LayoutCommand::Save(name) => Some(Toast::new(format!("Saved layout {}", name))),
+        }))
+        .merge(bus.toast.clone())
+        .merge(watchlist_imports.clone().map(|import| match import {
+            Ok((name, symbols)) => Toast::new(format!(
+                "Imported {} symbols into watchlist {}",
+                symbols.len(),
+                name
+            )),
+            Err(message) => Toast::new(message.clone()),
+        }))
+        .merge(chart_layout_loads.clone().filter_map(|load| {
+            load.as_ref()
+                .err()
+                .map(|name| Toast::new(format!("No layout named {}", name)))
+        }))
+        .merge(hook_action_lists.clone().filter_map(|hook_actions| {
+            let messages: Vec<_> = hook_actions
+                .iter()
+                .filter_map(|hook_action| match hook_action {
+                    HookAction::Notify(message) => Some(message.as_str()),
+                    _ => None,
+                })
+                .collect();
+            if messages.is_empty() {
+                None
+            } else {
+                Some(Toast::new(messages.join("; ")))
+            }
+        }))
+        .map(|toast| Some(toast.clone()))
+        .start_with(None)
+        .topic();
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('v'),
+                        ..
+                    })
                 )
-            },
-        )
-        .combine_latest(
-            time_frame_menu_states.clone(),
-            |(
-                (time_frame, date_range, indicator, stock_symbol_field_state),
-                time_frame_menu_state,
-            )| {
-                (
-                    *time_frame,
-                    date_range.clone(),
-                    *indicator,
-                    stock_symbol_field_state.clone(),
-                    time_frame_menu_state.clone(),
+            })
+            .map(|_| Action::ToggleSummaryView),
+    );
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('e'),
+                        ..
+                    })
                 )
-            },
-        )
-        .combine_latest(
-            indicator_menu_states.clone(),
-            |(
-                (
-                    time_frame,
-                    date_range,
-                    indicator,
-                    stock_symbol_field_state,
-                    time_frame_menu_state,
-                ),
-                indicator_menu_state,
-            )| {
-                (
-                    *time_frame,
-                    date_range.clone(),
-                    *indicator,
-                    stock_symbol_field_state.clone(),
-                    time_frame_menu_state.clone(),
-                    indicator_menu_state.clone(),
+            })
+            .map(|_| Action::ToggleEventInspector),
+    );
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('b'),
+                        ..
+                    })
                 )
-            },
-        )
-        .combine_latest(
-            debug_draws.clone(),
-            |(
-                (
-                    time_frame,
-                    date_range,
-                    indicator,
-                    stock_symbol_field_state,
-                    time_frame_menu_state,
-                    indicator_menu_state,
-                ),
-                debug_draw,
-            )| {
-                (
-                    *time_frame,
-                    date_range.clone(),
-                    *indicator,
-                    stock_symbol_field_state.clone(),
-                    time_frame_menu_state.clone(),
-                    indicator_menu_state.clone(),
-                    *debug_draw,
+            })
+            .map(|_| Action::NextBarSize),
+    );
+
+    let crosshair_points = non_overlay_user_input_events
+        .clone()
+        .filter_map(|ev| match ev {
+            InputEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column,
+                row,
+                ..
+            }) => Some(Some((*column, *row))),
+            _ => None,
+        })
+        .start_with(None)
+        .topic();
+
+    // Unlike the crosshair, the menus of the overlays are highlighted under the mouse too
+    let hover_points = user_input_events
+        .clone()
+        .filter_map(|ev| match ev {
+            InputEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column,
+                row,
+                ..
+            }) => Some(Some((*column, *row))),
+            _ => None,
+        })
+        .distinct_until_changed()
+        .start_with(None)
+        .topic();
+
+    store.dispatch_from(
+        non_overlay_user_input_events
+            .clone()
+            .filter(|ev| {
+                matches!(
+                    ev,
+                    InputEvent::Key(KeyEvent {
+                        code: KeyCode::Char('x'),
+                        ..
+                    })
                 )
+            })
+            .map(|_| Action::ToggleCompressedTimeAxis),
+    );
+
+    // Each change updates only its own field of the UI state, instead of rebuilding the whole
+    // state from the latest value of every field. The errors of the text fields are derived from
+    // their values when they are updated
+    let ui_state_updates =
+        date_ranges
+            .clone()
+            .map(|date_range| UiStateUpdate::DateRange(date_range.clone()))
+            .merge(
+                stock_symbol_text_field_events
+                    .clone()
+                    .map(|(_, stock_symbol_field_state)| {
+                        UiStateUpdate::StockSymbolFieldState(stock_symbol_field_state.clone())
+                    }),
+            )
+            .merge(
+                time_frame_select_menu_events
+                    .clone()
+                    .map(|(_, time_frame_menu_state)| {
+                        UiStateUpdate::TimeFrameMenuState(time_frame_menu_state.clone())
+                    }),
+            )
+            .merge(
+                indicator_multi_select_menu_events
+                    .clone()
+                    .map(|(_, indicator_menu_state)| {
+                        UiStateUpdate::IndicatorMenuState(indicator_menu_state.clone())
+                    }),
+            )
+            .merge(
+                toasts
+                    .clone()
+                    .map(|toast| UiStateUpdate::Toast(toast.clone())),
+            )
+            .merge(recent_stock_symbol_select_menu_events.clone().map(
+                |(_, recent_stock_symbol_menu_state)| {
+                    UiStateUpdate::RecentStockSymbolMenuState(
+                        recent_stock_symbol_menu_state.clone(),
+                    )
+                },
+            ))
+            .merge(
+                watchlist_select_menu_events
+                    .clone()
+                    .map(|(_, watchlist_menu_state)| {
+                        UiStateUpdate::WatchlistMenuState(watchlist_menu_state.clone())
+                    }),
+            )
+            .merge(
+                crosshair_points
+                    .clone()
+                    .map(|crosshair_point| UiStateUpdate::CrosshairPoint(*crosshair_point)),
+            )
+            .merge(
+                company_profile_popup_events
+                    .clone()
+                    .map(|(_, company_profile_popup_state)| {
+                        UiStateUpdate::CompanyProfilePopupState(*company_profile_popup_state)
+                    }),
+            )
+            .merge(
+                compare_multi_select_menu_events
+                    .clone()
+                    .map(|(_, compare_menu_state)| {
+                        UiStateUpdate::CompareMenuState(compare_menu_state.clone())
+                    }),
+            )
+            .merge(
+                trending_selections.clone().map(|trending_selection| {
+                    UiStateUpdate::TrendingSelection(*trending_selection)
+                }),
+            )
+            .merge(
+                alert_text_field_events
+                    .clone()
+                    .map(|(_, alert_field_state)| {
+                        UiStateUpdate::AlertFieldState(alert_field_state.clone())
+                    }),
+            )
+            .merge(
+                alert_selections
+                    .clone()
+                    .map(|alert_selection| UiStateUpdate::AlertSelection(*alert_selection)),
+            )
+            .merge(
+                order_text_field_events
+                    .clone()
+                    .map(|(_, order_field_state)| {
+                        UiStateUpdate::OrderFieldState(order_field_state.clone())
+                    }),
+            )
+            .merge(note_text_field_events.clone().map(|(_, note_field_state)| {
+                UiStateUpdate::NoteFieldState(note_field_state.clone())
+            }))
+            .merge(
+                series_text_field_events
+                    .clone()
+                    .map(|(_, series_field_state)| {
+                        UiStateUpdate::SeriesFieldState(series_field_state.clone())
+                    }),
+            )
+            .merge(
+                layout_text_field_events
+                    .clone()
+                    .map(|(_, layout_field_state)| {
+                        UiStateUpdate::LayoutFieldState(layout_field_state.clone())
+                    }),
+            )
+            .merge(watchlist_import_text_field_events.clone().map(
+                |(_, watchlist_import_field_state)| {
+                    UiStateUpdate::WatchlistImportFieldState(watchlist_import_field_state.clone())
+                },
+            ))
+            .merge(
+                hover_points
+                    .clone()
+                    .map(|hover_point| UiStateUpdate::HoverPoint(*hover_point)),
+            )
+            .merge(
+                news_selections
+                    .clone()
+                    .map(|news_selection| UiStateUpdate::NewsSelection(*news_selection)),
+            )
+            .merge(watchlist_selections.clone().map(|watchlist_selection| {
+                UiStateUpdate::WatchlistSelection(*watchlist_selection)
+            }));
+
+    // The areas of the text fields are kept to put the cursor in the active one when drawing
+    let text_field_areas = bus
+        .ui_target_area
+        .clone()
+        .filter(|(ui_target, _)| {
+            matches!(
+                ui_target,
+                UiTarget::StockSymbolField
+                    | UiTarget::AlertField
+                    | UiTarget::OrderField
+                    | UiTarget::NoteField
+                    | UiTarget::SeriesField
+                    | UiTarget::LayoutField
+                    | UiTarget::WatchlistImportField
+            )
+        })
+        .fold(
+            hashmap! {},
+            |acc_text_field_areas: &HashMap<UiTarget, Rect>, (ui_target, area)| match area {
+                Some(area) => acc_text_field_areas.update(*ui_target, *area),
+                None => acc_text_field_areas.without(ui_target),
             },
         )
-        .fold(init_ui_state.clone(), {
-            let ui_target_areas = ui_target_areas.clone();
-            move |acc_ui_state,
-                  (
-                time_frame,
-                date_range,
-                indicator,
-                stock_symbol_field_state,
-                time_frame_menu_state,
-                indicator_menu_state,
-                debug_draw,
-            )| UiState {
-                date_range: date_range.clone(),
-                debug_draw: *debug_draw,
-                indicator: *indicator,
-                indicator_menu_state: Rc::new(RefCell::new(indicator_menu_state.clone())),
-                stock_symbol_field_state: Rc::new(RefCell::new(stock_symbol_field_state.clone())),
-                time_frame: *time_frame,
-                time_frame_menu_state: Rc::new(RefCell::new(time_frame_menu_state.clone())),
-                ui_target_areas: ui_target_areas.clone(),
-                ..acc_ui_state.clone()
-            }
-        })
-        .broadcast();
-
-    let cursor_points = stock_symbol_field_states
-        .clone()
-        .combine_latest(
-            ui_target_areas
-                .clone()
-                .filter(|(ui_target, ..)| matches!(ui_target, UiTarget::StockSymbolField)),
-            |(text_field_state, (_, area))| (text_field_state.clone(), *area),
-        )
-        .map(|(text_field_state, area)| {
-            if let Some(area) = *area {
-                text_field_state.cursor_point(area)
-            } else {
-                None
-            }
+        .start_with(hashmap! {})
+        .topic();
+
+    // Each change is dispatched to the store as an action, and the app to render is selected from
+    // the latest state of the store whenever it is needed
+    store.dispatch_from(
+        ui_state_updates
+            .map(|update| Action::UiState(update.clone()))
+            .merge(
+                recent_closes_maps
+                    .clone()
+                    .map(|recent_closes_map| Action::RecentCloses(recent_closes_map.clone())),
+            )
+            .merge(
+                watchlist_daily_closes_maps
+                    .clone()
+                    .map(|daily_closes_map| Action::WatchlistDailyCloses(daily_closes_map.clone())),
+            )
+            .merge(
+                screen_daily_closes_maps
+                    .clone()
+                    .map(|daily_closes_map| Action::ScreenDailyCloses(daily_closes_map.clone())),
+            )
+            .merge(
+                performance_daily_bars_maps
+                    .clone()
+                    .map(|daily_bars_map| Action::DailyBars(daily_bars_map.clone())),
+            )
+            .merge(
+                portfolios
+                    .clone()
+                    .map(|portfolio| Action::Portfolio(portfolio.clone())),
+            )
+            .merge(
+                order_books
+                    .clone()
+                    .map(|order_book| Action::OrderBook(order_book.clone())),
+            )
+            .merge(
+                stock_financials
+                    .clone()
+                    .map(|stock_financials| Action::StockFinancials(stock_financials.clone())),
+            )
+            .merge(
+                screener_quote_lists
+                    .clone()
+                    .map(|screener_quotes| Action::ScreenerQuotes(screener_quotes.clone())),
+            )
+            .merge(
+                trending_stock_symbol_lists
+                    .clone()
+                    .map(|trending_stock_symbols| {
+                        Action::TrendingStockSymbols(trending_stock_symbols.clone())
+                    }),
+            )
+            .merge(
+                alert_evaluation_lists
+                    .clone()
+                    .map(|alert_evaluations| Action::AlertEvaluations(alert_evaluations.clone())),
+            )
+            .merge(
+                comparison_bar_sets.clone().map(|comparison_bar_sets| {
+                    Action::ComparisonBarSets(comparison_bar_sets.clone())
+                }),
+            )
+            .merge(
+                hook_action_lists
+                    .clone()
+                    .map(|hook_actions| Action::HookActions(hook_actions.clone())),
+            )
+            .merge(
+                custom_series_lists
+                    .clone()
+                    .map(|custom_series| Action::CustomSeries(custom_series.clone())),
+            )
+            .merge(
+                plugin_series_lists
+                    .clone()
+                    .map(|plugin_series| Action::PluginSeries(plugin_series.clone())),
+            )
+            .merge(
+                plugin_panel_lists
+                    .clone()
+                    .map(|plugin_panels| Action::PluginPanels(plugin_panels.clone())),
+            )
+            .merge(
+                benchmark_bar_sets
+                    .clone()
+                    .map(|benchmark_bar_set| Action::BenchmarkBarSet(benchmark_bar_set.clone())),
+            ),
+    );
+
+    let states = store.states();
+
+    // The command line is copied to the clipboard, or only shown if the clipboard is unavailable
+    non_overlay_user_input_events
+        .clone()
+        .filter(|ev| {
+            matches!(
+                ev,
+                InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char('C'),
+                    ..
+                })
+            )
+        })
+        .with_latest_from(states.clone(), |(_, state)| {
+            store::select_app(state).command_line()
         })
-        .broadcast();
+        .subscribe({
+            let toasts = bus.toast.clone();
+            move |command_line| {
+                let copied = arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.set_text(command_line.clone()));
+                let toast = match copied {
+                    Ok(()) => Toast::new(format!("Copied: {}", command_line)),
+                    Err(err) => {
+                        warn!("clipboard write failed: {:?}", err);
+                        Toast::new(command_line.clone())
+                    }
+                };
+                toasts.send(toast);
+            }
+        });
 
-    tick_input_events
+    // Downloads block the event loop, so their progress must be drawn immediately. The progress is
+    // taken from the event, as its action is not reduced yet if the download was started while
+    // reducing another action
+    bus.data
         .clone()
+        .filter_map(|ev| match ev {
+            DataEvent::DownloadProgress(Some(download_progress)) => Some(download_progress.clone()),
+            _ => None,
+        })
+        .with_latest_from(states.clone(), |(download_progress, state)| App {
+            download_progress: Some(download_progress.clone()),
+            ..store::select_app(state)
+        })
+        .subscribe(|app| {
+            if let Err(err) = draw(&mut terminal.borrow_mut(), &last_frame, app, None) {
+                fail(err);
+            }
+        });
+
+    // Ring the bell so that alerts are noticed even when looking at another window
+    if config.alerts.bell {
+        fired_alert_lists.clone().subscribe(|_| {
+            if let Err(err) = execute!(
+                terminal.borrow_mut().backend_mut(),
+                crossterm::style::Print("\x07"),
+            ) {
+                fail(AppError::Terminal(err));
+            }
+        });
+    }
+
+    render_input_events
         .merge(non_overlay_user_input_events.clone())
-        .with_latest_from(stocks.clone(), |(ev, stock)| (*ev, stock.clone()))
-        .with_latest_from(ui_states.clone(), |((ev, stock), ui_state)| {
-            (*ev, stock.clone(), ui_state.clone())
+        .with_latest_from(text_field_areas.clone(), |(ev, text_field_areas)| {
+            (*ev, text_field_areas.clone())
         })
-        .with_latest_from(
-            cursor_points.clone(),
-            |((ev, stock, ui_state), cursor_point)| {
-                (*ev, stock.clone(), ui_state.clone(), *cursor_point)
-            },
-        )
-        .subscribe(|(ev, stock, ui_state, cursor_point)| match ev {
+        .with_latest_from(states.clone(), |((ev, text_field_areas), state)| {
+            (*ev, state.clone(), text_field_areas.clone())
+        })
+        .subscribe(|(ev, state, text_field_areas)| match ev {
             InputEvent::Key(KeyEvent { code, .. }) => match code {
                 KeyCode::Char('q') => {
                     should_quit.store(true, atomic::Ordering::Relaxed);
                 }
+                KeyCode::Char('*')
+                | KeyCode::Char('1'..='9')
+                | KeyCode::Char('B')
+                | KeyCode::Char('C')
+                | KeyCode::Char('S')
+                | KeyCode::Char('b')
+                | KeyCode::Char('c')
+                | KeyCode::Char('e')
+                | KeyCode::Char('f')
+                | KeyCode::Char('h')
+                | KeyCode::Char('l')
+                | KeyCode::Char('o')
+                | KeyCode::Char('p')
+                | KeyCode::Char('r')
+                | KeyCode::Char('v')
+                | KeyCode::Char('x')
+                | KeyCode::Char('y')
+                | KeyCode::Char('z')
+                | KeyCode::Char(' ')
+                | KeyCode::Char('[')
+                | KeyCode::Char(']')
+                | KeyCode::Char('{')
+                | KeyCode::Char('}') => {}
                 KeyCode::Char(_) => {
-                    execute!(terminal.backend_mut(), crossterm::style::Print("\x07"),).unwrap();
+                    if let Err(err) = execute!(
+                        terminal.borrow_mut().backend_mut(),
+                        crossterm::style::Print("\x07"),
+                    ) {
+                        fail(AppError::Terminal(err));
+                    }
                 }
                 _ => {}
             },
-            InputEvent::Tick => {
-                let app = App {
-                    stock: stock.clone(),
-                    ui_state: ui_state.clone(),
-                };
-                terminal
-                    .draw(|f| {
-                        ui::draw(f, &app).expect("draw failed");
-
-                        if let Some((cx, cy)) = *cursor_point {
-                            f.set_cursor(cx, cy);
-                        }
-                    })
-                    .unwrap();
+            InputEvent::Render => {
+                // The app is only selected when it is drawn, and its parts are shared with the state
+                let app = store::select_app(state);
+                let cursor_point = state.ui_state.cursor_point(text_field_areas);
+                if let Err(err) = draw(&mut terminal.borrow_mut(), &last_frame, &app, cursor_point)
+                {
+                    fail(err);
+                }
             }
             _ => {}
         });
 
     let input_event_stream = EventStream::new()
-        .filter(|ev| matches!(ev, Ok(Event::Key(_)) | Ok(Event::Mouse(_))))
+        .filter(|ev| !matches!(ev, Ok(Event::Resize(..))))
         .map(|ev| match ev {
-            Ok(Event::Key(key_event)) => InputEvent::Key(key_event),
-            Ok(Event::Mouse(mouse_event)) => InputEvent::Mouse(mouse_event),
-            _ => unreachable!(),
+            Ok(Event::Key(key_event)) => Ok(InputEvent::Key(key_event)),
+            Ok(Event::Mouse(mouse_event)) => Ok(InputEvent::Mouse(mouse_event)),
+            Ok(Event::Resize(..)) => unreachable!(),
+            Err(err) => Err(AppError::InputEvents(err)),
         });
-    let tick_stream = stream::interval(time::Duration::from_millis(TICK_RATE));
-    let input_tick_stream = tick_stream.map(|()| InputEvent::Tick);
-    let mut input_event_stream = input_event_stream.merge(input_tick_stream);
-
-    // draw once before hitting the network, as it is blocking
-    stocks.send(Stock {
-        symbol: args.symbol.clone(),
-        ..Stock::default()
-    });
-    ui_states.send(init_ui_state);
-    cursor_points.send(None);
-    input_events.send(InputEvent::Tick);
-
-    // send the initial values
-    chart_events.send(ChartEvent::Reset);
-    time_frames.send(args.time_frame);
-    indicators.send(args.indicator);
-    stock_symbols.send(args.symbol);
-    stock_symbol_field_states.send(init_stock_symbol_field_state);
-    time_frame_menu_states.send(init_time_frame_menu_state);
-    indicator_menu_states.send(init_indicator_menu_state);
-    debug_draws.send(args.debug_draw);
-    active_overlays.send(None);
-    overlay_states.feed(
-        vec![
-            (UiTarget::StockSymbolField, OverlayState::default()),
-            (UiTarget::TimeFrameMenu, OverlayState::default()),
-            (UiTarget::IndicatorMenu, OverlayState::default()),
-        ]
-        .iter(),
-    );
+    // The first frame is drawn and the pending loads are polled right away, instead of after the
+    // first interval
+    let render_stream = stream::once(())
+        .chain(stream::interval(time::Duration::from_millis(tick_rate)))
+        .map(|()| Ok(InputEvent::Render));
+    let poll_stream = stream::once(())
+        .chain(stream::interval(time::Duration::from_millis(POLL_RATE)))
+        .map(|()| Ok(InputEvent::Tick));
+    let mut input_event_stream = input_event_stream.merge(render_stream).merge(poll_stream);
+
+    // The message of the last panic, while the recovery screen is shown
+    let mut panic_message: Option<String> = None;
 
     while !should_quit.load(atomic::Ordering::Relaxed) {
-        let drained_overlay_states: VecDeque<_> =
-            overlay_state_queue.borrow_mut().drain(..).collect();
-        for (ui_target, overlay_state) in drained_overlay_states {
-            debug!(
-                "sending previously queued overlay state: {:?}",
-                (ui_target, overlay_state)
-            );
-            overlay_states.send((ui_target, overlay_state));
+        let input_event = match input_event_stream.next().await {
+            Some(Ok(input_event)) => input_event,
+            Some(Err(err)) => {
+                fail(err);
+                continue;
+            }
+            None => {
+                fail(AppError::InputEventsEnded);
+                continue;
+            }
+        };
+
+        if let Some(message) = &panic_message {
+            match input_event {
+                InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) => {
+                    panic_message = None;
+
+                    // The recovery screen was drawn over the app, so it must be redrawn in full
+                    if let Err(err) = terminal.borrow_mut().clear() {
+                        fail(AppError::Draw(err.into()));
+                    }
+                }
+                InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                }) => {
+                    should_quit.store(true, atomic::Ordering::Relaxed);
+                }
+                InputEvent::Render => {
+                    let mut draw_result = Ok(());
+                    if let Err(err) = terminal.borrow_mut().draw(|f| {
+                        draw_result = ui::draw_panic_recovery(f, message);
+                    }) {
+                        fail(AppError::Draw(err.into()));
+                    }
+                    if let Err(err) = draw_result {
+                        fail(AppError::Draw(err));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // The state of the app is kept across panics, so that it can be restarted where it was
+        panic_message = catch_panic(|| {
+            let drained_overlay_states: VecDeque<_> =
+                overlay_state_queue.borrow_mut().drain(..).collect();
+            for (ui_target, overlay_state) in drained_overlay_states {
+                debug!(
+                    "sending previously queued overlay state: {:?}",
+                    (ui_target, overlay_state)
+                );
+                bus.overlay_state.send((ui_target, overlay_state));
+            }
+            let drained_symbol_history_navigations: VecDeque<_> = symbol_history_navigation_queue
+                .borrow_mut()
+                .drain(..)
+                .collect();
+            for entry in drained_symbol_history_navigations {
+                debug!("navigating the symbol history to: {:?}", entry);
+                bus.symbol_history.send(entry);
+            }
+            bus.input.send(input_event);
+        })
+        .err();
+    }
+
+    if let Some(workspace) = latest_workspace.borrow().as_ref() {
+        if let Err(err) = storage::save(WORKSPACE_STORAGE_NAME, workspace) {
+            warn!("failed to save workspace: {:?}", err);
         }
-        input_events.send(input_event_stream.next().await.unwrap());
     }
 
-    cleanup_terminal();
+    if let Some(remote_socket) = &args.remote_socket {
+        if let Err(err) = remote::remove_socket(remote_socket) {
+            warn!("failed to remove remote control socket: {:?}", err);
+        }
+    }
 
-    Ok(())
+    match fatal_error.borrow_mut().take() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
 }
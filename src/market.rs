@@ -0,0 +1,459 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::{America, Asia, Australia, Europe, Tz};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+/// Market holidays of an exchange, on which there is no trading.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HolidayCalendar {
+    /// Holidays of the NYSE and Nasdaq.
+    Us,
+    /// The holidays are not known, so only weekends are closed.
+    WeekendsOnly,
+}
+
+impl HolidayCalendar {
+    pub fn is_holiday(self, date: NaiveDate) -> bool {
+        match self {
+            Self::Us => is_us_holiday(date),
+            Self::WeekendsOnly => false,
+        }
+    }
+}
+
+/// Regular trading session of an exchange. Breaks within the session are not accounted for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MarketSession {
+    pub close: NaiveTime,
+    pub holidays: HolidayCalendar,
+    pub open: NaiveTime,
+    pub timezone: Tz,
+}
+
+impl MarketSession {
+    /// Returns the session of the exchange, by its code on Yahoo Finance. Unknown exchanges are
+    /// assumed to trade during the regular hours of the US exchanges.
+    pub fn for_exchange(exchange: Option<&str>) -> Self {
+        let (timezone, (open_hour, open_min), (close_hour, close_min)) = match exchange {
+            Some("ASX") => (Australia::Sydney, (10, 0), (16, 0)),
+            Some("FRA") | Some("GER") => (Europe::Berlin, (9, 0), (17, 30)),
+            Some("HKG") => (Asia::Hong_Kong, (9, 30), (16, 0)),
+            Some("JPX") => (Asia::Tokyo, (9, 0), (15, 0)),
+            Some("KLS") => (Asia::Kuala_Lumpur, (9, 0), (17, 0)),
+            Some("LSE") => (Europe::London, (8, 0), (16, 30)),
+            Some("PAR") => (Europe::Paris, (9, 0), (17, 30)),
+            Some("SES") => (Asia::Singapore, (9, 0), (17, 0)),
+            Some("TOR") | Some("VAN") => (America::Toronto, (9, 30), (16, 0)),
+            _ => (America::New_York, (9, 30), (16, 0)),
+        };
+
+        let holidays = if timezone == America::New_York {
+            HolidayCalendar::Us
+        } else {
+            HolidayCalendar::WeekendsOnly
+        };
+
+        Self {
+            close: NaiveTime::from_hms(close_hour, close_min, 0),
+            holidays,
+            open: NaiveTime::from_hms(open_hour, open_min, 0),
+            timezone,
+        }
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let now = now.with_timezone(&self.timezone).naive_local();
+
+        self.is_trading_day(now.date()) && (self.open..self.close).contains(&now.time())
+    }
+
+    /// Returns when the market next opens if it is closed, or when it closes if it is open.
+    pub fn next_transition(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let local_now = now.with_timezone(&self.timezone).naive_local();
+
+        if self.is_open(now) {
+            return self.at(local_now.date(), self.close);
+        }
+
+        let mut date = local_now.date();
+        if local_now.time() >= self.open {
+            date = date.succ();
+        }
+        while !self.is_trading_day(date) {
+            date = date.succ();
+        }
+
+        self.at(date, self.open)
+    }
+
+    /// Returns the date of the latest session that has opened, in the timezone of the exchange.
+    pub fn latest_session_date(&self, now: DateTime<Utc>) -> NaiveDate {
+        let local_now = now.with_timezone(&self.timezone).naive_local();
+
+        let mut date = local_now.date();
+        if local_now.time() < self.open {
+            date = date.pred();
+        }
+        while !self.is_trading_day(date) {
+            date = date.pred();
+        }
+
+        date
+    }
+
+    /// Returns whether the latest bar is from before the latest session, i.e. the prices are out
+    /// of date.
+    pub fn is_stale(&self, latest_bar_time: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        latest_bar_time
+            .with_timezone(&self.timezone)
+            .naive_local()
+            .date()
+            < self.latest_session_date(now)
+    }
+
+    /// Returns the date of the session which the time falls on, in the timezone of the exchange.
+    pub fn session_date(&self, time: DateTime<Utc>) -> NaiveDate {
+        time.with_timezone(&self.timezone).naive_local().date()
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.is_holiday(date)
+    }
+
+    /// Returns the date `n` trading days before the date.
+    pub fn sub_trading_days(&self, date: NaiveDate, n: u32) -> NaiveDate {
+        let mut date = date;
+        let mut n = n;
+        while n > 0 {
+            date = date.pred();
+            if self.is_trading_day(date) {
+                n -= 1;
+            }
+        }
+
+        date
+    }
+
+    /// Returns the date after `n` trading days starting from the date.
+    pub fn add_trading_days(&self, date: NaiveDate, n: u32) -> NaiveDate {
+        let mut date = date;
+        let mut n = n;
+        while n > 0 {
+            if self.is_trading_day(date) {
+                n -= 1;
+            }
+            date = date.succ();
+        }
+
+        date
+    }
+
+    fn at(&self, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+        let local = date.and_time(time);
+
+        self.timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .map_or_else(|| Utc.from_utc_datetime(&local), |t| t.with_timezone(&Utc))
+    }
+}
+
+impl Default for MarketSession {
+    fn default() -> Self {
+        Self::for_exchange(None)
+    }
+}
+
+/// Listings of the symbols with an exchange suffix on Yahoo Finance, e.g. `SHOP.TO`, by suffix.
+const SUFFIXED_LISTINGS: [(&str, Listing); 11] = [
+    ("AX", Listing::new(Some("ASX"), "AUD", 2)),
+    ("DE", Listing::new(Some("GER"), "EUR", 2)),
+    ("F", Listing::new(Some("FRA"), "EUR", 2)),
+    ("HK", Listing::new(Some("HKG"), "HKD", 2)),
+    ("KL", Listing::new(Some("KLS"), "MYR", 3)),
+    // Prices on the London Stock Exchange are quoted in pence
+    ("L", Listing::new(Some("LSE"), "GBp", 2)),
+    ("PA", Listing::new(Some("PAR"), "EUR", 2)),
+    ("SI", Listing::new(Some("SES"), "SGD", 3)),
+    ("T", Listing::new(Some("JPX"), "JPY", 1)),
+    ("TO", Listing::new(Some("TOR"), "CAD", 2)),
+    ("V", Listing::new(Some("VAN"), "CAD", 2)),
+];
+
+/// Exchange where a symbol is listed, and how its prices are quoted, as told by the suffix of the
+/// symbol. Symbols without a known suffix are assumed to be listed in the US.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Listing {
+    /// Currency the prices are quoted in.
+    pub currency: &'static str,
+    /// Code of the exchange on Yahoo Finance, if it is known from the symbol alone.
+    pub exchange: Option<&'static str>,
+    /// Number of decimal places the prices are shown with.
+    pub price_decimals: usize,
+}
+
+impl Listing {
+    const fn new(
+        exchange: Option<&'static str>,
+        currency: &'static str,
+        price_decimals: usize,
+    ) -> Self {
+        Self {
+            currency,
+            exchange,
+            price_decimals,
+        }
+    }
+
+    pub fn for_symbol(symbol: &str) -> Self {
+        symbol_suffix(symbol)
+            .and_then(|suffix| {
+                SUFFIXED_LISTINGS
+                    .iter()
+                    .find(|(listing_suffix, _)| listing_suffix.eq_ignore_ascii_case(suffix))
+            })
+            .map_or_else(Self::default, |(_, listing)| *listing)
+    }
+
+    pub fn format_price(&self, price: f64) -> String {
+        format!("{:.*}", self.price_decimals, price)
+    }
+
+    /// Formats the change in price, along with the percentage.
+    pub fn format_change(&self, change: f64, change_percent: f64) -> String {
+        format!(
+            "{:+.*} ({:+.2}%)",
+            self.price_decimals, change, change_percent
+        )
+    }
+}
+
+impl Default for Listing {
+    fn default() -> Self {
+        Self::new(None, "USD", 2)
+    }
+}
+
+/// Returns the exchange suffix of the symbol, e.g. `TO` for `SHOP.TO`, if any.
+pub fn symbol_suffix(symbol: &str) -> Option<&str> {
+    symbol
+        .rsplitn(2, '.')
+        .next()
+        .filter(|suffix| suffix.len() < symbol.len())
+}
+
+thread_local! {
+    /// Holidays of the US exchanges by year, as they are looked up for every day counted.
+    static US_HOLIDAYS: RefCell<HashMap<i32, HashSet<NaiveDate>>> = RefCell::new(HashMap::new());
+}
+
+fn is_us_holiday(date: NaiveDate) -> bool {
+    US_HOLIDAYS.with(|us_holidays| {
+        us_holidays
+            .borrow_mut()
+            .entry(date.year())
+            .or_insert_with(|| us_holidays_of_year(date.year()))
+            .contains(&date)
+    })
+}
+
+fn us_holidays_of_year(year: i32) -> HashSet<NaiveDate> {
+    let nth_weekday = |month, weekday, n| NaiveDate::from_weekday_of_month(year, month, weekday, n);
+    let last_weekday = |month, weekday| {
+        NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+            .unwrap_or_else(|| nth_weekday(month, weekday, 4))
+    };
+    // Holidays on a Saturday are observed on the Friday before, and those on a Sunday on the
+    // Monday after
+    let observed = |month, day| {
+        let date = NaiveDate::from_ymd(year, month, day);
+        match date.weekday() {
+            Weekday::Sat => date.pred(),
+            Weekday::Sun => date.succ(),
+            _ => date,
+        }
+    };
+
+    // New Year's Day is not observed on the Friday before when it falls on a Saturday
+    let new_years_day = NaiveDate::from_ymd(year, 1, 1);
+    let new_years_day = if new_years_day.weekday() == Weekday::Sun {
+        new_years_day.succ()
+    } else {
+        new_years_day
+    };
+
+    let mut holidays: HashSet<_> = vec![
+        new_years_day,
+        // Martin Luther King Jr. Day
+        nth_weekday(1, Weekday::Mon, 3),
+        // Washington's Birthday
+        nth_weekday(2, Weekday::Mon, 3),
+        // Good Friday
+        easter_sunday(year) - Duration::days(2),
+        // Memorial Day
+        last_weekday(5, Weekday::Mon),
+        // Independence Day
+        observed(7, 4),
+        // Labor Day
+        nth_weekday(9, Weekday::Mon, 1),
+        // Thanksgiving Day
+        nth_weekday(11, Weekday::Thu, 4),
+        // Christmas Day
+        observed(12, 25),
+    ]
+    .into_iter()
+    .collect();
+    if year >= 2022 {
+        // Juneteenth National Independence Day
+        holidays.insert(observed(6, 19));
+    }
+
+    holidays
+}
+
+/// Computes the date of Easter Sunday with the anonymous Gregorian algorithm.
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd(year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_session_date_skips_weekends_and_holidays() {
+        let market_session = MarketSession::default();
+
+        // Before the open on Tuesday, 2021-01-19, after the Martin Luther King Jr. Day weekend
+        let now = Utc.ymd(2021, 1, 19).and_hms(14, 0, 0);
+        assert_eq!(
+            market_session.latest_session_date(now),
+            NaiveDate::from_ymd(2021, 1, 15)
+        );
+
+        // After the open on the same day
+        let now = Utc.ymd(2021, 1, 19).and_hms(15, 0, 0);
+        assert_eq!(
+            market_session.latest_session_date(now),
+            NaiveDate::from_ymd(2021, 1, 19)
+        );
+    }
+
+    #[test]
+    fn is_stale_once_a_session_is_missing() {
+        let market_session = MarketSession::default();
+        let friday_bar_time = Utc.ymd(2021, 1, 15).and_hms(14, 30, 0);
+
+        assert!(!market_session.is_stale(friday_bar_time, Utc.ymd(2021, 1, 19).and_hms(14, 0, 0)));
+        assert!(market_session.is_stale(friday_bar_time, Utc.ymd(2021, 1, 19).and_hms(15, 0, 0)));
+    }
+
+    #[test]
+    fn session_date_is_in_timezone_of_exchange() {
+        let market_session = MarketSession::default();
+
+        // Late in the evening in New York is already the next day in UTC
+        assert_eq!(
+            market_session.session_date(Utc.ymd(2021, 1, 16).and_hms(1, 0, 0)),
+            NaiveDate::from_ymd(2021, 1, 15)
+        );
+        assert_eq!(
+            MarketSession::for_exchange(Some("JPX"))
+                .session_date(Utc.ymd(2021, 1, 15).and_hms(23, 0, 0)),
+            NaiveDate::from_ymd(2021, 1, 16)
+        );
+    }
+
+    #[test]
+    fn easter_sunday_of_years() {
+        for &(year, month, day) in &[(2000, 4, 23), (2019, 4, 21), (2024, 3, 31), (2025, 4, 20)] {
+            assert_eq!(
+                easter_sunday(year),
+                NaiveDate::from_ymd(year, month, day),
+                "{}",
+                year
+            );
+        }
+    }
+
+    #[test]
+    fn us_holidays_are_observed() {
+        for &(year, month, day, holiday) in &[
+            // Good Friday
+            (2024, 3, 29, true),
+            (2025, 4, 18, true),
+            (2024, 3, 28, false),
+            // Independence Day on a Saturday is observed on the Friday before
+            (2020, 7, 3, true),
+            // Independence Day on a Sunday is observed on the Monday after
+            (2021, 7, 5, true),
+            // Thanksgiving Day, but not the day after
+            (2024, 11, 28, true),
+            (2025, 11, 27, true),
+            (2024, 11, 29, false),
+            // Juneteenth on a Sunday, only since 2022
+            (2022, 6, 20, true),
+            (2021, 6, 18, false),
+            // New Year's Day on a Sunday is observed on the Monday after, but not on the Friday
+            // before when on a Saturday
+            (2023, 1, 2, true),
+            (2021, 12, 31, false),
+        ] {
+            let date = NaiveDate::from_ymd(year, month, day);
+            assert_eq!(is_us_holiday(date), holiday, "{}", date);
+        }
+    }
+
+    #[test]
+    fn us_holidays_of_year_include_juneteenth_since_2022() {
+        assert_eq!(us_holidays_of_year(2021).len(), 9);
+        assert_eq!(us_holidays_of_year(2024).len(), 10);
+    }
+
+    #[test]
+    fn trading_days_skip_holiday_week() {
+        let market_session = MarketSession::default();
+
+        for &((year, month, day), trading_day_count) in &[
+            // Thanksgiving week
+            ((2024, 11, 25), 4),
+            // Good Friday week
+            ((2025, 4, 14), 4),
+            // Week without a holiday
+            ((2025, 4, 7), 5),
+        ] {
+            let monday = NaiveDate::from_ymd(year, month, day);
+            let count = (0..7)
+                .map(|days| monday + Duration::days(days))
+                .filter(|&date| market_session.is_trading_day(date))
+                .count();
+            assert_eq!(count, trading_day_count, "{}", monday);
+        }
+
+        assert_eq!(
+            market_session.sub_trading_days(NaiveDate::from_ymd(2024, 12, 2), 5),
+            NaiveDate::from_ymd(2024, 11, 22)
+        );
+        assert_eq!(
+            market_session.add_trading_days(NaiveDate::from_ymd(2025, 4, 17), 2),
+            NaiveDate::from_ymd(2025, 4, 22)
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use crate::stock::{self, StockSymbolError};
+use chrono::{DateTime, TimeZone, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use thiserror::Error;
+
+/// A simulated account, which trades at the latest prices without any real money involved.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Portfolio {
+    pub cash: f64,
+    /// Equity at the end of each day on which the portfolio was traded or marked, in chronological
+    /// order.
+    pub equity_curve: Vec<EquityPoint>,
+    /// Cash the portfolio started with, for measuring its performance.
+    pub initial_cash: f64,
+    /// Notes written in the trade journal.
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// Orders waiting to be filled, in the order they were placed.
+    #[serde(default)]
+    pub orders: Vec<Order>,
+    pub positions: BTreeMap<String, Position>,
+    pub trades: Vec<Trade>,
+}
+
+impl Portfolio {
+    pub fn new(initial_cash: f64) -> Self {
+        Self {
+            cash: initial_cash,
+            equity_curve: vec![],
+            initial_cash,
+            notes: vec![],
+            orders: vec![],
+            positions: BTreeMap::new(),
+            trades: vec![],
+        }
+    }
+
+    /// Returns the cash along with the value of the positions at their latest prices.
+    pub fn equity(&self) -> f64 {
+        self.cash
+            + self
+                .positions
+                .values()
+                .map(|position| position.market_value())
+                .sum::<f64>()
+    }
+
+    /// Returns the trades and the notes, by symbol and then in chronological order.
+    pub fn journal(&self) -> Vec<JournalEntry> {
+        self.trades
+            .iter()
+            .cloned()
+            .map(JournalEntry::Trade)
+            .chain(self.notes.iter().cloned().map(JournalEntry::Note))
+            .sorted_by(|a, b| {
+                a.symbol()
+                    .cmp(b.symbol())
+                    .then(a.timestamp().cmp(&b.timestamp()))
+            })
+            .collect()
+    }
+
+    /// Applies the event, returning the orders filled or rejected as a result.
+    pub fn apply(
+        &mut self,
+        ev: &PortfolioEvent,
+        now: DateTime<Utc>,
+    ) -> Vec<Result<Trade, PaperTradingError>> {
+        match ev {
+            PortfolioEvent::Mark(prices) => self.mark(prices, now),
+            PortfolioEvent::Note { symbol, text } => {
+                self.notes.push(Note {
+                    symbol: symbol.clone(),
+                    text: text.clone(),
+                    timestamp: now.timestamp(),
+                });
+                vec![]
+            }
+            PortfolioEvent::Place { order, price } => self.place(order.clone(), *price, now),
+        }
+    }
+
+    /// Places the order, which is filled right away if the latest price of its symbol is known
+    /// and satisfies the order.
+    pub fn place(
+        &mut self,
+        order: Order,
+        price: Option<f64>,
+        now: DateTime<Utc>,
+    ) -> Vec<Result<Trade, PaperTradingError>> {
+        let symbol = order.symbol.clone();
+        self.orders.push(order);
+
+        match price {
+            Some(price) => self.fill_orders(&symbol, price, now),
+            None => vec![],
+        }
+    }
+
+    /// Updates the latest prices of the positions held, fills the orders satisfied by the
+    /// prices, and records the equity.
+    pub fn mark(
+        &mut self,
+        prices: &[(String, f64)],
+        now: DateTime<Utc>,
+    ) -> Vec<Result<Trade, PaperTradingError>> {
+        let mut marked = false;
+        for (symbol, price) in prices {
+            if let Some(position) = self.positions.get_mut(symbol) {
+                position.last_price = *price;
+                marked = true;
+            }
+        }
+
+        let fills: Vec<_> = prices
+            .iter()
+            .flat_map(|(symbol, price)| self.fill_orders(symbol, *price, now))
+            .collect();
+
+        if marked {
+            self.record_equity(now);
+        }
+
+        fills
+    }
+
+    /// Fills the orders of the symbol which are satisfied by the price. An order which cannot be
+    /// filled for lack of cash or shares is rejected.
+    fn fill_orders(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        now: DateTime<Utc>,
+    ) -> Vec<Result<Trade, PaperTradingError>> {
+        let (fillable_orders, orders): (Vec<_>, Vec<_>) = self
+            .orders
+            .drain(..)
+            .partition(|order| order.symbol == symbol && order.is_fillable_at(price));
+        self.orders = orders;
+
+        fillable_orders
+            .iter()
+            .map(|order| self.fill(order, price, now))
+            .collect()
+    }
+
+    /// Fills the order in full at the given price. Selling short is not supported.
+    fn fill(
+        &mut self,
+        order: &Order,
+        price: f64,
+        now: DateTime<Utc>,
+    ) -> Result<Trade, PaperTradingError> {
+        let value = order.quantity * price;
+        match order.side {
+            Side::Buy => {
+                if value > self.cash {
+                    return Err(PaperTradingError::InsufficientCash {
+                        available: self.cash,
+                        required: value,
+                    });
+                }
+
+                self.cash -= value;
+                let position = self
+                    .positions
+                    .entry(order.symbol.clone())
+                    .or_insert_with(|| Position {
+                        average_price: price,
+                        last_price: price,
+                        quantity: 0.0,
+                    });
+                position.average_price = (position.average_price * position.quantity + value)
+                    / (position.quantity + order.quantity);
+                position.last_price = price;
+                position.quantity += order.quantity;
+            }
+            Side::Sell => {
+                let held = self
+                    .positions
+                    .get(&order.symbol)
+                    .map_or(0.0, |position| position.quantity);
+                if order.quantity > held {
+                    return Err(PaperTradingError::InsufficientPosition {
+                        held,
+                        symbol: order.symbol.clone(),
+                    });
+                }
+
+                self.cash += value;
+                if order.quantity < held {
+                    let position = self.positions.get_mut(&order.symbol).unwrap();
+                    position.last_price = price;
+                    position.quantity -= order.quantity;
+                } else {
+                    self.positions.remove(&order.symbol);
+                }
+            }
+        }
+
+        let trade = Trade {
+            price,
+            quantity: order.quantity,
+            side: order.side,
+            symbol: order.symbol.clone(),
+            timestamp: now.timestamp(),
+        };
+        self.trades.push(trade.clone());
+        self.record_equity(now);
+
+        Ok(trade)
+    }
+
+    /// Records the current equity, replacing the point recorded earlier on the same day, if any.
+    fn record_equity(&mut self, now: DateTime<Utc>) {
+        let point = EquityPoint {
+            equity: self.equity(),
+            timestamp: now.timestamp(),
+        };
+
+        match self.equity_curve.last_mut() {
+            Some(last_point) if Utc.timestamp(last_point.timestamp, 0).date() == now.date() => {
+                *last_point = point;
+            }
+            _ => self.equity_curve.push(point),
+        }
+    }
+}
+
+/// An entry of the trade journal, which is either a trade or a note.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JournalEntry {
+    Note(Note),
+    Trade(Trade),
+}
+
+impl JournalEntry {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Note(note) => &note.symbol,
+            Self::Trade(trade) => &trade.symbol,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Self::Note(note) => note.timestamp,
+            Self::Trade(trade) => trade.timestamp,
+        }
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Note(note) => write!(f, "{}", note.text),
+            Self::Trade(trade) => write!(f, "{}", trade),
+        }
+    }
+}
+
+/// Format of the exported trade journal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JournalFormat {
+    Csv,
+    Json,
+}
+
+impl JournalFormat {
+    /// Formats the journal entries, one per row or array element.
+    pub fn format(self, entries: &[JournalEntry]) -> anyhow::Result<String> {
+        match self {
+            Self::Csv => {
+                let mut csv = String::from("time,symbol,type,side,quantity,price,note\n");
+                for entry in entries {
+                    let time = Utc.timestamp(entry.timestamp(), 0).to_rfc3339();
+                    let fields = match entry {
+                        JournalEntry::Note(note) => vec![
+                            time,
+                            note.symbol.clone(),
+                            "note".to_owned(),
+                            String::new(),
+                            String::new(),
+                            String::new(),
+                            note.text.clone(),
+                        ],
+                        JournalEntry::Trade(trade) => vec![
+                            time,
+                            trade.symbol.clone(),
+                            "trade".to_owned(),
+                            trade.side.to_string(),
+                            trade.quantity.to_string(),
+                            trade.price.to_string(),
+                            String::new(),
+                        ],
+                    };
+                    csv.push_str(&fields.iter().map(|field| escape_csv_field(field)).join(","));
+                    csv.push('\n');
+                }
+
+                Ok(csv)
+            }
+            Self::Json => Ok(serde_json::to_string_pretty(entries)?),
+        }
+    }
+}
+
+impl FromStr for JournalFormat {
+    type Err = ParseJournalFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "" => Err(ParseJournalFormatError::Empty),
+            _ => Err(ParseJournalFormatError::Invalid),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EquityPoint {
+    pub equity: f64,
+    pub timestamp: i64,
+}
+
+/// A note written in the trade journal, about the given symbol.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Note {
+    pub symbol: String,
+    pub text: String,
+    pub timestamp: i64,
+}
+
+/// An order to trade the given quantity, either at the latest price or at the limit price or
+/// better.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Order {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<f64>,
+    pub quantity: f64,
+    pub side: Side,
+    pub symbol: String,
+}
+
+impl Order {
+    pub fn is_fillable_at(&self, price: f64) -> bool {
+        match (self.side, self.limit_price) {
+            (_, None) => true,
+            (Side::Buy, Some(limit_price)) => price <= limit_price,
+            (Side::Sell, Some(limit_price)) => price >= limit_price,
+        }
+    }
+}
+
+/// Parses an order from its side, quantity, symbol and optionally its limit price, e.g.
+/// `buy 10 AAPL` or `sell 5 AAPL @ 150`.
+impl FromStr for Order {
+    type Err = ParseOrderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, limit_price) = match s.find('@') {
+            Some(i) => {
+                let limit_price = s[i + 1..].trim();
+                match limit_price.parse::<f64>() {
+                    Ok(limit_price) if limit_price > 0.0 && limit_price.is_finite() => {
+                        (&s[..i], Some(limit_price))
+                    }
+                    _ => return Err(ParseOrderError::InvalidPrice(limit_price.to_owned())),
+                }
+            }
+            None => (s, None),
+        };
+
+        let mut words = s.split_whitespace();
+        let side = match words.next() {
+            Some(side) if side.eq_ignore_ascii_case("buy") => Side::Buy,
+            Some(side) if side.eq_ignore_ascii_case("sell") => Side::Sell,
+            Some(side) => return Err(ParseOrderError::InvalidSide(side.to_owned())),
+            None => return Err(ParseOrderError::Empty),
+        };
+        let quantity = match words.next() {
+            Some(quantity) => match quantity.parse::<f64>() {
+                Ok(quantity) if quantity > 0.0 && quantity.is_finite() => quantity,
+                _ => return Err(ParseOrderError::InvalidQuantity(quantity.to_owned())),
+            },
+            None => return Err(ParseOrderError::MissingQuantity),
+        };
+        let symbol = match words.next() {
+            Some(symbol) => {
+                stock::validate_stock_symbol(symbol)?;
+                symbol.to_ascii_uppercase()
+            }
+            None => return Err(ParseOrderError::MissingSymbol),
+        };
+        if let Some(word) = words.next() {
+            return Err(ParseOrderError::UnexpectedWord(word.to_owned()));
+        }
+
+        Ok(Self {
+            limit_price,
+            quantity,
+            side,
+            symbol,
+        })
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.side, self.quantity, self.symbol)?;
+        if let Some(limit_price) = self.limit_price {
+            write!(f, " @ {}", limit_price)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Position {
+    pub average_price: f64,
+    pub last_price: f64,
+    pub quantity: f64,
+}
+
+impl Position {
+    pub fn market_value(&self) -> f64 {
+        self.quantity * self.last_price
+    }
+
+    /// Returns the profit or loss of the position if it were closed at the latest price.
+    pub fn unrealized_profit(&self) -> f64 {
+        (self.last_price - self.average_price) * self.quantity
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Buy => write!(f, "buy"),
+            Self::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Trade {
+    pub price: f64,
+    pub quantity: f64,
+    pub side: Side,
+    pub symbol: String,
+    pub timestamp: i64,
+}
+
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self.side {
+            Side::Buy => "Bought",
+            Side::Sell => "Sold",
+        };
+
+        write!(
+            f,
+            "{} {} {} at {:.2}",
+            verb, self.quantity, self.symbol, self.price
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PortfolioEvent {
+    /// The latest prices of some symbols.
+    Mark(Vec<(String, f64)>),
+    /// A note written in the trade journal.
+    Note { symbol: String, text: String },
+    /// An order placed, along with the latest price of its symbol if known.
+    Place { order: Order, price: Option<f64> },
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum PaperTradingError {
+    #[error("insufficient cash: {required:.2} required, {available:.2} available")]
+    InsufficientCash { available: f64, required: f64 },
+    #[error("insufficient position: {held} {symbol} held")]
+    InsufficientPosition { held: f64, symbol: String },
+}
+
+#[derive(Debug, Error)]
+pub enum ParseJournalFormatError {
+    #[error("cannot parse journal format from empty string")]
+    Empty,
+    #[error("invalid journal format literal, expected csv or json")]
+    Invalid,
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ParseOrderError {
+    #[error("cannot parse order from empty string")]
+    Empty,
+    #[error("invalid limit price {0}")]
+    InvalidPrice(String),
+    #[error("invalid quantity {0}")]
+    InvalidQuantity(String),
+    #[error("invalid side {0}, expected buy or sell")]
+    InvalidSide(String),
+    #[error("missing quantity, e.g. buy 10 AAPL")]
+    MissingQuantity,
+    #[error("missing symbol, e.g. buy 10 AAPL")]
+    MissingSymbol,
+    #[error(transparent)]
+    Symbol(#[from] StockSymbolError),
+    #[error("unexpected {0}")]
+    UnexpectedWord(String),
+}
+
+/// Quotes the field if it contains any delimiter, quote or line break.
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
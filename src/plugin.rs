@@ -0,0 +1,367 @@
+use crate::series::CustomSeries;
+use anyhow::{anyhow, Context};
+use chrono::{TimeZone, Utc};
+use im::OrdSet;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{cell::Cell, fs, path::Path};
+use tracing::{debug_span, warn};
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+use yahoo_finance::{Bar, Timestamped};
+
+/// Fuel which each call into a plugin may consume, roughly the number of instructions executed, so
+/// that a plugin which loops forever is stopped instead of hanging the app.
+const CALL_FUEL: u64 = 1_000_000_000;
+
+/// Pages of 64 KiB which the memory of a plugin may grow to, i.e. 64 MiB, so that a plugin which
+/// allocates without bound is denied more memory instead of exhausting that of the app.
+const MAX_MEMORY_PAGES: u32 = 1024;
+
+/// Elements which each table of a plugin may grow to.
+const MAX_TABLE_ELEMENTS: u32 = 10_000;
+
+/// Names of the plugin and of the indicators and panels which it provides.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PluginManifest {
+    #[serde(default)]
+    pub indicators: Vec<String>,
+    pub name: String,
+    #[serde(default)]
+    pub panels: Vec<String>,
+}
+
+/// A panel rendered by a plugin, as lines of text.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PluginPanel {
+    pub lines: Vec<String>,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginInput<'a> {
+    bars: Vec<PluginBar>,
+    price: Option<f64>,
+    symbol: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginBar {
+    close: f64,
+    high: f64,
+    low: f64,
+    open: f64,
+    time: i64,
+    volume: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndicatorOutput {
+    series: Vec<IndicatorSeries>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndicatorSeries {
+    name: String,
+    points: Vec<(i64, f64)>,
+}
+
+/// A WebAssembly module in the plugins directory, which is loaded at startup. Plugins exchange JSON
+/// documents with the app through their linear memory:
+///
+/// - `memory` is the exported memory
+/// - `alloc(len: i32) -> i32` allocates a buffer of the length for the input, returning its offset
+/// - `manifest() -> i64` returns the manifest, which names the plugin and the indicators and panels
+///   provided by it, e.g. `{"name": "example", "indicators": ["zscore"], "panels": ["notes"]}`
+/// - `indicator(name: i32, name_len: i32, input: i32, input_len: i32) -> i64` computes the series
+///   of the named indicator, e.g. `{"series": [{"name": "Z-score", "points": [[1609459200, 1.5]]}]}`
+/// - `panel(name: i32, name_len: i32, input: i32, input_len: i32) -> i64` renders the named panel,
+///   e.g. `{"title": "Notes", "lines": ["Earnings next week"]}`
+///
+/// The input is the current stock, e.g. `{"symbol": "AAPL", "price": 130.0, "bars": [...]}` with
+/// each bar as `{"time": 1609459200, "open": ..., "high": ..., "low": ..., "close": ...,
+/// "volume": ...}`. The outputs are returned as the offset in the upper 32 bits and the length in
+/// the lower 32 bits. Each call is limited to [`CALL_FUEL`], and the memory of the plugin to
+/// [`MAX_MEMORY_PAGES`].
+pub struct Plugin {
+    alloc: TypedFunc<i32, i32>,
+    /// Fuel added to the store so far, as the store only counts the fuel consumed.
+    fuel_added: Cell<u64>,
+    instance: Instance,
+    pub manifest: PluginManifest,
+    memory: Memory,
+    store: Store,
+}
+
+impl Plugin {
+    pub fn load<P>(engine: &Engine, path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let module = Module::from_file(engine, path)
+            .with_context(|| format!("failed to compile {}", path.display()))?;
+
+        Self::instantiate(engine, &module)
+    }
+
+    fn instantiate(engine: &Engine, module: &Module) -> anyhow::Result<Self> {
+        let store = Store::new_with_limits(engine, limits());
+        // Instantiating the plugin and reading its manifest share the fuel of a call
+        store.add_fuel(CALL_FUEL)?;
+        // Plugins have no access to the host, other than through their inputs
+        let instance =
+            Instance::new(&store, module, &[]).with_context(|| "failed to instantiate plugin")?;
+        let memory = instance
+            .get_memory("memory")
+            .with_context(|| "plugin does not export its memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>("alloc")?;
+        let manifest_fn = instance.get_typed_func::<(), i64>("manifest")?;
+
+        let manifest = serde_json::from_slice(&read_output(&memory, manifest_fn.call(())?)?)
+            .with_context(|| "invalid plugin manifest")?;
+
+        Ok(Self {
+            alloc,
+            fuel_added: Cell::new(CALL_FUEL),
+            instance,
+            manifest,
+            memory,
+            store,
+        })
+    }
+
+    /// Computes the series of the indicator for the stock.
+    pub fn indicator(
+        &self,
+        name: &str,
+        stock_symbol: &str,
+        bars: &OrdSet<Bar>,
+        price: Option<f64>,
+    ) -> anyhow::Result<Vec<CustomSeries>> {
+        let output: IndicatorOutput =
+            self.call("indicator", name, &to_input(stock_symbol, bars, price))?;
+
+        Ok(output
+            .series
+            .into_iter()
+            .map(|IndicatorSeries { name, points }| CustomSeries {
+                points: points
+                    .into_iter()
+                    .filter_map(|(time, value)| match Utc.timestamp_opt(time, 0).single() {
+                        Some(datetime) => Some((datetime, value)),
+                        None => {
+                            warn!("dropped point of {} with invalid time {}", name, time);
+                            None
+                        }
+                    })
+                    .collect(),
+                name,
+            })
+            .collect())
+    }
+
+    /// Renders the panel for the stock.
+    pub fn panel(
+        &self,
+        name: &str,
+        stock_symbol: &str,
+        bars: &OrdSet<Bar>,
+        price: Option<f64>,
+    ) -> anyhow::Result<PluginPanel> {
+        self.call("panel", name, &to_input(stock_symbol, bars, price))
+    }
+
+    fn call<I, O>(&self, export: &str, name: &str, input: &I) -> anyhow::Result<O>
+    where
+        I: Serialize,
+        O: DeserializeOwned,
+    {
+        let _span =
+            debug_span!("call_plugin", plugin = %self.manifest.name, export, name).entered();
+
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32, i32, i32), i64>(export)?;
+        self.refuel()?;
+        let (name_offset, name_len) = self.write_input(name.as_bytes())?;
+        let (input_offset, input_len) = self.write_input(&serde_json::to_vec(input)?)?;
+        let output = func.call((name_offset, name_len, input_offset, input_len))?;
+
+        serde_json::from_slice(&read_output(&self.memory, output)?)
+            .with_context(|| format!("invalid output of {} {}", export, name))
+    }
+
+    /// Tops up the fuel of the store, so that the next call may consume up to [`CALL_FUEL`]
+    /// regardless of how much the previous calls have left.
+    fn refuel(&self) -> anyhow::Result<()> {
+        let fuel_consumed = self.store.fuel_consumed().unwrap_or_default();
+        let fuel_remaining = self.fuel_added.get().saturating_sub(fuel_consumed);
+        let fuel = CALL_FUEL.saturating_sub(fuel_remaining);
+        self.store.add_fuel(fuel)?;
+        self.fuel_added.set(self.fuel_added.get() + fuel);
+
+        Ok(())
+    }
+
+    fn write_input(&self, bytes: &[u8]) -> anyhow::Result<(i32, i32)> {
+        let len = bytes.len() as i32;
+        let offset = self.alloc.call(len)?;
+        self.memory
+            .write(offset as usize, bytes)
+            .map_err(|err| anyhow!("input out of bounds: {}", err))?;
+
+        Ok((offset, len))
+    }
+}
+
+/// Loads the plugins in the directory. The plugins which fail to load are skipped.
+pub fn load_plugins<P>(plugins_dir: P) -> Vec<Plugin>
+where
+    P: AsRef<Path>,
+{
+    let entries = match fs::read_dir(plugins_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let engine = match Engine::new(Config::new().consume_fuel(true)) {
+        Ok(engine) => engine,
+        Err(err) => {
+            warn!("failed to create plugin engine: {:?}", err);
+            return vec![];
+        }
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "wasm")
+        })
+        .filter_map(|path| match Plugin::load(&engine, &path) {
+            Ok(plugin) => Some(plugin),
+            Err(err) => {
+                warn!("failed to load plugin {}: {:?}", path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Limits the growth of the memory and tables of a plugin. Growing them past the limits fails
+/// within the plugin, as if the host had run out of memory.
+fn limits() -> StoreLimits {
+    StoreLimitsBuilder::new()
+        .memory_pages(MAX_MEMORY_PAGES)
+        .table_elements(MAX_TABLE_ELEMENTS)
+        .build()
+}
+
+fn to_input<'a>(stock_symbol: &'a str, bars: &OrdSet<Bar>, price: Option<f64>) -> PluginInput<'a> {
+    PluginInput {
+        bars: bars
+            .iter()
+            .map(|bar| PluginBar {
+                close: bar.close,
+                high: bar.high,
+                low: bar.low,
+                open: bar.open,
+                time: bar.timestamp_seconds() as i64,
+                volume: bar.volume,
+            })
+            .collect(),
+        price,
+        symbol: stock_symbol,
+    }
+}
+
+/// Reads the output at the offset in the upper 32 bits and of the length in the lower 32 bits.
+fn read_output(memory: &Memory, output: i64) -> anyhow::Result<Vec<u8>> {
+    let offset = (output as u64 >> 32) as usize;
+    let len = (output as u64 & 0xffff_ffff) as usize;
+    // The length is checked before allocating, as it is up to the plugin
+    if offset
+        .checked_add(len)
+        .map_or(true, |end| end > memory.data_size())
+    {
+        return Err(anyhow!(
+            "output out of bounds: {} bytes at offset {}",
+            len,
+            offset
+        ));
+    }
+    let mut bytes = vec![0; len];
+    memory
+        .read(offset, &mut bytes)
+        .map_err(|err| anyhow!("output out of bounds: {}", err))?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{"name":"greedy","panels":["notes"]}"#;
+    const PANEL: &str = r#"{"title":"Notes","lines":[]}"#;
+    const PANEL_OFFSET: u64 = 256;
+
+    fn engine() -> Engine {
+        Engine::new(Config::new().consume_fuel(true)).unwrap()
+    }
+
+    /// Compiles a plugin with the initial pages of memory, whose `alloc` grows the memory by the
+    /// pages and returns the offset of the grown pages.
+    fn greedy_module(engine: &Engine, init_pages: u32, alloc_pages: u32) -> Module {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") {init_pages})
+                (data (i32.const 0) "{manifest}")
+                (data (i32.const {panel_offset}) "{panel}")
+                (func (export "manifest") (result i64)
+                    (i64.const {manifest_output}))
+                (func (export "alloc") (param i32) (result i32)
+                    (i32.mul (memory.grow (i32.const {alloc_pages})) (i32.const 65536)))
+                (func (export "panel") (param i32 i32 i32 i32) (result i64)
+                    (i64.const {panel_output})))"#,
+            init_pages = init_pages,
+            manifest = MANIFEST.replace('"', "\\\""),
+            manifest_output = MANIFEST.len(),
+            panel_offset = PANEL_OFFSET,
+            panel = PANEL.replace('"', "\\\""),
+            panel_output = (PANEL_OFFSET << 32) | PANEL.len() as u64,
+            alloc_pages = alloc_pages,
+        );
+
+        Module::new(engine, wat).unwrap()
+    }
+
+    #[test]
+    fn plugin_growing_memory_within_limit_is_called() {
+        let engine = engine();
+        let plugin = Plugin::instantiate(&engine, &greedy_module(&engine, 1, 1)).unwrap();
+        let panel = plugin.panel("notes", "AAPL", &OrdSet::new(), None).unwrap();
+
+        assert_eq!(plugin.manifest.name, "greedy");
+        assert_eq!(panel.title, "Notes");
+        // The name and the input are allocated a page each
+        assert_eq!(plugin.memory.size(), 3);
+    }
+
+    #[test]
+    fn plugin_growing_memory_past_limit_is_stopped() {
+        let engine = engine();
+        let plugin = Plugin::instantiate(&engine, &greedy_module(&engine, 1, 4096)).unwrap();
+
+        assert!(plugin.panel("notes", "AAPL", &OrdSet::new(), None).is_err());
+        assert_eq!(plugin.memory.size(), 1);
+    }
+
+    #[test]
+    fn plugin_with_memory_past_limit_is_not_instantiated() {
+        let engine = engine();
+        let module = greedy_module(&engine, MAX_MEMORY_PAGES + 1, 0);
+
+        assert!(Plugin::instantiate(&engine, &module).is_err());
+    }
+}
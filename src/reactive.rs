@@ -1,3 +1,5 @@
 pub use stream_ext::*;
+pub use topic::*;
 
 mod stream_ext;
+mod topic;
@@ -1,3 +1,4 @@
+use super::Topic;
 use derivative::Derivative;
 use im::{hashmap, HashMap};
 use reactive_rs::{Broadcast, Stream};
@@ -76,6 +77,18 @@ pub trait StreamExt<'a>: Stream<'a> {
         }
     }
 
+    /// Emits the value to each new subscriber before the values of the stream.
+    fn start_with(self, value: Self::Item) -> StartWith<Self, Self::Item>
+    where
+        Self::Item: 'a + Sized,
+        Self::Context: Default,
+    {
+        StartWith {
+            stream: self,
+            value,
+        }
+    }
+
     fn switch(self) -> Switch<Self>
     where
         Self::Item: Stream<'a>,
@@ -83,6 +96,16 @@ pub trait StreamExt<'a>: Stream<'a> {
         Switch { stream: self }
     }
 
+    /// Shares the stream as a topic, which replays its latest value to the subscribers set up later.
+    fn topic(self) -> Topic<'a, Self::Item>
+    where
+        Self::Item: 'a + Clone + Sized,
+    {
+        let topic = Topic::new();
+        topic.feed_from(self);
+        topic
+    }
+
     fn with_latest_from<U, F, T>(
         self,
         other: U,
@@ -340,6 +363,29 @@ where
     }
 }
 
+pub struct StartWith<S, T: Sized> {
+    stream: S,
+    value: T,
+}
+
+impl<'a, S, T> Stream<'a> for StartWith<S, T>
+where
+    S: Stream<'a, Item = T>,
+    S::Context: Default,
+    T: 'a + Sized,
+{
+    type Context = S::Context;
+    type Item = T;
+
+    fn subscribe_ctx<O>(self, mut observer: O)
+    where
+        O: 'a + FnMut(&Self::Context, &Self::Item),
+    {
+        observer(&S::Context::default(), &self.value);
+        self.stream.subscribe_ctx(observer);
+    }
+}
+
 pub struct Switch<S> {
     stream: S,
 }
@@ -395,8 +441,16 @@ where
     where
         O: 'a + FnMut(&Self::Context, &Self::Item),
     {
-        self.stream_a.subscribe_ctx({
+        // The other stream is subscribed to first, so that its latest value is already known when
+        // the stream replays its own
+        self.stream_b.subscribe({
             let buf_b = self.buf_b.clone();
+            move |b| {
+                buf_b.borrow_mut().replace(b.clone());
+            }
+        });
+        self.stream_a.subscribe_ctx({
+            let buf_b = self.buf_b;
             let mut func = self.func;
             move |ctx, a| {
                 let buf_b = buf_b.borrow();
@@ -407,12 +461,6 @@ where
                 }
             }
         });
-        self.stream_b.subscribe({
-            let buf_b = self.buf_b;
-            move |b| {
-                buf_b.borrow_mut().replace(b.clone());
-            }
-        });
     }
 }
 
@@ -0,0 +1,246 @@
+use reactive_rs::{Broadcast, Stream};
+use std::{cell::RefCell, rc::Rc};
+
+/// Broadcast which replays the latest value sent to each new subscriber, so that the subscribers
+/// which are set up after a value has been sent still start from it. It is meant for state, while
+/// events are sent to an [`EventTopic`].
+pub struct Topic<'a, T> {
+    broadcast: Broadcast<'a, (), T>,
+    latest: Rc<RefCell<Option<T>>>,
+}
+
+impl<'a, T> Topic<'a, T>
+where
+    T: 'a + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            broadcast: Broadcast::new(),
+            latest: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Creates a topic which replays the value until another one is sent.
+    pub fn with_value(value: T) -> Self {
+        let topic = Self::new();
+        topic.send(value);
+        topic
+    }
+
+    pub fn send(&self, value: T) {
+        *self.latest.borrow_mut() = Some(value.clone());
+        self.broadcast.send(value);
+    }
+
+    /// Sends each value of the stream to the topic.
+    pub fn feed_from<S>(&self, values: S)
+    where
+        S: Stream<'a, Item = T>,
+    {
+        let topic = self.clone();
+        values.subscribe(move |value| topic.send(value.clone()));
+    }
+}
+
+impl<'a, T> Clone for Topic<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            broadcast: self.broadcast.clone(),
+            latest: self.latest.clone(),
+        }
+    }
+}
+
+impl<'a, T> Default for Topic<'a, T>
+where
+    T: 'a + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> Stream<'a> for Topic<'a, T>
+where
+    T: 'a + Clone,
+{
+    type Context = ();
+    type Item = T;
+
+    fn subscribe_ctx<O>(self, mut observer: O)
+    where
+        O: 'a + FnMut(&Self::Context, &Self::Item),
+    {
+        // The value is cloned first, as the observer may send to the topic
+        let latest = self.latest.borrow().clone();
+        if let Some(latest) = latest {
+            observer(&(), &latest);
+        }
+        self.broadcast.subscribe_ctx(observer);
+    }
+}
+
+/// Broadcast of events, which are only sent to the subscribers set up before them. Unlike a
+/// [`Topic`], an event is never replayed, so that it is handled once.
+pub struct EventTopic<'a, T> {
+    broadcast: Broadcast<'a, (), T>,
+}
+
+impl<'a, T> EventTopic<'a, T>
+where
+    T: 'a + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            broadcast: Broadcast::new(),
+        }
+    }
+
+    pub fn send(&self, event: T) {
+        self.broadcast.send(event);
+    }
+
+    /// Sends each value of the stream to the topic.
+    pub fn feed_from<S>(&self, events: S)
+    where
+        S: Stream<'a, Item = T>,
+    {
+        let topic = self.clone();
+        events.subscribe(move |event| topic.send(event.clone()));
+    }
+}
+
+impl<'a, T> Clone for EventTopic<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            broadcast: self.broadcast.clone(),
+        }
+    }
+}
+
+impl<'a, T> Default for EventTopic<'a, T>
+where
+    T: 'a + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> Stream<'a> for EventTopic<'a, T>
+where
+    T: 'a + Clone,
+{
+    type Context = ();
+    type Item = T;
+
+    fn subscribe_ctx<O>(self, observer: O)
+    where
+        O: 'a + FnMut(&Self::Context, &Self::Item),
+    {
+        self.broadcast.subscribe_ctx(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reactive::StreamExt;
+
+    fn collect<'a, S>(topic: &S) -> Rc<RefCell<Vec<S::Item>>>
+    where
+        S: Clone + Stream<'a>,
+        S::Item: 'a + Clone + Sized,
+    {
+        let values = Rc::new(RefCell::new(vec![]));
+        topic.clone().subscribe({
+            let values = values.clone();
+            move |value| values.borrow_mut().push(value.clone())
+        });
+        values
+    }
+
+    #[test]
+    fn topic_replays_latest_value_to_late_subscriber() {
+        let topic = Topic::new();
+        topic.send(1);
+        topic.send(2);
+        let values = collect(&topic);
+        topic.send(3);
+
+        assert_eq!(*values.borrow(), vec![2, 3]);
+    }
+
+    #[test]
+    fn topic_replays_nothing_until_value_is_sent() {
+        let topic: Topic<i32> = Topic::new();
+        let values = collect(&topic);
+
+        assert!(values.borrow().is_empty());
+    }
+
+    #[test]
+    fn topic_replays_initial_value() {
+        let topic = Topic::with_value("init");
+        let early_values = collect(&topic);
+        let late_values = collect(&topic);
+
+        assert_eq!(*early_values.borrow(), vec!["init"]);
+        assert_eq!(*late_values.borrow(), vec!["init"]);
+    }
+
+    #[test]
+    fn topic_fed_from_stream_replays_its_latest_value() {
+        let source = Topic::new();
+        let topic = source.clone().map(|value| value * 10).topic();
+        source.send(1);
+        let values = collect(&topic);
+
+        assert_eq!(*values.borrow(), vec![10]);
+    }
+
+    #[test]
+    fn start_with_replays_value_before_stream() {
+        let source = Topic::new();
+        let topic = source.clone().start_with(0).topic();
+        let values = collect(&topic);
+        source.send(1);
+
+        assert_eq!(*values.borrow(), vec![0, 1]);
+    }
+
+    #[test]
+    fn with_latest_from_combines_replayed_values() {
+        let a = Topic::with_value(1);
+        let b = Topic::with_value("b");
+        let topic = a.with_latest_from(b, |(a, b)| (*a, *b)).topic();
+        let values = collect(&topic);
+
+        assert_eq!(*values.borrow(), vec![(1, "b")]);
+    }
+
+    #[test]
+    fn event_topic_sends_event_only_to_earlier_subscribers() {
+        let topic: EventTopic<i32> = EventTopic::new();
+        let early_values = collect(&topic);
+        topic.send(1);
+        let late_values = collect(&topic);
+        topic.send(2);
+
+        assert_eq!(*early_values.borrow(), vec![1, 2]);
+        assert_eq!(*late_values.borrow(), vec![2]);
+    }
+
+    #[test]
+    fn event_topic_fed_from_topic_sends_its_latest_value_once() {
+        let source = Topic::with_value(1);
+        let topic: EventTopic<i32> = EventTopic::new();
+        let values = collect(&topic);
+        topic.feed_from(source.clone());
+        let late_values = collect(&topic);
+        source.send(2);
+
+        assert_eq!(*values.borrow(), vec![1, 2]);
+        assert_eq!(*late_values.borrow(), vec![2]);
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use yahoo_finance::{Bar, Timestamped};
+
+/// Least squares line through a set of points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearRegression {
+    pub intercept: f64,
+    pub slope: f64,
+}
+
+impl LinearRegression {
+    /// Fits a line through the (x, y) points, or returns `None` if there are fewer than 2 points or
+    /// the x values do not vary.
+    pub fn fit(points: &[(f64, f64)]) -> Option<Self> {
+        if points.len() < 2 {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let (covariance, variance) =
+            points
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                    (
+                        covariance + (x - mean_x) * (y - mean_y),
+                        variance + (x - mean_x).powi(2),
+                    )
+                });
+        if variance == 0.0 {
+            return None;
+        }
+
+        let slope = covariance / variance;
+
+        Some(Self {
+            intercept: mean_y - slope * mean_x,
+            slope,
+        })
+    }
+}
+
+/// Returns the beta of a stock against a benchmark, which is the slope of the returns of the stock
+/// regressed on the returns of the benchmark, over the bars which both of them have.
+pub fn to_beta<'a, I, J>(bars: I, benchmark_bars: J) -> Option<f64>
+where
+    I: IntoIterator<Item = &'a Bar>,
+    J: IntoIterator<Item = &'a Bar>,
+{
+    let benchmark_closes: BTreeMap<i64, f64> = benchmark_bars
+        .into_iter()
+        .map(|bar| (bar.timestamp_seconds() as i64, bar.close))
+        .collect();
+    let closes: Vec<(f64, f64)> = bars
+        .into_iter()
+        .filter_map(|bar| {
+            let benchmark_close = benchmark_closes.get(&(bar.timestamp_seconds() as i64))?;
+
+            Some((*benchmark_close, bar.close))
+        })
+        .collect();
+    let returns: Vec<(f64, f64)> = closes
+        .windows(2)
+        .map(|window| {
+            let (benchmark_close_0, close_0) = window[0];
+            let (benchmark_close_1, close_1) = window[1];
+
+            (
+                benchmark_close_1 / benchmark_close_0 - 1.0,
+                close_1 / close_0 - 1.0,
+            )
+        })
+        .filter(|(benchmark_return, stock_return)| {
+            benchmark_return.is_finite() && stock_return.is_finite()
+        })
+        .collect();
+
+    LinearRegression::fit(&returns).map(|regression| regression.slope)
+}
@@ -0,0 +1,378 @@
+use crate::{
+    app::{ParseTimeFrameError, TimeFrame},
+    paper,
+    stock::StockSymbolError,
+};
+use im::OrdSet;
+use reactive_rs::Stream;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+use thiserror::Error;
+use tracing::{debug, debug_span, warn};
+use yahoo_finance::{Bar, Timestamped};
+
+/// Command sent to the running app through the remote control socket, one per line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteCommand {
+    /// `export csv PATH` writes the bars of the current stock to the file.
+    Export(PathBuf),
+    /// `symbol SYMBOL` shows the stock.
+    Symbol(String),
+    /// `timeframe TIME_FRAME` changes the time frame, e.g. `1y`.
+    TimeFrame(TimeFrame),
+}
+
+impl FromStr for RemoteCommand {
+    type Err = ParseRemoteCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = words.next().ok_or(ParseRemoteCommandError::Empty)?;
+
+        match command {
+            "export" => {
+                let format = words
+                    .next()
+                    .ok_or(ParseRemoteCommandError::MissingArgument("format"))?;
+                if !format.eq_ignore_ascii_case("csv") {
+                    return Err(ParseRemoteCommandError::UnsupportedFormat(
+                        format.to_owned(),
+                    ));
+                }
+                // The path is the rest of the line, so that it may contain spaces
+                let path = s
+                    .trim()
+                    .splitn(3, char::is_whitespace)
+                    .nth(2)
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .ok_or(ParseRemoteCommandError::MissingArgument("path"))?;
+
+                Ok(Self::Export(PathBuf::from(path)))
+            }
+            "symbol" => {
+                let symbol = words
+                    .next()
+                    .ok_or(ParseRemoteCommandError::MissingArgument("symbol"))?;
+
+                Ok(Self::Symbol(symbol.to_ascii_uppercase()))
+            }
+            "timeframe" => {
+                let time_frame = words
+                    .next()
+                    .ok_or(ParseRemoteCommandError::MissingArgument("time frame"))?
+                    .parse()?;
+
+                Ok(Self::TimeFrame(time_frame))
+            }
+            _ => Err(ParseRemoteCommandError::UnknownCommand(command.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseRemoteCommandError {
+    #[error("empty command")]
+    Empty,
+    #[error("invalid time frame: {0}")]
+    InvalidTimeFrame(#[from] ParseTimeFrameError),
+    #[error("missing {0}")]
+    MissingArgument(&'static str),
+    #[error("unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("unsupported export format: {0}")]
+    UnsupportedFormat(String),
+}
+
+/// Maps the ticks to the commands received on the remote control socket since the last tick. The
+/// socket is listened on in the background from the first subscription. The symbols are resolved
+/// on the listening thread before the commands are forwarded, so that an invalid or unknown
+/// symbol is replied to with its error instead.
+pub fn to_remote_commands<'a, T, R>(
+    socket_path: PathBuf,
+    ticks: T,
+    resolve_symbol: R,
+) -> ToRemoteCommands<T, R>
+where
+    T: Stream<'a>,
+    R: 'static + Fn(&str) -> Result<String, StockSymbolError> + Send + Sync,
+{
+    ToRemoteCommands {
+        resolve_symbol,
+        socket_path,
+        ticks,
+    }
+}
+
+pub struct ToRemoteCommands<T, R> {
+    resolve_symbol: R,
+    socket_path: PathBuf,
+    ticks: T,
+}
+
+impl<'a, T, R> Stream<'a> for ToRemoteCommands<T, R>
+where
+    T: Stream<'a>,
+    R: 'static + Fn(&str) -> Result<String, StockSymbolError> + Send + Sync,
+{
+    type Context = T::Context;
+    type Item = RemoteCommand;
+
+    fn subscribe_ctx<O>(self, mut observer: O)
+    where
+        O: 'a + FnMut(&Self::Context, &Self::Item),
+    {
+        let mut command_receiver = Some(listen(self.socket_path, self.resolve_symbol));
+        self.ticks.subscribe_ctx(move |ctx, _| {
+            let receiver = match &command_receiver {
+                Some(receiver) => receiver,
+                None => return,
+            };
+            loop {
+                match receiver.try_recv() {
+                    Ok(command) => observer(ctx, &command),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        command_receiver = None;
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Writes the bars as CSV rows of (time, open, high, low, close, volume), with a header row.
+pub fn export_bars_csv<P>(path: P, bars: &OrdSet<Bar>) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut csv = ["time", "open", "high", "low", "close", "volume"].join(",");
+    csv.push('\n');
+    for bar in bars {
+        let fields = [
+            bar.datetime().to_rfc3339(),
+            bar.open.to_string(),
+            bar.high.to_string(),
+            bar.low.to_string(),
+            bar.close.to_string(),
+            bar.volume
+                .map_or_else(String::new, |volume| volume.to_string()),
+        ];
+        csv.push_str(
+            &fields
+                .iter()
+                .map(|field| paper::escape_csv_field(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+
+    fs::write(path, csv)
+}
+
+/// Listens on the socket in a background thread, replying to each line with `ok` or the error.
+/// The sender is dropped if the socket cannot be bound.
+#[cfg(unix)]
+fn listen<R>(socket_path: PathBuf, resolve_symbol: R) -> Receiver<RemoteCommand>
+where
+    R: 'static + Fn(&str) -> Result<String, StockSymbolError> + Send + Sync,
+{
+    use std::os::unix::net::UnixListener;
+
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _span =
+            debug_span!("listen_remote_commands", socket_path = %socket_path.display()).entered();
+
+        if let Err(err) = remove_stale_socket(&socket_path) {
+            warn!("failed to bind remote control socket: {:?}", err);
+            return;
+        }
+        let listener = match UnixListener::bind(&socket_path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!("failed to bind remote control socket: {:?}", err);
+                return;
+            }
+        };
+
+        // Each connection is handled in its own thread, so that an idle client does not block the
+        // others
+        let resolve_symbol = Arc::new(resolve_symbol);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("remote control connection failed: {:?}", err);
+                    continue;
+                }
+            };
+            let writer = match stream.try_clone() {
+                Ok(writer) => writer,
+                Err(err) => {
+                    warn!("remote control connection failed: {:?}", err);
+                    continue;
+                }
+            };
+            let sender = sender.clone();
+            let resolve_symbol = resolve_symbol.clone();
+            thread::spawn(move || {
+                let _span = debug_span!("handle_remote_connection").entered();
+
+                // The commands are dropped if the app has exited
+                let _ =
+                    handle_connection(BufReader::new(stream), writer, &sender, &*resolve_symbol);
+            });
+        }
+    });
+
+    receiver
+}
+
+/// Removes the socket of an instance which has exited, as it is left behind. Anything else at the
+/// path is kept, failing instead.
+#[cfg(unix)]
+fn remove_stale_socket(socket_path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match fs::symlink_metadata(socket_path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(socket_path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", socket_path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Removes the socket when the app exits, so that it is not left behind.
+#[cfg(unix)]
+pub fn remove_socket(socket_path: &Path) -> io::Result<()> {
+    remove_stale_socket(socket_path)
+}
+
+#[cfg(not(unix))]
+pub fn remove_socket(_socket_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen<R>(_socket_path: PathBuf, _resolve_symbol: R) -> Receiver<RemoteCommand> {
+    warn!("remote control socket is only supported on Unix");
+
+    let (_, receiver) = mpsc::channel();
+
+    receiver
+}
+
+/// Forwards the commands of the connection, until it is closed. Fails if the receiver has been
+/// dropped.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn handle_connection<R, W, F>(
+    reader: R,
+    mut writer: W,
+    sender: &Sender<RemoteCommand>,
+    resolve_symbol: &F,
+) -> Result<(), mpsc::SendError<RemoteCommand>>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(&str) -> Result<String, StockSymbolError>,
+{
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = line
+            .parse::<RemoteCommand>()
+            .map_err(|err| err.to_string())
+            .and_then(|command| match command {
+                RemoteCommand::Symbol(symbol) => resolve_symbol(&symbol)
+                    .map(RemoteCommand::Symbol)
+                    .map_err(|err| err.to_string()),
+                command => Ok(command),
+            });
+        let reply = match command {
+            Ok(command) => {
+                debug!("received remote command: {:?}", command);
+                sender.send(command)?;
+                "ok".to_owned()
+            }
+            Err(err) => format!("error: {}", err),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replies<F>(input: &str, resolve_symbol: F) -> (String, Vec<RemoteCommand>)
+    where
+        F: Fn(&str) -> Result<String, StockSymbolError>,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let mut output = vec![];
+        handle_connection(input.as_bytes(), &mut output, &sender, &resolve_symbol).unwrap();
+
+        (
+            String::from_utf8(output).unwrap(),
+            receiver.try_iter().collect(),
+        )
+    }
+
+    #[test]
+    fn handle_connection_forwards_resolved_symbol() {
+        let (output, commands) = replies("symbol apple\n", |symbol| {
+            assert_eq!(symbol, "APPLE");
+            Ok("AAPL".to_owned())
+        });
+
+        assert_eq!(output, "ok\n");
+        assert_eq!(commands, vec![RemoteCommand::Symbol("AAPL".to_owned())]);
+    }
+
+    #[test]
+    fn handle_connection_replies_with_symbol_error() {
+        let (output, commands) = replies("symbol xyzzy\ntimeframe 1y\n", |symbol| {
+            Err(StockSymbolError::NotFound {
+                symbol: symbol.to_owned(),
+                suggestions: vec![],
+            })
+        });
+
+        assert_eq!(output, "error: unknown symbol XYZZY\nok\n");
+        assert_eq!(commands, vec![RemoteCommand::TimeFrame(TimeFrame::OneYear)]);
+    }
+
+    #[test]
+    fn handle_connection_replies_with_parse_error() {
+        let (output, commands) = replies("zoom in\n", |_| unreachable!());
+
+        assert_eq!(output, "error: unknown command: zoom\n");
+        assert!(commands.is_empty());
+    }
+}
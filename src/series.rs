@@ -0,0 +1,94 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+/// A series of values imported from a CSV file, e.g. the outputs of one's own model, which is
+/// overlaid on the chart along with the prices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomSeries {
+    /// Name shown in the legend, which is the file name without the extension.
+    pub name: String,
+    /// Points of the series, in chronological order.
+    pub points: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl CustomSeries {
+    /// Loads the series from a CSV file of (timestamp, value) rows, with an optional header row.
+    /// The timestamps are either Unix timestamps in seconds, RFC 3339 date-times, or dates.
+    pub fn load<P>(path: P) -> Result<Self, LoadSeriesError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| LoadSeriesError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+
+        let mut points = vec![];
+        for (n, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            match parse_point(line) {
+                Some(point) => points.push(point),
+                // The first row may be the header
+                None if n == 0 => continue,
+                None => {
+                    return Err(LoadSeriesError::InvalidRow {
+                        line: n + 1,
+                        row: line.to_owned(),
+                    });
+                }
+            }
+        }
+        if points.is_empty() {
+            return Err(LoadSeriesError::Empty {
+                path: path.display().to_string(),
+            });
+        }
+        points.sort_by_key(|(time, _)| *time);
+
+        let name = path
+            .file_stem()
+            .map(|file_stem| file_stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        Ok(Self { name, points })
+    }
+}
+
+fn parse_point(row: &str) -> Option<(DateTime<Utc>, f64)> {
+    let mut fields = row
+        .splitn(2, ',')
+        .map(|field| field.trim().trim_matches('"'));
+    let time = parse_time(fields.next()?)?;
+    let value = fields.next()?.parse().ok()?;
+
+    Some((time, value))
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return Some(Utc.timestamp(timestamp, 0));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .map(|date| Utc.from_utc_date(&date).and_hms(0, 0, 0))
+}
+
+#[derive(Debug, Error)]
+pub enum LoadSeriesError {
+    #[error("no points in {path}")]
+    Empty { path: String },
+    #[error("invalid row {line}: {row}")]
+    InvalidRow { line: usize, row: String },
+    #[error("cannot read {path}: {source}")]
+    Read { path: String, source: io::Error },
+}
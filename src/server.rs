@@ -0,0 +1,242 @@
+use crate::{paper::Portfolio, stock::Stock};
+use anyhow::anyhow;
+use serde::Serialize;
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{debug_span, warn};
+use yahoo_finance::Timestamped;
+
+/// Read-only HTTP API serving the data of the running app as JSON, so that it can be consumed by
+/// other dashboards:
+///
+/// - `GET /quote` returns the latest price and daily change of the current stock
+/// - `GET /bars` returns the bars of the current stock
+/// - `GET /portfolio` returns the paper trading portfolio
+///
+/// The requests are served in a background thread from the latest state which has been set. Web
+/// pages may only read the API from the allowed origin, if any. Unless the API is public, requests
+/// for any host other than loopback are rejected, so that web pages cannot reach it through DNS
+/// rebinding.
+pub struct ApiServer {
+    state: Arc<Mutex<ApiState>>,
+}
+
+#[derive(Debug, Default)]
+struct ApiState {
+    portfolio: Option<Portfolio>,
+    stock: Stock,
+}
+
+#[derive(Debug, Serialize)]
+struct QuoteResponse<'a> {
+    change: Option<f64>,
+    change_percent: Option<f64>,
+    name: Option<&'a str>,
+    price: Option<f64>,
+    symbol: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct BarsResponse<'a> {
+    bars: Vec<BarResponse>,
+    symbol: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct BarResponse {
+    close: f64,
+    high: f64,
+    low: f64,
+    open: f64,
+    time: i64,
+    volume: Option<u64>,
+}
+
+impl ApiServer {
+    pub fn serve(
+        addr: SocketAddr,
+        allowed_origin: Option<String>,
+        public: bool,
+    ) -> anyhow::Result<Self> {
+        let allowed_origin_header = allowed_origin
+            .map(|allowed_origin| {
+                Header::from_bytes(
+                    &b"Access-Control-Allow-Origin"[..],
+                    allowed_origin.as_bytes(),
+                )
+                .map_err(|_| anyhow!("invalid origin: {}", allowed_origin))
+            })
+            .transpose()?;
+        let server = Server::http(addr)
+            .map_err(|err| anyhow!(err))
+            .map_err(|err| err.context(format!("failed to serve the API on {}", addr)))?;
+        let state = Arc::new(Mutex::new(ApiState::default()));
+
+        thread::spawn({
+            let state = state.clone();
+            move || {
+                for request in server.incoming_requests() {
+                    let _span =
+                        debug_span!("serve_api", method = %request.method(), url = %request.url())
+                            .entered();
+
+                    let response = if !public && !has_loopback_host(&request) {
+                        Response::from_string("host not allowed").with_status_code(403)
+                    } else {
+                        match state.lock() {
+                            Ok(state) => respond(&request, &state, allowed_origin_header.clone()),
+                            Err(err) => {
+                                warn!("failed to read API state: {}", err);
+                                Response::from_string("internal server error").with_status_code(500)
+                            }
+                        }
+                    };
+                    if let Err(err) = request.respond(response) {
+                        warn!("failed to respond to API request: {:?}", err);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { state })
+    }
+
+    // The state is replaced as a whole, so it is consistent even if a request panicked
+    pub fn set_portfolio(&self, portfolio: &Portfolio) {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .portfolio = Some(portfolio.clone());
+    }
+
+    pub fn set_stock(&self, stock: &Stock) {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stock = stock.clone();
+    }
+}
+
+fn respond(
+    request: &Request,
+    state: &ApiState,
+    allowed_origin_header: Option<Header>,
+) -> Response<io::Cursor<Vec<u8>>> {
+    if *request.method() != Method::Get {
+        return Response::from_string("method not allowed").with_status_code(405);
+    }
+
+    let stock = &state.stock;
+    let body = match request.url().split('?').next().unwrap_or_default() {
+        "/quote" => {
+            let daily_change = stock.daily_change();
+            serde_json::to_string(&QuoteResponse {
+                change: daily_change.map(|daily_change| daily_change.change),
+                change_percent: daily_change.map(|daily_change| daily_change.change_percent),
+                name: stock.name(),
+                price: stock.latest_price(),
+                symbol: &stock.symbol,
+            })
+        }
+        "/bars" => serde_json::to_string(&BarsResponse {
+            bars: stock
+                .bars
+                .iter()
+                .map(|bar| BarResponse {
+                    close: bar.close,
+                    high: bar.high,
+                    low: bar.low,
+                    open: bar.open,
+                    time: bar.timestamp_seconds() as i64,
+                    volume: bar.volume,
+                })
+                .collect(),
+            symbol: &stock.symbol,
+        }),
+        "/portfolio" => serde_json::to_string(&state.portfolio),
+        _ => return Response::from_string("not found").with_status_code(404),
+    };
+
+    match body {
+        Ok(body) => {
+            let response =
+                Response::from_string(body).with_header(header("Content-Type", "application/json"));
+            // Any other web page which is visited must not be able to read the portfolio
+            match allowed_origin_header {
+                Some(allowed_origin_header) => response
+                    .with_header(allowed_origin_header)
+                    .with_header(header("Vary", "Origin")),
+                None => response,
+            }
+        }
+        Err(err) => {
+            warn!("failed to serialize API response: {:?}", err);
+            Response::from_string("internal server error").with_status_code(500)
+        }
+    }
+}
+
+/// Checks that the request is for a loopback host, e.g. `127.0.0.1:8080` or `localhost:8080`, and
+/// not a domain name of a web page which has been rebound to a loopback address.
+fn has_loopback_host(request: &Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Host"))
+        .map_or(false, |header| is_loopback_host(header.value.as_str()))
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let hostname = if host.starts_with('[') {
+        // IPv6 address, e.g. [::1]:8080
+        host[1..].split(']').next().unwrap_or_default()
+    } else {
+        host.split(':').next().unwrap_or_default()
+    };
+
+    hostname.eq_ignore_ascii_case("localhost")
+        || hostname
+            .parse::<IpAddr>()
+            .map_or(false, |ip_addr| ip_addr.is_loopback())
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_hosts_are_allowed() {
+        for host in &[
+            "127.0.0.1:8080",
+            "localhost:8080",
+            "LOCALHOST",
+            "[::1]:8080",
+            "127.0.0.2",
+        ] {
+            assert!(is_loopback_host(host), "{} is not allowed", host);
+        }
+    }
+
+    #[test]
+    fn other_hosts_are_rejected() {
+        for host in &[
+            "example.com:8080",
+            "127.0.0.1.example.com:8080",
+            "localhost.example.com",
+            "192.168.1.2:8080",
+            "[2001:db8::1]:8080",
+            "",
+        ] {
+            assert!(!is_loopback_host(host), "{} is allowed", host);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static PENDING_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator to keep track of the live heap allocations.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+/// Marks a request to the provider as pending for as long as it is alive.
+pub struct PendingRequest(());
+
+impl PendingRequest {
+    pub fn start() -> Self {
+        PENDING_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        Self(())
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        PENDING_REQUESTS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub allocated_bytes: usize,
+    pub allocations: usize,
+    pub pending_requests: usize,
+    /// Resident set size in bytes. Only available on Linux.
+    pub rss: Option<usize>,
+}
+
+pub fn collect() -> Stats {
+    Stats {
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        pending_requests: PENDING_REQUESTS.load(Ordering::Relaxed),
+        rss: rss(),
+    }
+}
+
+fn rss() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let rss_kb: usize = status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+
+    Some(rss_kb * 1024)
+}
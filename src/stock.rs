@@ -1,122 +1,2042 @@
 use crate::{
-    app::{Indicator, TimeFrame},
-    reactive::StreamExt,
+    annotation::Annotation,
+    app::{FinancialPeriod, FinancialStatementKind, Indicator, TimeFrame},
+    cache::{self, CachedBar, CachedBars, TtlCache},
+    config::TimeoutConfig,
+    event::{DataEvent, StockEvent},
+    market::{self, Listing, MarketSession},
+    reactive::{EventTopic, StreamExt},
+    stats,
 };
+use anyhow::Context;
 use async_compat::Compat;
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use async_std::future;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 use futures::executor;
 use gcollections::ops::{Bounded, Difference, Union};
 use im::{hashmap, ordset, HashMap, OrdSet};
 use interval::interval_set::{IntervalSet, ToIntervalSet};
+use once_cell::sync::OnceCell;
 use reactive_rs::Stream;
-use std::{cell::RefCell, ops::Range, rc::Rc};
-use yahoo_finance::{history, Bar, Profile, Quote, Timestamped};
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    StatusCode, Url,
+};
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    cmp,
+    collections::BTreeMap,
+    future::Future,
+    ops::Range,
+    rc::Rc,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::{self, Instant},
+};
+use thiserror::Error;
+use tracing::{debug_span, instrument, warn};
+use yahoo_finance::{Bar, Quote, Timestamped};
+
+const MAX_RECENT_CLOSES: usize = 30;
+const MAX_RETRY_DELAY_SECS: u64 = 60;
+const MIN_RETRY_DELAY_SECS: u64 = 5;
+/// Name of the data provider, as used in the credentials config.
+pub const PROVIDER_NAME: &str = "yahoo";
+/// Whether the data provider requires an API key.
+pub const PROVIDER_REQUIRES_API_KEY: bool = false;
+/// Header of the requests to the provider with the API key.
+const API_KEY_HEADER: &str = "x-api-key";
+/// Downloads spanning at least this many days report their progress.
+const MIN_PROGRESS_DOWNLOAD_DAYS: i64 = 365 * 5;
+/// Large date ranges are downloaded in chunks of at most this many days.
+const DOWNLOAD_CHUNK_DAYS: i64 = 30 * 3;
+/// Names of the well-known indices, which are shorter than the ones used by the provider.
+const MARKET_INDEX_NAMES: &[(&str, &str)] = &[
+    ("^DJI", "Dow Jones"),
+    ("^FTSE", "FTSE 100"),
+    ("^GDAXI", "DAX"),
+    ("^GSPC", "S&P 500"),
+    ("^HSI", "Hang Seng"),
+    ("^IXIC", "Nasdaq"),
+    ("^N225", "Nikkei 225"),
+    ("^RUT", "Russell 2000"),
+    ("^STI", "STI"),
+    ("^VIX", "VIX"),
+];
+
+/// Sector ETFs standing in for the performance of each sector, by the sector names used in the
+/// asset profiles.
+pub const SECTOR_ETFS: &[(&str, &str)] = &[
+    ("Basic Materials", "XLB"),
+    ("Communication Services", "XLC"),
+    ("Consumer Cyclical", "XLY"),
+    ("Consumer Defensive", "XLP"),
+    ("Energy", "XLE"),
+    ("Financial Services", "XLF"),
+    ("Healthcare", "XLV"),
+    ("Industrials", "XLI"),
+    ("Real Estate", "XLRE"),
+    ("Technology", "XLK"),
+    ("Utilities", "XLU"),
+];
+
+const ASSET_PROFILE_MODULES: &[&str] = &["assetProfile"];
+const CHART_URL: &str = "https://query2.finance.yahoo.com/v8/finance/chart";
+const FINANCIALS_MODULES: &[&str] = &[
+    "balanceSheetHistory",
+    "balanceSheetHistoryQuarterly",
+    "cashflowStatementHistory",
+    "cashflowStatementHistoryQuarterly",
+    "incomeStatementHistory",
+    "incomeStatementHistoryQuarterly",
+];
+const LIVE_QUOTE_MODULES: &[&str] = &["financialData", "price", "summaryDetail"];
+const PROFILE_MODULES: &[&str] = &["price"];
+const QUOTE_SUMMARY_MODULES: &[&str] = &[
+    "assetProfile",
+    "defaultKeyStatistics",
+    "esgScores",
+    "financialData",
+    "recommendationTrend",
+    "summaryDetail",
+    "upgradeDowngradeHistory",
+];
+const QUOTE_SUMMARY_URL: &str = "https://query2.finance.yahoo.com/v10/finance/quoteSummary";
+const QUOTE_URL: &str = "https://query2.finance.yahoo.com/v7/finance/quote";
+const SCREENER_COUNT: u8 = 100;
+const SCREENER_URL: &str = "https://query2.finance.yahoo.com/v1/finance/screener/predefined/saved";
+const SEARCH_URL: &str = "https://query2.finance.yahoo.com/v1/finance/search";
+const TRENDING_URL: &str = "https://query2.finance.yahoo.com/v1/finance/trending";
+const SEARCH_NEWS_COUNT: u8 = 20;
+const SEARCH_QUOTES_COUNT: u8 = 10;
+/// Bars at most this old are downloaded again when refreshing the latest prices.
+const REFRESH_DAYS: i64 = 1;
+/// Most symbols suggested in place of an unknown symbol.
+const SUGGESTED_SYMBOLS_COUNT: usize = 3;
+
+static API_KEY: OnceCell<String> = OnceCell::new();
+static PROXY: OnceCell<Url> = OnceCell::new();
+static TIMEOUTS: OnceCell<TimeoutConfig> = OnceCell::new();
+
+thread_local! {
+    static MARKET_SESSIONS: RefCell<HashMap<String, MarketSession>> = RefCell::new(hashmap! {});
+    static REQUEST_START_HOOK: RefCell<Option<Box<dyn Fn()>>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum StockSymbolError {
+    #[error("invalid character {0:?}")]
+    InvalidChar(char),
+    #[error("symbol lookup failed for {0}")]
+    LookupFailed(String),
+    #[error("missing symbol before the exchange suffix")]
+    MissingBase,
+    #[error("missing exchange suffix after the dot")]
+    MissingSuffix,
+    /// The symbols listed on other exchanges which the provider found for it are suggested.
+    #[error("unknown symbol {symbol}{}", format_suggestions(.suggestions))]
+    NotFound {
+        symbol: String,
+        suggestions: Vec<String>,
+    },
+}
+
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean {}?", suggestions.join(" or "))
+    }
+}
+
+/// Why the API key of the data provider cannot be used.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ApiKeyError {
+    #[error("missing API key")]
+    Missing,
+    #[error("API key rejected")]
+    Rejected,
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum StockError {
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("invalid response: {0}")]
+    Parse(String),
+    #[error("rate limited")]
+    RateLimited,
+    #[error("timed out after {0:?}")]
+    TimedOut(time::Duration),
+    #[error("API key rejected")]
+    Unauthorized,
+}
+
+impl From<reqwest::Error> for StockError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(StatusCode::NOT_FOUND) => {
+                Self::NotFound(err.url().map_or_else(String::new, |url| url.to_string()))
+            }
+            Some(StatusCode::TOO_MANY_REQUESTS) => Self::RateLimited,
+            Some(StatusCode::UNAUTHORIZED) | Some(StatusCode::FORBIDDEN) => Self::Unauthorized,
+            _ if err.is_decode() => Self::Parse(err.to_string()),
+            _ => Self::Network(err.to_string()),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct Stock {
+    /// Events marked on the chart, e.g. the ex-dividend dates and the splits.
+    pub annotations: Vec<Annotation>,
+    pub asset_profile: Option<AssetProfile>,
     pub bars: OrdSet<Bar>,
+    /// Quote summary with only the modules of the financial statements.
+    pub financials: Option<QuoteSummary>,
+    /// Quote summary with only the modules which change throughout the trading day.
+    pub live_quote: Option<QuoteSummary>,
+    pub market_session: Option<MarketSession>,
+    pub news: Vec<NewsItem>,
     pub profile: Option<Profile>,
     pub quote: Option<Quote>,
+    pub quote_summary: Option<QuoteSummary>,
     pub symbol: String,
 }
 
 impl Stock {
     pub fn name(&self) -> Option<&str> {
-        match &self.profile {
-            Some(Profile::Company(company)) => Some(company.name.as_str()),
-            Some(Profile::Fund(fund)) => Some(fund.name.as_str()),
-            None => None,
+        self.profile.as_ref().map(|profile| profile.name.as_str())
+    }
+
+    /// Returns the live price, falling back to the latest close.
+    pub fn latest_price(&self) -> Option<f64> {
+        self.live_quote
+            .as_ref()
+            .and_then(|live_quote| live_quote.price.as_ref())
+            .and_then(|price| price.regular_market_price.as_ref())
+            .and_then(|regular_market_price| regular_market_price.raw)
+            .or_else(|| self.bars.iter().next_back().map(|bar| bar.close))
+    }
+
+    /// Returns the consensus price target of the analysts, from either the live quote or the
+    /// fundamentals, whichever has it.
+    pub fn price_target(&self) -> Option<PriceTarget> {
+        [&self.live_quote, &self.quote_summary]
+            .iter()
+            .filter_map(|quote_summary| quote_summary.as_ref()?.financial_data.as_ref())
+            .find_map(|financial_data| {
+                let mean = financial_data.target_mean_price.as_ref()?.raw?;
+                let raw = |value: &Option<FormattedValue>| {
+                    value.as_ref().and_then(|value| value.raw).unwrap_or(mean)
+                };
+                Some(PriceTarget {
+                    high: raw(&financial_data.target_high_price),
+                    low: raw(&financial_data.target_low_price),
+                    mean,
+                })
+            })
+    }
+
+    /// Returns the exchange where the stock is listed, and how its prices are quoted.
+    pub fn listing(&self) -> Listing {
+        Listing::for_symbol(&self.symbol)
+    }
+
+    /// Returns the change of the latest close from the close of the session before, if there are
+    /// bars for both.
+    pub fn daily_change(&self) -> Option<DailyChange> {
+        let mut bars = self.bars.iter().rev();
+        let (latest_bar, previous_bar) = (bars.next()?, bars.next()?);
+
+        DailyChange::from_closes(&[previous_bar.close, latest_bar.close])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DailyChange {
+    pub change: f64,
+    pub change_percent: f64,
+    pub close: f64,
+}
+
+impl DailyChange {
+    /// Returns the change of the last of the daily closes from the one before it, if there are at
+    /// least 2 of them.
+    pub fn from_closes(closes: &[f64]) -> Option<Self> {
+        let (latest_close, previous_close) = match closes {
+            [.., previous_close, latest_close] => (*latest_close, *previous_close),
+            _ => return None,
+        };
+        let change = latest_close - previous_close;
+
+        Some(Self {
+            change,
+            change_percent: change / previous_close * 100.0,
+            close: latest_close,
+        })
+    }
+}
+
+/// The latest figures of a symbol, as returned in bulk by the quote and the screener endpoints.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketQuote {
+    pub market_cap: Option<f64>,
+    pub regular_market_change_percent: Option<f64>,
+    pub regular_market_price: Option<f64>,
+    pub regular_market_volume: Option<f64>,
+    pub symbol: String,
+    #[serde(rename = "trailingPE")]
+    pub trailing_pe: Option<f64>,
+}
+
+/// The symbols a screener is evaluated against.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ScreenerUniverse {
+    /// One of the predefined screeners of the provider, by ID, e.g. `day_gainers`.
+    Predefined(String),
+    Symbols(Vec<String>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewsItem {
+    pub link: String,
+    pub provider_publish_time: i64,
+    pub publisher: String,
+    pub title: String,
+}
+
+/// Profile of the stock, with the name of the company or the fund.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QuoteSummary {
+    pub asset_profile: Option<AssetProfile>,
+    pub balance_sheet_history: Option<BalanceSheetHistory>,
+    pub balance_sheet_history_quarterly: Option<BalanceSheetHistory>,
+    pub cashflow_statement_history: Option<CashflowStatementHistory>,
+    pub cashflow_statement_history_quarterly: Option<CashflowStatementHistory>,
+    pub default_key_statistics: Option<KeyStatistics>,
+    pub esg_scores: Option<EsgScores>,
+    pub financial_data: Option<FinancialData>,
+    pub income_statement_history: Option<IncomeStatementHistory>,
+    pub income_statement_history_quarterly: Option<IncomeStatementHistory>,
+    pub price: Option<QuotePrice>,
+    pub recommendation_trend: Option<RecommendationTrend>,
+    pub summary_detail: Option<SummaryDetail>,
+    pub upgrade_downgrade_history: Option<UpgradeDowngradeHistory>,
+}
+
+impl QuoteSummary {
+    /// Returns the financial statements of the kind for each fiscal period, latest first.
+    pub fn financial_statements(
+        &self,
+        kind: FinancialStatementKind,
+        period: FinancialPeriod,
+    ) -> Vec<FinancialStatement> {
+        let quarterly = period == FinancialPeriod::Quarterly;
+        match kind {
+            FinancialStatementKind::Income => if quarterly {
+                &self.income_statement_history_quarterly
+            } else {
+                &self.income_statement_history
+            }
+            .iter()
+            .flat_map(|history| &history.income_statement_history)
+            .map(IncomeStatement::to_financial_statement)
+            .collect(),
+            FinancialStatementKind::BalanceSheet => if quarterly {
+                &self.balance_sheet_history_quarterly
+            } else {
+                &self.balance_sheet_history
+            }
+            .iter()
+            .flat_map(|history| &history.balance_sheet_statements)
+            .map(BalanceSheet::to_financial_statement)
+            .collect(),
+            FinancialStatementKind::CashFlow => if quarterly {
+                &self.cashflow_statement_history_quarterly
+            } else {
+                &self.cashflow_statement_history
+            }
+            .iter()
+            .flat_map(|history| &history.cashflow_statements)
+            .map(CashflowStatement::to_financial_statement)
+            .collect(),
+        }
+    }
+}
+
+/// A financial statement of a fiscal period, with the values of its line items in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FinancialStatement {
+    pub end_date: Option<i64>,
+    pub line_items: Vec<(&'static str, Option<f64>)>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IncomeStatementHistory {
+    pub income_statement_history: Vec<IncomeStatement>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IncomeStatement {
+    pub cost_of_revenue: Option<FormattedValue>,
+    pub ebit: Option<FormattedValue>,
+    pub end_date: Option<FormattedValue>,
+    pub gross_profit: Option<FormattedValue>,
+    pub net_income: Option<FormattedValue>,
+    pub operating_income: Option<FormattedValue>,
+    pub total_revenue: Option<FormattedValue>,
+}
+
+impl IncomeStatement {
+    fn to_financial_statement(&self) -> FinancialStatement {
+        FinancialStatement {
+            end_date: raw_timestamp(&self.end_date),
+            line_items: vec![
+                ("Total revenue", raw_value(&self.total_revenue)),
+                ("Cost of revenue", raw_value(&self.cost_of_revenue)),
+                ("Gross profit", raw_value(&self.gross_profit)),
+                ("Operating income", raw_value(&self.operating_income)),
+                ("EBIT", raw_value(&self.ebit)),
+                ("Net income", raw_value(&self.net_income)),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BalanceSheetHistory {
+    pub balance_sheet_statements: Vec<BalanceSheet>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BalanceSheet {
+    pub cash: Option<FormattedValue>,
+    pub end_date: Option<FormattedValue>,
+    pub long_term_debt: Option<FormattedValue>,
+    pub total_assets: Option<FormattedValue>,
+    pub total_current_assets: Option<FormattedValue>,
+    pub total_current_liabilities: Option<FormattedValue>,
+    pub total_liab: Option<FormattedValue>,
+    pub total_stockholder_equity: Option<FormattedValue>,
+}
+
+impl BalanceSheet {
+    fn to_financial_statement(&self) -> FinancialStatement {
+        FinancialStatement {
+            end_date: raw_timestamp(&self.end_date),
+            line_items: vec![
+                ("Cash", raw_value(&self.cash)),
+                (
+                    "Total current assets",
+                    raw_value(&self.total_current_assets),
+                ),
+                ("Total assets", raw_value(&self.total_assets)),
+                (
+                    "Total current liabilities",
+                    raw_value(&self.total_current_liabilities),
+                ),
+                ("Long-term debt", raw_value(&self.long_term_debt)),
+                ("Total liabilities", raw_value(&self.total_liab)),
+                (
+                    "Stockholders' equity",
+                    raw_value(&self.total_stockholder_equity),
+                ),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CashflowStatementHistory {
+    pub cashflow_statements: Vec<CashflowStatement>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CashflowStatement {
+    pub capital_expenditures: Option<FormattedValue>,
+    pub change_in_cash: Option<FormattedValue>,
+    pub dividends_paid: Option<FormattedValue>,
+    pub end_date: Option<FormattedValue>,
+    pub total_cash_from_financing_activities: Option<FormattedValue>,
+    pub total_cash_from_operating_activities: Option<FormattedValue>,
+    pub total_cashflows_from_investing_activities: Option<FormattedValue>,
+}
+
+impl CashflowStatement {
+    fn to_financial_statement(&self) -> FinancialStatement {
+        FinancialStatement {
+            end_date: raw_timestamp(&self.end_date),
+            line_items: vec![
+                (
+                    "Operating cash flow",
+                    raw_value(&self.total_cash_from_operating_activities),
+                ),
+                (
+                    "Capital expenditures",
+                    raw_value(&self.capital_expenditures),
+                ),
+                (
+                    "Investing cash flow",
+                    raw_value(&self.total_cashflows_from_investing_activities),
+                ),
+                (
+                    "Financing cash flow",
+                    raw_value(&self.total_cash_from_financing_activities),
+                ),
+                ("Dividends paid", raw_value(&self.dividends_paid)),
+                ("Change in cash", raw_value(&self.change_in_cash)),
+            ],
+        }
+    }
+}
+
+fn raw_value(value: &Option<FormattedValue>) -> Option<f64> {
+    value.as_ref().and_then(|value| value.raw)
+}
+
+fn raw_timestamp(value: &Option<FormattedValue>) -> Option<i64> {
+    raw_value(value).map(|raw| raw as i64)
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AssetProfile {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub full_time_employees: Option<u64>,
+    pub industry: Option<String>,
+    pub long_business_summary: Option<String>,
+    pub sector: Option<String>,
+    pub state: Option<String>,
+    pub website: Option<String>,
+}
+
+impl AssetProfile {
+    /// Returns the location of the headquarters, e.g. `Cupertino, CA, United States`.
+    pub fn headquarters(&self) -> Option<String> {
+        let parts: Vec<_> = [&self.city, &self.state, &self.country]
+            .iter()
+            .filter_map(|part| part.as_deref())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(", "))
+        }
+    }
+}
+
+/// Sustainability scores of the company, as risk scores where lower is better.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EsgScores {
+    pub environment_score: Option<FormattedValue>,
+    pub governance_score: Option<FormattedValue>,
+    /// Level of the most severe controversy involving the company, from 0 (none) to 5.
+    pub highest_controversy: Option<f64>,
+    pub peer_group: Option<String>,
+    pub percentile: Option<FormattedValue>,
+    pub social_score: Option<FormattedValue>,
+    pub total_esg: Option<FormattedValue>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FinancialData {
+    pub current_ratio: Option<FormattedValue>,
+    /// Debt to equity, as a percentage.
+    pub debt_to_equity: Option<FormattedValue>,
+    pub free_cashflow: Option<FormattedValue>,
+    pub gross_margins: Option<FormattedValue>,
+    pub number_of_analyst_opinions: Option<FormattedValue>,
+    pub profit_margins: Option<FormattedValue>,
+    pub return_on_equity: Option<FormattedValue>,
+    pub target_high_price: Option<FormattedValue>,
+    pub target_low_price: Option<FormattedValue>,
+    pub target_mean_price: Option<FormattedValue>,
+}
+
+/// Consensus price target of the analysts, along with the range of their targets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceTarget {
+    pub high: f64,
+    pub low: f64,
+    pub mean: f64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct KeyStatistics {
+    pub shares_short: Option<FormattedValue>,
+    pub short_percent_of_float: Option<FormattedValue>,
+    /// Days to cover the short interest at the average daily volume.
+    pub short_ratio: Option<FormattedValue>,
+    pub trailing_eps: Option<FormattedValue>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QuotePrice {
+    pub long_name: Option<String>,
+    pub market_cap: Option<FormattedValue>,
+    pub regular_market_price: Option<FormattedValue>,
+    pub regular_market_volume: Option<FormattedValue>,
+    pub short_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RecommendationTrend {
+    pub trend: Vec<Recommendations>,
+}
+
+impl RecommendationTrend {
+    /// Returns the counts of the recommendations of the current month.
+    pub fn current(&self) -> Option<&Recommendations> {
+        self.trend
+            .iter()
+            .find(|recommendations| recommendations.period == "0m")
+    }
+}
+
+/// Counts of the analyst recommendations in a period, e.g. `-1m` for the previous month.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Recommendations {
+    pub buy: u32,
+    pub hold: u32,
+    pub period: String,
+    pub sell: u32,
+    pub strong_buy: u32,
+    pub strong_sell: u32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UpgradeDowngradeHistory {
+    /// Changes of the analyst grades, latest first.
+    pub history: Vec<GradeChange>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GradeChange {
+    /// Kind of the change, i.e. `up`, `down`, `main` (maintained), `init` or `reit` (reiterated).
+    pub action: String,
+    pub epoch_grade_date: i64,
+    pub firm: String,
+    pub from_grade: String,
+    pub to_grade: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SummaryDetail {
+    pub ask: Option<FormattedValue>,
+    pub beta: Option<FormattedValue>,
+    pub bid: Option<FormattedValue>,
+    pub dividend_yield: Option<FormattedValue>,
+    pub fifty_two_week_high: Option<FormattedValue>,
+    pub fifty_two_week_low: Option<FormattedValue>,
+    pub market_cap: Option<FormattedValue>,
+    #[serde(rename = "trailingPE")]
+    pub trailing_pe: Option<FormattedValue>,
+}
+
+/// Status of the connection to the data provider.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConnectionStatus {
+    Connected,
+    /// The provider is unreachable, and the download will be retried at the given time.
+    Disconnected {
+        error: StockError,
+        retry_at: Instant,
+    },
+    /// Nothing can be fetched from the provider until its API key is fixed, so it is not retried.
+    Unauthorized(ApiKeyError),
+}
+
+impl Default for ConnectionStatus {
+    fn default() -> Self {
+        Self::Connected
+    }
+}
+
+/// Progress of downloading the historical prices of a stock, counted in requests.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DownloadProgress {
+    pub completed: usize,
+    pub stock_symbol: String,
+    pub total: usize,
+}
+
+impl DownloadProgress {
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.completed as f64 / self.total as f64
+    }
+}
+
+/// A numeric value along with its display format, as returned by the provider.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FormattedValue {
+    pub fmt: Option<String>,
+    pub raw: Option<f64>,
+}
+
+/// Response of the chart endpoint, with the historical prices and the events of the stock.
+#[derive(Debug, Deserialize)]
+struct ChartResponse {
+    chart: ChartResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartResult {
+    result: Option<Vec<ChartData>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChartData {
+    #[serde(default)]
+    events: StockEvents,
+    #[serde(default)]
+    indicators: ChartIndicators,
+    /// Timestamps of the bars, in seconds.
+    #[serde(default)]
+    timestamp: Vec<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChartIndicators {
+    #[serde(default)]
+    quote: Vec<ChartQuote>,
+}
+
+/// Prices and volumes of the bars, in the order of their timestamps. They are missing for the
+/// bars without any trades.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ChartQuote {
+    close: Vec<Option<f64>>,
+    high: Vec<Option<f64>>,
+    low: Vec<Option<f64>>,
+    open: Vec<Option<f64>>,
+    volume: Vec<Option<u64>>,
+}
+
+impl ChartData {
+    /// Returns the bars, skipping the ones with missing prices.
+    fn bars(&self) -> Vec<Bar> {
+        let quote = match self.indicators.quote.first() {
+            Some(quote) => quote,
+            None => return vec![],
+        };
+        let price = |prices: &[Option<f64>], i: usize| prices.get(i).copied().flatten();
+
+        self.timestamp
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &timestamp)| {
+                Some(Bar {
+                    timestamp: timestamp * 1_000,
+                    open: price(&quote.open, i)?,
+                    high: price(&quote.high, i)?,
+                    low: price(&quote.low, i)?,
+                    close: price(&quote.close, i)?,
+                    volume: quote.volume.get(i).copied().flatten(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StockEvents {
+    /// Dividends by the timestamp of the ex-dividend date.
+    #[serde(default)]
+    dividends: BTreeMap<String, DividendEvent>,
+    /// Splits by the timestamp of the split date.
+    #[serde(default)]
+    splits: BTreeMap<String, SplitEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DividendEvent {
+    amount: f64,
+    date: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SplitEvent {
+    date: i64,
+    denominator: f64,
+    numerator: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteSummaryResponse {
+    quote_summary: QuoteSummaryResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuoteSummaryResult {
+    result: Option<Vec<QuoteSummary>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    news: Vec<NewsItem>,
+    #[serde(default)]
+    quotes: Vec<SearchQuote>,
+}
+
+/// Response of the trending and the screener endpoints.
+#[derive(Debug, Deserialize)]
+struct FinanceResponse {
+    finance: FinanceResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinanceResult {
+    result: Option<Vec<FinanceQuotes>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinanceQuotes {
+    #[serde(default)]
+    quotes: Vec<MarketQuote>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteResponse {
+    quote_response: QuoteResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuoteResult {
+    #[serde(default)]
+    result: Vec<MarketQuote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuote {
+    exchange: Option<String>,
+    /// Name of the exchange for display, e.g. `Toronto`.
+    #[serde(rename = "exchDisp")]
+    exchange_name: Option<String>,
+    symbol: String,
+}
+
+#[instrument(level = "debug")]
+async fn load_quote_summary(
+    stock_symbol: &str,
+    modules: &[&str],
+) -> Result<QuoteSummary, StockError> {
+    let res: QuoteSummaryResponse = http_client()?
+        .get(&format!("{}/{}", QUOTE_SUMMARY_URL, stock_symbol))
+        .query(&[("modules", modules.join(","))])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    res.quote_summary
+        .result
+        .and_then(|result| result.into_iter().next())
+        .ok_or_else(|| StockError::NotFound(stock_symbol.to_owned()))
+}
+
+#[instrument(level = "debug")]
+async fn load_annotations(stock_symbol: &str) -> Result<Vec<Annotation>, StockError> {
+    let res: ChartResponse = http_client()?
+        .get(&format!("{}/{}", CHART_URL, stock_symbol))
+        .query(&[
+            ("range", "max"),
+            ("interval", "3mo"),
+            ("events", "div,split"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let StockEvents { dividends, splits } = res
+        .chart
+        .result
+        .and_then(|result| result.into_iter().next())
+        .ok_or_else(|| StockError::NotFound(stock_symbol.to_owned()))?
+        .events;
+
+    let mut annotations: Vec<_> = dividends
+        .values()
+        .map(|dividend| Annotation::dividend(Utc.timestamp(dividend.date, 0), dividend.amount))
+        .chain(splits.values().map(|split| {
+            Annotation::split(
+                Utc.timestamp(split.date, 0),
+                split.numerator,
+                split.denominator,
+            )
+        }))
+        .collect();
+    annotations.sort_by_key(|annotation| annotation.time);
+
+    Ok(annotations)
+}
+
+/// Loads the daily bars of the range ending today, e.g. `1mo`.
+#[instrument(level = "debug")]
+async fn load_bars_in_range(stock_symbol: &str, range: &str) -> Result<Vec<Bar>, StockError> {
+    load_bars(stock_symbol, &[("range", range.to_owned())]).await
+}
+
+/// Loads the daily bars between the timestamps, in seconds.
+#[instrument(level = "debug")]
+async fn load_bars_between(
+    stock_symbol: &str,
+    start: i64,
+    end: i64,
+) -> Result<Vec<Bar>, StockError> {
+    load_bars(
+        stock_symbol,
+        &[("period1", start.to_string()), ("period2", end.to_string())],
+    )
+    .await
+}
+
+async fn load_bars(stock_symbol: &str, query: &[(&str, String)]) -> Result<Vec<Bar>, StockError> {
+    let res: ChartResponse = http_client()?
+        .get(&format!("{}/{}", CHART_URL, stock_symbol))
+        .query(query)
+        .query(&[("interval", "1d")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let chart_data = res
+        .chart
+        .result
+        .and_then(|result| result.into_iter().next())
+        .ok_or_else(|| StockError::NotFound(stock_symbol.to_owned()))?;
+
+    Ok(chart_data.bars())
+}
+
+#[instrument(level = "debug")]
+async fn load_news(stock_symbol: &str) -> Result<Vec<NewsItem>, StockError> {
+    let res: SearchResponse = http_client()?
+        .get(SEARCH_URL)
+        .query(&[("q", stock_symbol)])
+        .query(&[("quotesCount", 0), ("newsCount", SEARCH_NEWS_COUNT)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(res.news)
+}
+
+#[instrument(level = "debug")]
+async fn search_quotes(stock_symbol: &str) -> Result<Vec<SearchQuote>, StockError> {
+    let res: SearchResponse = http_client()?
+        .get(SEARCH_URL)
+        .query(&[("q", stock_symbol)])
+        .query(&[("quotesCount", SEARCH_QUOTES_COUNT), ("newsCount", 0)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(res.quotes)
+}
+
+#[instrument(level = "debug")]
+async fn load_trending_stock_symbols(region: &str) -> Result<Vec<String>, StockError> {
+    let res: FinanceResponse = http_client()?
+        .get(&format!("{}/{}", TRENDING_URL, region))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    res.finance
+        .result
+        .and_then(|result| result.into_iter().next())
+        .map(|trending| {
+            trending
+                .quotes
+                .into_iter()
+                .map(|quote| quote.symbol)
+                .collect()
+        })
+        .ok_or_else(|| StockError::NotFound(region.to_owned()))
+}
+
+#[instrument(level = "debug")]
+async fn load_screener_quotes(universe: &ScreenerUniverse) -> Result<Vec<MarketQuote>, StockError> {
+    match universe {
+        ScreenerUniverse::Predefined(screener_id) => {
+            let res: FinanceResponse = http_client()?
+                .get(SCREENER_URL)
+                .query(&[("scrIds", screener_id.as_str())])
+                .query(&[("count", SCREENER_COUNT)])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            res.finance
+                .result
+                .and_then(|result| result.into_iter().next())
+                .map(|screener| screener.quotes)
+                .ok_or_else(|| StockError::NotFound(screener_id.clone()))
+        }
+        ScreenerUniverse::Symbols(stock_symbols) if stock_symbols.is_empty() => Ok(vec![]),
+        ScreenerUniverse::Symbols(stock_symbols) => {
+            let res: QuoteResponse = http_client()?
+                .get(QUOTE_URL)
+                .query(&[("symbols", stock_symbols.join(","))])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            Ok(res.quote_response.result)
+        }
+    }
+}
+
+/// Sets the API key sent along with the requests to the provider. Only the first call has any
+/// effect.
+pub fn set_api_key(api_key: String) {
+    API_KEY.set(api_key).ok();
+}
+
+/// Returns the API key sent along with the requests to the provider, if any.
+pub fn api_key() -> Option<&'static str> {
+    API_KEY.get().map(String::as_str)
+}
+
+/// Sets the proxy of the requests to the provider and of the order book stream. Only the first call
+/// has any effect.
+pub fn set_proxy(proxy: Url) {
+    PROXY.set(proxy).ok();
+}
+
+/// Returns the proxy of the requests to the provider and of the order book stream, if any.
+pub fn proxy() -> Option<&'static Url> {
+    PROXY.get()
+}
+
+/// Sets the timeouts of the requests to the provider. Only the first call has any effect.
+pub fn set_timeouts(timeouts: TimeoutConfig) {
+    TIMEOUTS.set(timeouts).ok();
+}
+
+/// Sets the hook called before each blocking request to the provider on the current thread, e.g. to
+/// show that the UI is waiting on it. The requests made on background threads are not blocking.
+pub fn set_request_start_hook<F>(hook: F)
+where
+    F: Fn() + 'static,
+{
+    REQUEST_START_HOOK.with(|request_start_hook| {
+        *request_start_hook.borrow_mut() = Some(Box::new(hook));
+    });
+}
+
+fn timeouts() -> TimeoutConfig {
+    TIMEOUTS.get().copied().unwrap_or_default()
+}
+
+/// Builds the client of the requests to the provider, which sends the API key if there is one. The
+/// proxy is set explicitly, so that the environment is never read behind the back of the config.
+fn http_client() -> Result<reqwest::Client, StockError> {
+    let timeouts = timeouts();
+
+    let client_builder = reqwest::Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read());
+    let client_builder = match proxy() {
+        Some(proxy) => client_builder.proxy(reqwest::Proxy::all(proxy.clone())?),
+        None => client_builder.no_proxy(),
+    };
+    let client_builder = match api_key() {
+        Some(api_key) => {
+            // A key which cannot be sent in a header would be rejected by the provider anyway
+            let mut api_key =
+                HeaderValue::from_str(api_key).map_err(|_| StockError::Unauthorized)?;
+            api_key.set_sensitive(true);
+            let mut headers = HeaderMap::new();
+            headers.insert(API_KEY_HEADER, api_key);
+            client_builder.default_headers(headers)
+        }
+        None => client_builder,
+    };
+
+    Ok(client_builder.build()?)
+}
+
+/// Runs the request to the provider to completion, giving up once the read timeout has elapsed,
+/// so that a hung endpoint cannot block indefinitely.
+fn block_on_request<F, T>(request: F) -> Result<T, StockError>
+where
+    F: Future<Output = Result<T, StockError>>,
+{
+    let read_timeout = timeouts().read();
+    let _pending_request = stats::PendingRequest::start();
+    REQUEST_START_HOOK.with(|request_start_hook| {
+        if let Some(hook) = &*request_start_hook.borrow() {
+            hook();
         }
+    });
+
+    executor::block_on(Compat::new(future::timeout(read_timeout, request)))
+        .map_err(|_| StockError::TimedOut(read_timeout))?
+}
+
+/// Looks up the symbol with the provider, so that unknown symbols can be rejected before
+/// attempting to fetch anything else for them.
+pub fn lookup_stock_symbol(stock_symbol: &str) -> Result<(), StockSymbolError> {
+    let quotes = block_on_request(search_quotes(stock_symbol)).map_err(|err| {
+        warn!("symbol lookup failed: {:?}", err);
+        StockSymbolError::LookupFailed(stock_symbol.to_owned())
+    })?;
+
+    if quotes
+        .iter()
+        .any(|quote| quote.symbol.eq_ignore_ascii_case(stock_symbol))
+    {
+        Ok(())
+    } else {
+        // e.g. `7203.T` on the Tokyo Stock Exchange for `7203`
+        let suggestions = quotes
+            .iter()
+            .take(SUGGESTED_SYMBOLS_COUNT)
+            .map(|quote| {
+                match quote
+                    .exchange_name
+                    .as_ref()
+                    .or_else(|| quote.exchange.as_ref())
+                {
+                    Some(exchange) => format!("{} ({})", quote.symbol, exchange),
+                    None => quote.symbol.clone(),
+                }
+            })
+            .collect();
+
+        Err(StockSymbolError::NotFound {
+            symbol: stock_symbol.to_owned(),
+            suggestions,
+        })
     }
 }
 
-pub fn to_stock_profiles<'a, S>(stock_symbols: S) -> ToStockProfiles<S>
+/// Maps each stock symbol to its quote summary. `None` symbols are passed through without
+/// fetching, so that the quote summary is only fetched when it is actually displayed.
+pub fn to_stock_quote_summaries<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Option<QuoteSummary>, Context = C>
+where
+    S: Stream<'a, Item = Option<String>, Context = C>,
+    C: 'a,
+{
+    let quote_summary_cache: Rc<RefCell<TtlCache<String, QuoteSummary>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols
+        .distinct_until_changed()
+        .map(move |stock_symbol| {
+            let stock_symbol = stock_symbol.as_ref()?;
+            let quote_summary = quote_summary_cache.borrow().get(stock_symbol).cloned();
+
+            quote_summary.or_else(|| {
+                match block_on_request(load_quote_summary(stock_symbol, QUOTE_SUMMARY_MODULES)) {
+                    Ok(quote_summary) => {
+                        quote_summary_cache
+                            .borrow_mut()
+                            .insert(stock_symbol.clone(), quote_summary.clone());
+                        Some(quote_summary)
+                    }
+                    Err(err) => {
+                        warn!("quote summary load failed: {:?}", err);
+                        None
+                    }
+                }
+            })
+        })
+}
+
+/// Maps each stock symbol to its financial statements. `None` symbols are passed through without
+/// fetching, so that the statements are only fetched when they are actually displayed.
+pub fn to_stock_financials<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Option<QuoteSummary>, Context = C>
+where
+    S: Stream<'a, Item = Option<String>, Context = C>,
+    C: 'a,
+{
+    let financials_cache: Rc<RefCell<TtlCache<String, QuoteSummary>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols
+        .distinct_until_changed()
+        .map(move |stock_symbol| {
+            let stock_symbol = stock_symbol.as_ref()?;
+            let financials = financials_cache.borrow().get(stock_symbol).cloned();
+
+            financials.or_else(|| {
+                match block_on_request(load_quote_summary(stock_symbol, FINANCIALS_MODULES)) {
+                    Ok(financials) => {
+                        financials_cache
+                            .borrow_mut()
+                            .insert(stock_symbol.clone(), financials.clone());
+                        Some(financials)
+                    }
+                    Err(err) => {
+                        warn!("financials load failed: {:?}", err);
+                        None
+                    }
+                }
+            })
+        })
+}
+
+/// Maps each stock symbol to its asset profile. The sector and industry are shown in the header, so
+/// unlike the rest of the quote summary, it is always fetched.
+pub fn to_stock_asset_profiles<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Option<AssetProfile>, Context = C>
+where
+    S: Stream<'a, Item = String, Context = C>,
+    C: 'a,
+{
+    let asset_profile_cache: Rc<RefCell<TtlCache<String, AssetProfile>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols
+        .distinct_until_changed()
+        .map(move |stock_symbol| {
+            let asset_profile = asset_profile_cache.borrow().get(stock_symbol).cloned();
+
+            asset_profile.or_else(|| {
+                match block_on_request(load_quote_summary(stock_symbol, ASSET_PROFILE_MODULES)) {
+                    Ok(QuoteSummary {
+                        asset_profile: Some(asset_profile),
+                        ..
+                    }) => {
+                        asset_profile_cache
+                            .borrow_mut()
+                            .insert(stock_symbol.clone(), asset_profile.clone());
+                        Some(asset_profile)
+                    }
+                    Ok(_) => None,
+                    Err(err) => {
+                        warn!("asset profile load failed: {:?}", err);
+                        None
+                    }
+                }
+            })
+        })
+}
+
+/// Maps each stock symbol to the events marked on its chart, i.e. the ex-dividend dates and the
+/// splits.
+pub fn to_stock_annotations<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Vec<Annotation>, Context = C>
+where
+    S: Stream<'a, Item = String, Context = C>,
+    C: 'a,
+{
+    let annotations_cache: Rc<RefCell<TtlCache<String, Vec<Annotation>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols
+        .distinct_until_changed()
+        .map(move |stock_symbol| {
+            let annotations = annotations_cache.borrow().get(stock_symbol).cloned();
+
+            annotations.unwrap_or_else(|| match block_on_request(load_annotations(stock_symbol)) {
+                Ok(annotations) => {
+                    annotations_cache
+                        .borrow_mut()
+                        .insert(stock_symbol.clone(), annotations.clone());
+                    annotations
+                }
+                Err(err) => {
+                    warn!("annotations load failed: {:?}", err);
+                    vec![]
+                }
+            })
+        })
+}
+
+/// Maps each stock symbol to its live quote, with the market cap, volume and bid/ask shown in the
+/// header. Unlike the rest of the quote summary, it is always fetched, and fetched again once the
+/// cached one has expired whenever the stock symbol is emitted.
+pub fn to_stock_live_quotes<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Option<QuoteSummary>, Context = C>
+where
+    S: Stream<'a, Item = String, Context = C>,
+    C: 'a,
+{
+    let live_quote_cache: Rc<RefCell<TtlCache<String, QuoteSummary>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols.map(move |stock_symbol| {
+        let live_quote = live_quote_cache.borrow().get(stock_symbol).cloned();
+
+        live_quote.or_else(|| {
+            match block_on_request(load_quote_summary(stock_symbol, LIVE_QUOTE_MODULES)) {
+                Ok(live_quote) => {
+                    live_quote_cache
+                        .borrow_mut()
+                        .insert(stock_symbol.clone(), live_quote.clone());
+                    Some(live_quote)
+                }
+                Err(err) => {
+                    warn!("live quote load failed: {:?}", err);
+                    None
+                }
+            }
+        })
+    })
+}
+
+/// Returns the name of the index, or the symbol itself if it is not a well-known one.
+pub fn market_index_name(symbol: &str) -> &str {
+    MARKET_INDEX_NAMES
+        .iter()
+        .find(|(index_symbol, _)| *index_symbol == symbol)
+        .map_or(symbol, |(_, name)| name)
+}
+
+/// Maps each list of stock symbols to the daily bars of the past 2 years of each of them, which is
+/// enough for the longer indicators. The cache is checked again on every emission, so that the
+/// bars are refreshed once they expire.
+pub fn to_daily_bars_maps<'a, S, C>(
+    stock_symbol_lists: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = HashMap<String, Vec<Bar>>, Context = C>
+where
+    S: Stream<'a, Item = Vec<String>, Context = C>,
+    C: 'a,
+{
+    let daily_bars_cache: Rc<RefCell<TtlCache<String, Vec<Bar>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbol_lists.map(move |stock_symbols| {
+        stock_symbols
+            .iter()
+            .filter_map(|stock_symbol| {
+                let daily_bars = daily_bars_cache.borrow().get(stock_symbol).cloned();
+
+                daily_bars
+                    .or_else(|| {
+                        let _span = debug_span!("retrieve_interval", %stock_symbol).entered();
+
+                        match block_on_request(load_bars_in_range(stock_symbol, "2y")) {
+                            Ok(daily_bars) => {
+                                daily_bars_cache
+                                    .borrow_mut()
+                                    .insert(stock_symbol.clone(), daily_bars.clone());
+                                Some(daily_bars)
+                            }
+                            Err(err) => {
+                                warn!("daily bars load failed: {:?}", err);
+                                None
+                            }
+                        }
+                    })
+                    .map(|daily_bars| (stock_symbol.clone(), daily_bars))
+            })
+            .collect()
+    })
+}
+
+/// Returns the change in percent from the last close before the time frame to the latest close, or
+/// `None` if the daily bars do not go back far enough. The time frame ends on the latest bar.
+pub fn period_return(
+    daily_bars: &[Bar],
+    time_frame: TimeFrame,
+    market_session: &MarketSession,
+) -> Option<f64> {
+    let latest_bar = daily_bars.last()?;
+    let latest_date = latest_bar.datetime().date().and_hms(0, 0, 0);
+    let date_range = if time_frame == TimeFrame::YearToDate {
+        latest_date.with_ordinal(1).unwrap()..latest_date
+    } else {
+        time_frame.date_range_ending(latest_date + Duration::days(1), market_session)?
+    };
+    let base_bar = daily_bars
+        .iter()
+        .rev()
+        .find(|bar| bar.datetime() < date_range.start)?;
+
+    Some((latest_bar.close / base_bar.close - 1.0) * 100.0)
+}
+
+/// Maps each list of stock symbols to the daily closes of the past month of each of them, for
+/// showing many stocks at a glance without loading their whole history. The cache is checked
+/// again on every emission, so that the closes are refreshed once they expire.
+pub fn to_daily_closes_maps<'a, S, C>(
+    stock_symbol_lists: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = HashMap<String, Vec<f64>>, Context = C>
+where
+    S: Stream<'a, Item = Vec<String>, Context = C>,
+    C: 'a,
+{
+    let daily_closes_cache: Rc<RefCell<TtlCache<String, Vec<f64>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbol_lists.map(move |stock_symbols| {
+        stock_symbols
+            .iter()
+            .filter_map(|stock_symbol| {
+                let daily_closes = daily_closes_cache.borrow().get(stock_symbol).cloned();
+
+                daily_closes
+                    .or_else(|| {
+                        let _span = debug_span!("retrieve_interval", %stock_symbol).entered();
+
+                        match block_on_request(load_bars_in_range(stock_symbol, "1mo")) {
+                            Ok(bars) => {
+                                let daily_closes: Vec<_> =
+                                    bars.iter().map(|bar| bar.close).collect();
+                                daily_closes_cache
+                                    .borrow_mut()
+                                    .insert(stock_symbol.clone(), daily_closes.clone());
+                                Some(daily_closes)
+                            }
+                            Err(err) => {
+                                warn!("daily closes load failed: {:?}", err);
+                                None
+                            }
+                        }
+                    })
+                    .map(|daily_closes| (stock_symbol.clone(), daily_closes))
+            })
+            .collect()
+    })
+}
+
+/// Maps each region to the symbols trending in it, most active first. The cache is checked again
+/// on every emission, so that the list is refreshed once it expires.
+pub fn to_trending_stock_symbol_lists<'a, S, C>(
+    regions: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Vec<String>, Context = C>
+where
+    S: Stream<'a, Item = String, Context = C>,
+    C: 'a,
+{
+    let trending_cache: Rc<RefCell<TtlCache<String, Vec<String>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    regions.map(move |region| {
+        let trending_stock_symbols = trending_cache.borrow().get(region).cloned();
+
+        trending_stock_symbols.unwrap_or_else(|| {
+            match block_on_request(load_trending_stock_symbols(region)) {
+                Ok(trending_stock_symbols) => {
+                    trending_cache
+                        .borrow_mut()
+                        .insert(region.clone(), trending_stock_symbols.clone());
+                    trending_stock_symbols
+                }
+                Err(err) => {
+                    warn!("trending symbols load failed: {:?}", err);
+                    vec![]
+                }
+            }
+        })
+    })
+}
+
+/// Maps each universe to the quotes of the symbols in it. The cache is checked again on every
+/// emission, so that the quotes are refreshed once they expire.
+pub fn to_screener_quote_lists<'a, S, C>(
+    universes: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Vec<MarketQuote>, Context = C>
+where
+    S: Stream<'a, Item = ScreenerUniverse, Context = C>,
+    C: 'a,
+{
+    let screener_cache: Rc<RefCell<TtlCache<ScreenerUniverse, Vec<MarketQuote>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    universes.map(move |universe| {
+        let quotes = screener_cache.borrow().get(universe).cloned();
+
+        quotes.unwrap_or_else(|| match block_on_request(load_screener_quotes(universe)) {
+            Ok(quotes) => {
+                screener_cache
+                    .borrow_mut()
+                    .insert(universe.clone(), quotes.clone());
+                quotes
+            }
+            Err(err) => {
+                warn!("screener quotes load failed: {:?}", err);
+                vec![]
+            }
+        })
+    })
+}
+
+/// Maps each stock symbol to its recent news. `None` symbols are passed through without
+/// fetching, so that the news is only fetched when it is actually displayed.
+pub fn to_stock_news<'a, S, C>(
+    stock_symbols: S,
+    cache_ttl: time::Duration,
+) -> impl Stream<'a, Item = Vec<NewsItem>, Context = C>
+where
+    S: Stream<'a, Item = Option<String>, Context = C>,
+    C: 'a,
+{
+    let news_cache: Rc<RefCell<TtlCache<String, Vec<NewsItem>>>> =
+        Rc::new(RefCell::new(TtlCache::new(cache_ttl)));
+
+    stock_symbols
+        .distinct_until_changed()
+        .map(move |stock_symbol| {
+            let stock_symbol = match stock_symbol {
+                Some(stock_symbol) => stock_symbol,
+                None => return vec![],
+            };
+            let news = news_cache.borrow().get(stock_symbol).cloned();
+
+            news.unwrap_or_else(|| match block_on_request(load_news(stock_symbol)) {
+                Ok(news) => {
+                    news_cache
+                        .borrow_mut()
+                        .insert(stock_symbol.clone(), news.clone());
+                    news
+                }
+                Err(err) => {
+                    warn!("news load failed: {:?}", err);
+                    vec![]
+                }
+            })
+        })
+}
+
+/// Checks that the symbol only contains characters which can be part of a ticker symbol, and that
+/// the exchange suffix, if any, follows the symbol, e.g. `SHOP.TO` or `7203.T`.
+pub fn validate_stock_symbol(symbol: &str) -> Result<(), StockSymbolError> {
+    if let Some(c) = symbol
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '=')))
+    {
+        return Err(StockSymbolError::InvalidChar(c));
+    }
+
+    match market::symbol_suffix(symbol) {
+        Some("") => Err(StockSymbolError::MissingSuffix),
+        Some(suffix) if suffix.len() + 1 == symbol.len() => Err(StockSymbolError::MissingBase),
+        _ => Ok(()),
+    }
+}
+
+/// Maps each stock symbol to its profile. The profile is loaded in the background, so that it is
+/// fetched concurrently with the historical prices; `None` is emitted until it has been loaded,
+/// which is checked on each tick.
+pub fn to_stock_profiles<'a, S, T>(
+    stock_symbols: S,
+    ticks: T,
+    cache_ttl: time::Duration,
+) -> ToStockProfiles<S, T>
 where
     S: Stream<'a, Item = String>,
+    T: Stream<'a, Context = S::Context>,
 {
     ToStockProfiles {
-        stock_profile_map: Rc::new(RefCell::new(hashmap! {})),
+        stock_profile_cache: Rc::new(RefCell::new(TtlCache::new(cache_ttl))),
         stock_symbols,
+        ticks,
     }
 }
 
-pub struct ToStockProfiles<S> {
-    stock_profile_map: Rc<RefCell<HashMap<String, Profile>>>,
+pub struct ToStockProfiles<S, T> {
+    stock_profile_cache: Rc<RefCell<TtlCache<String, Profile>>>,
     stock_symbols: S,
+    ticks: T,
 }
 
-impl<'a, S> Stream<'a> for ToStockProfiles<S>
+impl<'a, S, T> Stream<'a> for ToStockProfiles<S, T>
 where
     S: Stream<'a, Item = String>,
+    T: Stream<'a, Context = S::Context>,
 {
     type Context = S::Context;
-    type Item = Profile;
+    type Item = Option<Profile>;
 
     fn subscribe_ctx<O>(self, mut observer: O)
     where
         O: 'a + FnMut(&Self::Context, &Self::Item),
     {
-        let stock_profile_map = self.stock_profile_map.clone();
+        let stock_profile_cache = self.stock_profile_cache.clone();
+        let mut pending_profile_load: Option<(String, Receiver<Profile>)> = None;
         self.stock_symbols
             .distinct_until_changed()
+            .map(|stock_symbol| Some(stock_symbol.clone()))
+            .merge(self.ticks.map(|_| None))
             .subscribe_ctx(move |ctx, stock_symbol| {
-                let profile = {
-                    let stock_profile_map = stock_profile_map.borrow();
-                    stock_profile_map.get(stock_symbol).cloned()
+                if let Some(stock_symbol) = stock_symbol {
+                    let profile = {
+                        let stock_profile_cache = stock_profile_cache.borrow();
+                        stock_profile_cache.get(stock_symbol).cloned()
+                    };
+                    pending_profile_load = if profile.is_none() {
+                        Some((stock_symbol.clone(), load_profile(stock_symbol.clone())))
+                    } else {
+                        None
+                    };
+
+                    observer(ctx, &profile);
+                    return;
+                }
+
+                let (stock_symbol, profile) = match &pending_profile_load {
+                    Some((stock_symbol, receiver)) => match receiver.try_recv() {
+                        Ok(profile) => (stock_symbol.clone(), profile),
+                        Err(TryRecvError::Empty) => return,
+                        Err(TryRecvError::Disconnected) => {
+                            pending_profile_load = None;
+                            return;
+                        }
+                    },
+                    None => return,
                 };
-                let profile = profile.unwrap_or_else(|| {
-                    let profile = executor::block_on(Compat::new(async {
-                        Profile::load(stock_symbol.as_str()).await
-                    }))
-                    .expect("profile load failed");
-                    let mut stock_profile_map = stock_profile_map.borrow_mut();
-                    stock_profile_map.insert(stock_symbol.clone(), profile.clone());
-                    profile
-                });
-
-                observer(ctx, &profile);
+                pending_profile_load = None;
+                stock_profile_cache
+                    .borrow_mut()
+                    .insert(stock_symbol, profile.clone());
+
+                observer(ctx, &Some(profile));
             });
     }
 }
 
-pub fn to_stock_bar_sets<'a, S, U, R, V>(
+/// Loads the profile in a background thread. The sender is dropped without sending anything if
+/// the load fails.
+fn load_profile(stock_symbol: String) -> Receiver<Profile> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _span = debug_span!("load_profile", %stock_symbol).entered();
+
+        match block_on_request(load_quote_summary(&stock_symbol, PROFILE_MODULES)) {
+            Ok(quote_summary) => {
+                match quote_summary
+                    .price
+                    .and_then(|price| price.long_name.or(price.short_name))
+                {
+                    Some(name) => {
+                        sender.send(Profile { name }).ok();
+                    }
+                    None => {
+                        warn!("profile has no name");
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("profile load failed: {:?}", err);
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Keeps the most recent closing prices of every stock seen so far, so that they can be shown
+/// without fetching anything again.
+pub fn to_recent_closes_maps<'a, S, B, C>(
+    stock_symbols: S,
+    stock_bar_sets: B,
+) -> impl Stream<'a, Item = HashMap<String, Vec<f64>>, Context = C>
+where
+    S: Stream<'a, Item = String>,
+    B: Stream<'a, Item = OrdSet<Bar>, Context = C>,
+    C: 'a + Clone,
+{
+    stock_bar_sets
+        .with_latest_from(stock_symbols, |(stock_bar_set, stock_symbol)| {
+            (stock_symbol.clone(), stock_bar_set.clone())
+        })
+        .fold(
+            hashmap! {},
+            |acc_recent_closes_map, (stock_symbol, stock_bar_set)| {
+                let closes: Vec<_> = stock_bar_set.iter().map(|bar| bar.close).collect();
+                let recent_closes =
+                    closes[closes.len().saturating_sub(MAX_RECENT_CLOSES)..].to_vec();
+                acc_recent_closes_map.update(stock_symbol.clone(), recent_closes)
+            },
+        )
+}
+
+/// Maps the stock symbols, time frames, date ranges and indicators to the bar sets covering
+/// them. The progress of large downloads is sent to `data_events`, followed by `None` once done.
+///
+/// If the provider is unreachable, the bars downloaded so far are kept, and the download is
+/// retried on a later tick. Changes in the connection status are sent to `data_events` too.
+///
+/// While the market is open, the latest bars are downloaded again every `refresh_interval`, or
+/// at any time when a refresh is requested.
+#[allow(clippy::too_many_arguments)]
+pub fn to_stock_bar_sets<'a, S, U, R, V, T, E>(
     stock_symbols: S,
     time_frames: U,
     date_ranges: R,
     indicators: V,
-) -> ToStockBarSets<S, U, R, V>
+    ticks: T,
+    stock_events: E,
+    refresh_interval: Option<time::Duration>,
+    data_events: EventTopic<'a, DataEvent>,
+) -> ToStockBarSets<'a, S, U, R, V, T, E>
 where
     S: Stream<'a, Item = String>,
     U: Stream<'a, Item = TimeFrame>,
     R: Stream<'a, Item = Option<Range<DateTime<Utc>>>>,
-    V: Stream<'a, Item = Option<Indicator>>,
+    V: Stream<'a, Item = Vec<Indicator>>,
+    T: Stream<'a>,
+    E: Stream<'a, Item = StockEvent>,
 {
     ToStockBarSets {
+        data_events,
         date_ranges,
         indicators,
+        refresh_interval,
         stock_bars_map: Rc::new(RefCell::new(hashmap! {})),
+        stock_events,
         stock_symbols,
+        ticks,
         time_frames,
     }
 }
 
+/// Maps the lists of stock symbols to compare, time frames and date ranges to the bar sets of
+/// those stocks covering them, in the same order as the symbols.
+///
+/// Unlike [`to_stock_bar_sets`], the bars are only downloaded once, without any retries or
+/// refreshes, as the comparison is not meant to follow the latest prices.
+pub fn to_comparison_bar_sets<'a, S, U, R, C>(
+    stock_symbol_lists: S,
+    time_frames: U,
+    date_ranges: R,
+) -> impl Stream<'a, Item = Vec<(String, OrdSet<Bar>)>, Context = C>
+where
+    S: Stream<'a, Item = Vec<String>, Context = C>,
+    U: Stream<'a, Item = TimeFrame>,
+    R: Stream<'a, Item = Option<Range<DateTime<Utc>>>>,
+    C: 'a + Clone,
+{
+    let stock_bars_map: Rc<RefCell<BarCoverageHashMap>> = Rc::new(RefCell::new(hashmap! {}));
+
+    stock_symbol_lists
+        .distinct_until_changed()
+        .combine_latest(
+            time_frames.distinct_until_changed(),
+            |(stock_symbols, time_frame)| (stock_symbols.clone(), *time_frame),
+        )
+        .combine_latest(
+            date_ranges.distinct_until_changed(),
+            |((stock_symbols, time_frame), date_range)| {
+                (stock_symbols.clone(), *time_frame, date_range.clone())
+            },
+        )
+        .map(move |(stock_symbols, time_frame, date_range)| {
+            stock_symbols
+                .iter()
+                .map(|stock_symbol| {
+                    let (stock_bar_set, covered_date_ranges) = stock_bars_map
+                        .borrow()
+                        .get(stock_symbol)
+                        .cloned()
+                        .unwrap_or_else(|| load_cached_bars(stock_symbol));
+
+                    let (stock_bar_set, covered_date_ranges) = load_comparison_bars(
+                        stock_symbol,
+                        stock_bar_set,
+                        covered_date_ranges,
+                        *time_frame,
+                        date_range.as_ref(),
+                    );
+                    stock_bars_map.borrow_mut().insert(
+                        stock_symbol.clone(),
+                        (stock_bar_set.clone(), covered_date_ranges),
+                    );
+
+                    (stock_symbol.clone(), stock_bar_set)
+                })
+                .collect()
+        })
+}
+
+/// Downloads the bars of the date range which are not covered yet, or the whole time frame if
+/// there is no date range.
+fn load_comparison_bars(
+    stock_symbol: &str,
+    stock_bar_set: OrdSet<Bar>,
+    covered_date_ranges: DateRangeIntervalSet,
+    time_frame: TimeFrame,
+    date_range: Option<&Range<DateTime<Utc>>>,
+) -> (OrdSet<Bar>, DateRangeIntervalSet) {
+    let _span = debug_span!("load_comparison_bars", %stock_symbol, ?time_frame).entered();
+
+    let date_range = match date_range {
+        Some(date_range) => date_range,
+        None => {
+            return match block_on_request(load_bars_in_range(stock_symbol, time_frame.range())) {
+                Ok(bars) => (stock_bar_set + OrdSet::from(bars), covered_date_ranges),
+                Err(err) => {
+                    warn!("historical prices retrieval failed: {:?}", err);
+                    (stock_bar_set, covered_date_ranges)
+                }
+            };
+        }
+    };
+
+    let uncovered_date_ranges = (
+        date_range.start.timestamp(),
+        (date_range.end - Duration::seconds(1)).timestamp(),
+    )
+        .to_interval_set()
+        .difference(&covered_date_ranges);
+
+    let mut stock_bar_set = stock_bar_set;
+    let mut covered_date_ranges = covered_date_ranges;
+    for (lower, upper) in to_download_chunks(&uncovered_date_ranges) {
+        match block_on_request(load_bars_between(stock_symbol, lower, upper)) {
+            Ok(bars) => {
+                covered_date_ranges = covered_date_ranges.union(&(lower, upper).to_interval_set());
+                stock_bar_set = stock_bar_set + OrdSet::from(bars);
+            }
+            Err(err) => {
+                warn!("historical prices retrieval failed: {:?}", err);
+                break;
+            }
+        }
+    }
+
+    (stock_bar_set, covered_date_ranges)
+}
+
 type DateRangeIntervalSet = IntervalSet<i64>;
 type BarCoverageHashMap = HashMap<String, (OrdSet<Bar>, DateRangeIntervalSet)>;
 
-pub struct ToStockBarSets<S, U, R, V> {
+/// Loads the bars cached on disk in a previous session, if any.
+fn load_cached_bars(stock_symbol: &str) -> (OrdSet<Bar>, DateRangeIntervalSet) {
+    match cache::load_bars(stock_symbol) {
+        Ok(Some(cached_bars)) => (
+            cached_bars.bars.into_iter().map(Bar::from).collect(),
+            cached_bars.covered_date_ranges.to_interval_set(),
+        ),
+        Ok(None) => (ordset![], vec![].to_interval_set()),
+        Err(err) => {
+            warn!("failed to load cached bars: {:?}", err);
+            (ordset![], vec![].to_interval_set())
+        }
+    }
+}
+
+fn save_cached_bars(
+    stock_symbol: &str,
+    stock_bar_set: &OrdSet<Bar>,
+    covered_date_ranges: &DateRangeIntervalSet,
+) -> anyhow::Result<()> {
+    let cached_bars = CachedBars {
+        bars: stock_bar_set.iter().map(CachedBar::from).collect(),
+        covered_date_ranges: covered_date_ranges
+            .iter()
+            .map(|date_range| (date_range.lower(), date_range.upper()))
+            .collect(),
+    };
+
+    cache::save_bars(stock_symbol, &cached_bars)
+}
+
+/// Downloads the whole history of the stock into the disk cache, returning the number of bars
+/// cached.
+#[instrument(level = "debug")]
+pub fn warm_cache(stock_symbol: &str) -> anyhow::Result<usize> {
+    let (stock_bar_set, covered_date_ranges) = load_cached_bars(stock_symbol);
+
+    let bars = block_on_request(load_bars_in_range(stock_symbol, "max"))
+        .with_context(|| "historical prices retrieval failed")?;
+    let covered_date_ranges = if let (Some(first_bar), Some(last_bar)) = (bars.first(), bars.last())
+    {
+        covered_date_ranges.union(
+            &vec![(
+                first_bar.timestamp_seconds() as i64,
+                last_bar.timestamp_seconds() as i64,
+            )]
+            .to_interval_set(),
+        )
+    } else {
+        covered_date_ranges
+    };
+    let stock_bar_set = stock_bar_set + OrdSet::from(bars);
+
+    save_cached_bars(stock_symbol, &stock_bar_set, &covered_date_ranges)?;
+
+    Ok(stock_bar_set.len())
+}
+
+/// Downloads the bars of the time frame, for use outside of the dashboard.
+#[instrument(level = "debug")]
+pub fn retrieve_bars(stock_symbol: &str, time_frame: TimeFrame) -> anyhow::Result<OrdSet<Bar>> {
+    let bars = block_on_request(load_bars_in_range(stock_symbol, time_frame.range()))
+        .with_context(|| "historical prices retrieval failed")?;
+
+    Ok(OrdSet::from(bars))
+}
+
+/// Returns the trading session of the exchange where the stock is listed. The exchange is only
+/// looked up with the provider the first time.
+pub fn market_session(stock_symbol: &str) -> MarketSession {
+    if let Some(market_session) =
+        MARKET_SESSIONS.with(|market_sessions| market_sessions.borrow().get(stock_symbol).copied())
+    {
+        return market_session;
+    }
+
+    // The exchange is known from the suffix of the symbol, if any
+    let exchange = match Listing::for_symbol(stock_symbol).exchange {
+        Some(exchange) => Some(exchange.to_owned()),
+        None => match block_on_request(search_quotes(stock_symbol)) {
+            Ok(quotes) => quotes
+                .into_iter()
+                .find(|quote| quote.symbol.eq_ignore_ascii_case(stock_symbol))
+                .and_then(|quote| quote.exchange),
+            Err(err) => {
+                // Not retried, as the lookup would block every time
+                warn!("exchange lookup failed: {:?}", err);
+                None
+            }
+        },
+    };
+    let market_session = MarketSession::for_exchange(exchange.as_deref());
+    MARKET_SESSIONS.with(|market_sessions| {
+        market_sessions
+            .borrow_mut()
+            .insert(stock_symbol.to_owned(), market_session)
+    });
+
+    market_session
+}
+
+/// Schedule of the next attempt at downloading after a failed download.
+#[derive(Clone, Copy, Debug)]
+struct Retry {
+    delay_secs: u64,
+    retry_at: Instant,
+}
+
+/// Bars downloaded in the background, along with the inclusive timestamp range they cover.
+type PrefetchedBars = (String, (i64, i64), Vec<Bar>);
+
+/// Downloads the chunks in a background thread, sending the bars of each chunk once downloaded.
+fn prefetch_bars(stock_symbol: String, chunks: Vec<(i64, i64)>, sender: Sender<PrefetchedBars>) {
+    thread::spawn(move || {
+        for (lower, upper) in chunks {
+            let _span = debug_span!("prefetch_bars", %stock_symbol, lower, upper).entered();
+
+            let bars = block_on_request(load_bars_between(stock_symbol.as_str(), lower, upper));
+            match bars {
+                Ok(bars) => {
+                    if sender
+                        .send((stock_symbol.clone(), (lower, upper), bars))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    warn!("historical prices prefetch failed: {:?}", err);
+                }
+            }
+        }
+    });
+}
+
+/// Splits the date ranges into chunks which can each be downloaded in a single request. The
+/// chunks are inclusive timestamp ranges, in chronological order.
+fn to_download_chunks(date_ranges: &DateRangeIntervalSet) -> Vec<(i64, i64)> {
+    let chunk_duration = Duration::days(DOWNLOAD_CHUNK_DAYS).num_seconds();
+
+    date_ranges
+        .iter()
+        .flat_map(|date_range| {
+            let (lower, upper) = (date_range.lower(), date_range.upper());
+            (lower..=upper)
+                .step_by(chunk_duration as usize)
+                .map(move |start| (start, cmp::min(start + chunk_duration - 1, upper)))
+        })
+        .collect()
+}
+
+pub struct ToStockBarSets<'a, S, U, R, V, T, E> {
+    data_events: EventTopic<'a, DataEvent>,
     date_ranges: R,
     indicators: V,
+    refresh_interval: Option<time::Duration>,
     stock_bars_map: Rc<RefCell<BarCoverageHashMap>>,
+    stock_events: E,
     stock_symbols: S,
+    ticks: T,
     time_frames: U,
 }
 
-impl<'a, S, U, R, V, C> Stream<'a> for ToStockBarSets<S, U, R, V>
+impl<'a, S, U, R, V, T, E, C> Stream<'a> for ToStockBarSets<'a, S, U, R, V, T, E>
 where
     S: Stream<'a, Item = String, Context = C>,
     U: Stream<'a, Item = TimeFrame>,
     R: Stream<'a, Item = Option<Range<DateTime<Utc>>>>,
-    V: Stream<'a, Item = Option<Indicator>>,
+    V: Stream<'a, Item = Vec<Indicator>>,
+    T: Stream<'a, Context = C>,
+    E: Stream<'a, Item = StockEvent, Context = C>,
     C: 'a + Clone + Sized,
 {
     type Context = C;
@@ -126,7 +2046,14 @@ where
     where
         O: 'a + FnMut(&Self::Context, &Self::Item),
     {
+        let data_events = self.data_events.clone();
+        let refresh_interval = self.refresh_interval;
         let stock_bars_map = self.stock_bars_map.clone();
+        let mut last_request = None;
+        let mut last_download = Instant::now();
+        let mut retry = None;
+        let (prefetch_sender, prefetch_receiver) = mpsc::channel();
+        let mut prefetching_date_ranges_map: HashMap<String, DateRangeIntervalSet> = hashmap! {};
         self.stock_symbols
             .distinct_until_changed()
             .combine_latest(
@@ -141,90 +2068,200 @@ where
             )
             .combine_latest(
                 self.indicators.distinct_until_changed(),
-                |((stock_symbol, time_frame, date_range), indicator)| {
+                |((stock_symbol, time_frame, date_range), indicators)| {
                     (
                         stock_symbol.clone(),
                         *time_frame,
                         date_range.clone(),
-                        *indicator,
+                        indicators.clone(),
                     )
                 },
             )
-            .subscribe_ctx(
-                move |ctx, (stock_symbol, time_frame, date_range, indicator)| {
-                    let (stock_bar_set, covered_date_ranges) = {
-                        let stock_bars_map = stock_bars_map.borrow();
-                        stock_bars_map
-                            .get(stock_symbol)
-                            .cloned()
-                            .unwrap_or((ordset![], vec![].to_interval_set()))
-                    };
+            .map(|request| (Some(request.clone()), false))
+            .merge(self.ticks.map(|_| (None, false)))
+            .merge(self.stock_events.map(|ev| match ev {
+                StockEvent::RefreshRequested => (None, true),
+            }))
+            .subscribe_ctx(move |ctx, (request, refresh_requested)| {
+                let mut refreshing = *refresh_requested;
+                let (stock_symbol, time_frame, date_range, indicators) = match request {
+                    Some(request) => {
+                        last_request = Some(request.clone());
+                        request.clone()
+                    }
+                    None => match (&last_request, retry) {
+                        (Some(request), _) if *refresh_requested => request.clone(),
+                        (Some(request), Some(Retry { retry_at, .. }))
+                            if Instant::now() >= retry_at =>
+                        {
+                            request.clone()
+                        }
+                        (Some(request), None)
+                            if refresh_interval.map_or(false, |refresh_interval| {
+                                last_download.elapsed() >= refresh_interval
+                            }) && market_session(&request.0).is_open(Utc::now()) =>
+                        {
+                            refreshing = true;
+                            request.clone()
+                        }
+                        _ => return,
+                    },
+                };
+                let (stock_symbol, time_frame, date_range, indicators) =
+                    (&stock_symbol, &time_frame, &date_range, &indicators);
+                let mut error = None;
+                last_download = Instant::now();
 
-                    let (stock_bar_set, covered_date_ranges) = if let Some(date_range) = date_range
+                // Forget the latest bars, so that they are downloaded again
+                let refresh_from = (Utc::now() - Duration::days(REFRESH_DAYS)).timestamp();
+                let refresh_until = (Utc::now() + Duration::days(REFRESH_DAYS)).timestamp();
+                if refreshing
+                    && date_range
+                        .as_ref()
+                        .map_or(true, |date_range| date_range.end.timestamp() > refresh_from)
+                {
+                    let mut stock_bars_map = stock_bars_map.borrow_mut();
+                    if let Some((stock_bar_set, covered_date_ranges)) =
+                        stock_bars_map.get(stock_symbol).cloned()
                     {
-                        let uncovered_date_ranges = (
-                            date_range.start.timestamp(),
-                            (date_range.end - Duration::seconds(1)).timestamp(),
-                        )
-                            .to_interval_set();
-                        let uncovered_date_ranges = match indicator {
-                            Some(Indicator::BollingerBands(n, _)) => uncovered_date_ranges.union(
-                                &(
-                                    (date_range.start - Duration::days(**n as i64 - 1)).timestamp(),
-                                    (date_range.start - Duration::seconds(1)).timestamp(),
-                                )
-                                    .to_interval_set(),
-                            ),
-                            Some(Indicator::ExponentialMovingAverage(n)) => uncovered_date_ranges
-                                .union(
-                                    &(
-                                        (date_range.start - Duration::days(**n as i64 - 1))
-                                            .timestamp(),
-                                        (date_range.start - Duration::seconds(1)).timestamp(),
-                                    )
-                                        .to_interval_set(),
-                                ),
-                            Some(Indicator::SimpleMovingAverage(n)) => uncovered_date_ranges.union(
-                                &(
-                                    (date_range.start - Duration::days(**n as i64 - 1)).timestamp(),
-                                    (date_range.start - Duration::seconds(1)).timestamp(),
-                                )
-                                    .to_interval_set(),
+                        stock_bars_map.insert(
+                            stock_symbol.clone(),
+                            (
+                                stock_bar_set
+                                    .into_iter()
+                                    .filter(|bar| (bar.timestamp_seconds() as i64) < refresh_from)
+                                    .collect(),
+                                covered_date_ranges
+                                    .difference(&(refresh_from, refresh_until).to_interval_set()),
                             ),
-                            None => uncovered_date_ranges,
-                        };
-                        let uncovered_date_ranges =
-                            uncovered_date_ranges.difference(&covered_date_ranges);
-
-                        let mut covered_date_ranges = covered_date_ranges;
-                        let mut stock_bar_set = stock_bar_set;
-                        for uncovered_date_range in uncovered_date_ranges {
-                            let bars = executor::block_on(Compat::new(async {
-                                history::retrieve_range(
-                                    stock_symbol.as_str(),
-                                    Utc.timestamp(uncovered_date_range.lower(), 0),
-                                    Some(Utc.timestamp(uncovered_date_range.upper(), 0)),
-                                )
-                                .await
-                            }))
-                            .expect("historical prices retrieval failed");
-                            covered_date_ranges = covered_date_ranges.union(
-                                &(uncovered_date_range.lower(), uncovered_date_range.upper())
-                                    .to_interval_set(),
-                            );
-                            stock_bar_set = stock_bar_set + OrdSet::from(bars);
+                        );
+                    }
+                }
+
+                // Add the bars prefetched in the background since the last time
+                for (prefetched_stock_symbol, (lower, upper), bars) in prefetch_receiver.try_iter()
+                {
+                    let mut stock_bars_map = stock_bars_map.borrow_mut();
+                    let (stock_bar_set, covered_date_ranges) = stock_bars_map
+                        .get(&prefetched_stock_symbol)
+                        .cloned()
+                        .unwrap_or((ordset![], vec![].to_interval_set()));
+                    stock_bars_map.insert(
+                        prefetched_stock_symbol,
+                        (
+                            stock_bar_set + OrdSet::from(bars),
+                            covered_date_ranges.union(&(lower, upper).to_interval_set()),
+                        ),
+                    );
+                }
+
+                let (stock_bar_set, covered_date_ranges) = {
+                    let stock_bars_map = stock_bars_map.borrow();
+                    stock_bars_map
+                        .get(stock_symbol)
+                        .cloned()
+                        .unwrap_or_else(|| load_cached_bars(stock_symbol))
+                };
+
+                let (stock_bar_set, covered_date_ranges) = if let Some(date_range) = date_range {
+                    let uncovered_date_ranges = (
+                        date_range.start.timestamp(),
+                        (date_range.end - Duration::seconds(1)).timestamp(),
+                    )
+                        .to_interval_set();
+                    // Also cover the prices needed by the indicator with the longest period
+                    let max_period = indicators.iter().map(|indicator| indicator.period()).max();
+                    let uncovered_date_ranges = match max_period {
+                        Some(n) => uncovered_date_ranges.union(
+                            &(
+                                (date_range.start - Duration::days(n as i64 - 1)).timestamp(),
+                                (date_range.start - Duration::seconds(1)).timestamp(),
+                            )
+                                .to_interval_set(),
+                        ),
+                        None => uncovered_date_ranges,
+                    };
+                    let uncovered_date_ranges =
+                        uncovered_date_ranges.difference(&covered_date_ranges);
+
+                    let uncovered_duration: i64 = uncovered_date_ranges
+                        .iter()
+                        .map(|date_range| date_range.upper() - date_range.lower())
+                        .sum();
+                    let report_progress = uncovered_duration
+                        >= Duration::days(MIN_PROGRESS_DOWNLOAD_DAYS).num_seconds();
+                    let chunks = to_download_chunks(&uncovered_date_ranges);
+                    let total = chunks.len();
+
+                    let mut covered_date_ranges = covered_date_ranges;
+                    let mut stock_bar_set = stock_bar_set;
+                    for (completed, (lower, upper)) in chunks.into_iter().enumerate() {
+                        // Show what has been downloaded so far, so that the chart fills in
+                        // progressively
+                        if completed > 0 {
+                            observer(ctx, &stock_bar_set);
                         }
+                        if report_progress {
+                            data_events.send(DataEvent::DownloadProgress(Some(DownloadProgress {
+                                completed,
+                                stock_symbol: stock_symbol.clone(),
+                                total,
+                            })));
+                        }
+                        let bars = match debug_span!(
+                            "retrieve_range",
+                            %stock_symbol,
+                            lower,
+                            upper
+                        )
+                        .in_scope(|| {
+                            block_on_request(load_bars_between(stock_symbol.as_str(), lower, upper))
+                        }) {
+                            Ok(bars) => bars,
+                            Err(err) => {
+                                warn!("historical prices retrieval failed: {:?}", err);
+                                error = Some(err);
+                                break;
+                            }
+                        };
+                        covered_date_ranges =
+                            covered_date_ranges.union(&(lower, upper).to_interval_set());
+                        stock_bar_set = stock_bar_set + OrdSet::from(bars);
+                    }
+                    if report_progress {
+                        data_events.send(DataEvent::DownloadProgress(None));
+                    }
+                    if total > 0 && error.is_none() {
+                        save_cached_bars(stock_symbol, &stock_bar_set, &covered_date_ranges)
+                            .unwrap_or_else(|err| {
+                                warn!("failed to save cached bars: {:?}", err);
+                            });
+                    }
 
-                        (stock_bar_set, covered_date_ranges)
-                    } else {
-                        let bars = executor::block_on(Compat::new(async {
-                            history::retrieve_interval(stock_symbol.as_str(), time_frame.interval())
-                                .await
-                        }))
-                        .expect("historical prices retrieval failed");
-                        let covered_date_ranges = if let (Some(first_bar), Some(last_bar)) =
-                            (bars.first(), bars.last())
-                        {
+                    (stock_bar_set, covered_date_ranges)
+                } else {
+                    // The whole history is fetched in a single request, so the progress
+                    // can only show that it has started
+                    data_events.send(DataEvent::DownloadProgress(Some(DownloadProgress {
+                        completed: 0,
+                        stock_symbol: stock_symbol.clone(),
+                        total: 1,
+                    })));
+                    let bars = debug_span!("retrieve_interval", %stock_symbol, ?time_frame)
+                        .in_scope(|| {
+                            block_on_request(load_bars_in_range(
+                                stock_symbol.as_str(),
+                                time_frame.range(),
+                            ))
+                        })
+                        .unwrap_or_else(|err| {
+                            warn!("historical prices retrieval failed: {:?}", err);
+                            error = Some(err);
+                            vec![]
+                        });
+                    data_events.send(DataEvent::DownloadProgress(None));
+                    let covered_date_ranges =
+                        if let (Some(first_bar), Some(last_bar)) = (bars.first(), bars.last()) {
                             covered_date_ranges.union(
                                 &vec![(
                                     first_bar.timestamp_seconds() as i64,
@@ -235,16 +2272,146 @@ where
                         } else {
                             covered_date_ranges
                         };
-                        let stock_bar_set = stock_bar_set + OrdSet::from(bars);
-                        (stock_bar_set, covered_date_ranges)
+                    let stock_bar_set = stock_bar_set + OrdSet::from(bars);
+                    if error.is_none() {
+                        save_cached_bars(stock_symbol, &stock_bar_set, &covered_date_ranges)
+                            .unwrap_or_else(|err| {
+                                warn!("failed to save cached bars: {:?}", err);
+                            });
+                    }
+                    (stock_bar_set, covered_date_ranges)
+                };
+
+                observer(ctx, &stock_bar_set);
+
+                let failed = error.is_some();
+                retry = match error {
+                    // Retrying cannot help if the stock does not exist
+                    Some(StockError::NotFound(_)) => None,
+                    // Nor if the API key is rejected, which is shown until it is fixed
+                    Some(StockError::Unauthorized) => {
+                        data_events.send(DataEvent::ConnectionStatus(
+                            ConnectionStatus::Unauthorized(ApiKeyError::Rejected),
+                        ));
+                        None
+                    }
+                    Some(error) => {
+                        let retry_delay = match (&error, retry) {
+                            (StockError::RateLimited, _) => MAX_RETRY_DELAY_SECS,
+                            (_, Some(retry)) => {
+                                cmp::min(retry.delay_secs * 2, MAX_RETRY_DELAY_SECS)
+                            }
+                            (_, None) => MIN_RETRY_DELAY_SECS,
+                        };
+                        let retry_at = Instant::now() + time::Duration::from_secs(retry_delay);
+                        data_events.send(DataEvent::ConnectionStatus(
+                            ConnectionStatus::Disconnected { error, retry_at },
+                        ));
+                        Some(Retry {
+                            delay_secs: retry_delay,
+                            retry_at,
+                        })
+                    }
+                    None => {
+                        if retry.is_some() {
+                            data_events
+                                .send(DataEvent::ConnectionStatus(ConnectionStatus::Connected));
+                        }
+                        None
+                    }
+                };
+
+                // Prefetch the adjacent date ranges, so that panning does not block
+                if let (Some(date_range), false) = (date_range, failed) {
+                    let duration = date_range.end - date_range.start;
+                    let prev_date_range = (date_range.start - duration)..date_range.start;
+                    let next_date_range = date_range.end..(date_range.end + duration);
+                    let adjacent_date_ranges = if date_range.end < Utc::now() {
+                        vec![prev_date_range, next_date_range]
+                    } else {
+                        vec![prev_date_range]
                     };
 
-                    observer(ctx, &stock_bar_set);
+                    let prefetching_date_ranges = prefetching_date_ranges_map
+                        .get(stock_symbol)
+                        .cloned()
+                        .unwrap_or_else(|| vec![].to_interval_set());
+                    let uncovered_date_ranges = adjacent_date_ranges
+                        .iter()
+                        .map(|date_range| {
+                            (
+                                date_range.start.timestamp(),
+                                (date_range.end - Duration::seconds(1)).timestamp(),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .to_interval_set()
+                        .difference(&covered_date_ranges)
+                        .difference(&prefetching_date_ranges);
 
-                    let mut stock_bars_map = stock_bars_map.borrow_mut();
-                    stock_bars_map
-                        .insert(stock_symbol.clone(), (stock_bar_set, covered_date_ranges));
+                    let chunks = to_download_chunks(&uncovered_date_ranges);
+                    if !chunks.is_empty() {
+                        prefetching_date_ranges_map.insert(
+                            stock_symbol.clone(),
+                            prefetching_date_ranges.union(&uncovered_date_ranges),
+                        );
+                        prefetch_bars(stock_symbol.clone(), chunks, prefetch_sender.clone());
+                    }
+                }
+
+                let mut stock_bars_map = stock_bars_map.borrow_mut();
+                stock_bars_map.insert(stock_symbol.clone(), (stock_bar_set, covered_date_ranges));
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chart_bars_skip_missing_prices() {
+        let res: ChartResponse = serde_json::from_str(
+            r#"{
+                "chart": {
+                    "result": [{
+                        "timestamp": [1609459200, 1609545600, 1609632000],
+                        "indicators": {
+                            "quote": [{
+                                "open": [1.0, null, 3.0],
+                                "high": [1.5, null, 3.5],
+                                "low": [0.5, null, 2.5],
+                                "close": [1.2, null, 3.2],
+                                "volume": [100, null, null]
+                            }]
+                        }
+                    }]
+                }
+            }"#,
+        )
+        .unwrap();
+        let chart_data = res.chart.result.unwrap().remove(0);
+
+        assert_eq!(
+            chart_data.bars(),
+            vec![
+                Bar {
+                    timestamp: 1_609_459_200_000,
+                    open: 1.0,
+                    high: 1.5,
+                    low: 0.5,
+                    close: 1.2,
+                    volume: Some(100),
+                },
+                Bar {
+                    timestamp: 1_609_632_000_000,
+                    open: 3.0,
+                    high: 3.5,
+                    low: 2.5,
+                    close: 3.2,
+                    volume: None,
                 },
-            );
+            ]
+        );
     }
 }
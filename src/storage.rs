@@ -0,0 +1,44 @@
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::PathBuf};
+
+/// Returns the directory where state is persisted across sessions.
+pub fn data_dir() -> anyhow::Result<PathBuf> {
+    let data_dir = dirs::data_dir().with_context(|| "data directory not found")?;
+
+    Ok(data_dir.join(crate::APP_DIR_NAME))
+}
+
+/// Loads the value previously saved under the given name, if any.
+pub fn load<T>(name: &str) -> anyhow::Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    let path = data_dir()?.join(format!("{}.json", name));
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let value = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    Ok(Some(value))
+}
+
+/// Saves the value under the given name, overwriting any previously saved value.
+pub fn save<T>(name: &str, value: &T) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    let data_dir = data_dir()?;
+    fs::create_dir_all(&data_dir)
+        .with_context(|| format!("failed to create {}", data_dir.display()))?;
+
+    let path = data_dir.join(format!("{}.json", name));
+    fs::write(&path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(())
+}
@@ -0,0 +1,1026 @@
+use crate::{
+    alert::Alert,
+    annotation::Annotation,
+    app::{self, App, Focus, Screen, UiState, UiStateUpdate},
+    depth::OrderBook,
+    event::{FocusEvent, PaneEvent},
+    hook::HookAction,
+    layout::ChartLayout,
+    paper::Portfolio,
+    plugin::PluginPanel,
+    reactive::{StreamExt, Topic},
+    series::CustomSeries,
+    stock::{
+        self, AssetProfile, ConnectionStatus, DownloadProgress, MarketQuote, NewsItem, Profile,
+        QuoteSummary, Stock,
+    },
+    watchlist::Watchlists,
+    widgets,
+};
+use chrono::Utc;
+use im::{hashmap, HashMap, OrdSet};
+use reactive_rs::Stream;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    iter,
+    rc::Rc,
+};
+use yahoo_finance::Bar;
+
+/// Number of annotations of the hooks kept for each stock, dropping the oldest, as the hooks are
+/// run on every refresh for as long as the app runs.
+const MAX_HOOK_ANNOTATIONS: usize = 500;
+const MAX_RECENT_STOCK_SYMBOLS: usize = 10;
+
+/// Action dispatched to the store. Each action changes one part of the state, so that the parts
+/// are updated independently of each other. Most actions replace the part, while the others are
+/// reduced from its previous value, e.g. to toggle it.
+#[derive(Clone, Debug)]
+pub enum Action {
+    /// Alerts in the alerts store, and whether their condition is currently true.
+    AlertEvaluations(Vec<(Alert, bool)>),
+    BenchmarkBarSet(Option<(String, OrdSet<Bar>)>),
+    /// Saved chart layout which is recalled, replacing each part of the view.
+    ChartLayout(ChartLayout),
+    ComparisonBarSets(Vec<(String, OrdSet<Bar>)>),
+    ConnectionStatus(ConnectionStatus),
+    CustomSeries(Vec<CustomSeries>),
+    /// Moves the focus to the next or the previous element, which only happens on the chart
+    /// screen.
+    CycleFocus(FocusEvent),
+    DailyBars(HashMap<String, Vec<Bar>>),
+    DownloadProgress(Option<DownloadProgress>),
+    /// Actions requested by a run of the hooks, of which the annotations are added to the stocks.
+    HookActions(Vec<HookAction>),
+    NextBarSize,
+    NextChartType,
+    NextFinancialStatement,
+    NextLayoutPreset,
+    NextScreen,
+    OrderBook(Option<OrderBook>),
+    PluginPanels(Vec<PluginPanel>),
+    PluginSeries(Vec<CustomSeries>),
+    Portfolio(Portfolio),
+    RecentCloses(HashMap<String, Vec<f64>>),
+    ResizePanes(PaneEvent),
+    /// Daily closes of the stocks in the screen which is shown.
+    ScreenDailyCloses(HashMap<String, Vec<f64>>),
+    ScreenerQuotes(Vec<MarketQuote>),
+    /// Name of the watchlist shown in the watchlist pane.
+    SelectWatchlist(String),
+    /// Column of the screener results to sort by, which reverses the order if they are already
+    /// sorted by it.
+    SortScreener(usize),
+    StockAnnotations(Vec<Annotation>),
+    StockAssetProfile(Option<AssetProfile>),
+    StockBars(OrdSet<Bar>),
+    StockFinancials(Option<QuoteSummary>),
+    StockLiveQuote(Option<QuoteSummary>),
+    StockNews(Vec<NewsItem>),
+    StockProfile(Option<Profile>),
+    StockQuoteSummary(Option<QuoteSummary>),
+    /// Symbol of the stock which is selected, before its bars are loaded.
+    StockSymbol(String),
+    ToggleCompressedTimeAxis,
+    ToggleDebugDraw,
+    ToggleEventInspector,
+    /// Adds the stock which is selected to the favorites, or removes it if it is already one.
+    ToggleFavoriteStockSymbol,
+    ToggleFinancialPeriod,
+    ToggleFullScreen,
+    ToggleNewsMarkers,
+    ToggleSummaryView,
+    ToggleVolumeProfile,
+    TrendingStockSymbols(Vec<String>),
+    UiState(UiStateUpdate),
+    /// Daily closes of the stocks in the watchlist pane.
+    WatchlistDailyCloses(HashMap<String, Vec<f64>>),
+    /// Watchlists by name, after each of their edits.
+    Watchlists(Watchlists),
+}
+
+/// State of the store, which holds the latest value of each part as it was dispatched. The parts
+/// are combined into the app by [`select_app`]. The parts are shared between the states, so that
+/// the state is cloned in constant time on each action.
+#[derive(Clone, Debug)]
+pub struct State<'r> {
+    pub alert_evaluations: Rc<Vec<(Alert, bool)>>,
+    /// Custom series overlaid on the chart, derived from the custom and plugin series.
+    pub app_custom_series: Rc<Vec<CustomSeries>>,
+    /// Recent closes shown in the app, derived from the recent, watchlist and screen closes.
+    pub app_recent_closes: HashMap<String, Vec<f64>>,
+    /// Stock shown in the app, derived from the stock, its financials and the hook annotations.
+    pub app_stock: Rc<Stock>,
+    pub benchmark_bar_set: Option<(String, OrdSet<Bar>)>,
+    pub comparison_bar_sets: Rc<Vec<(String, OrdSet<Bar>)>>,
+    pub connection_status: ConnectionStatus,
+    pub custom_series: Rc<Vec<CustomSeries>>,
+    pub daily_bars: HashMap<String, Vec<Bar>>,
+    pub download_progress: Option<DownloadProgress>,
+    /// Focus as it was last cycled to, which the focus of the UI state is derived from.
+    pub focus: Focus,
+    /// Annotations added by the hooks, by stock symbol.
+    pub hook_annotations: HashMap<String, OrdSet<Annotation>>,
+    pub market_indices: Rc<Vec<String>>,
+    pub order_book: Rc<Option<OrderBook>>,
+    pub plugin_panels: Rc<Vec<PluginPanel>>,
+    pub plugin_series: Rc<Vec<CustomSeries>>,
+    pub portfolio: Rc<Portfolio>,
+    pub recent_closes: HashMap<String, Vec<f64>>,
+    /// Symbols of the stocks selected so far, starting from the latest.
+    pub recent_stock_symbols: Rc<Vec<String>>,
+    pub screen_daily_closes: HashMap<String, Vec<f64>>,
+    pub screener_quotes: Rc<Vec<MarketQuote>>,
+    pub stock: Rc<Stock>,
+    pub stock_financials: Rc<Option<QuoteSummary>>,
+    pub stock_symbol: String,
+    pub trending_stock_symbols: Rc<Vec<String>>,
+    pub ui_state: Rc<UiState<'r>>,
+    /// Symbols of the watchlist of the name, derived from the watchlists.
+    pub watchlist: Rc<Vec<String>>,
+    pub watchlist_daily_closes: HashMap<String, Vec<f64>>,
+    pub watchlist_name: String,
+    pub watchlists: Rc<Watchlists>,
+}
+
+impl<'r> State<'r> {
+    pub fn new(
+        ui_state: UiState<'r>,
+        stock_symbol: String,
+        portfolio: Portfolio,
+        market_indices: Vec<String>,
+    ) -> Self {
+        let stock = Rc::new(Stock {
+            market_session: Some(stock::market_session(&stock_symbol)),
+            symbol: stock_symbol.clone(),
+            ..Stock::default()
+        });
+
+        Self {
+            alert_evaluations: Rc::new(vec![]),
+            app_custom_series: Rc::new(vec![]),
+            app_recent_closes: hashmap! {},
+            app_stock: stock.clone(),
+            benchmark_bar_set: None,
+            comparison_bar_sets: Rc::new(vec![]),
+            connection_status: ConnectionStatus::default(),
+            custom_series: Rc::new(vec![]),
+            daily_bars: hashmap! {},
+            download_progress: None,
+            focus: Focus::default(),
+            hook_annotations: hashmap! {},
+            market_indices: Rc::new(market_indices),
+            order_book: Rc::new(None),
+            plugin_panels: Rc::new(vec![]),
+            plugin_series: Rc::new(vec![]),
+            portfolio: Rc::new(portfolio),
+            recent_closes: hashmap! {},
+            recent_stock_symbols: Rc::new(vec![stock_symbol.clone()]),
+            screen_daily_closes: hashmap! {},
+            screener_quotes: Rc::new(vec![]),
+            stock,
+            stock_financials: Rc::new(None),
+            stock_symbol,
+            trending_stock_symbols: Rc::new(vec![]),
+            ui_state: Rc::new(ui_state),
+            watchlist: Rc::new(vec![]),
+            watchlist_daily_closes: hashmap! {},
+            watchlist_name: String::new(),
+            watchlists: Rc::new(Watchlists::new()),
+        }
+    }
+}
+
+/// Returns the state after the action. Only the part replaced by the action is cloned, the others
+/// are shared with the previous state. The parts of the app derived from the replaced part are
+/// selected again, so that they are not selected on every frame.
+pub fn reduce<'r>(state: &State<'r>, action: &Action) -> State<'r> {
+    let mut state = state.clone();
+    match action {
+        Action::AlertEvaluations(alert_evaluations) => {
+            state.alert_evaluations = Rc::new(alert_evaluations.clone())
+        }
+        Action::BenchmarkBarSet(benchmark_bar_set) => {
+            state.benchmark_bar_set = benchmark_bar_set.clone()
+        }
+        Action::ChartLayout(chart_layout) => {
+            select_stock_symbol(&mut state, &chart_layout.stock_symbol);
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.chart_type = chart_layout.chart_type;
+            ui_state.indicators = chart_layout.indicators.clone();
+            ui_state.layout_preset = chart_layout.layout;
+            ui_state.time_frame = chart_layout.time_frame;
+            ui_state.volume_profile = chart_layout.volume_profile;
+        }
+        Action::ComparisonBarSets(comparison_bar_sets) => {
+            state.comparison_bar_sets = Rc::new(comparison_bar_sets.clone())
+        }
+        Action::ConnectionStatus(connection_status) => {
+            state.connection_status = connection_status.clone()
+        }
+        Action::CustomSeries(custom_series) => state.custom_series = Rc::new(custom_series.clone()),
+        Action::CycleFocus(ev) => {
+            if state.ui_state.screen == Screen::Chart {
+                state.focus = state
+                    .ui_state
+                    .layout_preset
+                    .cycle_focus(state.focus, matches!(ev, FocusEvent::Prev));
+            }
+        }
+        Action::DailyBars(daily_bars) => state.daily_bars = daily_bars.clone(),
+        Action::DownloadProgress(download_progress) => {
+            state.download_progress = download_progress.clone()
+        }
+        Action::HookActions(hook_actions) => {
+            // The same annotations are emitted again whenever the bars are refreshed
+            for hook_action in hook_actions {
+                if let HookAction::Annotate {
+                    annotation,
+                    stock_symbol,
+                } = hook_action
+                {
+                    let annotations = state
+                        .hook_annotations
+                        .entry(stock_symbol.clone())
+                        .or_insert_with(OrdSet::new);
+                    annotations.insert(annotation.clone());
+                    if annotations.len() > MAX_HOOK_ANNOTATIONS {
+                        annotations.remove_min();
+                    }
+                }
+            }
+        }
+        Action::NextBarSize => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.bar_size = ui_state.bar_size.next();
+        }
+        Action::NextChartType => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.chart_type = ui_state.chart_type.next();
+        }
+        Action::NextFinancialStatement => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.financial_statement = ui_state.financial_statement.next();
+        }
+        Action::NextLayoutPreset => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.layout_preset = ui_state.layout_preset.next();
+        }
+        Action::NextScreen => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.screen = ui_state.screen.next();
+        }
+        Action::OrderBook(order_book) => state.order_book = Rc::new(order_book.clone()),
+        Action::PluginPanels(plugin_panels) => state.plugin_panels = Rc::new(plugin_panels.clone()),
+        Action::PluginSeries(plugin_series) => state.plugin_series = Rc::new(plugin_series.clone()),
+        Action::Portfolio(portfolio) => state.portfolio = Rc::new(portfolio.clone()),
+        Action::RecentCloses(recent_closes) => state.recent_closes = recent_closes.clone(),
+        Action::ResizePanes(ev) => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.pane_sizes = ui_state.pane_sizes.resize(*ev);
+        }
+        Action::ScreenDailyCloses(screen_daily_closes) => {
+            state.screen_daily_closes = screen_daily_closes.clone()
+        }
+        Action::ScreenerQuotes(screener_quotes) => {
+            state.screener_quotes = Rc::new(screener_quotes.clone())
+        }
+        Action::SelectWatchlist(watchlist_name) => {
+            state.watchlist_name = watchlist_name.clone();
+            state.watchlist = Rc::new(select_watchlist(&state));
+        }
+        Action::SortScreener(column) => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.screener_sort = widgets::next_sort(ui_state.screener_sort, *column);
+        }
+        Action::StockAnnotations(annotations) => {
+            Rc::make_mut(&mut state.stock).annotations = annotations.clone()
+        }
+        Action::StockAssetProfile(asset_profile) => {
+            Rc::make_mut(&mut state.stock).asset_profile = asset_profile.clone()
+        }
+        Action::StockBars(bars) => Rc::make_mut(&mut state.stock).bars = bars.clone(),
+        Action::StockFinancials(stock_financials) => {
+            state.stock_financials = Rc::new(stock_financials.clone())
+        }
+        Action::StockLiveQuote(live_quote) => {
+            Rc::make_mut(&mut state.stock).live_quote = live_quote.clone()
+        }
+        Action::StockNews(news) => Rc::make_mut(&mut state.stock).news = news.clone(),
+        Action::StockProfile(profile) => Rc::make_mut(&mut state.stock).profile = profile.clone(),
+        Action::StockQuoteSummary(quote_summary) => {
+            Rc::make_mut(&mut state.stock).quote_summary = quote_summary.clone()
+        }
+        Action::StockSymbol(stock_symbol) => select_stock_symbol(&mut state, stock_symbol),
+        Action::ToggleCompressedTimeAxis => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.compressed_time_axis = !ui_state.compressed_time_axis;
+        }
+        Action::ToggleDebugDraw => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.debug_draw = !ui_state.debug_draw;
+        }
+        Action::ToggleEventInspector => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.event_inspector = !ui_state.event_inspector;
+        }
+        Action::ToggleFavoriteStockSymbol => {
+            let mut favorite_stock_symbols = (*state.ui_state.favorite_stock_symbols).clone();
+            if let Some(i) = favorite_stock_symbols
+                .iter()
+                .position(|s| *s == state.stock_symbol)
+            {
+                favorite_stock_symbols.remove(i);
+            } else {
+                favorite_stock_symbols.push(state.stock_symbol.clone());
+            }
+            Rc::make_mut(&mut state.ui_state).favorite_stock_symbols =
+                Rc::new(favorite_stock_symbols);
+        }
+        Action::ToggleFinancialPeriod => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.financial_period = ui_state.financial_period.toggle();
+        }
+        Action::ToggleFullScreen => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.full_screen = !ui_state.full_screen;
+        }
+        Action::ToggleNewsMarkers => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.news_markers = !ui_state.news_markers;
+        }
+        Action::ToggleSummaryView => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.summary_view = !ui_state.summary_view;
+        }
+        Action::ToggleVolumeProfile => {
+            let ui_state = Rc::make_mut(&mut state.ui_state);
+            ui_state.volume_profile = !ui_state.volume_profile;
+        }
+        Action::TrendingStockSymbols(trending_stock_symbols) => {
+            state.trending_stock_symbols = Rc::new(trending_stock_symbols.clone())
+        }
+        // The UI state is always shared with the previous state, so it is cloned. The clone is
+        // shallow, as the widget states and the larger fields are shared as well
+        Action::UiState(update) => update.apply(Rc::make_mut(&mut state.ui_state)),
+        Action::WatchlistDailyCloses(watchlist_daily_closes) => {
+            state.watchlist_daily_closes = watchlist_daily_closes.clone()
+        }
+        Action::Watchlists(watchlists) => {
+            state.watchlists = Rc::new(watchlists.clone());
+            state.watchlist = Rc::new(select_watchlist(&state));
+        }
+    }
+
+    match action {
+        Action::CustomSeries(_) | Action::PluginSeries(_) => {
+            state.app_custom_series = Rc::new(select_custom_series(&state))
+        }
+        Action::ChartLayout(_)
+        | Action::HookActions(_)
+        | Action::StockAnnotations(_)
+        | Action::StockAssetProfile(_)
+        | Action::StockBars(_)
+        | Action::StockFinancials(_)
+        | Action::StockLiveQuote(_)
+        | Action::StockNews(_)
+        | Action::StockProfile(_)
+        | Action::StockQuoteSummary(_)
+        | Action::StockSymbol(_) => state.app_stock = Rc::new(select_stock(&state)),
+        Action::RecentCloses(_)
+        | Action::ScreenDailyCloses(_)
+        | Action::WatchlistDailyCloses(_) => state.app_recent_closes = select_recent_closes(&state),
+        _ => {}
+    }
+
+    // The focus falls back to the chart whenever the element it is on is not shown
+    let focus = select_focus(&state);
+    if state.ui_state.focus != focus {
+        Rc::make_mut(&mut state.ui_state).focus = focus;
+    }
+
+    state
+}
+
+/// Selects the stock of the symbol, and adds the symbol to the recent ones. The parts of the
+/// previous stock are dropped, so that they are never taken for those of the new stock, e.g. its
+/// latest price.
+fn select_stock_symbol(state: &mut State, stock_symbol: &str) {
+    if state.stock_symbol == stock_symbol {
+        return;
+    }
+
+    state.stock_symbol = stock_symbol.to_owned();
+    state.stock = Rc::new(Stock {
+        market_session: Some(stock::market_session(stock_symbol)),
+        symbol: stock_symbol.to_owned(),
+        ..Stock::default()
+    });
+    state.recent_stock_symbols = Rc::new(
+        iter::once(stock_symbol)
+            .chain(
+                state
+                    .recent_stock_symbols
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|s| *s != stock_symbol),
+            )
+            .take(MAX_RECENT_STOCK_SYMBOLS)
+            .map(str::to_owned)
+            .collect(),
+    );
+}
+
+/// Selects the symbols of the watchlist of the name, if it exists.
+fn select_watchlist(state: &State) -> Vec<String> {
+    state
+        .watchlists
+        .get(&state.watchlist_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Selects the focus in effect. Only the chart is shown on the chart screen in full screen, without
+/// the header and footer, and none of the panes on the other screens, so the chart takes the
+/// navigation keys.
+fn select_focus(state: &State) -> Focus {
+    let ui_state = &state.ui_state;
+    match state.focus {
+        _ if ui_state.screen != Screen::Chart || ui_state.full_screen => Focus::default(),
+        Focus::Pane(pane) if !ui_state.layout_preset.has_pane(pane) => Focus::default(),
+        focus => focus,
+    }
+}
+
+/// Selects the chart layout of the view, which is saved as the workspace and by the layout field.
+pub fn select_chart_layout(state: &State) -> ChartLayout {
+    ChartLayout {
+        chart_type: state.ui_state.chart_type,
+        indicators: state.ui_state.indicators.clone(),
+        layout: state.ui_state.layout_preset,
+        stock_symbol: state.stock_symbol.clone(),
+        time_frame: state.ui_state.time_frame,
+        volume_profile: state.ui_state.volume_profile,
+    }
+}
+
+/// Selects the symbols of the recent stocks menu. Favorites always come first.
+pub fn select_recent_stock_symbols(state: &State) -> Vec<String> {
+    let favorite_stock_symbols = &state.ui_state.favorite_stock_symbols;
+    favorite_stock_symbols
+        .iter()
+        .chain(
+            state
+                .recent_stock_symbols
+                .iter()
+                .filter(|s| !favorite_stock_symbols.contains(s)),
+        )
+        .cloned()
+        .collect()
+}
+
+/// Selects the symbols of the watchlist pane, with the favorites pinned above the watchlist.
+pub fn select_watchlist_stock_symbols(state: &State) -> Vec<String> {
+    app::to_watchlist_symbols(&state.ui_state.favorite_stock_symbols, &state.watchlist)
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+/// Selects the custom series overlaid on the chart, with the plugin series drawn over the series
+/// imported from CSV files.
+fn select_custom_series(state: &State) -> Vec<CustomSeries> {
+    state
+        .custom_series
+        .iter()
+        .chain(state.plugin_series.iter())
+        .cloned()
+        .collect()
+}
+
+/// Selects the recent closes of the stocks. The closes of the stocks seen so far take precedence,
+/// as they are refreshed along with the bars.
+fn select_recent_closes(state: &State) -> HashMap<String, Vec<f64>> {
+    state
+        .recent_closes
+        .clone()
+        .union(state.watchlist_daily_closes.clone())
+        .union(state.screen_daily_closes.clone())
+}
+
+/// Selects the stock shown in the app, with its financials and the annotations added by the hooks.
+fn select_stock(state: &State) -> Stock {
+    Stock {
+        annotations: state
+            .stock
+            .annotations
+            .iter()
+            .chain(
+                state
+                    .hook_annotations
+                    .get(&state.stock.symbol)
+                    .into_iter()
+                    .flatten(),
+            )
+            .cloned()
+            .collect(),
+        financials: (*state.stock_financials).clone(),
+        ..(*state.stock).clone()
+    }
+}
+
+/// Selects the app to render from the state. The parts of the app are shared with the state, so
+/// that it is selected in constant time on each frame, apart from the status of the alerts.
+pub fn select_app<'r>(state: &State<'r>) -> App<'r> {
+    let now = Utc::now();
+
+    App {
+        alerts: state
+            .alert_evaluations
+            .iter()
+            .map(|(alert, triggered)| (alert.clone(), alert.status(*triggered, now)))
+            .collect(),
+        benchmark_bar_set: state.benchmark_bar_set.clone(),
+        comparison_bar_sets: state.comparison_bar_sets.clone(),
+        connection_status: state.connection_status.clone(),
+        custom_series: state.app_custom_series.clone(),
+        daily_bars: state.daily_bars.clone(),
+        download_progress: state.download_progress.clone(),
+        market_indices: state.market_indices.clone(),
+        order_book: state.order_book.clone(),
+        plugin_panels: state.plugin_panels.clone(),
+        portfolio: state.portfolio.clone(),
+        recent_closes: state.app_recent_closes.clone(),
+        screener_quotes: state.screener_quotes.clone(),
+        stock: state.app_stock.clone(),
+        trending_stock_symbols: state.trending_stock_symbols.clone(),
+        ui_state: state.ui_state.clone(),
+        watchlist: state.watchlist.clone(),
+        watchlist_name: state.watchlist_name.clone(),
+    }
+}
+
+/// Store which reduces the dispatched actions into its states. The actions dispatched while a state
+/// is being sent, e.g. by the streams selected from the state, are queued and reduced after it, so
+/// that the states are sent in the order of their actions.
+#[derive(Clone)]
+pub struct Store<'r> {
+    pending_actions: Rc<RefCell<VecDeque<Action>>>,
+    reducing: Rc<Cell<bool>>,
+    state: Rc<RefCell<State<'r>>>,
+    states: Topic<'r, State<'r>>,
+}
+
+impl<'r> Store<'r> {
+    pub fn new(init_state: State<'r>) -> Self {
+        Self {
+            pending_actions: Rc::new(RefCell::new(VecDeque::new())),
+            reducing: Rc::new(Cell::new(false)),
+            state: Rc::new(RefCell::new(init_state.clone())),
+            states: Topic::with_value(init_state),
+        }
+    }
+
+    /// Reduces the action into the next state and sends it, unless a state is already being sent,
+    /// in which case the action is reduced after it.
+    pub fn dispatch(&self, action: Action) {
+        self.pending_actions.borrow_mut().push_back(action);
+        if self.reducing.replace(true) {
+            return;
+        }
+        let _reducing = Reducing(&self.reducing);
+
+        loop {
+            let action = self.pending_actions.borrow_mut().pop_front();
+            let action = match action {
+                Some(action) => action,
+                None => break,
+            };
+            let state = reduce(&self.state.borrow(), &action);
+            *self.state.borrow_mut() = state.clone();
+            self.states.send(state);
+        }
+    }
+
+    /// Dispatches each action of the stream to the store.
+    pub fn dispatch_from<'a, S>(&self, actions: S)
+    where
+        S: Stream<'a, Item = Action>,
+        'r: 'a,
+    {
+        let store = self.clone();
+        actions.subscribe(move |action| store.dispatch(action.clone()));
+    }
+
+    /// Returns the states of the store, starting from its latest state.
+    pub fn states(&self) -> Topic<'r, State<'r>> {
+        self.states.clone()
+    }
+
+    /// Selects a part of the states, which is only sent when it changes.
+    pub fn select<T, F>(&self, selector: F) -> Topic<'r, T>
+    where
+        T: 'r + Clone + PartialEq,
+        F: 'r + Fn(&State<'r>) -> T,
+    {
+        self.states
+            .clone()
+            .map(move |state| selector(state))
+            .distinct_until_changed()
+            .topic()
+    }
+
+    /// Selects a shared part of the states, which is only sent when it is replaced. Unlike
+    /// [`Store::select`], the part is not compared, as it is too large to compare on every action,
+    /// e.g. the stock with its bars.
+    pub fn select_shared<T, F>(&self, selector: F) -> Topic<'r, Rc<T>>
+    where
+        T: 'r,
+        F: 'r + Fn(&State<'r>) -> Rc<T>,
+    {
+        let latest: RefCell<Option<Rc<T>>> = RefCell::new(None);
+        self.states
+            .clone()
+            .map(move |state| selector(state))
+            .filter(move |part| {
+                let replaced = latest
+                    .borrow()
+                    .as_ref()
+                    .map_or(true, |latest| !Rc::ptr_eq(latest, part));
+                if replaced {
+                    latest.replace(Some(part.clone()));
+                }
+                replaced
+            })
+            .topic()
+    }
+}
+
+/// Clears the flag of the store when dropped, so that it keeps reducing the actions after a
+/// subscriber of its states panics.
+struct Reducing<'a>(&'a Cell<bool>);
+
+impl Drop for Reducing<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{ChartType, LayoutPreset, Pane, TimeFrame},
+        widgets::PopupState,
+    };
+    use chrono::TimeZone;
+
+    fn init_state<'r>() -> State<'r> {
+        State::new(
+            UiState::default(),
+            "AAPL".to_owned(),
+            Portfolio::new(10_000.0),
+            vec![],
+        )
+    }
+
+    fn custom_series(name: &str) -> CustomSeries {
+        CustomSeries {
+            name: name.to_owned(),
+            points: vec![(Utc.timestamp(1_609_459_200, 0), 1.0)],
+        }
+    }
+
+    #[test]
+    fn reduce_replaces_only_the_part_of_the_action() {
+        let state = reduce(&init_state(), &Action::SelectWatchlist("Tech".to_owned()));
+        let next_state = reduce(
+            &state,
+            &Action::Watchlists(
+                vec![("Tech".to_owned(), vec!["AAPL".to_owned()])]
+                    .into_iter()
+                    .collect(),
+            ),
+        );
+
+        assert_eq!(next_state.watchlist_name, "Tech");
+        assert_eq!(*next_state.watchlist, vec!["AAPL".to_owned()]);
+        assert!(state.watchlist.is_empty());
+        assert!(Rc::ptr_eq(&state.stock, &next_state.stock));
+        assert!(Rc::ptr_eq(&state.portfolio, &next_state.portfolio));
+        assert!(Rc::ptr_eq(&state.ui_state, &next_state.ui_state));
+    }
+
+    #[test]
+    fn reduce_applies_ui_state_update_to_new_state() {
+        let state = init_state();
+        let next_state = reduce(
+            &state,
+            &Action::UiState(UiStateUpdate::TimeFrame(TimeFrame::OneYear)),
+        );
+
+        assert_eq!(next_state.ui_state.time_frame, TimeFrame::OneYear);
+        assert_eq!(state.ui_state.time_frame, TimeFrame::OneMonth);
+    }
+
+    #[test]
+    fn reduce_toggles_ui_state_from_previous_state() {
+        let state = [
+            Action::ToggleFullScreen,
+            Action::ToggleNewsMarkers,
+            Action::ToggleFullScreen,
+            Action::NextChartType,
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert!(!state.ui_state.full_screen);
+        assert!(state.ui_state.news_markers);
+        assert_eq!(state.ui_state.chart_type, ChartType::PointAndFigure);
+    }
+
+    #[test]
+    fn reduce_keeps_widget_state_of_previous_state() {
+        let state = init_state();
+        let next_state = reduce(
+            &state,
+            &Action::UiState(UiStateUpdate::CompanyProfilePopupState(PopupState {
+                active: true,
+                scroll: 3,
+            })),
+        );
+
+        assert!(
+            next_state
+                .ui_state
+                .company_profile_popup_state
+                .borrow()
+                .active
+        );
+        assert!(!state.ui_state.company_profile_popup_state.borrow().active);
+    }
+
+    #[test]
+    fn reduce_keeps_latest_value_of_each_part() {
+        let state = [
+            Action::StockSymbol("TSLA".to_owned()),
+            Action::TrendingStockSymbols(vec!["GME".to_owned()]),
+            Action::StockSymbol("MSFT".to_owned()),
+            Action::StockFinancials(None),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert_eq!(state.stock.symbol, "MSFT");
+        assert_eq!(*state.trending_stock_symbols, vec!["GME".to_owned()]);
+    }
+
+    #[test]
+    fn store_reduces_actions_dispatched_by_subscribers_after_state() {
+        let store = Store::new(init_state());
+        let stock_symbols = Rc::new(RefCell::new(vec![]));
+        store.states().subscribe({
+            let store = store.clone();
+            let stock_symbols = stock_symbols.clone();
+            move |state| {
+                stock_symbols.borrow_mut().push(state.stock_symbol.clone());
+                if state.stock_symbol == "MSFT" {
+                    store.dispatch(Action::StockSymbol("TSLA".to_owned()));
+                }
+            }
+        });
+        store.dispatch(Action::StockSymbol("MSFT".to_owned()));
+
+        assert_eq!(*stock_symbols.borrow(), vec!["AAPL", "MSFT", "TSLA"]);
+    }
+
+    #[test]
+    fn store_selects_part_only_when_it_changes() {
+        let store = Store::new(init_state());
+        let full_screens = Rc::new(RefCell::new(vec![]));
+        store.select(|state| state.ui_state.full_screen).subscribe({
+            let full_screens = full_screens.clone();
+            move |full_screen| full_screens.borrow_mut().push(*full_screen)
+        });
+        store.dispatch(Action::StockNews(vec![]));
+        store.dispatch(Action::ToggleFullScreen);
+        store.dispatch(Action::StockSymbol("MSFT".to_owned()));
+        store.dispatch(Action::ToggleFullScreen);
+
+        assert_eq!(*full_screens.borrow(), vec![false, true, false]);
+    }
+
+    fn annotate(stock_symbol: &str, time: i64, label: &str) -> HookAction {
+        HookAction::Annotate {
+            annotation: Annotation::script(Utc.timestamp(time, 0), label, label),
+            stock_symbol: stock_symbol.to_owned(),
+        }
+    }
+
+    #[test]
+    fn reduce_adds_hook_annotations_once() {
+        let hook_actions = vec![
+            annotate("AAPL", 1_609_459_200, "A"),
+            HookAction::Notify("Apple".to_owned()),
+        ];
+        let state = [
+            Action::HookActions(hook_actions.clone()),
+            Action::HookActions(hook_actions),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert_eq!(state.hook_annotations["AAPL"].len(), 1);
+        assert!(!state.hook_annotations.contains_key("Apple"));
+    }
+
+    #[test]
+    fn reduce_drops_oldest_hook_annotations() {
+        let hook_actions = (0..MAX_HOOK_ANNOTATIONS as i64 + 2)
+            .map(|i| annotate("AAPL", 1_609_459_200 + i * 86_400, &i.to_string()))
+            .collect();
+        let state = reduce(&init_state(), &Action::HookActions(hook_actions));
+
+        let annotations = &state.hook_annotations["AAPL"];
+        assert_eq!(annotations.len(), MAX_HOOK_ANNOTATIONS);
+        assert_eq!(annotations.get_min().unwrap().label, "2");
+    }
+
+    #[test]
+    fn select_app_merges_hook_annotations_of_stock() {
+        let time = Utc.timestamp(1_609_459_200, 0);
+        let state = [
+            Action::HookActions(vec![
+                annotate("AAPL", 1_609_459_200, "Apple"),
+                annotate("MSFT", 1_609_459_200, "Microsoft"),
+            ]),
+            Action::StockSymbol("MSFT".to_owned()),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+        let app = select_app(&state);
+
+        assert_eq!(
+            app.stock.annotations,
+            vec![Annotation::script(time, "Microsoft", "Microsoft")]
+        );
+    }
+
+    #[test]
+    fn select_app_shares_parts_of_state() {
+        let state = reduce(&init_state(), &Action::StockSymbol("MSFT".to_owned()));
+        let app = select_app(&state);
+
+        assert_eq!(app.stock.symbol, "MSFT");
+        assert!(Rc::ptr_eq(&app.stock, &state.app_stock));
+        assert!(Rc::ptr_eq(&app.portfolio, &state.portfolio));
+        assert!(Rc::ptr_eq(&app.ui_state, &state.ui_state));
+    }
+
+    #[test]
+    fn select_app_prefers_recent_closes() {
+        let state = [
+            Action::WatchlistDailyCloses(hashmap! {
+                "AAPL".to_owned() => vec![2.0],
+                "MSFT".to_owned() => vec![3.0],
+            }),
+            Action::ScreenDailyCloses(hashmap! {
+                "MSFT".to_owned() => vec![4.0],
+                "TSLA".to_owned() => vec![5.0],
+            }),
+            Action::RecentCloses(hashmap! { "AAPL".to_owned() => vec![1.0] }),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+        let app = select_app(&state);
+
+        assert_eq!(app.recent_closes.get("AAPL"), Some(&vec![1.0]));
+        assert_eq!(app.recent_closes.get("MSFT"), Some(&vec![3.0]));
+        assert_eq!(app.recent_closes.get("TSLA"), Some(&vec![5.0]));
+    }
+
+    #[test]
+    fn select_app_overlays_plugin_series_after_custom_series() {
+        let state = [
+            Action::PluginSeries(vec![custom_series("plugin")]),
+            Action::CustomSeries(vec![custom_series("csv")]),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+        let app = select_app(&state);
+
+        assert_eq!(
+            *app.custom_series,
+            vec![custom_series("csv"), custom_series("plugin")]
+        );
+    }
+
+    #[test]
+    fn reduce_drops_parts_of_previous_stock() {
+        let state = [
+            Action::StockBars(OrdSet::unit(Bar {
+                timestamp: 1_609_459_200_000,
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: None,
+            })),
+            Action::StockSymbol("MSFT".to_owned()),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert_eq!(state.stock.symbol, "MSFT");
+        assert_eq!(state.app_stock.symbol, "MSFT");
+        assert!(state.stock.bars.is_empty());
+        assert!(state.stock.market_session.is_some());
+    }
+
+    #[test]
+    fn reduce_moves_recent_stock_symbol_to_front() {
+        let state = [
+            Action::StockSymbol("MSFT".to_owned()),
+            Action::StockSymbol("TSLA".to_owned()),
+            Action::StockSymbol("MSFT".to_owned()),
+            Action::StockSymbol("MSFT".to_owned()),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert_eq!(*state.recent_stock_symbols, vec!["MSFT", "TSLA", "AAPL"]);
+    }
+
+    #[test]
+    fn select_recent_stock_symbols_puts_favorites_first() {
+        let state = [
+            Action::StockSymbol("MSFT".to_owned()),
+            Action::StockSymbol("TSLA".to_owned()),
+            Action::ToggleFavoriteStockSymbol,
+            Action::StockSymbol("AAPL".to_owned()),
+            Action::ToggleFavoriteStockSymbol,
+            Action::StockSymbol("MSFT".to_owned()),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+
+        assert_eq!(*state.ui_state.favorite_stock_symbols, vec!["TSLA", "AAPL"]);
+        assert_eq!(
+            select_recent_stock_symbols(&state),
+            vec!["TSLA", "AAPL", "MSFT"]
+        );
+    }
+
+    #[test]
+    fn reduce_recalls_each_part_of_chart_layout() {
+        let chart_layout = ChartLayout {
+            chart_type: ChartType::Candlestick,
+            indicators: vec![],
+            layout: LayoutPreset::ChartWatchlist,
+            stock_symbol: "MSFT".to_owned(),
+            time_frame: TimeFrame::OneYear,
+            volume_profile: true,
+        };
+        let state = reduce(&init_state(), &Action::ChartLayout(chart_layout.clone()));
+
+        assert_eq!(select_chart_layout(&state), chart_layout);
+        assert_eq!(state.stock.symbol, "MSFT");
+    }
+
+    #[test]
+    fn reduce_keeps_focus_on_chart_in_full_screen() {
+        let state = [
+            Action::ChartLayout(ChartLayout {
+                layout: LayoutPreset::ChartWatchlist,
+                ..select_chart_layout(&init_state())
+            }),
+            Action::CycleFocus(FocusEvent::Next),
+        ]
+        .iter()
+        .fold(init_state(), |state, action| reduce(&state, action));
+        let focus = state.ui_state.focus;
+        assert_ne!(focus, Focus::Pane(Pane::Chart));
+
+        let state = reduce(&state, &Action::ToggleFullScreen);
+        assert_eq!(state.ui_state.focus, Focus::Pane(Pane::Chart));
+
+        let state = reduce(&state, &Action::ToggleFullScreen);
+        assert_eq!(state.ui_state.focus, focus);
+    }
+
+    #[test]
+    fn store_selects_shared_part_only_when_it_is_replaced() {
+        let store = Store::new(init_state());
+        let stock_symbols = Rc::new(RefCell::new(vec![]));
+        store.select_shared(|state| state.stock.clone()).subscribe({
+            let stock_symbols = stock_symbols.clone();
+            move |stock| stock_symbols.borrow_mut().push(stock.symbol.clone())
+        });
+        store.dispatch(Action::ToggleFullScreen);
+        store.dispatch(Action::StockSymbol("MSFT".to_owned()));
+        store.dispatch(Action::StockNews(vec![]));
+
+        assert_eq!(*stock_symbols.borrow(), vec!["AAPL", "MSFT", "MSFT"]);
+    }
+}
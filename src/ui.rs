@@ -1,44 +1,135 @@
 use crate::{
-    app::{App, Indicator, TimeFrame, UiState, UiTarget},
-    widgets::{SelectMenuBox, SelectMenuList, TextField},
+    alert::{Alert, AlertStatus},
+    annotation::{Annotation, AnnotationKind},
+    app::{
+        self, App, BarSize, ChartType, DateRange, Focus, FrameTimeStats, Indicator, LayoutPreset,
+        Pane, PaneSizes, Screen, TimeFrame, UiState, UiTarget,
+    },
+    config, depth,
+    market::{Listing, MarketSession},
+    paper::{JournalEntry, Order, Position, Side},
+    regression,
+    stats::{self, Stats},
+    stock::{
+        self, ApiKeyError, ConnectionStatus, DailyChange, DownloadProgress, FormattedValue,
+        MarketQuote, PriceTarget, QuoteSummary, Stock,
+    },
+    widgets::{
+        self, MultiSelectMenuBox, MultiSelectMenuList, Popup, SelectMenuBox, SelectMenuList,
+        Sparkline, Table, TableColumn, TableRow, TableState, TextField, TextFieldState,
+    },
 };
+use anyhow::anyhow;
 use chrono::{Duration, TimeZone, Utc};
 use im::{hashmap, HashMap};
 use itertools::Itertools;
 use itertools::MinMaxResult::{MinMax, NoElements, OneElement};
 use math::round;
-use std::{cmp, iter, ops::Range};
+use std::{
+    cell::RefCell,
+    cmp::{self, Ordering},
+    iter,
+    ops::Range,
+    slice,
+    time::Instant,
+};
 use strum::IntoEnumIterator;
 use ta::indicators;
 use ta::{DataItem, Next};
+use tracing::instrument;
 use tui::{
     backend::Backend,
+    buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    symbols::Marker,
+    symbols::{self, Marker},
     text::{Span, Spans},
-    widgets::{Axis, Block, Borders, Chart, Clear, Dataset, GraphType, ListItem, Paragraph},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem,
+        ListState, Paragraph, Wrap,
+    },
     Frame,
 };
-use yahoo_finance::Timestamped;
+use yahoo_finance::{Bar, Timestamped};
+
+const EVENT_INSPECTOR_WIDTH: u16 = 80;
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) -> anyhow::Result<()> {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(vec![
-            Constraint::Length(2),
-            Constraint::Min(5),
-            Constraint::Length(2),
-        ])
-        .split(f.size());
-    let header_area = chunks[0];
-    let body_area = chunks[1];
-    let footer_area = chunks[2];
+    // Nothing can be loaded without a valid API key, so the error is shown in place of the app
+    if let ConnectionStatus::Unauthorized(error) = &app.connection_status {
+        return draw_api_key_error(f, error, app.ui_state.masked_api_key.as_deref());
+    }
+
+    if app.ui_state.full_screen {
+        draw_chart(f, app, f.size())?;
+
+        // The header and footer are hidden, so their targets cannot be clicked
+        for ui_target in &[
+            UiTarget::StockSymbolButton,
+            UiTarget::StockNameButton,
+            UiTarget::SectorButton,
+            UiTarget::IndicatorBox,
+            UiTarget::TimeFrameBox,
+            UiTarget::AlertTable,
+            UiTarget::FinancialsTable,
+            UiTarget::NewsList,
+            UiTarget::ScreenerTable,
+            UiTarget::TrendingTable,
+            UiTarget::WatchlistTable,
+        ] {
+            app.ui_state.ui_target_areas.send((*ui_target, None));
+        }
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(2),
+                Constraint::Min(5),
+                Constraint::Length(2),
+            ])
+            .split(f.size());
+        let header_area = chunks[0];
+        let body_area = chunks[1];
+        let footer_area = chunks[2];
 
-    draw_header(f, app, header_area)?;
-    draw_body(f, app, body_area)?;
-    draw_footer(f, app, footer_area)?;
+        draw_header(f, app, header_area)?;
+        match app.ui_state.screen {
+            Screen::Alerts => draw_alerts(f, app, body_area)?,
+            Screen::Chart => draw_panes(f, app, body_area)?,
+            Screen::Depth => draw_depth(f, app, body_area)?,
+            Screen::Financials => draw_financials(f, app, body_area)?,
+            Screen::Journal => draw_journal(f, app, body_area)?,
+            Screen::Markets => draw_markets(f, app, body_area)?,
+            Screen::Performance => draw_performance(f, app, body_area)?,
+            Screen::Plugins => draw_plugins(f, app, body_area)?,
+            Screen::Portfolio => draw_portfolio(f, app, body_area)?,
+            Screen::Screener => draw_screener(f, app, body_area)?,
+            Screen::Sectors => draw_sectors(f, app, body_area)?,
+            Screen::Trending => draw_trending(f, app, body_area)?,
+        }
+
+        // The tables of the other screens are not shown, so they cannot be interacted with
+        for (screen, ui_target) in &[
+            (Screen::Alerts, UiTarget::AlertTable),
+            (Screen::Chart, UiTarget::Chart),
+            (Screen::Chart, UiTarget::NewsList),
+            (Screen::Chart, UiTarget::WatchlistTable),
+            (Screen::Financials, UiTarget::FinancialsTable),
+            (Screen::Screener, UiTarget::ScreenerTable),
+            (Screen::Trending, UiTarget::TrendingTable),
+        ] {
+            if app.ui_state.screen != *screen {
+                app.ui_state.ui_target_areas.send((*ui_target, None));
+            }
+        }
+        draw_footer(f, app, footer_area)?;
+    }
     draw_overlay(f, app)?;
+    draw_connection_banner(f, app)?;
+    draw_toast(f, app)?;
+    if app.ui_state.event_inspector {
+        draw_event_inspector(f, app)?;
+    }
     if app.ui_state.debug_draw {
         draw_debug(f, app)?;
     }
@@ -50,65 +141,513 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) -> anyhow::Result<()> {
 fn draw_header<B: Backend>(
     f: &mut Frame<B>,
     App {
-        stock, ui_state, ..
+        benchmark_bar_set,
+        stock,
+        ui_state,
+        ..
     }: &App,
     area: Rect,
 ) -> anyhow::Result<()> {
     let stock_name = stock.name().unwrap_or("");
+    let symbol_alias = ui_state.symbol_aliases.alias_of(&stock.symbol);
+    let listing = stock.listing();
+    let daily_change = stock.daily_change().map(|daily_change| {
+        (
+            format!(
+                "{} {} {}",
+                listing.format_price(daily_change.close),
+                listing.currency,
+                listing.format_change(daily_change.change, daily_change.change_percent)
+            ),
+            if daily_change.change >= 0.0 {
+                Color::LightGreen
+            } else {
+                Color::LightRed
+            },
+        )
+    });
+
+    let sector = stock.asset_profile.as_ref().and_then(|asset_profile| {
+        match (&asset_profile.sector, &asset_profile.industry) {
+            (Some(sector), Some(industry)) => Some(format!("{} \u{b7} {}", sector, industry)),
+            (Some(sector), None) => Some(sector.clone()),
+            (None, _) => None,
+        }
+    });
+
+    // The prices stop updating when the provider is unreachable, which would otherwise go unnoticed
+    let stale_text = stock.market_session.as_ref().and_then(|market_session| {
+        let latest_bar_time = stock.bars.last()?.datetime();
+        if market_session.is_stale(latest_bar_time, Utc::now()) {
+            Some(format!(
+                "STALE {} ",
+                latest_bar_time
+                    .with_timezone(&market_session.timezone)
+                    .format("%Y-%m-%d")
+            ))
+        } else {
+            None
+        }
+    });
 
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .horizontal_margin(1)
         .constraints(vec![
-            Constraint::Length(10),
+            // Room for the exchange suffix, the alias and the favorite star
+            Constraint::Length(cmp::max(
+                stock.symbol.chars().count() as u16
+                    + symbol_alias.map_or(0, |alias| alias.chars().count() as u16 + 3)
+                    + 3,
+                10,
+            )),
+            Constraint::Length(
+                daily_change
+                    .as_ref()
+                    .map_or(0, |(text, _)| text.chars().count() as u16 + 2),
+            ),
             Constraint::Length(cmp::max(stock_name.chars().count() as u16, 20)),
+            Constraint::Length(
+                sector
+                    .as_ref()
+                    .map_or(0, |sector| sector.chars().count() as u16 + 2),
+            ),
             Constraint::Min(0),
+            Constraint::Length(
+                26 + stale_text
+                    .as_ref()
+                    .map_or(0, |stale_text| stale_text.chars().count() as u16),
+            ),
         ])
         .split(area);
     let stock_symbol_area = chunks[0];
-    let stock_name_area = chunks[1];
+    let daily_change_area = chunks[1];
+    let stock_name_area = chunks[2];
+    let sector_area = chunks[3];
+    let live_quote_area = chunks[4];
+    let market_status_area = chunks[5];
 
-    let header_base_style = Style::default().fg(Color::White).bg(Color::DarkGray);
+    // The header flashes along with the toast of a fired alert
+    let header_base_style = match &ui_state.toast {
+        Some(toast) if toast.is_flashing() => Style::default().fg(Color::Black).bg(Color::Yellow),
+        _ => Style::default().fg(Color::White).bg(Color::DarkGray),
+    };
 
     let header_block = Block::default().style(header_base_style);
     f.render_widget(header_block, area);
 
-    let stock_symbol_paragraph = Paragraph::new(Span::styled(
+    // The buttons are underlined under the mouse, as they can be clicked, and reversed while they
+    // have the keyboard focus
+    let button_style = |button_area: Rect, ui_target: UiTarget| {
+        let style = if widgets::is_hovered(ui_state.hover_point, button_area) {
+            header_base_style.add_modifier(Modifier::UNDERLINED)
+        } else {
+            header_base_style
+        };
+        if ui_state.focus == Focus::Control(ui_target) {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    };
+
+    let mut stock_symbol_spans = vec![Span::styled(
         stock.symbol.as_str(),
-        header_base_style.add_modifier(Modifier::BOLD),
-    ))
-    .block(Block::default().style(header_base_style));
+        button_style(stock_symbol_area, UiTarget::StockSymbolButton).add_modifier(Modifier::BOLD),
+    )];
+    if let Some(symbol_alias) = symbol_alias {
+        stock_symbol_spans.push(Span::styled(
+            format!(" ({})", symbol_alias),
+            header_base_style.fg(Color::Gray),
+        ));
+    }
+    if ui_state.favorite_stock_symbols.contains(&stock.symbol) {
+        stock_symbol_spans.push(Span::styled(
+            " \u{2605}",
+            header_base_style.fg(Color::Yellow),
+        ));
+    }
+    let stock_symbol_paragraph = Paragraph::new(Spans::from(stock_symbol_spans))
+        .block(Block::default().style(header_base_style));
     f.render_widget(stock_symbol_paragraph, stock_symbol_area);
 
     ui_state
         .ui_target_areas
         .send((UiTarget::StockSymbolButton, Some(stock_symbol_area)));
 
-    let stock_name_paragraph = Paragraph::new(Span::styled(stock_name, header_base_style))
+    if let Some((text, color)) = daily_change {
+        let daily_change_paragraph = Paragraph::new(Span::styled(
+            text,
+            header_base_style.fg(color).add_modifier(Modifier::BOLD),
+        ))
         .block(Block::default().style(header_base_style));
+        f.render_widget(daily_change_paragraph, daily_change_area);
+    }
+
+    let stock_name_paragraph = Paragraph::new(Span::styled(
+        stock_name,
+        button_style(stock_name_area, UiTarget::StockNameButton),
+    ))
+    .block(Block::default().style(header_base_style));
     f.render_widget(stock_name_paragraph, stock_name_area);
 
     ui_state
         .ui_target_areas
         .send((UiTarget::StockNameButton, Some(stock_name_area)));
 
+    if let Some(sector) = &sector {
+        let sector_paragraph = Paragraph::new(Span::styled(
+            sector.as_str(),
+            button_style(sector_area, UiTarget::SectorButton).fg(Color::Gray),
+        ))
+        .block(Block::default().style(header_base_style));
+        f.render_widget(sector_paragraph, sector_area);
+    }
+
+    // Reserved for opening the sector view
+    ui_state
+        .ui_target_areas
+        .send((UiTarget::SectorButton, sector.as_ref().map(|_| sector_area)));
+
+    let mut live_quote_parts = vec![];
+    if let Some(live_quote) = &stock.live_quote {
+        let format_value =
+            |value: Option<&FormattedValue>| value.and_then(|value| value.fmt.clone());
+        let raw_value = |value: Option<&FormattedValue>| {
+            value
+                .and_then(|value| value.raw)
+                .filter(|&value| value > 0.0)
+        };
+        let price = live_quote.price.clone().unwrap_or_default();
+        let summary_detail = live_quote.summary_detail.clone().unwrap_or_default();
+
+        if let Some(market_cap) = format_value(price.market_cap.as_ref()) {
+            live_quote_parts.push(market_cap);
+        }
+        if let Some(volume) = format_value(price.regular_market_volume.as_ref()) {
+            live_quote_parts.push(format!("{} vol", volume));
+        }
+        // The bid and ask are 0 when there are none, e.g. outside of trading hours
+        if let (Some(bid), Some(ask)) = (
+            raw_value(summary_detail.bid.as_ref()),
+            raw_value(summary_detail.ask.as_ref()),
+        ) {
+            live_quote_parts.push(format!(
+                "{}/{} ({})",
+                listing.format_price(bid),
+                listing.format_price(ask),
+                listing.format_price(ask - bid)
+            ));
+        }
+    }
+    // Beta over the bars shown on the chart, rather than the 5-year monthly beta of the provider
+    if let Some((benchmark, benchmark_bars)) = benchmark_bar_set
+        .as_ref()
+        .filter(|(benchmark, _)| *benchmark != stock.symbol)
+    {
+        let in_date_range = |bar: &&Bar| {
+            ui_state
+                .date_range
+                .as_ref()
+                .map_or(true, |date_range| date_range.contains(&bar.datetime()))
+        };
+        if let Some(beta) = regression::to_beta(
+            stock.bars.iter().filter(in_date_range),
+            benchmark_bars.iter().filter(in_date_range),
+        ) {
+            live_quote_parts.push(format!("\u{3b2} {:.2} vs {}", beta, benchmark));
+        }
+    }
+
+    if !live_quote_parts.is_empty() {
+        let live_quote_paragraph = Paragraph::new(Span::styled(
+            live_quote_parts.join(" \u{b7} "),
+            header_base_style,
+        ))
+        .block(Block::default().style(header_base_style));
+        f.render_widget(live_quote_paragraph, live_quote_area);
+    }
+
+    if let Some(market_session) = &stock.market_session {
+        let now = Utc::now();
+        let countdown = format_countdown(market_session.next_transition(now) - now);
+        let mut market_status_spans = vec![];
+        if let Some(stale_text) = stale_text {
+            market_status_spans.push(Span::styled(
+                stale_text,
+                header_base_style
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        market_status_spans.extend(if market_session.is_open(now) {
+            vec![
+                Span::styled(
+                    "OPEN",
+                    header_base_style
+                        .fg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!(" closes in {}", countdown), header_base_style),
+            ]
+        } else {
+            vec![
+                Span::styled(
+                    "CLOSED",
+                    header_base_style
+                        .fg(Color::LightRed)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!(" opens in {}", countdown), header_base_style),
+            ]
+        });
+        let market_status_paragraph = Paragraph::new(Spans::from(market_status_spans))
+            .block(Block::default().style(header_base_style))
+            .alignment(Alignment::Right);
+        f.render_widget(market_status_paragraph, market_status_area);
+    }
+
+    Ok(())
+}
+
+/// Formats the duration until an event, e.g. `2d 3h`, `3h 12m` or `12m`.
+fn format_countdown(duration: Duration) -> String {
+    let mins = cmp::max(duration.num_minutes(), 0);
+    let (days, hours, mins) = (mins / (24 * 60), mins / 60 % 24, mins % 60);
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else {
+        format!("{}m", mins)
+    }
+}
+
+fn draw_panes<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    const ESG_SCORES_HEIGHT: u16 = 8;
+    const KEY_RATIOS_HEIGHT: u16 = 8;
+
+    let PaneSizes {
+        bottom: bottom_size,
+        side: side_size,
+    } = app.ui_state.pane_sizes;
+
+    match app.ui_state.layout_preset {
+        LayoutPreset::ChartOnly => {
+            draw_chart(f, app, area)?;
+        }
+        LayoutPreset::ChartWatchlist => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Percentage(100 - side_size),
+                    Constraint::Percentage(side_size),
+                ])
+                .split(area);
+            let chart_area = chunks[0];
+            let watchlist_area = chunks[1];
+
+            draw_chart(f, app, chart_area)?;
+            draw_watchlist(f, app, watchlist_area)?;
+        }
+        LayoutPreset::ChartNewsFundamentals => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Percentage(100 - side_size),
+                    Constraint::Percentage(side_size),
+                ])
+                .split(area);
+            let main_area = chunks[0];
+            let side_area = chunks[1];
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Percentage(100 - bottom_size),
+                    Constraint::Percentage(bottom_size),
+                ])
+                .split(main_area);
+            let chart_area = chunks[0];
+            let news_area = chunks[1];
+            // The analyst ratings are lined up with the news
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Percentage(100 - bottom_size),
+                    Constraint::Percentage(bottom_size),
+                ])
+                .split(side_area);
+            let analyst_ratings_area = chunks[1];
+            // The ESG scores are left out for the stocks which the provider has none for
+            let esg_scores_height = if app
+                .stock
+                .quote_summary
+                .as_ref()
+                .map_or(false, |quote_summary| quote_summary.esg_scores.is_some())
+            {
+                ESG_SCORES_HEIGHT
+            } else {
+                0
+            };
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Min(0),
+                    Constraint::Length(KEY_RATIOS_HEIGHT),
+                    Constraint::Length(esg_scores_height),
+                ])
+                .split(chunks[0]);
+            let fundamentals_area = chunks[0];
+            let key_ratios_area = chunks[1];
+            let esg_scores_area = chunks[2];
+
+            draw_chart(f, app, chart_area)?;
+            draw_news(f, app, news_area)?;
+            draw_fundamentals(f, app, fundamentals_area)?;
+            draw_key_ratios(f, app, key_ratios_area)?;
+            if esg_scores_height > 0 {
+                draw_esg_scores(f, app, esg_scores_area)?;
+            }
+            draw_analyst_ratings(f, app, analyst_ratings_area)?;
+        }
+        LayoutPreset::ChartHeatmap => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Percentage(100 - side_size),
+                    Constraint::Percentage(side_size),
+                ])
+                .split(area);
+            let chart_area = chunks[0];
+            let heatmap_area = chunks[1];
+
+            draw_chart(f, app, chart_area)?;
+            draw_heatmap(f, app, heatmap_area)?;
+        }
+    }
+
+    // The panes of the other layouts are not shown, so they cannot be interacted with
+    for (layout_preset, ui_target) in &[
+        (LayoutPreset::ChartNewsFundamentals, UiTarget::NewsList),
+        (LayoutPreset::ChartWatchlist, UiTarget::WatchlistTable),
+    ] {
+        if app.ui_state.layout_preset != *layout_preset {
+            app.ui_state.ui_target_areas.send((*ui_target, None));
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_chart<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    const MIN_CHART_HEIGHT: u16 = 8;
+    const MIN_CHART_WIDTH: u16 = 40;
+
+    // The chart is unreadable in very small areas, so fall back to the text summary
+    if app.ui_state.summary_view || area.height < MIN_CHART_HEIGHT || area.width < MIN_CHART_WIDTH {
+        draw_summary(f, app, area)?;
+    } else if !app.comparison_bar_sets.is_empty() {
+        draw_comparison(f, app, area)?;
+    } else {
+        match app.ui_state.chart_type {
+            ChartType::Line | ChartType::Baseline | ChartType::Candlestick => {
+                draw_body(f, app, area)?
+            }
+            ChartType::PointAndFigure => draw_point_and_figure(f, app, area)?,
+        }
+    }
+
+    if let Some(download_progress) = &app.download_progress {
+        draw_download_progress(f, download_progress, area)?;
+    }
+
+    app.ui_state
+        .ui_target_areas
+        .send((UiTarget::Chart, Some(area)));
+
     Ok(())
 }
 
 #[allow(clippy::unnecessary_wraps)]
+fn draw_download_progress<B: Backend>(
+    f: &mut Frame<B>,
+    download_progress: &DownloadProgress,
+    area: Rect,
+) -> anyhow::Result<()> {
+    const GAUGE_WIDTH: u16 = 40;
+
+    let width = cmp::min(GAUGE_WIDTH, area.width);
+    let height = cmp::min(3, area.height);
+    let gauge_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .title(format!("Downloading {}", download_progress.stock_symbol))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+        )
+        .gauge_style(Style::default().fg(Color::Cyan).bg(Color::Black))
+        .ratio(download_progress.ratio())
+        .label(format!(
+            "{}/{}",
+            download_progress.completed, download_progress.total
+        ));
+
+    f.render_widget(Clear, gauge_area);
+    f.render_widget(gauge, gauge_area);
+
+    Ok(())
+}
+
 fn draw_body<B: Backend>(
     f: &mut Frame<B>,
-    App { stock, ui_state }: &App,
+    App {
+        custom_series,
+        stock,
+        ui_state,
+        ..
+    }: &App,
     area: Rect,
 ) -> anyhow::Result<()> {
+    const CUSTOM_SERIES_COLORS: [Color; 4] = [
+        Color::LightMagenta,
+        Color::LightYellow,
+        Color::LightCyan,
+        Color::White,
+    ];
+    const MIN_OSCILLATOR_PANE_HEIGHT: u16 = 6;
+    const PRICE_TARGET_COLOR: Color = Color::LightBlue;
+    const VOLUME_PROFILE_WIDTH: u16 = 14;
     const X_AXIS_LABEL_PADDING: u8 = 4;
     const X_AXIS_LABEL_WIDTH: u8 = 10;
-    const Y_AXIS_LABEL_HEIGHT: u8 = 1;
-    const Y_AXIS_LABEL_PADDING: u8 = 2;
+
+    let bars = ui_state.bar_size.aggregate(&stock.bars);
+
+    // The baseline chart compares the prices to the close of the session before the time frame
+    let baseline = if ui_state.chart_type == ChartType::Baseline {
+        bars.iter()
+            .take_while(|bar| {
+                ui_state
+                    .date_range
+                    .as_ref()
+                    .map_or(false, |date_range| bar.datetime() < date_range.start)
+            })
+            .last()
+            .or_else(|| bars.first())
+            .map(|bar| round::half_to_even(bar.close, 2))
+    } else {
+        None
+    };
 
     let mut historical_prices_data: HashMap<String, Vec<_>> = hashmap! {};
-    let stock_data = stock
-        .bars
+    let stock_data = bars
         .iter()
         .filter(|&bar| {
             ui_state
@@ -125,154 +664,321 @@ fn draw_body<B: Backend>(
         .collect();
     historical_prices_data.insert(stock.symbol.clone(), stock_data);
 
-    let mut historical_prices_datasets = vec![];
+    let visible_bars: Vec<_> = bars
+        .iter()
+        .filter(|&bar| {
+            ui_state
+                .date_range
+                .as_ref()
+                .map_or(true, |date_range| date_range.contains(&bar.datetime()))
+        })
+        .collect();
 
-    if let Some(indicator) = ui_state.indicator {
-        let indicator_prices_data = stock.bars.iter().map(|bar| {
-            let data_item = DataItem::builder()
-                .open(bar.open)
-                .high(bar.high)
-                .low(bar.low)
-                .close(bar.close);
-            let data_item = if let Some(volume) = bar.volume {
-                data_item.volume(volume as f64)
-            } else {
-                data_item
-            };
-            let data_item = data_item.build().unwrap();
-            (bar.timestamp_seconds() as f64, data_item)
-        });
+    let (oscillators, overlays): (Vec<_>, Vec<_>) = ui_state
+        .indicators
+        .iter()
+        .partition(|indicator| indicator.is_oscillator());
 
-        match indicator {
-            Indicator::BollingerBands(n, k) => {
-                let indicator_prices_data = indicator_prices_data.filter(|(timestamp, _)| {
-                    ui_state.date_range.as_ref().map_or(true, |date_range| {
-                        let date_range =
-                            (date_range.start - Duration::days(*n as i64 - 1))..date_range.end;
-                        date_range.contains(&Utc.timestamp(*timestamp as i64, 0))
-                    })
-                });
-                let mut bb = indicators::BollingerBands::new(*n as usize, *k as f64).unwrap();
-                let (bb_upper_data, bb_middle_data, bb_lower_data) = indicator_prices_data.fold(
-                    (vec![], vec![], vec![]),
-                    |mut acc_data, (timestamp, data_item)| {
-                        let bb_output = bb.next(&data_item);
-                        acc_data.0.push((timestamp, bb_output.upper));
-                        acc_data.1.push((timestamp, bb_output.average));
-                        acc_data.2.push((timestamp, bb_output.lower));
-                        acc_data
-                    },
-                );
-                historical_prices_data = historical_prices_data
-                    + hashmap! {
-                        "BB Upper".to_owned() => bb_upper_data,
-                        "BB Middle".to_owned() => bb_middle_data,
-                        "BB Lower".to_owned() => bb_lower_data,
-                    };
-                let bb_upper_data = historical_prices_data.get("BB Upper").unwrap();
-                let bb_middle_data = historical_prices_data.get("BB Middle").unwrap();
-                let bb_lower_data = historical_prices_data.get("BB Lower").unwrap();
-
-                historical_prices_datasets.push(
-                    Dataset::default()
-                        .marker(Marker::Braille)
-                        .style(Style::default().fg(Color::DarkGray))
-                        .graph_type(GraphType::Line)
-                        .data(&bb_upper_data),
-                );
-                historical_prices_datasets.push(
-                    Dataset::default()
-                        .marker(Marker::Braille)
-                        .style(Style::default().fg(Color::DarkGray))
-                        .graph_type(GraphType::Line)
-                        .data(&bb_lower_data),
-                );
-                historical_prices_datasets.push(
-                    Dataset::default()
-                        .marker(Marker::Braille)
-                        .style(Style::default().fg(Color::Cyan))
-                        .graph_type(GraphType::Line)
-                        .data(&bb_middle_data),
-                );
-            }
-            Indicator::ExponentialMovingAverage(n) => {
-                let indicator_prices_data = indicator_prices_data.filter(|(timestamp, _)| {
-                    ui_state.date_range.as_ref().map_or(true, |date_range| {
-                        let date_range =
-                            (date_range.start - Duration::days(*n as i64 - 1))..date_range.end;
-                        date_range.contains(&Utc.timestamp(*timestamp as i64, 0))
-                    })
-                });
-                let mut ema = indicators::ExponentialMovingAverage::new(*n as usize).unwrap();
-                let ema_data = indicator_prices_data
-                    .map(|(timestamp, data_item)| (timestamp, ema.next(&data_item)))
-                    .collect();
-                historical_prices_data.insert("EMA".to_owned(), ema_data);
-                let ema_data = historical_prices_data.get("EMA").unwrap();
-
-                historical_prices_datasets.push(
-                    Dataset::default()
-                        .marker(Marker::Braille)
-                        .style(Style::default().fg(Color::Cyan))
-                        .graph_type(GraphType::Line)
-                        .data(&ema_data),
-                );
-            }
-            Indicator::SimpleMovingAverage(n) => {
-                let indicator_prices_data = indicator_prices_data.filter(|(timestamp, _)| {
-                    ui_state.date_range.as_ref().map_or(true, |date_range| {
-                        let date_range =
-                            (date_range.start - Duration::days(*n as i64 - 1))..date_range.end;
-                        date_range.contains(&Utc.timestamp(*timestamp as i64, 0))
-                    })
-                });
-                let mut sma = indicators::SimpleMovingAverage::new(*n as usize).unwrap();
-                let sma_data = indicator_prices_data
-                    .map(|(timestamp, data_item)| (timestamp, sma.next(&data_item)))
-                    .collect();
-                historical_prices_data.insert("SMA".to_owned(), sma_data);
-                let sma_data = historical_prices_data.get("SMA").unwrap();
-
-                historical_prices_datasets.push(
-                    Dataset::default()
-                        .marker(Marker::Braille)
-                        .style(Style::default().fg(Color::Cyan))
-                        .graph_type(GraphType::Line)
-                        .data(&sma_data),
-                );
-            }
+    let overlay_series: Vec<_> = overlays
+        .into_iter()
+        .map(|&indicator| {
+            let series = to_indicator_series(
+                indicator,
+                stock,
+                ui_state.bar_size,
+                ui_state.date_range.as_ref(),
+            )?;
+            Ok((indicator, series))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let indicator_series: Vec<_> = overlay_series
+        .iter()
+        .flat_map(|(_, series)| series)
+        .collect();
+    for (name, _, data) in &indicator_series {
+        historical_prices_data.insert(name.clone(), data.clone());
+    }
+
+    let bar_timestamps: Vec<f64> = historical_prices_data[&stock.symbol]
+        .iter()
+        .map(|(timestamp, _)| *timestamp)
+        .collect();
+    // On a compressed time axis, the bars are placed at their indices instead of their
+    // timestamps, so that there are no gaps for the days without trading
+    let to_chart_data = |data: &[(f64, f64)]| -> Vec<(f64, f64)> {
+        if !ui_state.compressed_time_axis {
+            return data.to_vec();
         }
+
+        data.iter()
+            .filter_map(|&(timestamp, value)| {
+                bar_timestamps
+                    .binary_search_by(|bar_timestamp| {
+                        bar_timestamp
+                            .partial_cmp(&timestamp)
+                            .unwrap_or(Ordering::Equal)
+                    })
+                    .ok()
+                    .map(|n| (n as f64, value))
+            })
+            .collect()
+    };
+    // The prices of intraday bars are not connected across the gaps between the sessions, which
+    // are left out on a compressed time axis like the days without trading
+    let stock_sessions_data: Vec<_> = split_at_sessions(
+        &historical_prices_data[&stock.symbol],
+        &stock.market_session.unwrap_or_default(),
+    )
+    .iter()
+    .map(|data| to_chart_data(data))
+    .collect();
+    for (_, data) in historical_prices_data.iter_mut() {
+        *data = to_chart_data(data);
     }
 
-    let stock_data = historical_prices_data.get(&stock.symbol).unwrap();
-    let (stock_timestamps, stock_prices): (Vec<_>, Vec<_>) = stock_data.clone().into_iter().unzip();
+    // The points of the custom series are placed at the bars they fall on, like the annotations,
+    // so that they line up with the prices
+    let custom_series_data: Vec<_> = custom_series
+        .iter()
+        .zip(CUSTOM_SERIES_COLORS.iter().copied().cycle())
+        .map(|(custom_series, color)| {
+            let data: Vec<_> = custom_series
+                .points
+                .iter()
+                .filter(|(time, _)| {
+                    ui_state
+                        .date_range
+                        .as_ref()
+                        .map_or(true, |date_range| date_range.contains(time))
+                })
+                .filter_map(|(time, value)| {
+                    let bar = visible_bars
+                        .iter()
+                        .rev()
+                        .find(|bar| bar.datetime().date() <= time.date())?;
+                    Some((bar.timestamp_seconds() as f64, *value))
+                })
+                .coalesce(|prev, point| {
+                    if prev.0 == point.0 {
+                        Ok(point)
+                    } else {
+                        Err((prev, point))
+                    }
+                })
+                .collect();
+            (custom_series.name.clone(), color, to_chart_data(&data))
+        })
+        .collect();
 
-    let historical_prices_dataset = Dataset::default()
-        .marker(Marker::Braille)
-        .style(Style::default().fg({
-            let first_price = stock_prices.first().unwrap_or(&0f64);
-            let last_price = stock_prices.last().unwrap_or(&0f64);
-            if last_price >= first_price {
-                Color::Green
-            } else {
-                Color::Red
+    let news_annotations: Vec<_> = if ui_state.news_markers {
+        stock
+            .news
+            .iter()
+            .map(|news_item| {
+                Annotation::news(
+                    Utc.timestamp(news_item.provider_publish_time, 0),
+                    &news_item.title,
+                )
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    // Each annotation is placed at the bar it falls on, which may span several days
+    let annotation_markers: Vec<_> = stock
+        .annotations
+        .iter()
+        .chain(&news_annotations)
+        .filter_map(|annotation| {
+            let n = visible_bars
+                .iter()
+                .rposition(|bar| bar.datetime().date() <= annotation.time.date())?;
+            if n == visible_bars.len() - 1
+                && ui_state
+                    .date_range
+                    .as_ref()
+                    .map_or(false, |date_range| !date_range.contains(&annotation.time))
+            {
+                return None;
             }
-        }))
-        .graph_type(GraphType::Line)
-        .data(&stock_data);
-    historical_prices_datasets.push(historical_prices_dataset);
+            let x = if ui_state.compressed_time_axis {
+                n as f64
+            } else {
+                visible_bars[n].timestamp_seconds() as f64
+            };
+            Some((x, annotation))
+        })
+        .collect();
 
-    let timestamp_steps: Vec<_> = match stock_timestamps.clone().into_iter().minmax() {
-        MinMax(min, max) => {
-            let n = cmp::min(
-                round::floor(
-                    (area.width - 2) as f64 / (X_AXIS_LABEL_WIDTH + X_AXIS_LABEL_PADDING) as f64,
-                    0,
-                ) as usize,
-                stock_timestamps.len(),
-            );
+    // Each candle is drawn as a wick from the low to the high, with a body of full blocks from the
+    // open to the close, colored by whether the price rose over the bar
+    let candles: Vec<_> = if ui_state.chart_type == ChartType::Candlestick {
+        visible_bars
+            .iter()
+            .map(|bar| {
+                let t = bar.timestamp_seconds() as f64;
+                let wick = to_chart_data(&[
+                    (t, round::half_to_even(bar.low, 2)),
+                    (t, round::half_to_even(bar.high, 2)),
+                ]);
+                let body = to_chart_data(&[
+                    (t, round::half_to_even(bar.open, 2)),
+                    (t, round::half_to_even(bar.close, 2)),
+                ]);
+                (bar.close >= bar.open, wick, body)
+            })
+            .collect()
+    } else {
+        vec![]
+    };
 
-            itertools_num::linspace(min, max, n).collect()
+    let oscillator_series: Vec<_> = oscillators
+        .into_iter()
+        .map(|&indicator| {
+            let series: Vec<_> = to_indicator_series(
+                indicator,
+                stock,
+                ui_state.bar_size,
+                ui_state.date_range.as_ref(),
+            )?
+            .into_iter()
+            .map(|(name, color, data)| (name, color, to_chart_data(&data)))
+            .collect();
+            Ok((indicator, series))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let mut historical_prices_datasets: Vec<_> = indicator_series
+        .iter()
+        .map(|(name, color, _)| {
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(*color))
+                .graph_type(GraphType::Line)
+                .data(&historical_prices_data[name])
+        })
+        .collect();
+    historical_prices_datasets.extend(custom_series_data.iter().map(|(_, color, data)| {
+        Dataset::default()
+            .marker(Marker::Braille)
+            .style(Style::default().fg(*color))
+            .graph_type(GraphType::Line)
+            .data(data)
+    }));
+
+    let stock_data = historical_prices_data.get(&stock.symbol).unwrap();
+    let (stock_timestamps, stock_prices): (Vec<_>, Vec<_>) = stock_data.clone().into_iter().unzip();
+
+    let stock_color = {
+        let first_price = stock_prices.first().unwrap_or(&0f64);
+        let last_price = stock_prices.last().unwrap_or(&0f64);
+        if last_price >= first_price {
+            Color::Green
+        } else {
+            Color::Red
+        }
+    };
+
+    let (baseline_data, baseline_segments) = match baseline {
+        Some(baseline) => (
+            match stock_timestamps.iter().copied().minmax() {
+                MinMax(min, max) => vec![(min, baseline), (max, baseline)],
+                OneElement(t) => vec![(t, baseline)],
+                NoElements => vec![],
+            },
+            stock_sessions_data
+                .iter()
+                .flat_map(|data| split_at_baseline(data, baseline))
+                .collect(),
+        ),
+        None => (vec![], vec![]),
+    };
+
+    if baseline.is_some() {
+        historical_prices_datasets.push(
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(Color::DarkGray))
+                .graph_type(GraphType::Line)
+                .data(&baseline_data),
+        );
+        historical_prices_datasets.extend(baseline_segments.iter().map(|(above, data)| {
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(if *above { Color::Green } else { Color::Red }))
+                .graph_type(GraphType::Line)
+                .data(data)
+        }));
+    } else if ui_state.chart_type == ChartType::Candlestick {
+        for (rising, wick, body) in &candles {
+            let style = Style::default().fg(if *rising { Color::Green } else { Color::Red });
+            historical_prices_datasets.push(
+                Dataset::default()
+                    .marker(Marker::Braille)
+                    .style(style)
+                    .graph_type(GraphType::Line)
+                    .data(wick),
+            );
+            historical_prices_datasets.push(
+                Dataset::default()
+                    .marker(Marker::Block)
+                    .style(style)
+                    .graph_type(GraphType::Line)
+                    .data(body),
+            );
+        }
+    } else {
+        historical_prices_datasets.extend(stock_sessions_data.iter().map(|data| {
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(stock_color))
+                .graph_type(GraphType::Line)
+                .data(data)
+        }));
+    }
+
+    // The volume profile is drawn along the right edge of the chart
+    let (area, volume_profile_area) =
+        if ui_state.volume_profile && area.width > VOLUME_PROFILE_WIDTH * 2 {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Min(0),
+                    Constraint::Length(VOLUME_PROFILE_WIDTH),
+                ])
+                .split(area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (area, None)
+        };
+
+    let pane_areas = {
+        let pane_height = cmp::max(area.height / 4, MIN_OSCILLATOR_PANE_HEIGHT);
+        let constraints: Vec<_> = iter::once(Constraint::Min(0))
+            .chain(
+                oscillator_series
+                    .iter()
+                    .map(|_| Constraint::Length(pane_height)),
+            )
+            .collect();
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(area)
+    };
+
+    let timestamp_steps: Vec<_> = match stock_timestamps.clone().into_iter().minmax() {
+        MinMax(min, max) => {
+            let n = cmp::min(
+                round::floor(
+                    (area.width - 2) as f64 / (X_AXIS_LABEL_WIDTH + X_AXIS_LABEL_PADDING) as f64,
+                    0,
+                ) as usize,
+                stock_timestamps.len(),
+            );
+
+            itertools_num::linspace(min, max, n).collect()
         }
         OneElement(t) => vec![t, t],
         NoElements => {
@@ -297,42 +1003,3058 @@ fn draw_body<B: Backend>(
         *timestamp_steps.first().unwrap(),
         *timestamp_steps.last().unwrap(),
     ];
+    let x_axis_labels: Vec<_> = timestamp_steps
+        .iter()
+        .map(|&t| {
+            // The labels show the dates of the nearest bars on a compressed time axis
+            let t = if ui_state.compressed_time_axis && !bar_timestamps.is_empty() {
+                bar_timestamps[cmp::min(t.round() as usize, bar_timestamps.len() - 1)]
+            } else {
+                t
+            };
+            Span::from(Utc.timestamp(t as i64, 0).format("%Y-%m-%d").to_string())
+        })
+        .collect();
+
+    let (_, prices): (Vec<_>, Vec<_>) = historical_prices_data.values().flatten().copied().unzip();
+    let price_target = stock.price_target();
+    let price_y_axis_steps = to_y_axis_steps(
+        prices
+            .into_iter()
+            .chain(
+                custom_series_data
+                    .iter()
+                    .flat_map(|(_, _, data)| data.iter().map(|(_, value)| *value)),
+            )
+            .chain(
+                candles
+                    .iter()
+                    .flat_map(|(_, wick, _)| wick.iter().map(|(_, value)| *value)),
+            )
+            .chain(baseline)
+            .chain(price_target.map(|price_target| price_target.mean)),
+        pane_areas[0].height,
+        None,
+    );
+    let price_y_axis_bounds = [
+        *price_y_axis_steps.first().unwrap(),
+        *price_y_axis_steps.last().unwrap(),
+    ];
+
+    // The splits are marked with vertical lines across the price pane, behind the prices, as they
+    // explain the sudden changes in the price level
+    let split_lines: Vec<_> = annotation_markers
+        .iter()
+        .filter(|(_, annotation)| annotation.kind == AnnotationKind::Split)
+        .map(|&(x, _)| vec![(x, price_y_axis_bounds[0]), (x, price_y_axis_bounds[1])])
+        .collect();
+    historical_prices_datasets.splice(
+        0..0,
+        split_lines.iter().map(|data| {
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(annotation_color(AnnotationKind::Split)))
+                .graph_type(GraphType::Line)
+                .data(data)
+        }),
+    );
+
+    // The consensus price target is drawn as a dashed line behind the prices, by scattering points
+    // at every other column
+    let price_target_data: Vec<_> = match price_target {
+        Some(price_target) => itertools_num::linspace(
+            x_axis_bounds[0],
+            x_axis_bounds[1],
+            cmp::max(area.width as usize / 2, 2),
+        )
+        .map(|x| (x, price_target.mean))
+        .collect(),
+        None => vec![],
+    };
+    if price_target.is_some() {
+        historical_prices_datasets.insert(
+            0,
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(PRICE_TARGET_COLOR))
+                .graph_type(GraphType::Scatter)
+                .data(&price_target_data),
+        );
+    }
+
+    let mut panes = vec![ChartPane {
+        crosshair_line: vec![],
+        datasets: historical_prices_datasets,
+        title: match baseline {
+            Some(baseline) => format!("Historical Prices vs Previous Close {:.2}", baseline),
+            None => "Historical Prices".to_owned(),
+        },
+        legend: vec![],
+        y_axis_steps: price_y_axis_steps,
+    }];
+    panes.extend(oscillator_series.iter().zip(&pane_areas[1..]).map(
+        |((indicator, series), pane_area)| {
+            ChartPane {
+                crosshair_line: vec![],
+                datasets: series
+                    .iter()
+                    .map(|(_, color, data)| {
+                        Dataset::default()
+                            .marker(Marker::Braille)
+                            .style(Style::default().fg(*color))
+                            .graph_type(GraphType::Line)
+                            .data(data)
+                    })
+                    .collect(),
+                legend: vec![],
+                title: indicator.to_string(),
+                y_axis_steps: to_y_axis_steps(
+                    series
+                        .iter()
+                        .flat_map(|(_, _, data)| data.iter().map(|(_, value)| *value)),
+                    pane_area.height,
+                    indicator.bounds(),
+                ),
+            }
+        },
+    ));
+
+    let y_axis_label_width = to_y_axis_label_width(&panes, &x_axis_labels);
+
+    // The crosshair snaps to the bar nearest to the mouse, if it is over the chart
+    let crosshair_x = ui_state.crosshair_point.and_then(|(column, row)| {
+        let graph_area = to_graph_area(pane_areas[0], y_axis_label_width, false);
+        let last_pane_area = pane_areas[pane_areas.len() - 1];
+        if !(graph_area.left()..graph_area.right()).contains(&column)
+            || !(graph_area.top()..last_pane_area.bottom()).contains(&row)
+        {
+            return None;
+        }
+
+        let x = x_axis_bounds[0]
+            + (column - graph_area.left()) as f64 / cmp::max(graph_area.width - 1, 1) as f64
+                * (x_axis_bounds[1] - x_axis_bounds[0]);
+        stock_timestamps.iter().copied().min_by(|a, b| {
+            (a - x)
+                .abs()
+                .partial_cmp(&(b - x).abs())
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+
+    panes[0].legend = iter::once(LegendEntry {
+        color: stock_color,
+        label: stock.symbol.clone(),
+        value: value_at(stock_data, crosshair_x),
+    })
+    .chain(overlay_series.iter().flat_map(|(indicator, series)| {
+        to_legend_entries(
+            *indicator,
+            series
+                .iter()
+                .map(|(name, color, _)| (name, *color, &historical_prices_data[name][..])),
+            crosshair_x,
+        )
+    }))
+    .chain(
+        custom_series_data
+            .iter()
+            .map(|(name, color, data)| LegendEntry {
+                color: *color,
+                label: name.clone(),
+                value: value_at(data, crosshair_x),
+            }),
+    )
+    .chain(price_target.map(|price_target| LegendEntry {
+        color: PRICE_TARGET_COLOR,
+        label: format!(
+            "Target ({:.2} - {:.2})",
+            price_target.low, price_target.high
+        ),
+        value: Some(price_target.mean),
+    }))
+    .collect();
+    for (pane, (indicator, series)) in panes[1..].iter_mut().zip(&oscillator_series) {
+        pane.legend = to_legend_entries(
+            *indicator,
+            series
+                .iter()
+                .map(|(name, color, data)| (name, *color, &data[..])),
+            crosshair_x,
+        );
+    }
+    if let Some(crosshair_x) = crosshair_x {
+        for pane in &mut panes {
+            pane.crosshair_line = vec![
+                (crosshair_x, *pane.y_axis_steps.first().unwrap()),
+                (crosshair_x, *pane.y_axis_steps.last().unwrap()),
+            ];
+        }
+    }
+
+    if let Some(volume_profile_area) = volume_profile_area {
+        let price_pane_area = pane_areas[0];
+        // The price pane only has the x axis labels if there are no other panes below it
+        let x_axis_height = if oscillator_series.is_empty() { 2 } else { 0 };
+        draw_volume_profile(
+            f,
+            &visible_bars,
+            [
+                *panes[0].y_axis_steps.first().unwrap(),
+                *panes[0].y_axis_steps.last().unwrap(),
+            ],
+            x_axis_height,
+            Rect {
+                y: price_pane_area.y,
+                height: price_pane_area.height,
+                ..volume_profile_area
+            },
+        );
+    }
+
+    draw_chart_panes(
+        f,
+        panes,
+        &pane_areas,
+        y_axis_label_width,
+        x_axis_bounds,
+        x_axis_labels,
+        pane_border_style(ui_state, Pane::Chart),
+    );
+
+    let price_graph_area = to_graph_area(
+        pane_areas[0],
+        y_axis_label_width,
+        oscillator_series.is_empty(),
+    );
+    if let Some(price_target) = price_target {
+        draw_price_target_band(f, price_target, price_y_axis_bounds, price_graph_area);
+    }
+    draw_annotation_markers(f, &annotation_markers, x_axis_bounds, price_graph_area);
+
+    if let (Some(crosshair_x), Some(crosshair_point)) = (crosshair_x, ui_state.crosshair_point) {
+        let timestamp = if ui_state.compressed_time_axis {
+            bar_timestamps[crosshair_x as usize]
+        } else {
+            crosshair_x
+        };
+        if let Some(n) = bars
+            .iter()
+            .position(|bar| bar.timestamp_seconds() == timestamp as i64)
+        {
+            let previous_bar = n.checked_sub(1).map(|n| &bars[n]);
+            let annotations: Vec<_> = annotation_markers
+                .iter()
+                .filter(|(x, _)| *x == crosshair_x)
+                .map(|(_, annotation)| *annotation)
+                .collect();
+            draw_bar_tooltip(
+                f,
+                &bars[n],
+                previous_bar,
+                &annotations,
+                crosshair_point,
+                area,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws the performance of the stock and the stocks compared with it, as the percentage change
+/// since the first bar of the time frame. The legend shows the return of each stock over the whole
+/// time frame, along with the change at the crosshair.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_comparison<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        comparison_bar_sets,
+        stock,
+        ui_state,
+        ..
+    }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    const COMPARISON_COLORS: [Color; 6] = [
+        Color::Cyan,
+        Color::Magenta,
+        Color::Yellow,
+        Color::LightBlue,
+        Color::LightRed,
+        Color::LightGreen,
+    ];
+    const X_AXIS_LABEL_PADDING: u8 = 4;
+    const X_AXIS_LABEL_WIDTH: u8 = 10;
+
+    let series: Vec<_> = iter::once((&stock.symbol, &stock.bars))
+        .chain(
+            comparison_bar_sets
+                .iter()
+                .filter(|(symbol, _)| *symbol != stock.symbol)
+                .map(|(symbol, bars)| (symbol, bars)),
+        )
+        .zip(iter::once(Color::White).chain(COMPARISON_COLORS.iter().copied().cycle()))
+        .map(|((symbol, bars), color)| {
+            let closes: Vec<_> = ui_state
+                .bar_size
+                .aggregate(bars)
+                .iter()
+                .filter(|&bar| {
+                    ui_state
+                        .date_range
+                        .as_ref()
+                        .map_or(true, |date_range| date_range.contains(&bar.datetime()))
+                })
+                .map(|bar| (bar.timestamp_seconds() as f64, bar.close))
+                .collect();
+            let data: Vec<_> = match closes.first() {
+                Some(&(_, first_close)) if first_close != 0.0 => closes
+                    .iter()
+                    .map(|&(timestamp, close)| {
+                        (
+                            timestamp,
+                            round::half_to_even((close / first_close - 1.0) * 100.0, 2),
+                        )
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            (symbol.clone(), color, data)
+        })
+        .collect();
+
+    let timestamps: Vec<_> = series
+        .iter()
+        .flat_map(|(_, _, data)| data.iter().map(|(timestamp, _)| *timestamp))
+        .sorted_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .dedup()
+        .collect();
+    let timestamp_steps: Vec<_> = match timestamps.iter().copied().minmax() {
+        MinMax(min, max) => {
+            let n = cmp::min(
+                round::floor(
+                    (area.width - 2) as f64 / (X_AXIS_LABEL_WIDTH + X_AXIS_LABEL_PADDING) as f64,
+                    0,
+                ) as usize,
+                timestamps.len(),
+            );
+
+            itertools_num::linspace(min, max, n).collect()
+        }
+        OneElement(t) => vec![t, t],
+        NoElements => vec![
+            Utc.ymd(1, 1, 1).and_hms(0, 0, 0).timestamp() as f64,
+            Utc::now().timestamp() as f64,
+        ],
+    };
+    let x_axis_bounds = [
+        *timestamp_steps.first().unwrap(),
+        *timestamp_steps.last().unwrap(),
+    ];
     let x_axis_labels: Vec<_> = timestamp_steps
         .iter()
         .map(|&t| Span::from(Utc.timestamp(t as i64, 0).format("%Y-%m-%d").to_string()))
         .collect();
 
-    let (_, prices): (Vec<_>, Vec<_>) = historical_prices_data.values().flatten().copied().unzip();
-    let price_steps: Vec<_> = match prices.into_iter().minmax() {
-        MinMax(min, max) => {
-            let n = round::floor(
-                (area.height - 2) as f64 / (Y_AXIS_LABEL_HEIGHT + Y_AXIS_LABEL_PADDING) as f64,
-                0,
-            ) as usize;
+    // The line at 0% is where each stock started the time frame
+    let zero_line = vec![(x_axis_bounds[0], 0.0), (x_axis_bounds[1], 0.0)];
+    let mut datasets = vec![Dataset::default()
+        .marker(Marker::Braille)
+        .style(Style::default().fg(Color::DarkGray))
+        .graph_type(GraphType::Line)
+        .data(&zero_line)];
+    datasets.extend(series.iter().map(|(_, color, data)| {
+        Dataset::default()
+            .marker(Marker::Braille)
+            .style(Style::default().fg(*color))
+            .graph_type(GraphType::Line)
+            .data(data)
+    }));
+
+    let mut pane = ChartPane {
+        crosshair_line: vec![],
+        datasets,
+        legend: vec![],
+        title: "Performance Comparison (%)".to_owned(),
+        y_axis_steps: to_y_axis_steps(
+            series
+                .iter()
+                .flat_map(|(_, _, data)| data.iter().map(|(_, change)| *change))
+                .chain(iter::once(0.0)),
+            area.height,
+            None,
+        ),
+    };
+
+    let y_axis_label_width = to_y_axis_label_width(slice::from_ref(&pane), &x_axis_labels);
+
+    // The crosshair snaps to the bar nearest to the mouse, if it is over the chart
+    let crosshair_x = ui_state.crosshair_point.and_then(|(column, row)| {
+        let graph_area = to_graph_area(area, y_axis_label_width, true);
+        if !(graph_area.left()..graph_area.right()).contains(&column)
+            || !(graph_area.top()..graph_area.bottom()).contains(&row)
+        {
+            return None;
+        }
+
+        let x = x_axis_bounds[0]
+            + (column - graph_area.left()) as f64 / cmp::max(graph_area.width - 1, 1) as f64
+                * (x_axis_bounds[1] - x_axis_bounds[0]);
+        timestamps.iter().copied().min_by(|a, b| {
+            (a - x)
+                .abs()
+                .partial_cmp(&(b - x).abs())
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+
+    pane.legend = series
+        .iter()
+        .map(|(symbol, color, data)| LegendEntry {
+            color: *color,
+            label: format!(
+                "{} ({} {})",
+                symbol,
+                ui_state.time_frame,
+                data.last()
+                    .map_or_else(|| "-".to_owned(), |(_, change)| format!("{:+.2}%", change))
+            ),
+            value: value_at(data, crosshair_x),
+        })
+        .collect();
+    if let Some(crosshair_x) = crosshair_x {
+        pane.crosshair_line = vec![
+            (crosshair_x, *pane.y_axis_steps.first().unwrap()),
+            (crosshair_x, *pane.y_axis_steps.last().unwrap()),
+        ];
+    }
+
+    draw_chart_panes(
+        f,
+        vec![pane],
+        &[area],
+        y_axis_label_width,
+        x_axis_bounds,
+        x_axis_labels,
+        pane_border_style(ui_state, Pane::Chart),
+    );
+
+    Ok(())
+}
+
+/// Draws the prices of the bar under the crosshair next to the mouse, on whichever side of it has
+/// room, so that the bar itself is not covered. The annotations of the bar are listed below the
+/// prices.
+fn draw_bar_tooltip<B: Backend>(
+    f: &mut Frame<B>,
+    bar: &Bar,
+    previous_bar: Option<&Bar>,
+    annotations: &[&Annotation],
+    (column, row): (u16, u16),
+    area: Rect,
+) {
+    const MAX_TOOLTIP_WIDTH: u16 = 50;
+    const TOOLTIP_WIDTH: u16 = 20;
+
+    let volume = bar
+        .volume
+        .map_or_else(|| "-".to_owned(), |volume| volume.to_string());
+    let change = previous_bar.map_or_else(
+        || "-".to_owned(),
+        |previous_bar| format!("{:+.2}%", (bar.close / previous_bar.close - 1.0) * 100.0),
+    );
+    let mut lines = vec![
+        Spans::from(Span::styled(
+            bar.datetime().format("%Y-%m-%d").to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(format!("Open   {:>11.2}", bar.open)),
+        Spans::from(format!("High   {:>11.2}", bar.high)),
+        Spans::from(format!("Low    {:>11.2}", bar.low)),
+        Spans::from(format!("Close  {:>11.2}", bar.close)),
+        Spans::from(format!("Volume {:>11}", volume)),
+        Spans::from(format!("Change {:>11}", change)),
+    ];
+    lines.extend(annotations.iter().map(|annotation| {
+        Spans::from(Span::styled(
+            annotation.detail.clone(),
+            Style::default().fg(annotation_color(annotation.kind)),
+        ))
+    }));
+
+    // The tooltip is widened for the annotations with longer details, e.g. the news headlines,
+    // which are cut off beyond the maximum width
+    let width = annotations
+        .iter()
+        .map(|annotation| annotation.detail.chars().count() as u16 + 2)
+        .fold(TOOLTIP_WIDTH, cmp::max)
+        .min(MAX_TOOLTIP_WIDTH)
+        .min(cmp::max(area.width, TOOLTIP_WIDTH));
+    let height = lines.len() as u16 + 2;
+    if width > area.width || height > area.height {
+        return;
+    }
+    let x = if column + 2 + width <= area.right() {
+        column + 2
+    } else {
+        column.saturating_sub(width + 1).max(area.left())
+    };
+    let y = cmp::min(row, area.bottom() - height).max(area.top());
+
+    let tooltip_area = Rect::new(x, y, width, height);
+    f.render_widget(Clear, tooltip_area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+        ),
+        tooltip_area,
+    );
+}
+
+/// Shades the rows of the graph between the lowest and the highest price targets, keeping what has
+/// been drawn on them.
+fn draw_price_target_band<B: Backend>(
+    f: &mut Frame<B>,
+    price_target: PriceTarget,
+    y_axis_bounds: [f64; 2],
+    graph_area: Rect,
+) {
+    if graph_area.height == 0
+        || y_axis_bounds[1] <= y_axis_bounds[0]
+        || price_target.high < y_axis_bounds[0]
+        || price_target.low > y_axis_bounds[1]
+    {
+        return;
+    }
+
+    let to_row = |price: f64| {
+        let ratio = ((price - y_axis_bounds[0]) / (y_axis_bounds[1] - y_axis_bounds[0]))
+            .max(0.0)
+            .min(1.0);
+        graph_area.bottom() - 1 - (ratio * (graph_area.height - 1) as f64).round() as u16
+    };
+    let (top, bottom) = (to_row(price_target.high), to_row(price_target.low));
+
+    f.render_widget(
+        Block::default().style(Style::default().bg(Color::Rgb(24, 32, 48))),
+        Rect::new(graph_area.left(), top, graph_area.width, bottom - top + 1),
+    );
+}
+
+/// Draws the markers of the annotations along the bottom of the graph, under the bars they fall on.
+fn draw_annotation_markers<B: Backend>(
+    f: &mut Frame<B>,
+    markers: &[(f64, &Annotation)],
+    x_axis_bounds: [f64; 2],
+    graph_area: Rect,
+) {
+    if graph_area.width == 0 || graph_area.height == 0 {
+        return;
+    }
+
+    let row = graph_area.bottom() - 1;
+    for (x, annotation) in markers {
+        let ratio = if x_axis_bounds[1] > x_axis_bounds[0] {
+            (x - x_axis_bounds[0]) / (x_axis_bounds[1] - x_axis_bounds[0])
+        } else {
+            0.0
+        };
+        let column = graph_area.left() + (ratio * (graph_area.width - 1) as f64).round() as u16;
+        let width = cmp::min(
+            annotation.label.chars().count() as u16,
+            graph_area.right().saturating_sub(column),
+        );
+        if width == 0 {
+            continue;
+        }
+
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                annotation.label.clone(),
+                Style::default()
+                    .fg(annotation_color(annotation.kind))
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Rect::new(column, row, width, 1),
+        );
+    }
+}
+
+fn annotation_color(kind: AnnotationKind) -> Color {
+    match kind {
+        AnnotationKind::Dividend => Color::Cyan,
+        AnnotationKind::News => Color::Yellow,
+        AnnotationKind::Script => Color::LightGreen,
+        AnnotationKind::Split => Color::Magenta,
+    }
+}
+
+/// Draws the histogram of the volume traded at each price level, with the rows lined up with the
+/// y axis of the price pane. The level with the most volume is highlighted.
+fn draw_volume_profile<B: Backend>(
+    f: &mut Frame<B>,
+    bars: &[&Bar],
+    y_axis_bounds: [f64; 2],
+    x_axis_height: u16,
+    area: Rect,
+) {
+    let block = Block::default()
+        .title("Volume")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let row_count = inner_area.height.saturating_sub(x_axis_height) as usize;
+    let [min, max] = y_axis_bounds;
+    let range = max - min;
+    if row_count == 0 || !range.is_finite() || range <= 0.0 {
+        return;
+    }
+
+    let mut volumes = vec![0_u64; row_count];
+    for bar in bars {
+        if let Some(volume) = bar.volume {
+            let row = ((max - bar.close) / range * row_count as f64)
+                .floor()
+                .max(0.0) as usize;
+            volumes[cmp::min(row, row_count - 1)] += volume;
+        }
+    }
+
+    let max_volume = volumes.iter().copied().max().unwrap_or_default();
+    if max_volume == 0 {
+        return;
+    }
+
+    let lines: Vec<_> = volumes
+        .iter()
+        .map(|&volume| {
+            let width =
+                (volume as f64 / max_volume as f64 * inner_area.width as f64).round() as usize;
+            Spans::from(Span::styled(
+                symbols::block::FULL.repeat(width),
+                Style::default().fg(if volume == max_volume {
+                    Color::Yellow
+                } else {
+                    Color::Blue
+                }),
+            ))
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(lines),
+        Rect {
+            height: row_count as u16,
+            ..inner_area
+        },
+    );
+}
+
+/// Pane of the chart with its own y axis. The panes are stacked vertically and share the x axis.
+struct ChartPane<'a> {
+    /// Vertical line at the bar under the crosshair, if any.
+    crosshair_line: Vec<(f64, f64)>,
+    datasets: Vec<Dataset<'a>>,
+    legend: Vec<LegendEntry>,
+    title: String,
+    y_axis_steps: Vec<f64>,
+}
+
+/// Data series listed in the legend of a chart pane, with its value at the crosshair or the
+/// latest bar.
+struct LegendEntry {
+    color: Color,
+    label: String,
+    value: Option<f64>,
+}
+
+/// Returns the value of the data series at the x coordinate, or its latest value if there is none.
+fn value_at(data: &[(f64, f64)], x: Option<f64>) -> Option<f64> {
+    match x {
+        Some(x) => data.iter().rev().find(|(data_x, _)| *data_x <= x),
+        None => data.last(),
+    }
+    .map(|(_, value)| *value)
+}
+
+/// Returns the legend entries of the data series of an indicator. Indicators with a single series
+/// are labelled as the indicator itself, so that its parameters are shown.
+fn to_legend_entries<'a, I>(indicator: Indicator, series: I, x: Option<f64>) -> Vec<LegendEntry>
+where
+    I: ExactSizeIterator<Item = (&'a String, Color, &'a [(f64, f64)])>,
+{
+    let single = series.len() == 1;
+    series
+        .map(|(name, color, data)| LegendEntry {
+            color,
+            label: if single {
+                indicator.to_string()
+            } else {
+                name.clone()
+            },
+            value: value_at(data, x),
+        })
+        .collect()
+}
+
+/// Returns the width the y axis labels of all the panes are padded to. Panes with the x axis
+/// labels reserve at least the width of the first x axis label, so it is included as well.
+fn to_y_axis_label_width(panes: &[ChartPane], x_axis_labels: &[Span]) -> usize {
+    panes
+        .iter()
+        .flat_map(|pane| &pane.y_axis_steps)
+        .map(|v| format!("{:.2}", v).len())
+        .chain(x_axis_labels.first().map(|label| label.width()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the area in which the chart plots the data, inside the borders and the axes.
+fn to_graph_area(area: Rect, y_axis_label_width: usize, x_axis_labels: bool) -> Rect {
+    let left = cmp::min(
+        area.left() + 1 + y_axis_label_width as u16 + 1,
+        area.right(),
+    );
+    let right = cmp::max(area.right().saturating_sub(1), left);
+    let top = cmp::min(area.top() + 1, area.bottom());
+    let bottom = cmp::max(
+        area.bottom()
+            .saturating_sub(1 + if x_axis_labels { 2 } else { 0 }),
+        top,
+    );
+
+    Rect::new(left, top, right - left, bottom - top)
+}
+
+/// Computes the steps of the y axis which fit in the height. The bounds are fitted to the values,
+/// unless fixed bounds are given.
+fn to_y_axis_steps<I>(values: I, height: u16, fixed_bounds: Option<[f64; 2]>) -> Vec<f64>
+where
+    I: Iterator<Item = f64>,
+{
+    const Y_AXIS_LABEL_HEIGHT: u8 = 1;
+    const Y_AXIS_LABEL_PADDING: u8 = 2;
+
+    let n = cmp::max(
+        round::floor(
+            height.saturating_sub(2) as f64 / (Y_AXIS_LABEL_HEIGHT + Y_AXIS_LABEL_PADDING) as f64,
+            0,
+        ) as usize,
+        2,
+    );
+
+    if let Some([min, max]) = fixed_bounds {
+        return itertools_num::linspace(min, max, n).collect();
+    }
+
+    match values.minmax() {
+        MinMax(min, max) => itertools_num::linspace(min, max, n).collect(),
+        OneElement(v) => vec![v, v],
+        NoElements => vec![0_f64, f64::INFINITY],
+    }
+}
+
+/// Draws the panes of the chart in their areas. The y axis labels of all the panes are padded to
+/// the same width so that the plots line up, and only the bottom pane has the x axis labels.
+fn draw_chart_panes<B: Backend>(
+    f: &mut Frame<B>,
+    panes: Vec<ChartPane>,
+    areas: &[Rect],
+    y_axis_label_width: usize,
+    x_axis_bounds: [f64; 2],
+    x_axis_labels: Vec<Span>,
+    border_style: Style,
+) {
+    let last_pane_index = panes.len() - 1;
+
+    for (n, (pane, &area)) in panes.into_iter().zip(areas).enumerate() {
+        let y_axis_bounds = [
+            *pane.y_axis_steps.first().unwrap(),
+            *pane.y_axis_steps.last().unwrap(),
+        ];
+        let y_axis_labels: Vec<_> = pane
+            .y_axis_steps
+            .iter()
+            .map(|&v| Span::from(format!("{:>width$.2}", v, width = y_axis_label_width)))
+            .collect();
+
+        let x_axis = Axis::default().bounds(x_axis_bounds);
+        let x_axis = if n == last_pane_index {
+            x_axis.labels(x_axis_labels.clone())
+        } else {
+            x_axis
+        };
+
+        // The crosshair is drawn first, so that it is behind the data
+        let mut datasets = vec![];
+        if !pane.crosshair_line.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .marker(Marker::Braille)
+                    .style(Style::default().fg(Color::DarkGray))
+                    .graph_type(GraphType::Line)
+                    .data(&pane.crosshair_line),
+            );
+        }
+        datasets.extend(pane.datasets);
+
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(pane.title)
+                    .borders(Borders::ALL)
+                    .border_style(border_style),
+            )
+            .x_axis(x_axis)
+            .y_axis(Axis::default().bounds(y_axis_bounds).labels(y_axis_labels));
+        f.render_widget(chart, area);
+
+        let graph_area = to_graph_area(area, y_axis_label_width, n == last_pane_index);
+        draw_legend(f, &pane.legend, graph_area);
+    }
+}
+
+/// Draws the legend in the top left corner of the graph area, if it fits.
+fn draw_legend<B: Backend>(f: &mut Frame<B>, legend: &[LegendEntry], graph_area: Rect) {
+    let lines: Vec<_> = legend
+        .iter()
+        .map(|entry| {
+            Spans::from(vec![
+                Span::styled("\u{25a0} ", Style::default().fg(entry.color)),
+                Span::raw(format!(
+                    "{} {}",
+                    entry.label,
+                    entry
+                        .value
+                        .map_or_else(|| "-".to_owned(), |value| format!("{:.2}", value))
+                )),
+            ])
+        })
+        .collect();
+    let width = lines.iter().map(Spans::width).max().unwrap_or(0) as u16 + 2;
+    let height = lines.len() as u16 + 2;
+    if lines.is_empty() || width > graph_area.width || height > graph_area.height {
+        return;
+    }
+
+    let legend_area = Rect::new(graph_area.x, graph_area.y, width, height);
+    f.render_widget(Clear, legend_area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        legend_area,
+    );
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_point_and_figure<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        stock, ui_state, ..
+    }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    const COLUMN_WIDTH: u16 = 2;
+    const Y_AXIS_LABEL_WIDTH: u16 = 10;
+
+    let closes: Vec<_> = ui_state
+        .bar_size
+        .aggregate(&stock.bars)
+        .iter()
+        .filter(|&bar| {
+            ui_state
+                .date_range
+                .as_ref()
+                .map_or(true, |date_range| date_range.contains(&bar.datetime()))
+        })
+        .map(|bar| bar.close)
+        .collect();
+    let box_size = closes.last().map_or(0.0, |close| {
+        close * ui_state.point_and_figure.box_size / 100.0
+    });
+    let reversal = ui_state.point_and_figure.reversal;
+
+    let block = Block::default()
+        .title(format!(
+            "Point & Figure ({:.2} box, {}-box reversal)",
+            box_size, reversal
+        ))
+        .borders(Borders::ALL)
+        .border_style(pane_border_style(ui_state, Pane::Chart));
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = if box_size > 0.0 {
+        to_point_and_figure_columns(&closes, box_size, reversal)
+    } else {
+        vec![]
+    };
+    let last_column = match columns.last() {
+        Some(last_column) => last_column,
+        None => {
+            f.render_widget(Paragraph::new("No price data"), inner_area);
+            return Ok(());
+        }
+    };
+
+    // Only the latest columns which fit are shown, with the rows centered on the latest box if
+    // they do not all fit
+    let visible_column_count =
+        (inner_area.width.saturating_sub(Y_AXIS_LABEL_WIDTH) / COLUMN_WIDTH) as usize;
+    let visible_columns = &columns[columns.len().saturating_sub(visible_column_count)..];
+    let row_count = inner_area.height as i64;
+    let (min_box, max_box) = visible_columns
+        .iter()
+        .fold((i64::MAX, i64::MIN), |(min_box, max_box), column| {
+            (min_box.min(column.low), max_box.max(column.high))
+        });
+    let latest_box = if last_column.rising {
+        last_column.high
+    } else {
+        last_column.low
+    };
+    let top_box = if max_box - min_box < row_count {
+        max_box
+    } else {
+        (latest_box + row_count / 2)
+            .min(max_box)
+            .max(min_box + row_count - 1)
+    };
+
+    let lines: Vec<_> = (0..row_count)
+        .map(|row| {
+            let level = top_box - row;
+            let mut spans = vec![Span::styled(
+                format!(
+                    "{:>width$} ",
+                    format!("{:.2}", level as f64 * box_size),
+                    width = (Y_AXIS_LABEL_WIDTH - 1) as usize
+                ),
+                Style::default().fg(Color::Gray),
+            )];
+            spans.extend(visible_columns.iter().map(|column| {
+                if (column.low..=column.high).contains(&level) {
+                    if column.rising {
+                        Span::styled("X ", Style::default().fg(Color::Green))
+                    } else {
+                        Span::styled("O ", Style::default().fg(Color::Red))
+                    }
+                } else {
+                    Span::raw("  ")
+                }
+            }));
+            Spans::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner_area);
+
+    Ok(())
+}
+
+/// Column of a point and figure chart, spanning the boxes from `low` to `high` inclusive. Boxes
+/// are numbered by the multiple of the box size they start at.
+#[derive(Clone, Copy, Debug)]
+struct PointAndFigureColumn {
+    high: i64,
+    low: i64,
+    rising: bool,
+}
+
+/// Builds the columns of a point and figure chart from the closing prices.
+fn to_point_and_figure_columns(
+    closes: &[f64],
+    box_size: f64,
+    reversal: u8,
+) -> Vec<PointAndFigureColumn> {
+    let reversal = reversal as i64;
+    let mut columns: Vec<PointAndFigureColumn> = vec![];
+    let mut start_box = None;
+
+    for &close in closes {
+        let close_box = (close / box_size).floor() as i64;
+        match columns.last_mut() {
+            None => {
+                // The first column starts once the price has moved by a box in either direction
+                let start_box = *start_box.get_or_insert(close_box);
+                if close_box != start_box {
+                    columns.push(PointAndFigureColumn {
+                        high: cmp::max(start_box, close_box),
+                        low: cmp::min(start_box, close_box),
+                        rising: close_box > start_box,
+                    });
+                }
+            }
+            Some(column) if column.rising => {
+                if close_box > column.high {
+                    column.high = close_box;
+                } else if close_box <= column.high - reversal {
+                    let high = column.high - 1;
+                    columns.push(PointAndFigureColumn {
+                        high,
+                        low: close_box,
+                        rising: false,
+                    });
+                }
+            }
+            Some(column) => {
+                if close_box < column.low {
+                    column.low = close_box;
+                } else if close_box >= column.low + reversal {
+                    let low = column.low + 1;
+                    columns.push(PointAndFigureColumn {
+                        high: close_box,
+                        low,
+                        rising: true,
+                    });
+                }
+            }
+        }
+    }
+
+    columns
+}
+
+fn draw_summary<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        stock, ui_state, ..
+    }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let bars: Vec<_> = stock
+        .bars
+        .iter()
+        .filter(|&bar| {
+            ui_state
+                .date_range
+                .as_ref()
+                .map_or(true, |date_range| date_range.contains(&bar.datetime()))
+        })
+        .collect();
+
+    let mut summary_lines = vec![];
+
+    if let (Some(first_bar), Some(last_bar)) = (bars.first(), bars.last()) {
+        let listing = stock.listing();
+        summary_lines.push(Spans::from(format!(
+            "Latest price: {} {} on {}",
+            listing.format_price(last_bar.close),
+            listing.currency,
+            last_bar.datetime().format("%Y-%m-%d")
+        )));
+
+        let (low, high) = bars
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), bar| {
+                (low.min(bar.low), high.max(bar.high))
+            });
+        summary_lines.push(Spans::from(format!(
+            "Range: {} to {}",
+            listing.format_price(low),
+            listing.format_price(high)
+        )));
+
+        let change = last_bar.close - first_bar.close;
+        let change_percent = change / first_bar.close * 100.0;
+        let trend = if change > 0.0 {
+            "up"
+        } else if change < 0.0 {
+            "down"
+        } else {
+            "flat"
+        };
+        summary_lines.push(Spans::from(format!(
+            "Trend: {} {:+.2} ({:+.2}%) over {}",
+            trend, change, change_percent, ui_state.time_frame
+        )));
+    } else {
+        summary_lines.push(Spans::from("No price data"));
+    }
+
+    for &indicator in &ui_state.indicators {
+        let indicator_values = to_indicator_series(
+            indicator,
+            stock,
+            ui_state.bar_size,
+            ui_state.date_range.as_ref(),
+        )?
+        .into_iter()
+        .filter_map(|(name, _, data)| {
+            data.last()
+                .map(|(_, value)| format!("{} {:.2}", name, value))
+        })
+        .join(", ");
+        summary_lines.push(Spans::from(format!(
+            "Indicator {}: {}",
+            indicator,
+            if indicator_values.is_empty() {
+                "no data"
+            } else {
+                indicator_values.as_str()
+            }
+        )));
+    }
+
+    let summary_paragraph = Paragraph::new(summary_lines)
+        .block(
+            Block::default()
+                .title("Summary")
+                .borders(Borders::ALL)
+                .border_style(pane_border_style(ui_state, Pane::Chart)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(summary_paragraph, area);
+
+    Ok(())
+}
+
+/// Returns the style of the borders of the pane, which stand out while it is focused, unless it is
+/// the only pane which can be focused.
+fn pane_border_style(ui_state: &UiState, pane: Pane) -> Style {
+    if ui_state.focus == Focus::Pane(pane)
+        && !ui_state.full_screen
+        && ui_state.layout_preset.focusable_panes().len() > 1
+    {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default().fg(Color::Gray)
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_watchlist<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        recent_closes,
+        stock,
+        ui_state,
+        watchlist,
+        watchlist_name,
+        ..
+    }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let watchlist_block = Block::default()
+        .title(format!("Watchlist: {}", watchlist_name))
+        .borders(Borders::ALL)
+        .border_style(pane_border_style(ui_state, Pane::Watchlist));
+
+    ui_state
+        .ui_target_areas
+        .send((UiTarget::WatchlistTable, Some(area)));
+
+    // Favorites always come first
+    let watchlist = app::to_watchlist_symbols(&ui_state.favorite_stock_symbols, watchlist);
+
+    if watchlist.is_empty() {
+        let watchlist_paragraph = Paragraph::new("No symbols in watchlist")
+            .block(watchlist_block)
+            .wrap(Wrap { trim: true });
+        f.render_widget(watchlist_paragraph, area);
+
+        return Ok(());
+    }
+
+    let watchlist_rows = watchlist.iter().map(|&symbol| WatchlistRow {
+        closes: recent_closes.get(symbol).cloned().unwrap_or_default(),
+        current: *symbol == stock.symbol,
+        favorite: ui_state.favorite_stock_symbols.contains(symbol),
+        symbol: symbol.clone(),
+    });
+    let watchlist_table = Table::new(vec![
+        TableColumn::new("Symbol", Constraint::Min(10)),
+        TableColumn::new(
+            "Trend",
+            Constraint::Length(WatchlistRow::SPARKLINE_WIDTH as u16),
+        ),
+    ])
+    .block(watchlist_block);
+    let mut watchlist_table_state = TableState::new(watchlist_rows);
+    watchlist_table_state.select_index(ui_state.watchlist_selection);
+    f.render_stateful_widget(watchlist_table, area, &mut watchlist_table_state);
+
+    Ok(())
+}
+
+/// Draws the watchlist as a grid of tiles, each colored by the daily change of the stock, so that
+/// the movers stand out at a glance.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_heatmap<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    const TILE_WIDTH: u16 = 12;
+
+    let heatmap_block = Block::default()
+        .title("Heatmap")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let watchlist = app.watchlist_symbols();
+
+    if watchlist.is_empty() {
+        let heatmap_paragraph = Paragraph::new("No symbols in watchlist")
+            .block(heatmap_block)
+            .wrap(Wrap { trim: true });
+        f.render_widget(heatmap_paragraph, area);
+
+        return Ok(());
+    }
+
+    let heatmap_area = heatmap_block.inner(area);
+    f.render_widget(heatmap_block, area);
+
+    let tiles: Vec<_> = watchlist
+        .iter()
+        .map(|&symbol| HeatmapTile {
+            change_percent: app
+                .recent_closes
+                .get(symbol)
+                .and_then(|closes| DailyChange::from_closes(closes))
+                .map(|daily_change| daily_change.change_percent),
+            current: *symbol == app.stock.symbol,
+            label: symbol,
+            symbol,
+        })
+        .collect();
+    draw_heatmap_tiles(f, &tiles, TILE_WIDTH, heatmap_area);
+
+    Ok(())
+}
+
+/// Draws the alerts in the alerts store along with their status. The selected alert can be edited,
+/// enabled or disabled, snoozed or deleted.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_alerts<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let alerts_block = Block::default()
+        .title(Span::styled(
+            "Alerts (a: add, enter: edit, space: enable/disable, z: snooze, del: delete)",
+            Style::default().fg(Color::Gray),
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    app.ui_state
+        .ui_target_areas
+        .send((UiTarget::AlertTable, Some(area)));
+
+    if app.alerts.is_empty() {
+        let alerts_paragraph = Paragraph::new("No alerts").block(alerts_block);
+        f.render_widget(alerts_paragraph, area);
+
+        return Ok(());
+    }
+
+    let mut alert_table_state = TableState::new(app.alerts.iter().cloned());
+    alert_table_state.select_index(app.ui_state.alert_selection);
+    let alert_table = Table::new(vec![
+        TableColumn::new("Symbol", Constraint::Length(10)),
+        TableColumn::new("Condition", Constraint::Min(20)),
+        TableColumn::new("Status", Constraint::Length(10)),
+    ])
+    .block(alerts_block);
+    f.render_stateful_widget(alert_table, area, &mut alert_table_state);
+
+    Ok(())
+}
+
+impl TableRow for (Alert, AlertStatus) {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let (alert, status) = self;
+        let status_style = match status {
+            AlertStatus::Armed => Style::default().fg(Color::Green),
+            AlertStatus::Disabled | AlertStatus::Snoozed => Style::default().fg(Color::DarkGray),
+            AlertStatus::Triggered => Style::default().fg(Color::Yellow),
+        };
+
+        vec![
+            Cell::from(Span::styled(
+                alert.symbol.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(alert.condition.to_string()),
+            Cell::from(Span::styled(status.to_string(), status_style)),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.0.symbol.cmp(&other.0.symbol),
+            1 => self
+                .0
+                .condition
+                .to_string()
+                .cmp(&other.0.condition.to_string()),
+            _ => self.1.to_string().cmp(&other.1.to_string()),
+        }
+    }
+}
+
+/// Draws an overview of the market indices, each with its daily change and a sparkline of the
+/// last month.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_markets<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let markets_block = Block::default()
+        .title("Markets")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let market_index_rows = app.market_indices.iter().map(|symbol| {
+        let closes = app.recent_closes.get(symbol).cloned().unwrap_or_default();
+        QuoteRow {
+            daily_change: DailyChange::from_closes(&closes),
+            closes,
+            name: Some(stock::market_index_name(symbol).to_owned()),
+            symbol: symbol.clone(),
+        }
+    });
+    let markets_table = Table::new(vec![
+        TableColumn::new("Index", Constraint::Min(14)),
+        TableColumn::new("Symbol", Constraint::Length(10)),
+        TableColumn::new("Last", Constraint::Length(12)),
+        TableColumn::new("Change", Constraint::Length(20)),
+        TableColumn::new(
+            "Trend",
+            Constraint::Length(QuoteRow::SPARKLINE_WIDTH as u16),
+        ),
+    ])
+    .block(markets_block);
+    f.render_stateful_widget(markets_table, area, &mut TableState::new(market_index_rows));
+
+    Ok(())
+}
+
+/// Draws the panels rendered by the plugins for the current stock, stacked on top of each other.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_plugins<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    if app.plugin_panels.is_empty() {
+        let plugins_block = Block::default()
+            .title("Plugins")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let plugins_paragraph = Paragraph::new("No plugin panels").block(plugins_block);
+        f.render_widget(plugins_paragraph, area);
+
+        return Ok(());
+    }
+
+    let panel_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            app.plugin_panels
+                .iter()
+                .map(|panel| Constraint::Min(panel.lines.len() as u16 + 2))
+                .collect::<Vec<_>>(),
+        )
+        .split(area);
+    for (panel, panel_area) in app.plugin_panels.iter().zip(panel_areas) {
+        let panel_block = Block::default()
+            .title(panel.title.as_str())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let panel_paragraph = Paragraph::new(
+            panel
+                .lines
+                .iter()
+                .map(|line| Spans::from(line.as_str()))
+                .collect::<Vec<_>>(),
+        )
+        .block(panel_block);
+        f.render_widget(panel_paragraph, panel_area);
+    }
+
+    Ok(())
+}
+
+/// Draws the returns of the current stock and the stocks in the watchlist over several time
+/// frames, so that they can be compared at a glance.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_performance<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let performance_block = Block::default()
+        .title("Performance")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let performance_rows = iter::once(&app.stock.symbol)
+        .chain(app.watchlist_symbols())
+        .unique()
+        .map(|symbol| {
+            let market_session = stock::market_session(symbol);
+            PerformanceRow {
+                current: *symbol == app.stock.symbol,
+                returns: PerformanceRow::TIME_FRAMES
+                    .iter()
+                    .map(|(_, time_frame)| {
+                        app.daily_bars.get(symbol).and_then(|daily_bars| {
+                            stock::period_return(daily_bars, *time_frame, &market_session)
+                        })
+                    })
+                    .collect(),
+                symbol: symbol.clone(),
+            }
+        });
+    let performance_table = Table::new(
+        iter::once(TableColumn::new("Symbol", Constraint::Min(10)))
+            .chain(
+                PerformanceRow::TIME_FRAMES
+                    .iter()
+                    .map(|(title, _)| TableColumn::new(*title, Constraint::Length(10))),
+            )
+            .collect::<Vec<_>>(),
+    )
+    .block(performance_block);
+    f.render_stateful_widget(
+        performance_table,
+        area,
+        &mut TableState::new(performance_rows),
+    );
+
+    Ok(())
+}
+
+/// A row of the returns of a stock over each of the time frames of the performance screen.
+#[derive(Clone, Debug)]
+struct PerformanceRow {
+    current: bool,
+    /// Returns in percent, in the same order as the time frames.
+    returns: Vec<Option<f64>>,
+    symbol: String,
+}
+
+impl PerformanceRow {
+    /// The 5D time frame counts 5 trading days, so it is shown as a week.
+    const TIME_FRAMES: [(&'static str, TimeFrame); 5] = [
+        ("1W", TimeFrame::FiveDays),
+        ("1M", TimeFrame::OneMonth),
+        ("3M", TimeFrame::ThreeMonths),
+        ("YTD", TimeFrame::YearToDate),
+        ("1Y", TimeFrame::OneYear),
+    ];
+}
+
+impl TableRow for PerformanceRow {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let symbol_cell = Cell::from(Span::styled(
+            self.symbol.as_str(),
+            if self.current {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            },
+        ));
+
+        iter::once(symbol_cell)
+            .chain(
+                self.returns
+                    .iter()
+                    .map(|period_return| match period_return {
+                        Some(period_return) => Cell::from(Span::styled(
+                            format!("{:+.2}%", period_return),
+                            if *period_return > 0.0 {
+                                Style::default().fg(Color::Green)
+                            } else if *period_return < 0.0 {
+                                Style::default().fg(Color::Red)
+                            } else {
+                                Style::default()
+                            },
+                        )),
+                        None => Cell::from("-"),
+                    }),
+            )
+            .collect()
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.symbol.cmp(&other.symbol),
+            _ => self
+                .returns
+                .get(column - 1)
+                .partial_cmp(&other.returns.get(column - 1))
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+/// A row of the daily change and the recent trend of a symbol, with the name in a leading column
+/// if there is one.
+#[derive(Clone, Debug)]
+struct QuoteRow {
+    closes: Vec<f64>,
+    daily_change: Option<DailyChange>,
+    name: Option<String>,
+    symbol: String,
+}
+
+impl QuoteRow {
+    const SPARKLINE_WIDTH: usize = 24;
+
+    fn change_style(&self) -> Style {
+        match self.daily_change {
+            Some(DailyChange { change, .. }) if change > 0.0 => Style::default().fg(Color::Green),
+            Some(DailyChange { change, .. }) if change < 0.0 => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        }
+    }
+}
+
+impl TableRow for QuoteRow {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let (last, change) = match self.daily_change {
+            Some(DailyChange {
+                change,
+                change_percent,
+                close,
+            }) => {
+                let listing = Listing::for_symbol(&self.symbol);
+                (
+                    listing.format_price(close),
+                    listing.format_change(change, change_percent),
+                )
+            }
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        let sparkline = Sparkline::new(&self.closes);
+        let bold_style = Style::default().add_modifier(Modifier::BOLD);
+
+        let name_cell = self
+            .name
+            .as_deref()
+            .map(|name| Cell::from(Span::styled(name, bold_style)));
+        let symbol_cell = if name_cell.is_some() {
+            Cell::from(self.symbol.as_str())
+        } else {
+            Cell::from(Span::styled(self.symbol.as_str(), bold_style))
+        };
+
+        name_cell
+            .into_iter()
+            .chain(vec![
+                symbol_cell,
+                Cell::from(last),
+                Cell::from(Span::styled(change, self.change_style())),
+                Cell::from(Span::styled(
+                    sparkline.symbols(Self::SPARKLINE_WIDTH),
+                    self.change_style(),
+                )),
+            ])
+            .collect()
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        let change_percent = |row: &Self| {
+            row.daily_change
+                .map(|daily_change| daily_change.change_percent)
+        };
+
+        // Without the name, the columns start from the symbol
+        let column = if self.name.is_some() {
+            column
+        } else {
+            column + 1
+        };
+
+        match column {
+            0 => self.name.cmp(&other.name),
+            1 => self.symbol.cmp(&other.symbol),
+            3 | 4 => change_percent(self)
+                .partial_cmp(&change_percent(other))
+                .unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// Draws the quotes which passed the filters of the stock screener. The results are sorted by
+/// pressing the number of the column.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_screener<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let screener_block = Block::default()
+        .title("Screener")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    app.ui_state
+        .ui_target_areas
+        .send((UiTarget::ScreenerTable, Some(area)));
+
+    if app.screener_quotes.is_empty() {
+        let screener_paragraph = Paragraph::new("No matches").block(screener_block);
+        f.render_widget(screener_paragraph, area);
+
+        return Ok(());
+    }
+
+    let mut screener_table_state = TableState::new(app.screener_quotes.iter().cloned());
+    screener_table_state.set_sort(app.ui_state.screener_sort);
+    let screener_table = Table::new(vec![
+        TableColumn::new("1 Symbol", Constraint::Min(10)),
+        TableColumn::new("2 Price", Constraint::Length(12)),
+        TableColumn::new("3 Change", Constraint::Length(12)),
+        TableColumn::new("4 Volume", Constraint::Length(12)),
+        TableColumn::new("5 Market Cap", Constraint::Length(16)),
+        TableColumn::new("6 P/E", Constraint::Length(10)),
+    ])
+    .block(screener_block);
+    f.render_stateful_widget(screener_table, area, &mut screener_table_state);
+
+    Ok(())
+}
+
+impl TableRow for MarketQuote {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let format_value = |value: Option<f64>, format: fn(f64) -> String| {
+            value.map_or_else(|| "-".to_owned(), format)
+        };
+        let change_style = match self.regular_market_change_percent {
+            Some(change_percent) if change_percent > 0.0 => Style::default().fg(Color::Green),
+            Some(change_percent) if change_percent < 0.0 => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+
+        vec![
+            Cell::from(Span::styled(
+                self.symbol.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(format_value(self.regular_market_price, |price| {
+                format!("{:.2}", price)
+            })),
+            Cell::from(Span::styled(
+                format_value(self.regular_market_change_percent, |change_percent| {
+                    format!("{:+.2}%", change_percent)
+                }),
+                change_style,
+            )),
+            Cell::from(format_value(self.regular_market_volume, format_abbreviated)),
+            Cell::from(format_value(self.market_cap, format_abbreviated)),
+            Cell::from(format_value(self.trailing_pe, |pe| format!("{:.2}", pe))),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        let value = |quote: &Self| match column {
+            1 => quote.regular_market_price,
+            2 => quote.regular_market_change_percent,
+            3 => quote.regular_market_volume,
+            4 => quote.market_cap,
+            5 => quote.trailing_pe,
+            _ => None,
+        };
+
+        match column {
+            0 => self.symbol.cmp(&other.symbol),
+            _ => value(self)
+                .partial_cmp(&value(other))
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+/// Formats a large number with the suffix of its magnitude, e.g. 1.23B.
+fn format_abbreviated(value: f64) -> String {
+    match value.abs() {
+        v if v >= 1e12 => format!("{:.2}T", value / 1e12),
+        v if v >= 1e9 => format!("{:.2}B", value / 1e9),
+        v if v >= 1e6 => format!("{:.2}M", value / 1e6),
+        v if v >= 1e3 => format!("{:.2}K", value / 1e3),
+        _ => format!("{:.0}", value),
+    }
+}
+
+/// Draws the depth chart of the order book, with the cumulative quantities of the bids and the
+/// asks filled in on either side of the mid price.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_depth<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let depth_block = Block::default()
+        .title(format!("Order Book Depth {}", app.stock.symbol))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let order_book = match &*app.order_book {
+        Some(order_book) if order_book.stock_symbol == app.stock.symbol => order_book,
+        _ => {
+            let message = if depth::exchange_symbol(&app.stock.symbol).is_some() {
+                "Connecting to the exchange..."
+            } else {
+                "The depth chart is only available for crypto symbols, e.g. BTC-USD"
+            };
+            let depth_paragraph = Paragraph::new(message).block(depth_block);
+            f.render_widget(depth_paragraph, area);
+
+            return Ok(());
+        }
+    };
+    let mid_price = match order_book.mid_price() {
+        Some(mid_price) => mid_price,
+        None => {
+            let depth_paragraph = Paragraph::new("The order book is empty").block(depth_block);
+            f.render_widget(depth_paragraph, area);
+
+            return Ok(());
+        }
+    };
+
+    let cumulative_bids = order_book.cumulative_bids();
+    let cumulative_asks = order_book.cumulative_asks();
+    let max_quantity = cumulative_bids
+        .iter()
+        .chain(&cumulative_asks)
+        .map(|(_, quantity)| *quantity)
+        .fold(0.0, f64::max);
+    let x_axis_bounds = [
+        cumulative_bids
+            .last()
+            .map_or(mid_price, |(price, _)| *price),
+        cumulative_asks
+            .last()
+            .map_or(mid_price, |(price, _)| *price),
+    ];
+
+    // Each side is filled in by zigzagging between the axis and the curve, once every braille dot
+    // column, with the quantity at each price being what is available up to that price
+    let samples = cmp::max(area.width as usize * 2, 2);
+    let to_filled_data = |levels: &[(f64, f64)], [start, end]: [f64; 2]| -> Vec<(f64, f64)> {
+        itertools_num::linspace(start, end, samples)
+            .filter_map(|price| {
+                let quantity = levels
+                    .iter()
+                    .take_while(|(level_price, _)| {
+                        (*level_price - mid_price).abs() <= (price - mid_price).abs()
+                    })
+                    .last()
+                    .map(|(_, quantity)| *quantity)?;
+                Some(vec![(price, 0.0), (price, quantity)])
+            })
+            .flatten()
+            .collect()
+    };
+    let bids_data = to_filled_data(&cumulative_bids, [x_axis_bounds[0], mid_price]);
+    let asks_data = to_filled_data(&cumulative_asks, [mid_price, x_axis_bounds[1]]);
+    let mid_price_line = vec![(mid_price, 0.0), (mid_price, max_quantity)];
+
+    let y_axis_steps = to_y_axis_steps(
+        iter::once(0.0).chain(iter::once(max_quantity)),
+        area.height,
+        None,
+    );
+    let x_axis_labels = vec![
+        Span::from(format!("{:.2}", x_axis_bounds[0])),
+        Span::styled(
+            format!("{:.2}", mid_price),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::from(format!("{:.2}", x_axis_bounds[1])),
+    ];
+
+    let pane = ChartPane {
+        crosshair_line: vec![],
+        datasets: vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(Color::Green))
+                .graph_type(GraphType::Line)
+                .data(&bids_data),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(Color::Red))
+                .graph_type(GraphType::Line)
+                .data(&asks_data),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .style(Style::default().fg(Color::DarkGray))
+                .graph_type(GraphType::Line)
+                .data(&mid_price_line),
+        ],
+        legend: vec![
+            LegendEntry {
+                color: Color::Green,
+                label: "Bids".to_owned(),
+                value: cumulative_bids.last().map(|(_, quantity)| *quantity),
+            },
+            LegendEntry {
+                color: Color::Red,
+                label: "Asks".to_owned(),
+                value: cumulative_asks.last().map(|(_, quantity)| *quantity),
+            },
+        ],
+        title: format!("Order Book Depth {}", app.stock.symbol),
+        y_axis_steps,
+    };
+
+    let y_axis_label_width = to_y_axis_label_width(slice::from_ref(&pane), &x_axis_labels);
+    draw_chart_panes(
+        f,
+        vec![pane],
+        &[area],
+        y_axis_label_width,
+        x_axis_bounds,
+        x_axis_labels,
+        Style::default().fg(Color::Gray),
+    );
+
+    Ok(())
+}
+
+/// Draws the chosen financial statement of the stock, with a column for each fiscal period, latest
+/// first, followed by the change from the same period of the year before.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_financials<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    const MAX_PERIODS: usize = 4;
+
+    let UiState {
+        financial_period,
+        financial_statement,
+        ..
+    } = *app.ui_state;
+    let financials_block = Block::default()
+        .title(Span::styled(
+            format!(
+                "{} {} ({}) (tab: next statement, y: annual/quarterly)",
+                app.stock.symbol, financial_statement, financial_period
+            ),
+            Style::default().fg(Color::Gray),
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    app.ui_state
+        .ui_target_areas
+        .send((UiTarget::FinancialsTable, Some(area)));
+
+    let statements = app
+        .stock
+        .financials
+        .as_ref()
+        .map(|financials| financials.financial_statements(financial_statement, financial_period))
+        .unwrap_or_default();
+    if statements.is_empty() {
+        let financials_paragraph =
+            Paragraph::new("No financial statements").block(financials_block);
+        f.render_widget(financials_paragraph, area);
+
+        return Ok(());
+    }
+
+    let periods_per_year = financial_period.periods_per_year();
+    let period_titles: Vec<_> = statements
+        .iter()
+        .take(MAX_PERIODS)
+        .map(|statement| {
+            statement.end_date.map_or_else(
+                || "-".to_owned(),
+                |end_date| Utc.timestamp(end_date, 0).format("%Y-%m-%d").to_string(),
+            )
+        })
+        .collect();
+    let rows: Vec<_> = statements[0]
+        .line_items
+        .iter()
+        .enumerate()
+        .map(|(i, &(label, _))| FinancialsRow {
+            label,
+            values: (0..period_titles.len())
+                .map(|n| {
+                    let value_at = |n: usize| {
+                        statements
+                            .get(n)
+                            .and_then(|statement| statement.line_items[i].1)
+                    };
+                    let value = value_at(n);
+                    let change = match (value, value_at(n + periods_per_year)) {
+                        (Some(value), Some(previous_value)) if previous_value != 0.0 => {
+                            Some((value - previous_value) / previous_value.abs() * 100.0)
+                        }
+                        _ => None,
+                    };
+                    (value, change)
+                })
+                .collect(),
+        })
+        .collect();
+
+    let columns: Vec<_> = iter::once(TableColumn::new("", Constraint::Min(24)))
+        .chain(period_titles.iter().flat_map(|period_title| {
+            vec![
+                TableColumn::new(period_title.as_str(), Constraint::Length(12)),
+                TableColumn::new("YoY", Constraint::Length(9)),
+            ]
+        }))
+        .collect();
+    let mut financials_table_state = TableState::new(rows);
+    let financials_table = Table::new(columns).block(financials_block);
+    f.render_stateful_widget(financials_table, area, &mut financials_table_state);
+
+    Ok(())
+}
+
+/// Line item of a financial statement, with its value in each fiscal period and the change from
+/// the year before.
+struct FinancialsRow {
+    label: &'static str,
+    values: Vec<(Option<f64>, Option<f64>)>,
+}
+
+impl TableRow for FinancialsRow {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        iter::once(Cell::from(self.label))
+            .chain(self.values.iter().flat_map(|(value, change)| {
+                let change_style = match change {
+                    Some(change) if *change > 0.0 => Style::default().fg(Color::Green),
+                    Some(change) if *change < 0.0 => Style::default().fg(Color::Red),
+                    _ => Style::default().fg(Color::Gray),
+                };
+                vec![
+                    Cell::from(value.map_or_else(|| "-".to_owned(), format_abbreviated)),
+                    Cell::from(Span::styled(
+                        change.map_or_else(|| "-".to_owned(), |change| format!("{:+.1}%", change)),
+                        change_style,
+                    )),
+                ]
+            }))
+            .collect()
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.label.cmp(other.label),
+            n => {
+                let value =
+                    |row: &Self| {
+                        row.values.get((n - 1) / 2).and_then(|(value, change)| {
+                            if n % 2 == 1 {
+                                *value
+                            } else {
+                                *change
+                            }
+                        })
+                    };
+                value(self)
+                    .partial_cmp(&value(other))
+                    .unwrap_or(Ordering::Equal)
+            }
+        }
+    }
+}
+
+/// Draws the trade journal of the paper trading portfolio, grouped by symbol.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_journal<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let journal_block = Block::default()
+        .title("Journal (j: write a note on the current stock)")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let journal = app.portfolio.journal();
+    if journal.is_empty() {
+        let journal_paragraph = Paragraph::new("No trades or notes").block(journal_block);
+        f.render_widget(journal_paragraph, area);
+
+        return Ok(());
+    }
+
+    let mut journal_table_state = TableState::new(journal);
+    let journal_table = Table::new(vec![
+        TableColumn::new("Symbol", Constraint::Length(10)),
+        TableColumn::new("Time", Constraint::Length(17)),
+        TableColumn::new("Entry", Constraint::Min(20)),
+    ])
+    .block(journal_block);
+    f.render_stateful_widget(journal_table, area, &mut journal_table_state);
+
+    Ok(())
+}
+
+impl TableRow for JournalEntry {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let entry_style = match self {
+            JournalEntry::Note(_) => Style::default().add_modifier(Modifier::ITALIC),
+            JournalEntry::Trade(_) => Style::default(),
+        };
+
+        vec![
+            Cell::from(Span::styled(
+                self.symbol(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(
+                Utc.timestamp(self.timestamp(), 0)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ),
+            Cell::from(Span::styled(self.to_string(), entry_style)),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.symbol().cmp(other.symbol()),
+            1 => self.timestamp().cmp(&other.timestamp()),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+}
+
+/// Draws the paper trading portfolio: its cash balance and equity, the positions held, and the
+/// equity curve.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_portfolio<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let portfolio = &app.portfolio;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(if portfolio.orders.is_empty() {
+                0
+            } else {
+                cmp::min(portfolio.orders.len() as u16 + 3, 8)
+            }),
+            Constraint::Length(8),
+        ])
+        .split(area);
+    let summary_area = chunks[0];
+    let positions_area = chunks[1];
+    let orders_area = chunks[2];
+    let equity_curve_area = chunks[3];
+
+    let equity = portfolio.equity();
+    let profit = equity - portfolio.initial_cash;
+    let profit_style = if profit >= 0.0 {
+        Style::default().fg(Color::Green)
+    } else {
+        Style::default().fg(Color::Red)
+    };
+    let summary_paragraph = Paragraph::new(Spans::from(vec![
+        Span::raw(format!(
+            "Cash {:.2} \u{b7} Equity {:.2} \u{b7} ",
+            portfolio.cash, equity
+        )),
+        Span::styled(
+            format!(
+                "{:+.2} ({:+.2}%)",
+                profit,
+                profit / portfolio.initial_cash * 100.0
+            ),
+            profit_style,
+        ),
+    ]))
+    .block(
+        Block::default()
+            .title("Paper Portfolio (B: buy, S: sell, n: new order)")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray)),
+    );
+    f.render_widget(summary_paragraph, summary_area);
+
+    let positions_block = Block::default()
+        .title("Positions")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+    if portfolio.positions.is_empty() {
+        let positions_paragraph = Paragraph::new("No positions").block(positions_block);
+        f.render_widget(positions_paragraph, positions_area);
+    } else {
+        let mut positions_table_state = TableState::new(
+            portfolio
+                .positions
+                .iter()
+                .map(|(symbol, position)| (symbol.clone(), *position)),
+        );
+        let positions_table = Table::new(vec![
+            TableColumn::new("Symbol", Constraint::Min(10)),
+            TableColumn::new("Quantity", Constraint::Length(10)),
+            TableColumn::new("Avg Price", Constraint::Length(12)),
+            TableColumn::new("Last", Constraint::Length(12)),
+            TableColumn::new("Value", Constraint::Length(14)),
+            TableColumn::new("P/L", Constraint::Length(14)),
+        ])
+        .block(positions_block);
+        f.render_stateful_widget(positions_table, positions_area, &mut positions_table_state);
+    }
+
+    if !portfolio.orders.is_empty() {
+        let mut orders_table_state = TableState::new(portfolio.orders.iter().cloned());
+        let orders_table = Table::new(vec![
+            TableColumn::new("Side", Constraint::Length(6)),
+            TableColumn::new("Symbol", Constraint::Min(10)),
+            TableColumn::new("Quantity", Constraint::Length(10)),
+            TableColumn::new("Limit", Constraint::Length(12)),
+        ])
+        .block(
+            Block::default()
+                .title("Open Orders")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+        );
+        f.render_stateful_widget(orders_table, orders_area, &mut orders_table_state);
+    }
+
+    let equity_curve_data: Vec<_> = portfolio
+        .equity_curve
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (i as f64, point.equity))
+        .collect();
+    let (min_equity, max_equity) =
+        match equity_curve_data.iter().map(|(_, equity)| *equity).minmax() {
+            MinMax(min, max) => (min, max),
+            OneElement(equity) => (equity, equity),
+            NoElements => (portfolio.initial_cash, portfolio.initial_cash),
+        };
+    let equity_curve_chart = Chart::new(vec![Dataset::default()
+        .marker(Marker::Braille)
+        .style(profit_style)
+        .graph_type(GraphType::Line)
+        .data(&equity_curve_data)])
+    .block(
+        Block::default()
+            .title("Equity")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray)),
+    )
+    .x_axis(Axis::default().bounds([0.0, cmp::max(equity_curve_data.len(), 2) as f64 - 1.0]))
+    .y_axis(
+        Axis::default()
+            .bounds([min_equity, max_equity])
+            .labels(vec![
+                Span::raw(format!("{:.0}", min_equity)),
+                Span::raw(format!("{:.0}", max_equity)),
+            ]),
+    );
+    f.render_widget(equity_curve_chart, equity_curve_area);
+
+    Ok(())
+}
+
+impl TableRow for Order {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let side_style = match self.side {
+            Side::Buy => Style::default().fg(Color::Green),
+            Side::Sell => Style::default().fg(Color::Red),
+        };
+
+        vec![
+            Cell::from(Span::styled(self.side.to_string(), side_style)),
+            Cell::from(Span::styled(
+                self.symbol.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(self.quantity.to_string()),
+            Cell::from(self.limit_price.map_or_else(
+                || "market".to_owned(),
+                |limit_price| format!("{:.2}", limit_price),
+            )),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.side.to_string().cmp(&other.side.to_string()),
+            1 => self.symbol.cmp(&other.symbol),
+            2 => self
+                .quantity
+                .partial_cmp(&other.quantity)
+                .unwrap_or(Ordering::Equal),
+            _ => self
+                .limit_price
+                .partial_cmp(&other.limit_price)
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+impl TableRow for (String, Position) {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let (symbol, position) = self;
+        let profit = position.unrealized_profit();
+        let profit_style = if profit >= 0.0 {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+
+        vec![
+            Cell::from(Span::styled(
+                symbol.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(position.quantity.to_string()),
+            Cell::from(format!("{:.2}", position.average_price)),
+            Cell::from(format!("{:.2}", position.last_price)),
+            Cell::from(format!("{:.2}", position.market_value())),
+            Cell::from(Span::styled(format!("{:+.2}", profit), profit_style)),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        let value = |(_, position): &Self| match column {
+            1 => position.quantity,
+            2 => position.average_price,
+            3 => position.last_price,
+            4 => position.market_value(),
+            _ => position.unrealized_profit(),
+        };
+
+        match column {
+            0 => self.0.cmp(&other.0),
+            _ => value(self)
+                .partial_cmp(&value(other))
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+/// Draws the symbols trending in the configured region, any of which can be chosen to jump into
+/// its chart.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_trending<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    let trending_block = Block::default()
+        .title("Trending")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    app.ui_state
+        .ui_target_areas
+        .send((UiTarget::TrendingTable, Some(area)));
+
+    if app.trending_stock_symbols.is_empty() {
+        let trending_paragraph = Paragraph::new("No trending symbols").block(trending_block);
+        f.render_widget(trending_paragraph, area);
+
+        return Ok(());
+    }
+
+    let trending_rows = app.trending_stock_symbols.iter().map(|symbol| {
+        let closes = app.recent_closes.get(symbol).cloned().unwrap_or_default();
+        QuoteRow {
+            daily_change: DailyChange::from_closes(&closes),
+            closes,
+            name: None,
+            symbol: symbol.clone(),
+        }
+    });
+    let mut trending_table_state = TableState::new(trending_rows);
+    trending_table_state.select_index(app.ui_state.trending_selection);
+    let trending_table = Table::new(vec![
+        TableColumn::new("Symbol", Constraint::Min(10)),
+        TableColumn::new("Last", Constraint::Length(12)),
+        TableColumn::new("Change", Constraint::Length(20)),
+        TableColumn::new(
+            "Trend",
+            Constraint::Length(QuoteRow::SPARKLINE_WIDTH as u16),
+        ),
+    ])
+    .block(trending_block);
+    f.render_stateful_widget(trending_table, area, &mut trending_table_state);
+
+    Ok(())
+}
+
+/// Draws the performance of each sector as a heatmap, using the sector ETFs as proxies. The
+/// sector of the current stock is underlined.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_sectors<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) -> anyhow::Result<()> {
+    const TILE_WIDTH: u16 = 26;
+
+    let sectors_block = Block::default()
+        .title("Sector Performance")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+    let sectors_area = sectors_block.inner(area);
+    f.render_widget(sectors_block, area);
+
+    let current_sector = app
+        .stock
+        .asset_profile
+        .as_ref()
+        .and_then(|asset_profile| asset_profile.sector.as_deref());
+    let tiles: Vec<_> = stock::SECTOR_ETFS
+        .iter()
+        .map(|&(sector, symbol)| HeatmapTile {
+            change_percent: app
+                .recent_closes
+                .get(symbol)
+                .and_then(|closes| DailyChange::from_closes(closes))
+                .map(|daily_change| daily_change.change_percent),
+            current: current_sector == Some(sector),
+            label: sector,
+            symbol,
+        })
+        .collect();
+    draw_heatmap_tiles(f, &tiles, TILE_WIDTH, sectors_area);
+
+    Ok(())
+}
+
+/// Lays out the tiles in rows from the top left corner, leaving out the ones which do not fit.
+fn draw_heatmap_tiles<B: Backend>(
+    f: &mut Frame<B>,
+    tiles: &[HeatmapTile],
+    min_tile_width: u16,
+    area: Rect,
+) {
+    const TILE_HEIGHT: u16 = 3;
+
+    let columns = cmp::max(area.width / min_tile_width, 1) as usize;
+    let tile_width = area.width / columns as u16;
+    for (n, tile) in tiles.iter().enumerate() {
+        let (row, column) = ((n / columns) as u16, (n % columns) as u16);
+        let y = area.y + row * TILE_HEIGHT;
+        if y + TILE_HEIGHT > area.bottom() {
+            break;
+        }
+        let tile_area = Rect::new(area.x + column * tile_width, y, tile_width, TILE_HEIGHT);
+
+        let tile_style = Style::default().fg(Color::White).bg(tile.color());
+        let change = tile
+            .change_percent
+            .map_or_else(|| "-".to_owned(), |change| format!("{:+.2}%", change));
+        let tile_paragraph = Paragraph::new(vec![
+            Spans::from(Span::styled(
+                tile.label,
+                if tile.current {
+                    tile_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    tile_style.add_modifier(Modifier::BOLD)
+                },
+            )),
+            Spans::from(Span::styled(
+                if tile.label == tile.symbol {
+                    change
+                } else {
+                    format!("{} {}", tile.symbol, change)
+                },
+                tile_style,
+            )),
+        ])
+        .block(Block::default().style(tile_style))
+        .alignment(Alignment::Center);
+        // Leave a gap between the tiles, so that tiles of the same color can be told apart
+        f.render_widget(
+            tile_paragraph,
+            Rect {
+                width: tile_area.width.saturating_sub(1),
+                ..tile_area
+            },
+        );
+    }
+}
+
+#[derive(Clone, Debug)]
+struct HeatmapTile<'a> {
+    change_percent: Option<f64>,
+    current: bool,
+    label: &'a str,
+    symbol: &'a str,
+}
+
+impl<'a> HeatmapTile<'a> {
+    /// Daily change at which the color is at its most intense.
+    const MAX_CHANGE_PERCENT: f64 = 3.0;
+
+    fn color(&self) -> Color {
+        match self.change_percent {
+            Some(change) if change != 0.0 => {
+                let intensity = (change.abs() / Self::MAX_CHANGE_PERCENT).min(1.0);
+                let level = (64.0 + intensity * 128.0) as u8;
+                if change > 0.0 {
+                    Color::Rgb(0, level, 0)
+                } else {
+                    Color::Rgb(level, 0, 0)
+                }
+            }
+            _ => Color::DarkGray,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct WatchlistRow {
+    closes: Vec<f64>,
+    current: bool,
+    favorite: bool,
+    symbol: String,
+}
+
+impl WatchlistRow {
+    const SPARKLINE_WIDTH: usize = 12;
+
+    /// Returns the change in price over the sparkline, as a ratio.
+    fn change(&self) -> Option<f64> {
+        let closes = &self.closes[self.closes.len().saturating_sub(Self::SPARKLINE_WIDTH)..];
+
+        match (closes.first(), closes.last()) {
+            (Some(first), Some(last)) if *first != 0.0 => Some((last - first) / first),
+            _ => None,
+        }
+    }
+}
+
+impl TableRow for WatchlistRow {
+    fn cells(&self) -> Vec<Cell<'_>> {
+        let mut spans = vec![Span::styled(
+            self.symbol.as_str(),
+            if self.current {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            },
+        )];
+        if self.favorite {
+            spans.push(Span::styled(
+                " \u{2605}",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        let sparkline = Sparkline::new(&self.closes);
+        let sparkline_style = match self.change() {
+            Some(change) if change > 0.0 => Style::default().fg(Color::Green),
+            Some(change) if change < 0.0 => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+
+        vec![
+            Cell::from(Spans::from(spans)),
+            Cell::from(Span::styled(
+                sparkline.symbols(Self::SPARKLINE_WIDTH),
+                sparkline_style,
+            )),
+        ]
+    }
+
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering {
+        match column {
+            0 => self.symbol.cmp(&other.symbol),
+            1 => self
+                .change()
+                .partial_cmp(&other.change())
+                .unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_news<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        stock, ui_state, ..
+    }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let news_block = Block::default()
+        .title("News")
+        .borders(Borders::ALL)
+        .border_style(pane_border_style(ui_state, Pane::News));
+
+    ui_state
+        .ui_target_areas
+        .send((UiTarget::NewsList, Some(area)));
+
+    if stock.news.is_empty() {
+        let news_paragraph = Paragraph::new("No news").block(news_block);
+        f.render_widget(news_paragraph, area);
+
+        return Ok(());
+    }
+
+    let news_items: Vec<_> = stock
+        .news
+        .iter()
+        .map(|news_item| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    Utc.timestamp(news_item.provider_publish_time, 0)
+                        .format("%Y-%m-%d ")
+                        .to_string(),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::raw(news_item.title.as_str()),
+                Span::styled(
+                    format!(" ({})", news_item.publisher),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+    let news_list = List::new(news_items)
+        .block(news_block)
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    let mut news_list_state = ListState::default();
+    news_list_state.select(ui_state.news_selection.filter(|&n| n < stock.news.len()));
+    f.render_stateful_widget(news_list, area, &mut news_list_state);
+
+    Ok(())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_fundamentals<B: Backend>(
+    f: &mut Frame<B>,
+    App { stock, .. }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let fundamentals_block = Block::default()
+        .title("Fundamentals")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let quote_summary = if let Some(quote_summary) = &stock.quote_summary {
+        quote_summary
+    } else {
+        let fundamentals_paragraph = Paragraph::new("No fundamentals").block(fundamentals_block);
+        f.render_widget(fundamentals_paragraph, area);
+
+        return Ok(());
+    };
+
+    let format_value = |value: Option<&FormattedValue>| {
+        value
+            .and_then(|value| value.fmt.clone())
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    let asset_profile = quote_summary.asset_profile.clone().unwrap_or_default();
+    let key_statistics = quote_summary
+        .default_key_statistics
+        .clone()
+        .unwrap_or_default();
+    let summary_detail = quote_summary.summary_detail.clone().unwrap_or_default();
+
+    let fundamentals_rows = vec![
+        (
+            "Sector",
+            asset_profile.sector.unwrap_or_else(|| "-".to_owned()),
+        ),
+        (
+            "Industry",
+            asset_profile.industry.unwrap_or_else(|| "-".to_owned()),
+        ),
+        (
+            "Employees",
+            asset_profile
+                .full_time_employees
+                .map_or_else(|| "-".to_owned(), |employees| employees.to_string()),
+        ),
+        (
+            "Market cap",
+            format_value(summary_detail.market_cap.as_ref()),
+        ),
+        (
+            "P/E (TTM)",
+            format_value(summary_detail.trailing_pe.as_ref()),
+        ),
+        (
+            "EPS (TTM)",
+            format_value(key_statistics.trailing_eps.as_ref()),
+        ),
+        (
+            "Dividend yield",
+            format_value(summary_detail.dividend_yield.as_ref()),
+        ),
+        ("Beta", format_value(summary_detail.beta.as_ref())),
+        (
+            "52-week range",
+            format!(
+                "{} - {}",
+                format_value(summary_detail.fifty_two_week_low.as_ref()),
+                format_value(summary_detail.fifty_two_week_high.as_ref())
+            ),
+        ),
+        (
+            "Price target",
+            stock.price_target().map_or_else(
+                || "-".to_owned(),
+                |price_target| {
+                    format!(
+                        "{:.2} ({:.2} - {:.2})",
+                        price_target.mean, price_target.low, price_target.high
+                    )
+                },
+            ),
+        ),
+        (
+            "Short interest",
+            format_value(key_statistics.shares_short.as_ref()),
+        ),
+        (
+            "Short ratio",
+            format_value(key_statistics.short_ratio.as_ref()),
+        ),
+        (
+            "Float short",
+            format_value(key_statistics.short_percent_of_float.as_ref()),
+        ),
+    ];
+    let fundamentals_lines: Vec<_> = fundamentals_rows
+        .into_iter()
+        .map(|(label, value)| {
+            Spans::from(vec![
+                Span::styled(format!("{}: ", label), Style::default().fg(Color::Gray)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+
+    let fundamentals_paragraph = Paragraph::new(fundamentals_lines)
+        .block(fundamentals_block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(fundamentals_paragraph, area);
+
+    Ok(())
+}
+
+/// Draws the ratios of the profitability and the financial health of the company. The free cash
+/// flow yield is computed from the market cap, the rest are as reported by the provider.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_key_ratios<B: Backend>(
+    f: &mut Frame<B>,
+    App { stock, .. }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let key_ratios_block = Block::default()
+        .title("Key Ratios")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let (financial_data, summary_detail) = match &stock.quote_summary {
+        Some(QuoteSummary {
+            financial_data: Some(financial_data),
+            summary_detail,
+            ..
+        }) => (financial_data, summary_detail.clone().unwrap_or_default()),
+        _ => {
+            let key_ratios_paragraph = Paragraph::new("No key ratios").block(key_ratios_block);
+            f.render_widget(key_ratios_paragraph, area);
+
+            return Ok(());
+        }
+    };
+
+    let format_value = |value: Option<&FormattedValue>| {
+        value
+            .and_then(|value| value.fmt.clone())
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    let raw_value = |value: Option<&FormattedValue>| value.and_then(|value| value.raw);
+    let free_cashflow_yield = match (
+        raw_value(financial_data.free_cashflow.as_ref()),
+        raw_value(summary_detail.market_cap.as_ref()),
+    ) {
+        (Some(free_cashflow), Some(market_cap)) if market_cap > 0.0 => {
+            format!("{:.2}%", free_cashflow / market_cap * 100.0)
+        }
+        _ => "-".to_owned(),
+    };
+
+    let key_ratios_rows = vec![
+        (
+            "Gross margin",
+            format_value(financial_data.gross_margins.as_ref()),
+        ),
+        (
+            "Net margin",
+            format_value(financial_data.profit_margins.as_ref()),
+        ),
+        (
+            "ROE",
+            format_value(financial_data.return_on_equity.as_ref()),
+        ),
+        (
+            "Debt/equity",
+            raw_value(financial_data.debt_to_equity.as_ref()).map_or_else(
+                || "-".to_owned(),
+                |debt_to_equity| format!("{:.2}", debt_to_equity / 100.0),
+            ),
+        ),
+        (
+            "Current ratio",
+            format_value(financial_data.current_ratio.as_ref()),
+        ),
+        ("FCF yield", free_cashflow_yield),
+    ];
+    let key_ratios_lines: Vec<_> = key_ratios_rows
+        .into_iter()
+        .map(|(label, value)| {
+            Spans::from(vec![
+                Span::styled(format!("{}: ", label), Style::default().fg(Color::Gray)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+
+    let key_ratios_paragraph = Paragraph::new(key_ratios_lines).block(key_ratios_block);
+    f.render_widget(key_ratios_paragraph, area);
+
+    Ok(())
+}
+
+/// Draws the ESG risk scores of the company, along with the level of its most severe controversy.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_esg_scores<B: Backend>(
+    f: &mut Frame<B>,
+    App { stock, .. }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    let esg_scores = stock
+        .quote_summary
+        .as_ref()
+        .and_then(|quote_summary| quote_summary.esg_scores.clone())
+        .unwrap_or_default();
+    let esg_scores_block = Block::default()
+        .title(match &esg_scores.peer_group {
+            Some(peer_group) => format!("ESG Risk ({})", peer_group),
+            None => "ESG Risk".to_owned(),
+        })
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let format_value = |value: Option<&FormattedValue>| {
+        value
+            .and_then(|value| value.fmt.clone())
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    let controversy_style = match esg_scores.highest_controversy {
+        Some(level) if level >= 4.0 => Style::default().fg(Color::Red),
+        Some(level) if level >= 2.0 => Style::default().fg(Color::Yellow),
+        Some(_) => Style::default().fg(Color::Green),
+        None => Style::default(),
+    };
+
+    let esg_scores_rows = vec![
+        (
+            "Total",
+            format_value(esg_scores.total_esg.as_ref()),
+            Style::default(),
+        ),
+        (
+            "Environment",
+            format_value(esg_scores.environment_score.as_ref()),
+            Style::default(),
+        ),
+        (
+            "Social",
+            format_value(esg_scores.social_score.as_ref()),
+            Style::default(),
+        ),
+        (
+            "Governance",
+            format_value(esg_scores.governance_score.as_ref()),
+            Style::default(),
+        ),
+        (
+            "Percentile",
+            format_value(esg_scores.percentile.as_ref()),
+            Style::default(),
+        ),
+        (
+            "Controversy",
+            esg_scores
+                .highest_controversy
+                .map_or_else(|| "-".to_owned(), |level| format!("{:.0}/5", level)),
+            controversy_style,
+        ),
+    ];
+    let esg_scores_lines: Vec<_> = esg_scores_rows
+        .into_iter()
+        .map(|(label, value, style)| {
+            Spans::from(vec![
+                Span::styled(format!("{}: ", label), Style::default().fg(Color::Gray)),
+                Span::styled(value, style),
+            ])
+        })
+        .collect();
+
+    let esg_scores_paragraph = Paragraph::new(esg_scores_lines).block(esg_scores_block);
+    f.render_widget(esg_scores_paragraph, area);
+
+    Ok(())
+}
+
+/// Draws the counts of the analyst recommendations of the current month, followed by the recent
+/// upgrades and downgrades.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_analyst_ratings<B: Backend>(
+    f: &mut Frame<B>,
+    App { stock, .. }: &App,
+    area: Rect,
+) -> anyhow::Result<()> {
+    const RECENT_GRADE_CHANGES: usize = 10;
+
+    let analyst_ratings_block = Block::default()
+        .title("Analyst Ratings")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+
+    let quote_summary = stock.quote_summary.clone().unwrap_or_default();
+    let recommendations = quote_summary
+        .recommendation_trend
+        .as_ref()
+        .and_then(|recommendation_trend| recommendation_trend.current());
+    let grade_changes = quote_summary
+        .upgrade_downgrade_history
+        .map(|upgrade_downgrade_history| upgrade_downgrade_history.history)
+        .unwrap_or_default();
+
+    if recommendations.is_none() && grade_changes.is_empty() {
+        let analyst_ratings_paragraph =
+            Paragraph::new("No analyst ratings").block(analyst_ratings_block);
+        f.render_widget(analyst_ratings_paragraph, area);
+
+        return Ok(());
+    }
+
+    let mut analyst_ratings_lines = vec![];
+    if let Some(recommendations) = recommendations {
+        analyst_ratings_lines.extend(
+            vec![
+                ("Strong buy", recommendations.strong_buy, Color::Green),
+                ("Buy", recommendations.buy, Color::LightGreen),
+                ("Hold", recommendations.hold, Color::Yellow),
+                ("Sell", recommendations.sell, Color::LightRed),
+                ("Strong sell", recommendations.strong_sell, Color::Red),
+            ]
+            .into_iter()
+            .map(|(label, count, color)| {
+                Spans::from(vec![
+                    Span::styled(format!("{:<12}", label), Style::default().fg(Color::Gray)),
+                    Span::raw(format!("{:>3} ", count)),
+                    Span::styled("■".repeat(count as usize), Style::default().fg(color)),
+                ])
+            }),
+        );
+    }
+    if !grade_changes.is_empty() {
+        analyst_ratings_lines.push(Spans::default());
+    }
+    analyst_ratings_lines.extend(grade_changes.iter().take(RECENT_GRADE_CHANGES).map(
+        |grade_change| {
+            let color = match grade_change.action.as_str() {
+                "up" => Color::Green,
+                "down" => Color::Red,
+                _ => Color::Reset,
+            };
+            let grades = if grade_change.from_grade.is_empty() {
+                grade_change.to_grade.clone()
+            } else {
+                format!("{} → {}", grade_change.from_grade, grade_change.to_grade)
+            };
+            Spans::from(vec![
+                Span::styled(
+                    Utc.timestamp(grade_change.epoch_grade_date, 0)
+                        .format("%Y-%m-%d ")
+                        .to_string(),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::raw(format!("{}: ", grade_change.firm)),
+                Span::styled(grades, Style::default().fg(color)),
+            ])
+        },
+    ));
+
+    let analyst_ratings_paragraph =
+        Paragraph::new(analyst_ratings_lines).block(analyst_ratings_block);
+    f.render_widget(analyst_ratings_paragraph, area);
+
+    Ok(())
+}
+
+/// Splits the data series into the segments above (or at) and below the baseline. Segments are
+/// joined where the series crosses the baseline, so that the line is not broken.
+fn split_at_baseline(data: &[(f64, f64)], baseline: f64) -> Vec<(bool, Vec<(f64, f64)>)> {
+    let mut segments: Vec<(bool, Vec<(f64, f64)>)> = vec![];
+
+    for &(x, y) in data {
+        let above = y >= baseline;
+        match segments.last_mut() {
+            Some((segment_above, segment)) if *segment_above != above => {
+                let &(prev_x, prev_y) = segment.last().unwrap();
+                let crossing_x = prev_x + (baseline - prev_y) / (y - prev_y) * (x - prev_x);
+                segment.push((crossing_x, baseline));
+                segments.push((above, vec![(crossing_x, baseline), (x, y)]));
+            }
+            Some((_, segment)) => segment.push((x, y)),
+            None => segments.push((above, vec![(x, y)])),
+        }
+    }
+
+    segments
+}
+
+/// Splits the data series at the boundaries of the sessions, so that the prices of intraday bars
+/// are not connected across the gaps between the sessions. Series of daily or longer bars, which
+/// have a single bar per session, are kept whole.
+fn split_at_sessions(data: &[(f64, f64)], market_session: &MarketSession) -> Vec<Vec<(f64, f64)>> {
+    let session_date = |&(timestamp, _): &(f64, f64)| {
+        market_session.session_date(Utc.timestamp(timestamp as i64, 0))
+    };
+
+    let is_intraday = data
+        .windows(2)
+        .any(|points| session_date(&points[0]) == session_date(&points[1]));
+    if !is_intraday {
+        return vec![data.to_vec()];
+    }
+
+    data.iter()
+        .group_by(|point| session_date(*point))
+        .into_iter()
+        .map(|(_, points)| points.copied().collect())
+        .collect()
+}
+
+/// Computes the data series of an indicator, in drawing order.
+#[instrument(level = "debug", skip(stock, date_range), fields(stock_symbol = %stock.symbol))]
+pub fn to_indicator_series(
+    indicator: Indicator,
+    stock: &Stock,
+    bar_size: BarSize,
+    date_range: Option<&DateRange>,
+) -> anyhow::Result<Vec<(String, Color, Vec<(f64, f64)>)>> {
+    let bars = bar_size.aggregate(&stock.bars);
+    let indicator_prices_data = bars
+        .iter()
+        .map(|bar| {
+            let data_item = DataItem::builder()
+                .open(bar.open)
+                .high(bar.high)
+                .low(bar.low)
+                .close(bar.close);
+            let data_item = if let Some(volume) = bar.volume {
+                data_item.volume(volume as f64)
+            } else {
+                data_item
+            };
+            let data_item = data_item
+                .build()
+                .map_err(|err| anyhow!("invalid bar at {}: {}", bar.datetime(), err))?;
+            Ok((bar.timestamp_seconds() as f64, data_item))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter();
+
+    let in_date_range = |n: u16, timestamp: f64| {
+        date_range.map_or(true, |date_range| {
+            let date_range =
+                (date_range.start - bar_size.duration() * (n as i32 - 1))..date_range.end;
+            date_range.contains(&Utc.timestamp(timestamp as i64, 0))
+        })
+    };
+
+    match indicator {
+        Indicator::BollingerBands(n, k) => {
+            let mut bb = indicators::BollingerBands::new(*n as usize, *k as f64)
+                .map_err(|err| anyhow!("{}: {}", indicator, err))?;
+            let (bb_upper_data, bb_middle_data, bb_lower_data) = indicator_prices_data
+                .filter(|(timestamp, _)| in_date_range(*n, *timestamp))
+                .fold(
+                    (vec![], vec![], vec![]),
+                    |mut acc_data, (timestamp, data_item)| {
+                        let bb_output = bb.next(&data_item);
+                        acc_data.0.push((timestamp, bb_output.upper));
+                        acc_data.1.push((timestamp, bb_output.average));
+                        acc_data.2.push((timestamp, bb_output.lower));
+                        acc_data
+                    },
+                );
+
+            Ok(vec![
+                ("BB Upper".to_owned(), Color::DarkGray, bb_upper_data),
+                ("BB Lower".to_owned(), Color::DarkGray, bb_lower_data),
+                ("BB Middle".to_owned(), Color::Cyan, bb_middle_data),
+            ])
+        }
+        Indicator::ExponentialMovingAverage(n) => {
+            let mut ema = indicators::ExponentialMovingAverage::new(*n as usize)
+                .map_err(|err| anyhow!("{}: {}", indicator, err))?;
+            let ema_data = indicator_prices_data
+                .filter(|(timestamp, _)| in_date_range(*n, *timestamp))
+                .map(|(timestamp, data_item)| (timestamp, ema.next(&data_item)))
+                .collect();
+
+            Ok(vec![("EMA".to_owned(), Color::Cyan, ema_data)])
+        }
+        Indicator::HistoricalVolatility(n) => {
+            let closes: Vec<_> = bars
+                .iter()
+                .map(|bar| (bar.timestamp_seconds() as f64, bar.close))
+                .filter(|(timestamp, _)| in_date_range(indicator.period(), *timestamp))
+                .collect();
+            let log_returns: Vec<_> = closes
+                .windows(2)
+                .map(|closes| (closes[1].0, (closes[1].1 / closes[0].1).ln()))
+                .collect();
+            let annualization = (bar_size.periods_per_year() as f64).sqrt() * 100.0;
+            let hv_data = log_returns
+                .windows(*n as usize)
+                .map(|log_returns| {
+                    let len = log_returns.len() as f64;
+                    let mean = log_returns.iter().map(|(_, r)| r).sum::<f64>() / len;
+                    let variance = log_returns
+                        .iter()
+                        .map(|(_, r)| (r - mean).powi(2))
+                        .sum::<f64>()
+                        / (len - 1.0);
+                    let (timestamp, _) = log_returns[log_returns.len() - 1];
+                    (timestamp, variance.sqrt() * annualization)
+                })
+                .collect();
 
-            itertools_num::linspace(min, max, n).collect()
+            Ok(vec![("HV".to_owned(), Color::Cyan, hv_data)])
         }
-        OneElement(p) => vec![p, p],
-        NoElements => vec![0_f64, f64::INFINITY],
-    };
-    let y_axis_bounds = [*price_steps.first().unwrap(), *price_steps.last().unwrap()];
-    let y_axis_labels: Vec<_> = price_steps
-        .iter()
-        .map(|&p| Span::from(format!("{:.2}", p)))
-        .collect();
+        Indicator::MovingAverageConvergenceDivergence(fast, slow, signal) => {
+            let mut macd = indicators::MovingAverageConvergenceDivergence::new(
+                *fast as usize,
+                *slow as usize,
+                *signal as usize,
+            )
+            .map_err(|err| anyhow!("{}: {}", indicator, err))?;
+            let n = indicator.period();
+            let (macd_data, signal_data, histogram_data) = indicator_prices_data
+                .filter(|(timestamp, _)| in_date_range(n, *timestamp))
+                .fold(
+                    (vec![], vec![], vec![]),
+                    |mut acc_data, (timestamp, data_item)| {
+                        let macd_output = macd.next(&data_item);
+                        acc_data.0.push((timestamp, macd_output.macd));
+                        acc_data.1.push((timestamp, macd_output.signal));
+                        acc_data.2.push((timestamp, macd_output.histogram));
+                        acc_data
+                    },
+                );
 
-    let historical_prices_chart = Chart::new(historical_prices_datasets)
-        .block(
-            Block::default()
-                .title("Historical Prices")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Gray)),
-        )
-        .x_axis(Axis::default().bounds(x_axis_bounds).labels(x_axis_labels))
-        .y_axis(Axis::default().bounds(y_axis_bounds).labels(y_axis_labels));
-    f.render_widget(historical_prices_chart, area);
+            Ok(vec![
+                ("MACD Histogram".to_owned(), Color::DarkGray, histogram_data),
+                ("MACD Signal".to_owned(), Color::Yellow, signal_data),
+                ("MACD".to_owned(), Color::Cyan, macd_data),
+            ])
+        }
+        Indicator::RelativeStrengthIndex(n) => {
+            let mut rsi = indicators::RelativeStrengthIndex::new(*n as usize)
+                .map_err(|err| anyhow!("{}: {}", indicator, err))?;
+            let rsi_data = indicator_prices_data
+                .filter(|(timestamp, _)| in_date_range(*n, *timestamp))
+                .map(|(timestamp, data_item)| (timestamp, rsi.next(&data_item)))
+                .collect();
 
-    Ok(())
+            Ok(vec![("RSI".to_owned(), Color::Cyan, rsi_data)])
+        }
+        Indicator::SimpleMovingAverage(n) => {
+            let mut sma = indicators::SimpleMovingAverage::new(*n as usize)
+                .map_err(|err| anyhow!("{}: {}", indicator, err))?;
+            let sma_data = indicator_prices_data
+                .filter(|(timestamp, _)| in_date_range(*n, *timestamp))
+                .map(|(timestamp, data_item)| (timestamp, sma.next(&data_item)))
+                .collect();
+
+            Ok(vec![("SMA".to_owned(), Color::Cyan, sma_data)])
+        }
+    }
 }
 
 #[allow(clippy::unnecessary_wraps)]
@@ -347,7 +4069,7 @@ fn draw_footer<B: Backend>(
             .constraints(vec![
                 Constraint::Min(0),
                 Constraint::Length(30),
-                Constraint::Length(20),
+                Constraint::Length(24),
             ])
             .split(area);
         (chunks[1], chunks[2])
@@ -366,9 +4088,9 @@ fn draw_footer<B: Backend>(
         chunks[0]
     };
 
-    let indicator_box = SelectMenuBox::new(Spans::from(vec![
+    let indicator_box = MultiSelectMenuBox::new(Spans::from(vec![
         Span::styled(
-            "Indicator: ",
+            "Indicators: ",
             if indicator_menu_state.active {
                 menu_active_base_style
             } else {
@@ -376,10 +4098,10 @@ fn draw_footer<B: Backend>(
             },
         ),
         Span::styled(
-            if let Some(indicator) = ui_state.indicator {
-                indicator.to_string()
-            } else {
+            if ui_state.indicators.is_empty() {
                 "None".to_owned()
+            } else {
+                ui_state.indicators.iter().join(", ")
             },
             if indicator_menu_state.active {
                 menu_active_base_style
@@ -390,10 +4112,13 @@ fn draw_footer<B: Backend>(
     ]))
     .active_style(menu_active_base_style)
     .active_border_style(Style::default().fg(Color::Gray))
-    .alignment(Alignment::Right);
+    .alignment(Alignment::Right)
+    .focused(ui_state.focus == Focus::Control(UiTarget::IndicatorBox))
+    .hover_point(ui_state.hover_point);
     drop(indicator_menu_state);
     let mut indicator_menu_state = ui_state.indicator_menu_state.borrow_mut();
     f.render_stateful_widget(indicator_box, indicator_box_area, &mut indicator_menu_state);
+    drop(indicator_menu_state);
 
     ui_state
         .ui_target_areas
@@ -420,7 +4145,11 @@ fn draw_footer<B: Backend>(
             },
         ),
         Span::styled(
-            ui_state.time_frame.to_string(),
+            if ui_state.bar_size == BarSize::default() {
+                ui_state.time_frame.to_string()
+            } else {
+                format!("{} ({})", ui_state.time_frame, ui_state.bar_size)
+            },
             if time_frame_menu_state.active {
                 menu_active_base_style
             } else {
@@ -430,10 +4159,13 @@ fn draw_footer<B: Backend>(
     ]))
     .active_style(menu_active_base_style)
     .active_border_style(Style::default().fg(Color::Gray))
-    .alignment(Alignment::Right);
+    .alignment(Alignment::Right)
+    .focused(ui_state.focus == Focus::Control(UiTarget::TimeFrameBox))
+    .hover_point(ui_state.hover_point);
     drop(time_frame_menu_state);
     let mut time_frame_menu_state = ui_state.time_frame_menu_state.borrow_mut();
     f.render_stateful_widget(time_frame_box, time_frame_area, &mut time_frame_menu_state);
+    drop(time_frame_menu_state);
 
     ui_state
         .ui_target_areas
@@ -442,49 +4174,279 @@ fn draw_footer<B: Backend>(
     Ok(())
 }
 
-#[allow(clippy::unnecessary_wraps)]
-fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> anyhow::Result<()> {
+/// Draws the text field at the top left of the screen while it is active, with the error in place
+/// of the title if there is one. Its area is sent either way, so that there is one per frame.
+fn draw_text_field_overlay<B: Backend>(
+    f: &mut Frame<B>,
+    ui_state: &UiState,
+    width: u16,
+    title: Option<&str>,
+    text_field_state: &RefCell<TextFieldState>,
+    error: Option<String>,
+    ui_target: UiTarget,
+) {
     let active_base_style = Style::default().fg(Color::White).bg(Color::DarkGray);
+
+    if !text_field_state.borrow().active {
+        ui_state.ui_target_areas.send((ui_target, None));
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Length(width), Constraint::Min(0)])
+        .split(f.size());
+    let text_field_area = chunks[0];
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .split(text_field_area);
+    let text_field_area = chunks[1];
+
+    let text_field = TextField::new(Span::styled(
+        text_field_state.borrow().value.clone(),
+        active_base_style,
+    ));
+    let text_field = match (error, title) {
+        (Some(err), _) => text_field
+            .border_style(Style::default().fg(Color::Red))
+            .title(Span::styled(err, Style::default().fg(Color::Red))),
+        (None, Some(title)) => text_field
+            .border_style(Style::default().fg(Color::Gray))
+            .title(Span::styled(title, Style::default().fg(Color::Gray))),
+        (None, None) => text_field.border_style(Style::default().fg(Color::Gray)),
+    };
+    // The borrow must end before the area is sent, as the events it triggers may update the state
+    f.render_stateful_widget(
+        text_field,
+        text_field_area,
+        &mut text_field_state.borrow_mut(),
+    );
+
+    ui_state
+        .ui_target_areas
+        .send((ui_target, Some(text_field_area)));
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_overlay<B: Backend>(
+    f: &mut Frame<B>,
+    App {
+        stock, ui_state, ..
+    }: &App,
+) -> anyhow::Result<()> {
     let highlight_base_style = Style::default().fg(Color::Black).bg(Color::White);
 
-    let stock_symbol_field_state = ui_state.stock_symbol_field_state.borrow();
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        30,
+        None,
+        &ui_state.stock_symbol_field_state,
+        ui_state
+            .stock_symbol_field_error
+            .as_ref()
+            .map(ToString::to_string),
+        UiTarget::StockSymbolField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        60,
+        Some("Alert (SYMBOL: condition)"),
+        &ui_state.alert_field_state,
+        ui_state.alert_field_error.as_ref().map(ToString::to_string),
+        UiTarget::AlertField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        50,
+        Some("Order (buy|sell QUANTITY SYMBOL [@ LIMIT])"),
+        &ui_state.order_field_state,
+        ui_state.order_field_error.as_ref().map(ToString::to_string),
+        UiTarget::OrderField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        60,
+        Some(&format!("Journal note on {}", stock.symbol)),
+        &ui_state.note_field_state,
+        None,
+        UiTarget::NoteField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        60,
+        Some("Overlay CSV file (empty to clear)"),
+        &ui_state.series_field_state,
+        None,
+        UiTarget::SeriesField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        60,
+        Some("Import watchlist from file"),
+        &ui_state.watchlist_import_field_state,
+        None,
+        UiTarget::WatchlistImportField,
+    );
+    draw_text_field_overlay(
+        f,
+        ui_state,
+        60,
+        Some("Layout (save NAME, load NAME, delete NAME)"),
+        &ui_state.layout_field_state,
+        None,
+        UiTarget::LayoutField,
+    );
 
-    if stock_symbol_field_state.active {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Length(30), Constraint::Min(0)])
-            .split(f.size());
-        let stock_symbol_field_area = chunks[0];
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![
-                Constraint::Length(1),
-                Constraint::Length(3),
-                Constraint::Min(0),
-            ])
-            .split(stock_symbol_field_area);
-        let stock_symbol_field_area = chunks[1];
+    let recent_stock_symbol_menu_state = ui_state.recent_stock_symbol_menu_state.borrow();
 
-        let stock_symbol_field = TextField::new(Span::styled(
-            stock_symbol_field_state.value.clone(),
-            active_base_style,
-        ))
-        .border_style(Style::default().fg(Color::Gray));
-        drop(stock_symbol_field_state);
-        let mut stock_symbol_field_state = ui_state.stock_symbol_field_state.borrow_mut();
+    if recent_stock_symbol_menu_state.active {
+        let recent_stock_symbol_list_area = {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Length(20), Constraint::Min(0)])
+                .split(f.size());
+            let recent_stock_symbol_list_area = chunks[0];
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Length(1),
+                    Constraint::Length(cmp::min(
+                        recent_stock_symbol_menu_state.items.len() as u16 + 2,
+                        recent_stock_symbol_list_area.height - 1,
+                    )),
+                    Constraint::Min(0),
+                ])
+                .split(recent_stock_symbol_list_area);
+            chunks[1]
+        };
+
+        let recent_stock_symbol_menu_items: Vec<_> = recent_stock_symbol_menu_state
+            .items
+            .iter()
+            .map(|s| ListItem::new(s.clone()))
+            .collect();
+        let recent_stock_symbol_list = SelectMenuList::new(recent_stock_symbol_menu_items)
+            .border_style(Style::default().fg(Color::Gray))
+            .highlight_style(highlight_base_style)
+            .hover_point(ui_state.hover_point);
+        drop(recent_stock_symbol_menu_state);
+        let mut recent_stock_symbol_menu_state =
+            ui_state.recent_stock_symbol_menu_state.borrow_mut();
+        f.render_stateful_widget(
+            recent_stock_symbol_list,
+            recent_stock_symbol_list_area,
+            &mut recent_stock_symbol_menu_state,
+        );
+        drop(recent_stock_symbol_menu_state);
+
+        ui_state.ui_target_areas.send((
+            UiTarget::RecentStockSymbolMenu,
+            Some(recent_stock_symbol_list_area),
+        ));
+    } else {
+        ui_state
+            .ui_target_areas
+            .send((UiTarget::RecentStockSymbolMenu, None));
+    }
+
+    let watchlist_menu_state = ui_state.watchlist_menu_state.borrow();
+
+    if watchlist_menu_state.active {
+        let watchlist_list_area = {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Length(24), Constraint::Min(0)])
+                .split(f.size());
+            let watchlist_list_area = chunks[0];
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Length(1),
+                    Constraint::Length(cmp::min(
+                        watchlist_menu_state.items.len() as u16 + 2,
+                        watchlist_list_area.height - 1,
+                    )),
+                    Constraint::Min(0),
+                ])
+                .split(watchlist_list_area);
+            chunks[1]
+        };
+
+        let watchlist_menu_items: Vec<_> = watchlist_menu_state
+            .items
+            .iter()
+            .map(|s| ListItem::new(s.clone()))
+            .collect();
+        let watchlist_list = SelectMenuList::new(watchlist_menu_items)
+            .border_style(Style::default().fg(Color::Gray))
+            .highlight_style(highlight_base_style)
+            .hover_point(ui_state.hover_point);
+        drop(watchlist_menu_state);
+        let mut watchlist_menu_state = ui_state.watchlist_menu_state.borrow_mut();
         f.render_stateful_widget(
-            stock_symbol_field,
-            stock_symbol_field_area,
-            &mut stock_symbol_field_state,
+            watchlist_list,
+            watchlist_list_area,
+            &mut watchlist_menu_state,
         );
+        drop(watchlist_menu_state);
 
         ui_state
             .ui_target_areas
-            .send((UiTarget::StockSymbolField, Some(stock_symbol_field_area)));
+            .send((UiTarget::WatchlistMenu, Some(watchlist_list_area)));
     } else {
         ui_state
             .ui_target_areas
-            .send((UiTarget::StockSymbolField, None));
+            .send((UiTarget::WatchlistMenu, None));
+    }
+
+    let compare_menu_state = ui_state.compare_menu_state.borrow();
+
+    if compare_menu_state.active {
+        let compare_list_area = {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Length(20), Constraint::Min(0)])
+                .split(f.size());
+            let compare_list_area = chunks[0];
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Length(1),
+                    Constraint::Length(cmp::min(
+                        compare_menu_state.items.len() as u16 + 2,
+                        compare_list_area.height - 1,
+                    )),
+                    Constraint::Min(0),
+                ])
+                .split(compare_list_area);
+            chunks[1]
+        };
+
+        let compare_list = MultiSelectMenuList::new()
+            .border_style(Style::default().fg(Color::Gray))
+            .highlight_style(highlight_base_style)
+            .hover_point(ui_state.hover_point);
+        drop(compare_menu_state);
+        let mut compare_menu_state = ui_state.compare_menu_state.borrow_mut();
+        f.render_stateful_widget(compare_list, compare_list_area, &mut compare_menu_state);
+        drop(compare_menu_state);
+
+        ui_state
+            .ui_target_areas
+            .send((UiTarget::CompareMenu, Some(compare_list_area)));
+    } else {
+        ui_state.ui_target_areas.send((UiTarget::CompareMenu, None));
     }
 
     let indicator_menu_state = ui_state.indicator_menu_state.borrow();
@@ -496,7 +4458,7 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
                 .constraints(vec![
                     Constraint::Min(0),
                     Constraint::Length(30),
-                    Constraint::Length(20),
+                    Constraint::Length(24),
                 ])
                 .split(f.size());
             let indicator_list_area = chunks[1];
@@ -505,7 +4467,7 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
                 .constraints(vec![
                     Constraint::Min(0),
                     Constraint::Length(cmp::min(
-                        Indicator::iter().count() as u16 + 1 + 2,
+                        Indicator::iter().count() as u16 + 2,
                         indicator_list_area.height - 2,
                     )),
                     Constraint::Length(2),
@@ -514,13 +4476,10 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
             chunks[1]
         };
 
-        let indicator_menu_items: Vec<_> = iter::once("None".to_owned())
-            .chain(Indicator::iter().map(|t| t.to_string()))
-            .map(ListItem::new)
-            .collect();
-        let indicator_list = SelectMenuList::new(indicator_menu_items)
+        let indicator_list = MultiSelectMenuList::new()
             .border_style(Style::default().fg(Color::Gray))
-            .highlight_style(highlight_base_style);
+            .highlight_style(highlight_base_style)
+            .hover_point(ui_state.hover_point);
         drop(indicator_menu_state);
         let mut indicator_menu_state = ui_state.indicator_menu_state.borrow_mut();
         f.render_stateful_widget(
@@ -528,6 +4487,7 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
             indicator_list_area,
             &mut indicator_menu_state,
         );
+        drop(indicator_menu_state);
 
         ui_state
             .ui_target_areas
@@ -544,7 +4504,7 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
         let time_frame_list_area = {
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints(vec![Constraint::Min(0), Constraint::Length(20)])
+                .constraints(vec![Constraint::Min(0), Constraint::Length(24)])
                 .split(f.size());
             let time_frame_list_area = chunks[1];
             let chunks = Layout::default()
@@ -566,7 +4526,8 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
             .collect();
         let time_frame_list = SelectMenuList::new(time_frame_menu_items)
             .border_style(Style::default().fg(Color::Gray))
-            .highlight_style(highlight_base_style);
+            .highlight_style(highlight_base_style)
+            .hover_point(ui_state.hover_point);
         drop(time_frame_menu_state);
         let mut time_frame_menu_state = ui_state.time_frame_menu_state.borrow_mut();
         f.render_stateful_widget(
@@ -574,6 +4535,7 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
             time_frame_list_area,
             &mut time_frame_menu_state,
         );
+        drop(time_frame_menu_state);
 
         ui_state
             .ui_target_areas
@@ -584,19 +4546,321 @@ fn draw_overlay<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> any
             .send((UiTarget::TimeFrameMenu, None));
     }
 
+    let company_profile_popup_state = ui_state.company_profile_popup_state.borrow();
+
+    if company_profile_popup_state.active {
+        let company_profile_popup_area = {
+            let size = f.size();
+            let width = cmp::min(80, size.width.saturating_sub(4));
+            let height = cmp::min(20, size.height.saturating_sub(4));
+            Rect::new(
+                size.x + (size.width - width) / 2,
+                size.y + (size.height - height) / 2,
+                width,
+                height,
+            )
+        };
+
+        let asset_profile = stock.asset_profile.clone().unwrap_or_default();
+        let label_style = Style::default().fg(Color::Gray);
+        let mut company_profile_lines = vec![];
+        if let Some(website) = &asset_profile.website {
+            company_profile_lines.push(Spans::from(vec![
+                Span::styled("Website       ", label_style),
+                Span::raw(website.clone()),
+            ]));
+        }
+        if let Some(headquarters) = asset_profile.headquarters() {
+            company_profile_lines.push(Spans::from(vec![
+                Span::styled("Headquarters  ", label_style),
+                Span::raw(headquarters),
+            ]));
+        }
+        if !company_profile_lines.is_empty() {
+            company_profile_lines.push(Spans::default());
+        }
+        company_profile_lines.push(Spans::from(
+            asset_profile
+                .long_business_summary
+                .unwrap_or_else(|| "No company profile available".to_owned()),
+        ));
+
+        let company_profile_popup = Popup::new(company_profile_lines).title(Span::styled(
+            stock.name().unwrap_or(&stock.symbol).to_owned(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        drop(company_profile_popup_state);
+        let mut company_profile_popup_state = ui_state.company_profile_popup_state.borrow_mut();
+        f.render_stateful_widget(
+            company_profile_popup,
+            company_profile_popup_area,
+            &mut company_profile_popup_state,
+        );
+        drop(company_profile_popup_state);
+
+        ui_state.ui_target_areas.send((
+            UiTarget::CompanyProfilePopup,
+            Some(company_profile_popup_area),
+        ));
+    } else {
+        ui_state
+            .ui_target_areas
+            .send((UiTarget::CompanyProfilePopup, None));
+    }
+
     Ok(())
 }
 
 #[allow(clippy::unnecessary_wraps)]
-fn draw_debug<B: Backend>(
+fn draw_connection_banner<B: Backend>(
     f: &mut Frame<B>,
     App {
-        ui_state: UiState {
-            frame_rate_counter, ..
-        },
-        ..
+        connection_status, ..
     }: &App,
 ) -> anyhow::Result<()> {
+    let (error, retry_at) = match connection_status {
+        ConnectionStatus::Disconnected { error, retry_at } => (error, retry_at),
+        ConnectionStatus::Connected | ConnectionStatus::Unauthorized(_) => return Ok(()),
+    };
+
+    let retry_secs = retry_at.saturating_duration_since(Instant::now()).as_secs();
+    let banner_text = if retry_secs > 0 {
+        format!(
+            "Disconnected ({}) \u{2014} retrying in {}s",
+            error, retry_secs
+        )
+    } else {
+        format!("Disconnected ({}) \u{2014} retrying...", error)
+    };
+
+    let banner_area = Rect {
+        height: cmp::min(1, f.size().height),
+        ..f.size()
+    };
+    let banner_paragraph = Paragraph::new(Span::styled(
+        banner_text,
+        Style::default()
+            .fg(Color::White)
+            .bg(Color::Red)
+            .add_modifier(Modifier::BOLD),
+    ))
+    .alignment(Alignment::Center)
+    .style(Style::default().bg(Color::Red));
+
+    f.render_widget(Clear, banner_area);
+    f.render_widget(banner_paragraph, banner_area);
+
+    Ok(())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_toast<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> anyhow::Result<()> {
+    let toast = match &ui_state.toast {
+        Some(toast) if !toast.is_expired() => toast,
+        _ => return Ok(()),
+    };
+
+    let width = cmp::min(toast.message.chars().count() as u16 + 2, f.size().width);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Min(0), Constraint::Length(width)])
+        .split(f.size());
+    let toast_area = chunks[1];
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Min(0),
+            Constraint::Length(3),
+            Constraint::Length(2),
+        ])
+        .split(toast_area);
+    let toast_area = chunks[1];
+
+    let toast_style = if toast.is_flashing() {
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let toast_paragraph = Paragraph::new(Span::styled(toast.message.clone(), toast_style)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .style(toast_style),
+    );
+
+    f.render_widget(Clear, toast_area);
+    f.render_widget(toast_paragraph, toast_area);
+
+    Ok(())
+}
+
+/// Draws the last frame of the app again, with a loading indicator in the top right corner, while
+/// the event loop is blocked on a request.
+#[allow(clippy::unnecessary_wraps)]
+pub fn draw_loading<B: Backend>(f: &mut Frame<B>, last_frame: &Buffer) -> anyhow::Result<()> {
+    const LOADING_TEXT: &str = " Loading… ";
+
+    let area = f.size();
+    let width = cmp::min(LOADING_TEXT.chars().count() as u16, area.width);
+    let loading_area = Rect {
+        x: area.right() - width,
+        y: area.top(),
+        width,
+        height: cmp::min(1, area.height),
+    };
+    let loading_paragraph = Paragraph::new(Span::styled(
+        LOADING_TEXT,
+        Style::default().fg(Color::Black).bg(Color::Yellow),
+    ));
+
+    f.render_widget(widgets::CapturedFrame(last_frame), area);
+    f.render_widget(Clear, loading_area);
+    f.render_widget(loading_paragraph, loading_area);
+
+    Ok(())
+}
+
+/// Draws the screen shown after recovering from a panic, in place of the app.
+#[allow(clippy::unnecessary_wraps)]
+pub fn draw_panic_recovery<B: Backend>(f: &mut Frame<B>, message: &str) -> anyhow::Result<()> {
+    let text = vec![
+        Spans::from(Span::styled(
+            "Something went wrong",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("Press "),
+            Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to restart, "),
+            Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to quit"),
+        ]),
+    ];
+    let paragraph = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, f.size());
+    f.render_widget(paragraph, f.size());
+
+    Ok(())
+}
+
+/// Draws the screen shown when the API key of the data provider is missing or rejected, in place of
+/// the app.
+#[allow(clippy::unnecessary_wraps)]
+fn draw_api_key_error<B: Backend>(
+    f: &mut Frame<B>,
+    error: &ApiKeyError,
+    masked_api_key: Option<&str>,
+) -> anyhow::Result<()> {
+    let config_path_text = config::config_path().map_or_else(
+        |_| "the config file".to_owned(),
+        |config_path| config_path.display().to_string(),
+    );
+    let text = vec![
+        Spans::from(Span::styled(
+            match error {
+                ApiKeyError::Missing => "Missing API key",
+                ApiKeyError::Rejected => "API key rejected",
+            },
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("API key: "),
+            Span::raw(masked_api_key.unwrap_or("-")),
+        ]),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("Set "),
+            Span::styled(
+                format!("credentials.{}", stock::PROVIDER_NAME),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" in {} or the ", config_path_text)),
+            Span::styled(
+                config::api_key_env_var(stock::PROVIDER_NAME),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" environment variable, and restart"),
+        ]),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("Press "),
+            Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to quit"),
+        ]),
+    ];
+    let paragraph = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, f.size());
+    f.render_widget(paragraph, f.size());
+
+    Ok(())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn draw_event_inspector<B: Backend>(
+    f: &mut Frame<B>,
+    App { ui_state, .. }: &App,
+) -> anyhow::Result<()> {
+    let event_log = ui_state.event_log.borrow();
+    let entries = event_log.entries();
+
+    let event_inspector_area = {
+        let width = cmp::min(EVENT_INSPECTOR_WIDTH, f.size().width);
+        let height = cmp::min(entries.len() as u16 + 2, f.size().height);
+        Rect {
+            x: f.size().width - width,
+            y: 0,
+            width,
+            height: cmp::max(height, cmp::min(3, f.size().height)),
+        }
+    };
+
+    // Newest first, so that the latest events are visible without scrolling
+    let event_list_items: Vec<_> = entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    entry.timestamp.format("%H:%M:%S%.3f ").to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:<7} ", entry.kind),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(entry.event.as_str()),
+            ]))
+        })
+        .collect();
+    let event_list = List::new(event_list_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::raw("Events")),
+    );
+
+    f.render_widget(Clear, event_inspector_area);
+    f.render_widget(event_list, event_inspector_area);
+
+    Ok(())
+}
+
+fn draw_debug<B: Backend>(f: &mut Frame<B>, App { ui_state, .. }: &App) -> anyhow::Result<()> {
+    let UiState {
+        frame_rate_counter,
+        masked_api_key,
+        tick_rate,
+        ..
+    } = &**ui_state;
     let frame_time = {
         let mut frame_rate_counter = frame_rate_counter.borrow_mut();
         if let Some(frame_time) = frame_rate_counter.incr() {
@@ -610,17 +4874,101 @@ fn draw_debug<B: Backend>(
     } else {
         "...".to_owned()
     };
+    let (frame_time_data, frame_time_stats) = {
+        let frame_rate_counter = frame_rate_counter.borrow();
+        let frame_time_data: Vec<_> = frame_rate_counter
+            .frame_times()
+            .iter()
+            .map(|frame_time| frame_time.num_milliseconds() as f64)
+            .collect();
+        (frame_time_data, frame_rate_counter.frame_time_stats())
+    };
+    let frame_time_stats_text = if let Some(FrameTimeStats { avg, min, p99 }) = frame_time_stats {
+        format!(
+            "{}/{}/{} ms",
+            min.num_milliseconds(),
+            avg.num_milliseconds(),
+            p99.num_milliseconds()
+        )
+    } else {
+        "...".to_owned()
+    };
 
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(vec![Constraint::Length(20), Constraint::Min(0)])
+        .constraints(vec![Constraint::Length(36), Constraint::Min(0)])
         .split(f.size());
-    let timestamp_area = chunks[0];
+    let debug_area = chunks[0];
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
-        .split(timestamp_area);
-    let timestamp_area = chunks[1];
+        .constraints(vec![
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(debug_area);
+    let api_key_area = chunks[1];
+    let memory_area = chunks[2];
+    let requests_area = chunks[3];
+    let frame_time_history_area = chunks[4];
+    let frame_time_stats_area = chunks[5];
+    let timestamp_area = chunks[6];
+
+    let Stats {
+        allocated_bytes,
+        allocations,
+        pending_requests,
+        rss,
+    } = stats::collect();
+    let memory_paragraph = Paragraph::new(Spans::from(vec![
+        Span::styled("Heap: ", Style::default()),
+        Span::styled(
+            format!("{:.1} MiB ({})", to_mib(allocated_bytes), allocations),
+            Style::default(),
+        ),
+        Span::styled(" RSS: ", Style::default()),
+        Span::styled(
+            rss.map_or_else(|| "-".to_owned(), |rss| format!("{:.1} MiB", to_mib(rss))),
+            Style::default(),
+        ),
+    ]));
+
+    f.render_widget(Clear, memory_area);
+    f.render_widget(memory_paragraph, memory_area);
+
+    let api_key_paragraph = Paragraph::new(Spans::from(vec![
+        Span::styled("API key: ", Style::default()),
+        Span::styled(masked_api_key.as_deref().unwrap_or("-"), Style::default()),
+    ]));
+
+    f.render_widget(Clear, api_key_area);
+    f.render_widget(api_key_paragraph, api_key_area);
+
+    let requests_paragraph = Paragraph::new(Spans::from(vec![
+        Span::styled("Pending requests: ", Style::default()),
+        Span::styled(pending_requests.to_string(), Style::default()),
+    ]));
+
+    f.render_widget(Clear, requests_area);
+    f.render_widget(requests_paragraph, requests_area);
+
+    let frame_time_sparkline =
+        Sparkline::new(&frame_time_data).style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(Clear, frame_time_history_area);
+    f.render_widget(frame_time_sparkline, frame_time_history_area);
+
+    let frame_time_stats_paragraph = Paragraph::new(Spans::from(vec![
+        Span::styled("Min/avg/p99: ", Style::default()),
+        Span::styled(frame_time_stats_text, Style::default()),
+    ]));
+
+    f.render_widget(Clear, frame_time_stats_area);
+    f.render_widget(frame_time_stats_paragraph, frame_time_stats_area);
 
     let timestamp_paragraph = Paragraph::new(Spans::from(vec![
         Span::styled("Frame time: ", Style::default()),
@@ -628,7 +4976,7 @@ fn draw_debug<B: Backend>(
             frame_time_text,
             if let Some(frame_time) = frame_time {
                 if frame_time
-                    >= Duration::milliseconds(round::ceil(crate::TICK_RATE as f64 * 1.1, 0) as i64)
+                    >= Duration::milliseconds(round::ceil(*tick_rate as f64 * 1.1, 0) as i64)
                 {
                     Style::default().fg(Color::Red)
                 } else {
@@ -645,3 +4993,7 @@ fn draw_debug<B: Backend>(
 
     Ok(())
 }
+
+fn to_mib(bytes: usize) -> f64 {
+    bytes as f64 / (1024 * 1024) as f64
+}
@@ -0,0 +1,144 @@
+use anyhow::Context;
+use itertools::Itertools;
+use std::{collections::BTreeMap, fs, io, path::Path};
+use thiserror::Error;
+
+/// Name of the watchlist which the symbols of the `watchlist` in the config file are shown in,
+/// unless a watchlist of the same name is stored.
+pub const DEFAULT_WATCHLIST_NAME: &str = "Default";
+
+/// Symbols of each watchlist, in the order they are shown, by name.
+pub type Watchlists = BTreeMap<String, Vec<String>>;
+
+/// Headers of the column which the symbols are listed under, in the portfolio exports of Yahoo
+/// Finance and the brokers.
+const SYMBOL_COLUMN_HEADERS: [&str; 4] = ["instrument", "symbol", "ticker", "ticker symbol"];
+
+/// Loads the watchlists from the TOML file at the path, with an array of symbols per watchlist.
+/// The default watchlist is added if it is not stored.
+pub fn load_watchlists<P>(path: P, default_watchlist: &[String]) -> anyhow::Result<Watchlists>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut watchlists: Watchlists = if path.exists() {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))?
+    } else {
+        Watchlists::new()
+    };
+    watchlists
+        .entry(DEFAULT_WATCHLIST_NAME.to_owned())
+        .or_insert_with(|| default_watchlist.to_vec());
+
+    Ok(watchlists)
+}
+
+/// Saves the watchlists to the TOML file at the path, overwriting it.
+pub fn save_watchlists<P>(path: P, watchlists: &Watchlists) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+
+    fs::write(path, toml::to_string_pretty(watchlists)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Imports a watchlist from a file, which is either a plain list of symbols, or a CSV export of a
+/// portfolio with a column of symbols. Returns the name of the watchlist, which is the file name
+/// without the extension, and its symbols.
+pub fn import_watchlist<P>(path: P) -> Result<(String, Vec<String>), ImportWatchlistError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|source| ImportWatchlistError::Read {
+        path: path.display().to_string(),
+        source,
+    })?;
+
+    let symbols = parse_symbols(&contents);
+    if symbols.is_empty() {
+        return Err(ImportWatchlistError::Empty {
+            path: path.display().to_string(),
+        });
+    }
+
+    let name = path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    Ok((name, symbols))
+}
+
+fn parse_symbols(contents: &str) -> Vec<String> {
+    let lines: Vec<_> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let delimiter = lines
+        .first()
+        .and_then(|line| [',', ';', '\t'].iter().copied().find(|&c| line.contains(c)));
+
+    let fields: Vec<_> = match delimiter {
+        Some(delimiter) => {
+            let header = split_row(lines[0], delimiter);
+            match header.iter().position(|field| {
+                SYMBOL_COLUMN_HEADERS.contains(&field.to_ascii_lowercase().as_str())
+            }) {
+                Some(column) => lines[1..]
+                    .iter()
+                    .filter_map(|line| split_row(line, delimiter).get(column).copied())
+                    .collect(),
+                // Without a header, the symbols are in the first column
+                None => lines
+                    .iter()
+                    .filter_map(|line| split_row(line, delimiter).first().copied())
+                    .collect(),
+            }
+        }
+        None => lines
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .collect(),
+    };
+
+    // Rows of totals and cash in the exports are left out, along with any header
+    fields
+        .into_iter()
+        .filter(|field| is_symbol(field))
+        .map(str::to_ascii_uppercase)
+        .unique()
+        .collect()
+}
+
+fn split_row(row: &str, delimiter: char) -> Vec<&str> {
+    row.split(delimiter)
+        .map(|field| field.trim().trim_matches('"').trim())
+        .collect()
+}
+
+fn is_symbol(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 12
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '='))
+        && !SYMBOL_COLUMN_HEADERS.contains(&s.to_ascii_lowercase().as_str())
+}
+
+#[derive(Debug, Error)]
+pub enum ImportWatchlistError {
+    #[error("no symbols in {path}")]
+    Empty { path: String },
+    #[error("cannot read {path}: {source}")]
+    Read { path: String, source: io::Error },
+}
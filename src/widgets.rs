@@ -1,5 +1,24 @@
+use tui::layout::Rect;
+
+pub use frame_snapshot::*;
+pub use multi_select_menu::*;
+pub use popup::*;
 pub use select_menu::*;
+pub use sparkline::*;
+pub use table::*;
 pub use text_field::*;
 
+mod frame_snapshot;
+mod multi_select_menu;
+mod popup;
 mod select_menu;
+mod sparkline;
+mod table;
 mod text_field;
+
+/// Returns whether the mouse is over the area, so that it can be highlighted as interactive.
+pub fn is_hovered(hover_point: Option<(u16, u16)>, area: Rect) -> bool {
+    hover_point.map_or(false, |(x, y)| {
+        area.left() <= x && area.right() > x && area.top() <= y && area.bottom() > y
+    })
+}
@@ -0,0 +1,24 @@
+use std::cell::RefCell;
+use tui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+/// Copies everything rendered so far in the frame, so that it can be drawn again later without the
+/// state it was drawn from. It must be rendered last.
+pub struct CaptureFrame<'a>(pub &'a RefCell<Buffer>);
+
+impl<'a> Widget for CaptureFrame<'a> {
+    fn render(self, _area: Rect, buf: &mut Buffer) {
+        self.0.borrow_mut().clone_from(buf);
+    }
+}
+
+/// Draws a frame previously copied by `CaptureFrame`. Nothing is drawn if the terminal has been
+/// resized since, as the frame would no longer fit.
+pub struct CapturedFrame<'a>(pub &'a Buffer);
+
+impl<'a> Widget for CapturedFrame<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.0.area == area {
+            buf.merge(self.0);
+        }
+    }
+}
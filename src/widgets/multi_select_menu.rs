@@ -0,0 +1,337 @@
+use crate::widgets::is_hovered;
+use anyhow::{ensure, Context};
+use std::{cmp, marker::PhantomData};
+use tracing::warn;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::Text,
+    widgets::{self, Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+pub struct MultiSelectMenuBox<'a, S: 'a>
+where
+    S: Clone + PartialEq + ToString,
+{
+    active_border_style: Style,
+    active_style: Style,
+    focus_style: Style,
+    focused: bool,
+    hover_point: Option<(u16, u16)>,
+    hover_style: Style,
+    paragraph: Paragraph<'a>,
+    phantom_s: PhantomData<&'a S>,
+}
+
+impl<'a, S> MultiSelectMenuBox<'a, S>
+where
+    S: Clone + PartialEq + ToString,
+{
+    pub fn new<T>(text: T) -> Self
+    where
+        T: Into<Text<'a>>,
+    {
+        Self {
+            active_border_style: Style::default().fg(Color::Gray),
+            active_style: Style::default().fg(Color::White).bg(Color::DarkGray),
+            focus_style: Style::default().add_modifier(Modifier::REVERSED),
+            focused: false,
+            hover_point: None,
+            hover_style: Style::default().add_modifier(Modifier::UNDERLINED),
+            paragraph: Paragraph::new(text),
+            phantom_s: PhantomData,
+        }
+    }
+
+    pub fn active_border_style(mut self, active_border_style: Style) -> Self {
+        self.active_border_style = active_border_style;
+        self
+    }
+
+    pub fn active_style(mut self, active_style: Style) -> Self {
+        self.active_style = active_style;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: Alignment) -> Self {
+        self.paragraph = self.paragraph.alignment(alignment);
+        self
+    }
+
+    pub fn focus_style(mut self, focus_style: Style) -> Self {
+        self.focus_style = focus_style;
+        self
+    }
+
+    /// Sets whether the box has the keyboard focus, which highlights it with the focus style.
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Sets the position of the mouse, which highlights the box with the hover style when over it.
+    pub fn hover_point(mut self, hover_point: Option<(u16, u16)>) -> Self {
+        self.hover_point = hover_point;
+        self
+    }
+
+    pub fn hover_style(mut self, hover_style: Style) -> Self {
+        self.hover_style = hover_style;
+        self
+    }
+}
+
+impl<'a, S> widgets::StatefulWidget for MultiSelectMenuBox<'a, S>
+where
+    S: Clone + PartialEq + ToString,
+{
+    type State = MultiSelectMenuState<S>;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let paragraph = self
+            .paragraph
+            .block(if state.active {
+                Block::default()
+                    .style(self.active_style)
+                    .borders(Borders::ALL ^ Borders::TOP)
+                    .border_style(self.active_border_style)
+            } else {
+                Block::default()
+            })
+            .style(if state.active {
+                self.active_style
+            } else if self.focused {
+                self.focus_style
+            } else if is_hovered(self.hover_point, area) {
+                self.hover_style
+            } else {
+                Style::default()
+            });
+
+        widgets::Widget::render(Clear, area, buf);
+        widgets::Widget::render(paragraph, area, buf);
+    }
+}
+
+/// A list of checkboxes, one for each item.
+pub struct MultiSelectMenuList<'a, S: 'a>
+where
+    S: Clone + PartialEq + ToString,
+{
+    block: Block<'a>,
+    highlight_style: Style,
+    hover_point: Option<(u16, u16)>,
+    hover_style: Style,
+    phantom_s: PhantomData<&'a S>,
+}
+
+impl<'a, S> MultiSelectMenuList<'a, S>
+where
+    S: Clone + PartialEq + ToString,
+{
+    pub fn new() -> Self {
+        Self {
+            block: Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+            highlight_style: Style::default().fg(Color::Black).bg(Color::White),
+            hover_point: None,
+            hover_style: Style::default().bg(Color::DarkGray),
+            phantom_s: PhantomData,
+        }
+    }
+
+    pub fn border_style(mut self, border_style: Style) -> Self {
+        self.block = self.block.border_style(border_style);
+        self
+    }
+
+    pub fn highlight_style(mut self, highlight_style: Style) -> Self {
+        self.highlight_style = highlight_style;
+        self
+    }
+
+    /// Sets the position of the mouse, which highlights the item under it with the hover style.
+    pub fn hover_point(mut self, hover_point: Option<(u16, u16)>) -> Self {
+        self.hover_point = hover_point;
+        self
+    }
+
+    pub fn hover_style(mut self, hover_style: Style) -> Self {
+        self.hover_style = hover_style;
+        self
+    }
+}
+
+impl<'a, S> Default for MultiSelectMenuList<'a, S>
+where
+    S: Clone + PartialEq + ToString,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, S> widgets::StatefulWidget for MultiSelectMenuList<'a, S>
+where
+    S: Clone + PartialEq + ToString,
+{
+    type State = MultiSelectMenuState<S>;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // The items of a scrolled list cannot be located
+        let hovered_index = self
+            .hover_point
+            .filter(|_| state.items.len() <= area.height.saturating_sub(2) as usize)
+            .and_then(|hover_point| state.point_to_index(area, hover_point));
+        let items: Vec<_> = state
+            .items
+            .iter()
+            .zip(&state.checked)
+            .enumerate()
+            .map(|(n, (item, &checked))| {
+                let list_item = ListItem::new(format!(
+                    "[{}] {}",
+                    if checked { "x" } else { " " },
+                    item.to_string()
+                ));
+                if hovered_index == Some(n) {
+                    list_item.style(self.hover_style)
+                } else {
+                    list_item
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.block)
+            .highlight_style(self.highlight_style);
+
+        widgets::Widget::render(Clear, area, buf);
+        widgets::StatefulWidget::render(list, area, buf, &mut state.list_state);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MultiSelectMenuState<T>
+where
+    T: Clone + PartialEq + ToString,
+{
+    pub active: bool,
+    pub items: Vec<T>,
+    checked: Vec<bool>,
+    list_state: ListState,
+}
+
+impl<T> MultiSelectMenuState<T>
+where
+    T: Clone + PartialEq + ToString,
+{
+    pub fn new<I>(items: I) -> MultiSelectMenuState<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let items: Vec<_> = items.into_iter().collect();
+        let checked = vec![false; items.len()];
+
+        Self {
+            active: false,
+            items,
+            checked,
+            list_state: ListState::default(),
+        }
+    }
+
+    /// Returns the checked items, in the same order as the items.
+    pub fn selected(&self) -> Vec<T> {
+        self.items
+            .iter()
+            .zip(&self.checked)
+            .filter(|(_, &checked)| checked)
+            .map(|(item, _)| item.clone())
+            .collect()
+    }
+
+    /// Checks exactly the given items.
+    pub fn select(&mut self, items: &[T]) -> anyhow::Result<()> {
+        let mut checked = vec![false; self.items.len()];
+        for item in items {
+            let n = self
+                .items
+                .iter()
+                .position(|t| t == item)
+                .with_context(|| "item not found")?;
+            checked[n] = true;
+        }
+        self.checked = checked;
+
+        Ok(())
+    }
+
+    pub fn toggle_index(&mut self, n: usize) -> anyhow::Result<()> {
+        ensure!(n < self.items.len(), "index out of bounds");
+        self.checked[n] = !self.checked[n];
+
+        Ok(())
+    }
+
+    pub fn highlighted_index(&self) -> Option<usize> {
+        self.list_state.selected()
+    }
+
+    pub fn highlight_prev(&mut self) -> anyhow::Result<()> {
+        let n = self.list_state.selected().map_or_else(
+            || self.items.len().saturating_sub(1),
+            |n| n.saturating_sub(1),
+        );
+
+        self.highlight_index(n)?;
+
+        Ok(())
+    }
+
+    pub fn highlight_next(&mut self) -> anyhow::Result<()> {
+        let n = self
+            .list_state
+            .selected()
+            .map_or(0, |n| cmp::min(n + 1, self.items.len().saturating_sub(1)));
+
+        self.highlight_index(n)?;
+
+        Ok(())
+    }
+
+    pub fn highlight_index(&mut self, n: usize) -> anyhow::Result<()> {
+        ensure!(n < self.items.len(), "index out of bounds");
+        self.list_state.select(Some(n));
+
+        Ok(())
+    }
+
+    pub fn point_to_index(&self, menu_area: Rect, (x, y): (u16, u16)) -> Option<usize> {
+        let border_margin = Margin {
+            horizontal: 1,
+            vertical: 1,
+        };
+        let inner_area = menu_area.inner(&border_margin);
+
+        if inner_area.left() <= x
+            && inner_area.right() >= x
+            && inner_area.top() <= y
+            && inner_area.bottom() >= y
+        {
+            // The rows of a scrolled list are not known here, so it cannot be clicked
+            if (inner_area.height as usize) < self.items.len() {
+                warn!("cannot select an item of a scrolled multi-select menu");
+                return None;
+            }
+            let n: usize = (y - inner_area.top()) as usize;
+
+            if n < self.items.len() {
+                return Some(n);
+            }
+        }
+
+        None
+    }
+}
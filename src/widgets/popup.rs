@@ -0,0 +1,79 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Spans, Text},
+    widgets::{self, Block, Borders, Clear, Paragraph, Wrap},
+};
+
+pub struct Popup<'a> {
+    block: Block<'a>,
+    text: Text<'a>,
+}
+
+impl<'a> Popup<'a> {
+    pub fn new<T>(text: T) -> Self
+    where
+        T: Into<Text<'a>>,
+    {
+        let block = Block::default()
+            .style(Style::default().fg(Color::White).bg(Color::DarkGray))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+
+        Self {
+            block,
+            text: text.into(),
+        }
+    }
+
+    pub fn border_style(mut self, border_style: Style) -> Self {
+        self.block = self.block.border_style(border_style);
+        self
+    }
+
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<Spans<'a>>,
+    {
+        self.block = self.block.title(title);
+        self
+    }
+}
+
+impl<'a> widgets::StatefulWidget for Popup<'a> {
+    type State = PopupState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // Keep the last line within view, so that scrolling past the end does not blank the popup
+        let inner_area = self.block.inner(area);
+        let max_scroll = wrapped_line_count(&self.text, inner_area.width)
+            .saturating_sub(inner_area.height as usize);
+        state.scroll = state.scroll.min(max_scroll as u16);
+
+        let paragraph = Paragraph::new(self.text)
+            .block(self.block)
+            .wrap(Wrap { trim: true })
+            .scroll((state.scroll, 0));
+
+        widgets::Widget::render(Clear, area, buf);
+        widgets::Widget::render(paragraph, area, buf);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PopupState {
+    pub active: bool,
+    pub scroll: u16,
+}
+
+/// Estimates the number of lines the text takes up when wrapped to the width.
+fn wrapped_line_count(text: &Text, width: u16) -> usize {
+    let width = width.max(1) as usize;
+
+    text.lines
+        .iter()
+        .map(|spans| (spans.width() + width - 1) / width)
+        .map(|n| n.max(1))
+        .sum()
+}
@@ -1,9 +1,10 @@
+use crate::widgets::is_hovered;
 use anyhow::{ensure, Context};
 use std::marker::PhantomData;
 use tui::{
     buffer::Buffer,
     layout::{Alignment, Margin, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::Text,
     widgets::{self, Block, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
@@ -14,6 +15,10 @@ where
 {
     active_border_style: Style,
     active_style: Style,
+    focus_style: Style,
+    focused: bool,
+    hover_point: Option<(u16, u16)>,
+    hover_style: Style,
     paragraph: Paragraph<'a>,
     phantom_s: PhantomData<&'a S>,
 }
@@ -29,6 +34,10 @@ where
         Self {
             active_border_style: Style::default().fg(Color::Gray),
             active_style: Style::default().fg(Color::White).bg(Color::DarkGray),
+            focus_style: Style::default().add_modifier(Modifier::REVERSED),
+            focused: false,
+            hover_point: None,
+            hover_style: Style::default().add_modifier(Modifier::UNDERLINED),
             paragraph: Paragraph::new(text),
             phantom_s: PhantomData,
         }
@@ -48,6 +57,28 @@ where
         self.paragraph = self.paragraph.alignment(alignment);
         self
     }
+
+    pub fn focus_style(mut self, focus_style: Style) -> Self {
+        self.focus_style = focus_style;
+        self
+    }
+
+    /// Sets whether the box has the keyboard focus, which highlights it with the focus style.
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Sets the position of the mouse, which highlights the box with the hover style when over it.
+    pub fn hover_point(mut self, hover_point: Option<(u16, u16)>) -> Self {
+        self.hover_point = hover_point;
+        self
+    }
+
+    pub fn hover_style(mut self, hover_style: Style) -> Self {
+        self.hover_style = hover_style;
+        self
+    }
 }
 
 impl<'a, S> widgets::StatefulWidget for SelectMenuBox<'a, S>
@@ -69,6 +100,10 @@ where
             })
             .style(if state.active {
                 self.active_style
+            } else if self.focused {
+                self.focus_style
+            } else if is_hovered(self.hover_point, area) {
+                self.hover_style
             } else {
                 Style::default()
             });
@@ -82,7 +117,11 @@ pub struct SelectMenuList<'a, S: 'a>
 where
     S: Clone + PartialEq + ToString,
 {
-    list: List<'a>,
+    block: Block<'a>,
+    highlight_style: Style,
+    hover_point: Option<(u16, u16)>,
+    hover_style: Style,
+    items: Vec<ListItem<'a>>,
     phantom_s: PhantomData<&'a S>,
 }
 
@@ -95,28 +134,35 @@ where
         L: Into<Vec<ListItem<'a>>>,
     {
         Self {
-            list: List::new(items)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::Gray)),
-                )
-                .highlight_style(Style::default().fg(Color::Black).bg(Color::White)),
+            block: Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+            highlight_style: Style::default().fg(Color::Black).bg(Color::White),
+            hover_point: None,
+            hover_style: Style::default().bg(Color::DarkGray),
+            items: items.into(),
             phantom_s: PhantomData,
         }
     }
 
     pub fn border_style(mut self, border_style: Style) -> Self {
-        self.list = self.list.block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(border_style),
-        );
+        self.block = self.block.border_style(border_style);
         self
     }
 
     pub fn highlight_style(mut self, highlight_style: Style) -> Self {
-        self.list = self.list.highlight_style(highlight_style);
+        self.highlight_style = highlight_style;
+        self
+    }
+
+    /// Sets the position of the mouse, which highlights the item under it with the hover style.
+    pub fn hover_point(mut self, hover_point: Option<(u16, u16)>) -> Self {
+        self.hover_point = hover_point;
+        self
+    }
+
+    pub fn hover_style(mut self, hover_style: Style) -> Self {
+        self.hover_style = hover_style;
         self
     }
 }
@@ -128,8 +174,30 @@ where
     type State = SelectMenuState<S>;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // The items of a scrolled list cannot be located
+        let hovered_index = self
+            .hover_point
+            .filter(|_| self.items.len() <= area.height.saturating_sub(2) as usize)
+            .and_then(|hover_point| state.point_to_index(area, hover_point));
+        let hover_style = self.hover_style;
+        let items: Vec<_> = self
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(n, item)| {
+                if hovered_index == Some(n) {
+                    item.style(hover_style)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.block)
+            .highlight_style(self.highlight_style);
+
         widgets::Widget::render(Clear, area, buf);
-        widgets::StatefulWidget::render(self.list, area, buf, &mut state.list_state);
+        widgets::StatefulWidget::render(list, area, buf, &mut state.list_state);
     }
 }
 
@@ -160,6 +228,20 @@ where
         }
     }
 
+    /// Replaces the items, selecting the first item (or the empty selection, if allowed).
+    pub fn set_items<I>(&mut self, items: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.items = items.into_iter().collect();
+        self.list_state
+            .select(if self.items.is_empty() && !self.allow_empty_selection {
+                None
+            } else {
+                Some(0)
+            });
+    }
+
     pub fn selected(&self) -> Option<T> {
         self.list_state
             .selected()
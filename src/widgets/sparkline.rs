@@ -0,0 +1,74 @@
+use itertools::Itertools;
+use itertools::MinMaxResult::{MinMax, NoElements, OneElement};
+use tui::{buffer::Buffer, layout::Rect, style::Style, symbols::bar, widgets};
+
+const BAR_SYMBOLS: [&str; 8] = [
+    bar::ONE_EIGHTH,
+    bar::ONE_QUARTER,
+    bar::THREE_EIGHTHS,
+    bar::HALF,
+    bar::FIVE_EIGHTHS,
+    bar::THREE_QUARTERS,
+    bar::SEVEN_EIGHTHS,
+    bar::FULL,
+];
+
+/// A single-line sparkline, compact enough to be shown inline, e.g. in a table row.
+pub struct Sparkline<'a> {
+    data: &'a [f64],
+    style: Style,
+}
+
+impl<'a> Sparkline<'a> {
+    pub fn new(data: &'a [f64]) -> Self {
+        Self {
+            data,
+            style: Style::default(),
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Returns the bar symbols for the last `width` data points.
+    pub fn symbols(&self, width: usize) -> String {
+        let data = &self.data[self.data.len().saturating_sub(width)..];
+
+        let (min, max) = match data.iter().minmax_by(|a, b| a.partial_cmp(b).unwrap()) {
+            MinMax(min, max) => (*min, *max),
+            OneElement(value) => (*value, *value),
+            NoElements => return String::new(),
+        };
+        let range = max - min;
+
+        data.iter()
+            .map(|value| {
+                let n = if range > 0.0 {
+                    ((value - min) / range * (BAR_SYMBOLS.len() - 1) as f64).round() as usize
+                } else {
+                    BAR_SYMBOLS.len() / 2
+                };
+                BAR_SYMBOLS[n]
+            })
+            .collect()
+    }
+}
+
+impl<'a> widgets::Widget for Sparkline<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+
+        let symbols = self.symbols(area.width as usize);
+        buf.set_stringn(
+            area.left(),
+            area.top(),
+            symbols,
+            area.width as usize,
+            self.style,
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    cmp::{self, Ordering},
+    marker::PhantomData,
+};
+use tui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{self, Block, Cell, Row},
+};
+
+/// A row which can be displayed in a [`Table`].
+pub trait TableRow {
+    /// Returns the cells of the row, one for each column.
+    fn cells(&self) -> Vec<Cell<'_>>;
+
+    /// Compares the row with another row by the values in the given column.
+    fn cmp_by_column(&self, other: &Self, column: usize) -> Ordering;
+}
+
+#[derive(Clone, Debug)]
+pub struct TableColumn<'a> {
+    pub title: &'a str,
+    pub width: Constraint,
+}
+
+impl<'a> TableColumn<'a> {
+    pub fn new(title: &'a str, width: Constraint) -> Self {
+        Self { title, width }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+pub struct Table<'a, R>
+where
+    R: Clone + TableRow,
+{
+    block: Option<Block<'a>>,
+    columns: Vec<TableColumn<'a>>,
+    header_style: Style,
+    highlight_style: Style,
+    phantom_r: PhantomData<&'a R>,
+}
+
+impl<'a, R> Table<'a, R>
+where
+    R: Clone + TableRow,
+{
+    pub fn new<C>(columns: C) -> Self
+    where
+        C: Into<Vec<TableColumn<'a>>>,
+    {
+        Self {
+            block: None,
+            columns: columns.into(),
+            header_style: Style::default().add_modifier(Modifier::BOLD),
+            highlight_style: Style::default().fg(Color::Black).bg(Color::White),
+            phantom_r: PhantomData,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn header_style(mut self, header_style: Style) -> Self {
+        self.header_style = header_style;
+        self
+    }
+
+    pub fn highlight_style(mut self, highlight_style: Style) -> Self {
+        self.highlight_style = highlight_style;
+        self
+    }
+}
+
+impl<'a, R> widgets::StatefulWidget for Table<'a, R>
+where
+    R: Clone + TableRow,
+{
+    type State = TableState<R>;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let widths: Vec<_> = self.columns.iter().map(|column| column.width).collect();
+        let header = Row::new(self.columns.iter().enumerate().map(
+            |(i, column)| match state.sort {
+                Some((sort_column, SortOrder::Ascending)) if sort_column == i => {
+                    format!("{} \u{25b2}", column.title)
+                }
+                Some((sort_column, SortOrder::Descending)) if sort_column == i => {
+                    format!("{} \u{25bc}", column.title)
+                }
+                _ => column.title.to_owned(),
+            },
+        ))
+        .style(self.header_style);
+        let rows: Vec<_> = state.rows.iter().map(|row| Row::new(row.cells())).collect();
+
+        let mut table = widgets::Table::new(rows)
+            .header(header)
+            .widths(&widths)
+            .highlight_style(self.highlight_style);
+        if let Some(block) = self.block {
+            table = table.block(block);
+        }
+
+        widgets::StatefulWidget::render(table, area, buf, &mut state.table_state);
+    }
+}
+
+/// Returns the sort after sorting by the given column, which reverses the sort order if already
+/// sorted by that column. For keeping the sort outside of the [`TableState`].
+pub fn next_sort(sort: Option<(usize, SortOrder)>, column: usize) -> Option<(usize, SortOrder)> {
+    match sort {
+        Some((sort_column, SortOrder::Ascending)) if sort_column == column => {
+            Some((column, SortOrder::Descending))
+        }
+        _ => Some((column, SortOrder::Ascending)),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TableState<R>
+where
+    R: Clone + TableRow,
+{
+    rows: Vec<R>,
+    sort: Option<(usize, SortOrder)>,
+    table_state: widgets::TableState,
+}
+
+impl<R> TableState<R>
+where
+    R: Clone + TableRow,
+{
+    pub fn new<I>(rows: I) -> TableState<R>
+    where
+        I: IntoIterator<Item = R>,
+    {
+        Self {
+            rows: rows.into_iter().collect(),
+            sort: None,
+            table_state: widgets::TableState::default(),
+        }
+    }
+
+    /// Returns the rows, in display order.
+    pub fn rows(&self) -> &[R] {
+        &self.rows
+    }
+
+    /// Replaces the rows, keeping the current sorting. The selection is cleared if it is out of
+    /// bounds.
+    pub fn set_rows<I>(&mut self, rows: I)
+    where
+        I: IntoIterator<Item = R>,
+    {
+        self.rows = rows.into_iter().collect();
+        if matches!(self.table_state.selected(), Some(n) if n >= self.rows.len()) {
+            self.table_state.select(None);
+        }
+        self.apply_sort();
+    }
+
+    pub fn sort(&self) -> Option<(usize, SortOrder)> {
+        self.sort
+    }
+
+    pub fn set_sort(&mut self, sort: Option<(usize, SortOrder)>) {
+        self.sort = sort;
+        self.apply_sort();
+    }
+
+    /// Sorts by the given column, reversing the sort order if already sorted by that column.
+    pub fn sort_by(&mut self, column: usize) {
+        self.set_sort(next_sort(self.sort, column));
+    }
+
+    pub fn selected(&self) -> Option<&R> {
+        self.table_state.selected().map(|n| &self.rows[n])
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.table_state.selected()
+    }
+
+    pub fn select_index(&mut self, n: Option<usize>) {
+        self.table_state.select(n.filter(|&n| n < self.rows.len()));
+    }
+
+    pub fn select_prev(&mut self) {
+        let n = self.table_state.selected().map_or_else(
+            || self.rows.len().checked_sub(1),
+            |n| Some(n.saturating_sub(1)),
+        );
+
+        self.select_index(n);
+    }
+
+    pub fn select_next(&mut self) {
+        let n = self
+            .table_state
+            .selected()
+            .map_or(0, |n| cmp::min(n + 1, self.rows.len().saturating_sub(1)));
+
+        self.select_index(Some(n));
+    }
+
+    /// Sorts the rows, keeping the same row selected.
+    fn apply_sort(&mut self) {
+        let (column, sort_order) = match self.sort {
+            Some(sort) => sort,
+            None => return,
+        };
+
+        let mut indices: Vec<_> = (0..self.rows.len()).collect();
+        indices.sort_by(|&a, &b| {
+            let ordering = self.rows[a].cmp_by_column(&self.rows[b], column);
+            match sort_order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+
+        let selected = self
+            .table_state
+            .selected()
+            .and_then(|selected| indices.iter().position(|&n| n == selected));
+        self.rows = indices.iter().map(|&n| self.rows[n].clone()).collect();
+        self.table_state.select(selected);
+    }
+}
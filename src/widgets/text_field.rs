@@ -2,7 +2,7 @@ use tui::{
     buffer::Buffer,
     layout::{Margin, Rect},
     style::{Color, Style},
-    text::Text,
+    text::{Spans, Text},
     widgets::{self, Block, Borders, Clear, Paragraph},
 };
 
@@ -31,6 +31,15 @@ impl<'a> TextField<'a> {
         self
     }
 
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<Spans<'a>>,
+    {
+        self.block = self.block.title(title);
+        self.paragraph = self.paragraph.block(self.block.clone());
+        self
+    }
+
     pub fn style(mut self, style: Style) -> Self {
         self.block = self.block.style(style);
         self.paragraph = self.paragraph.block(self.block.clone());
@@ -47,14 +56,56 @@ impl<'a> widgets::StatefulWidget for TextField<'a> {
     }
 }
 
+const MAX_HISTORY_LEN: usize = 100;
+
 #[derive(Clone, Debug, Default)]
 pub struct TextFieldState {
     pub active: bool,
     pub cursor_offset: usize,
+    /// Previously accepted values, oldest first.
+    pub history: Vec<String>,
+    /// Position in the history while navigating it, if any.
+    pub history_index: Option<usize>,
     pub value: String,
 }
 
 impl TextFieldState {
+    /// Appends the value to the history, removing any earlier occurrence of it.
+    pub fn push_history(&mut self, value: String) {
+        self.history.retain(|v| *v != value);
+        self.history.push(value);
+        if self.history.len() > MAX_HISTORY_LEN {
+            self.history.drain(..self.history.len() - MAX_HISTORY_LEN);
+        }
+        self.history_index = None;
+    }
+
+    /// Returns the state with the value recalled from the given history position, or with an
+    /// empty value if navigating past the most recent entry.
+    pub fn recall_history(&self, history_index: Option<usize>) -> Self {
+        let value = history_index
+            .and_then(|i| self.history.get(i))
+            .cloned()
+            .unwrap_or_default();
+
+        Self {
+            cursor_offset: value.chars().count(),
+            history_index,
+            value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the value with the text inserted at the cursor offset.
+    pub fn value_with_inserted(&self, text: &str) -> String {
+        self.value
+            .chars()
+            .take(self.cursor_offset)
+            .chain(text.chars())
+            .chain(self.value.chars().skip(self.cursor_offset))
+            .collect()
+    }
+
     pub fn cursor_point(&self, text_field_area: Rect) -> Option<(u16, u16)> {
         if !self.active {
             return None;